@@ -11,7 +11,7 @@ use std::process::Command;
 //}}}
 //{{{ dep imports 
 use topohedral_modeller::boxing::{ABox, ABoxable};
-use topohedral_modeller::common::Vec3;
+use topohedral_modeller::common::{Vec2, Vec3};
 use topohedral_modeller::geometry::{Bcurve, BcurveDescriptor, Curve, Line, LineDescriptor, Plane, PlaneDescriptor};
 #[cfg(feature = "viewer")]
 use topohedral_modeller::viewer::{
@@ -104,6 +104,7 @@ fn bcurve_view()
             // color: CurveColor::ParamFunction(Box::new(kappa)),
             with_param_pts: true,
             with_ctrl_pts: CtrlPointOptions::NoPts,
+            ..Default::default()
         };
 
         bcurve.view(50051, &bcurve_opts);
@@ -116,6 +117,41 @@ fn bcurve_view()
     }
 }
 
+fn bcurve_2d_view()
+{
+    #[cfg(feature = "viewer")]
+    {
+        let mut bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.4, 0.7, 1.0, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 2.0),
+                Vec2::new(3.0, 2.5),
+                Vec2::new(4.0, -1.0),
+                Vec2::new(6.0, 0.5),
+                Vec2::new(7.0, 2.0),
+            ],
+            cweights: vec![1.0; 6],
+        });
+
+        let bcurve_opts = BcurveViewOptions {
+            method: CurveViewMethod::Uniform,
+            num_div: 100,
+            color: CurveColor::Solid(Color::Red),
+            with_param_pts: false,
+            with_ctrl_pts: CtrlPointOptions::WithPts(Color::Green),
+            ..Default::default()
+        };
+        bcurve.view(50051, &bcurve_opts);
+
+        let abox_opts = ABoxViewOptions {
+            color: Color::White,
+        };
+        let mut abox = bcurve.get_box().clone();
+        abox.view(50051, &abox_opts);
+    }
+}
 
 fn plane_view()
 {
@@ -143,13 +179,16 @@ fn main()
 {
     init().unwrap();
 
+    // pass d2 to view the planar curve and its box, otherwise the 3D items are viewed
+    let is_2d = std::env::args().nth(1).as_deref() == Some("d2");
+
     let topoviewer_exec_result = locate_executable();
     if let Ok(topoviewer_exec) = topoviewer_exec_result {
         //{{{ trace
         info!("Found topoviewer executable at {:?}", topoviewer_exec);
         //}}}
         let _server_process = Command::new(topoviewer_exec)
-            .arg("d3")
+            .arg(if is_2d { "d2" } else { "d3" })
             .arg("with-port")
             .arg("50051")
             .spawn()
@@ -160,6 +199,15 @@ fn main()
         //}}}
         std::thread::sleep(std::time::Duration::from_millis(2000));
 
+        if is_2d
+        {
+            //{{{ trace
+            info!("Adding a 2D bcurve with its box");
+            //}}}
+            bcurve_2d_view();
+            return;
+        }
+
         axes_view();
         //{{{ trace
         info!("Server process running");
@@ -1,4 +1,4 @@
-use crate::common::Vector;
+use crate::common::{Mat3, Mat4};
 use crate::boxing::common::{ABox, ABoxable};
 use crate::geometry::{Curve, CurveMinValOpts};
 use crate::geometry::{Bcurve, BCURVE_DER_MAX};
@@ -6,6 +6,11 @@ use crate::geometry::{Bcurve, BCURVE_DER_MAX};
 use topohedral_integrate::gauss;
 use topohedral_optimisation::d1::{minimize_scalar, 
     MinimizeScalarOptions, MinimizeScalarReturns, Method, Bounds};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Number of samples per knot span used to bracket the extremes of the curve in `extent_sampled`
+const BOX_SAMPLES_PER_SPAN: usize = 8;
 
 
 //{{{ impl<const D: usize> Bcurve<D>
@@ -14,22 +19,19 @@ where
     [(); D + 1]:,
     [(); D * BCURVE_DER_MAX]:,
     [(); D * 3]:,
-    [(); D * 2]:,
 {
-
-    fn compute_box(&mut self)
+    //{{{ fun: knot_extremes
+    /// Finds, for each dimension, the indices of the knots at which the curve takes its smallest 
+    /// and largest values, these bracket the extremes of the curve.
+    fn knot_extremes(&self) -> ([usize; D], [usize; D])
     {
-        //{{{ locals
         let mut mins = [std::f64::MAX; D];
         let mut mins_idx = [0; D];
         let mut maxs = [std::f64::MIN; D];
         let mut max_idx = [0; D];
-        let mut min_vals = [0.0; D];
-        let mut max_vals = [0.0; D];
         let p = self.p();
         let knots = self.knots();
-        //}}}
-        //{{{ com: find the min and max values at knots in each dimension
+
         let n = knots.len();
         let start = p;
         let end = n - p;
@@ -51,7 +53,18 @@ where
                 }
             }
         }
-        //}}}
+        (mins_idx, max_idx)
+    }
+    //}}}
+    //{{{ fun: extent
+    /// Computes the minimum and maximum of the curve in dimension `j`, by minimisation over the 
+    /// knot intervals either side of the bracketing knots `min_idx` and `max_idx`.
+    fn extent(&self, j: usize, min_idx: usize, max_idx: usize) -> (f64, f64)
+    {
+        let knots = self.knots();
+        let n = knots.len();
+        let start = self.p();
+
         //{{{ com: create minimisation options struct, initialise it to use the bounded 1D method
         let mut min_scal_opts = CurveMinValOpts{
             tol: 1e-8,
@@ -59,65 +72,176 @@ where
             bounds: None,
         };
         //}}}
-        //{{{ com: For each dimension, bracket the min/max and then perform the minimisation. 
-        for j in 0..D 
+        //{{{ com: min for dimension j
+        let min_interval = match min_idx
+        {
+            0 => {
+                (knots[start], knots[start + 2])
+            }, 
+            _ if min_idx == n-1 => {
+
+                (knots[n-2], knots[n-1])
+            }, 
+            _ => {
+                (knots[start + min_idx - 1], knots[start + min_idx + 1])
+            }
+        };
+
+        let fmin = |u| {
+            let xi = self.eval(u);
+            xi[j]
+        };
+
+        min_scal_opts.bounds = Some(min_interval);
+        let min_res = self.min_value_scalar(fmin, &min_scal_opts);
+        //}}}
+        //{{{ com: max for dimension j
+        let max_interval = match max_idx
+        {
+            0 => {
+                (knots[start], knots[start + 2])
+            }, 
+            _ if max_idx == n-1 => {
+
+                (knots[n-2], knots[n-1])
+            }, 
+            _ => {
+                (knots[start + max_idx - 1], knots[start + max_idx + 1])
+            }
+        };
+        min_scal_opts.bounds = Some(max_interval);
+
+        let fmax = |u| {
+            let xi = self.eval(u);
+            -xi[j]
+        };
+
+        let max_res = self.min_value_scalar(fmax, &min_scal_opts);
+        //}}}
+        (min_res.1, -max_res.1)
+    }
+    //}}}
+    //{{{ fun: extent_sampled
+    /// Computes the minimum and maximum of the curve in dimension `j` by sampling each knot span
+    /// and refining every sample which is a local extreme by minimisation between its neighbours.
+    ///
+    /// Unlike [Bcurve::extent] this does not assume that the extremes lie next to the extreme 
+    /// knots, so it also finds the extremes of curves which oscillate within their spans.
+    fn extent_sampled(&self, j: usize) -> (f64, f64)
+    {
+        let knots = self.knots();
+        let mut samples = Vec::new();
+        for span in knots.windows(2).filter(|span| span[0] < span[1])
         {
-            //{{{ com: min for dimension j
-            let min_idx = mins_idx[j];
-            let min_interval = match min_idx
+            for i in 0..BOX_SAMPLES_PER_SPAN
             {
-                0 => {
-                    (knots[start], knots[start + 2])
-                }, 
-                _ if min_idx == n-1 => {
-
-                    (knots[n-2], knots[n-1])
-                }, 
-                _ => {
-                    (knots[start + min_idx - 1], knots[start + min_idx + 1])
-                }
-            };
-
-            let fmin = |u| {
-                let xi = self.eval(u);
-                xi[j]
-            };
-
-            min_scal_opts.bounds = Some(min_interval);
-            let min_res = self.min_value_scalar(fmin, &min_scal_opts);
-            min_vals[j] = min_res.1;
-            //}}}
-            //{{{ com: max for dimension j
-            let max_idx = max_idx[j];
-            let max_interval = match max_idx
+                samples.push(span[0] + (span[1] - span[0]) * i as f64 / BOX_SAMPLES_PER_SPAN as f64);
+            }
+        }
+        samples.push(*knots.last().unwrap());
+        let vals: Vec<f64> = samples.iter().map(|u| self.eval(*u)[j]).collect();
+
+        let mut opts = CurveMinValOpts{
+            tol: 1e-8,
+            max_iter: 100,
+            bounds: None,
+        };
+        let n = samples.len();
+        let (mut min_val, mut max_val) = (f64::MAX, f64::MIN);
+        for i in 0..n
+        {
+            let (lo, hi) = (i.saturating_sub(1), (i + 1).min(n - 1));
+            opts.bounds = Some((samples[lo], samples[hi]));
+            if vals[i] <= vals[lo] && vals[i] <= vals[hi]
             {
-                0 => {
-                    (knots[start], knots[start + 2])
-                }, 
-                _ if max_idx == n-1 => {
-
-                    (knots[n-2], knots[n-1])
-                }, 
-                _ => {
-                    (knots[start + max_idx - 1], knots[start + max_idx + 1])
-                }
-            };
-            min_scal_opts.bounds = Some(max_interval);
+                let res = self.min_value_scalar(|u| self.eval(u)[j], &opts);
+                min_val = min_val.min(vals[i]).min(res.1);
+            }
+            if vals[i] >= vals[lo] && vals[i] >= vals[hi]
+            {
+                let res = self.min_value_scalar(|u| -self.eval(u)[j], &opts);
+                max_val = max_val.max(vals[i]).max(-res.1);
+            }
+        }
+        (min_val, max_val)
+    }
+    //}}}
+    //{{{ fun: compute_box_serial
+    /// Computes the bounding box one dimension at a time.
+    #[cfg_attr(feature = "parallel", allow(dead_code))]
+    fn compute_box_serial(&self) -> ABox<D>
+    {
+        let (mins_idx, max_idx) = self.knot_extremes();
+        let mut min_vals = [0.0; D];
+        let mut max_vals = [0.0; D];
+        for j in 0..D 
+        {
+            (min_vals[j], max_vals[j]) = self.extent(j, mins_idx[j], max_idx[j]);
+        }
+        ABox::new(min_vals, max_vals)
+    }
+    //}}}
+    //{{{ fun: compute_box_parallel
+    /// Computes the bounding box with the dimensions handled in parallel, the extent in each 
+    /// dimension is independent of the others and only reads the curve.
+    #[cfg(feature = "parallel")]
+    fn compute_box_parallel(&self) -> ABox<D>
+    {
+        let (mins_idx, max_idx) = self.knot_extremes();
+        let extents: Vec<(f64, f64)> = (0..D)
+            .into_par_iter()
+            .map(|j| self.extent(j, mins_idx[j], max_idx[j]))
+            .collect();
+        let mut min_vals = [0.0; D];
+        let mut max_vals = [0.0; D];
+        for j in 0..D 
+        {
+            (min_vals[j], max_vals[j]) = extents[j];
+        }
+        ABox::new(min_vals, max_vals)
+    }
+    //}}}
+}
+//}}}
+//{{{ impl Bcurve<3>
+impl Bcurve<3>
+{
+    //{{{ fun: obb
+    /// Computes an oriented bounding box of the curve.
+    ///
+    /// The axes of the box are the principal axes of the control points, see
+    /// [Bcurve::control_point_pca], and the curve is boxed tightly in the frame of these axes. For curves
+    /// which lie diagonally to the coordinate axes this is much tighter than the axis-aligned box.
+    /// The extents in the local frame are found by [Bcurve::extent_sampled], since a curve which is 
+    /// thin across its principal axis is likely to oscillate across it within its spans.
+    ///
+    /// # Returns
+    /// A tuple of the rotation $R$, whose columns are the axes of the box, and the box in local 
+    /// coordinates, so that a point $\mathbf{x}$ lies in the box when $R^{T} \mathbf{x}$ lies in
+    /// the local box.
+    pub fn obb(&self) -> (Mat3, ABox<3>)
+    {
+        let (_, axes, _) = self.control_point_pca();
+        let mut rotation = Mat3::from_columns(&axes);
+        if rotation.determinant() < 0.0
+        {
+            rotation.set_column(2, &(-rotation.column(2)));
+        }
 
-            let fmax = |u| {
-                let xi = self.eval(u);
-                -xi[j]
-            };
+        let mut to_local = Mat4::identity();
+        to_local.fixed_view_mut::<3, 3>(0, 0).copy_from(&rotation.transpose());
+        let mut local = self.clone();
+        local.transform(&to_local);
 
-            let max_res = self.min_value_scalar(fmax, &min_scal_opts);
-            max_vals[j] = -max_res.1;
-            //}}}
+        let mut min_vals = [0.0; 3];
+        let mut max_vals = [0.0; 3];
+        for j in 0..3
+        {
+            (min_vals[j], max_vals[j]) = local.extent_sampled(j);
         }
-        //}}}
-        //{{{ com: Finally, assign to the abox field:
-        self.abox = Some(ABox::new(min_vals, max_vals));
-        //}}}
+        (rotation, ABox::new(min_vals, max_vals))
     }
+    //}}}
 }
 //}}}
 //{{{ impl<const D: usize> ABoxable<D> for Bcurve<D>
@@ -126,22 +250,28 @@ where
     [(); D + 1]:,
     [(); D * BCURVE_DER_MAX]:,
     [(); D * 3]:,
-    [(); D * 2]:,
 {
 
-    fn get_box(&mut self) -> &ABox<D>
+    fn compute_box(&self) -> ABox<D>
     {
-        if self.abox.is_none() 
+        #[cfg(feature = "parallel")]
+        {
+            self.compute_box_parallel()
+        }
+        #[cfg(not(feature = "parallel"))]
         {
-            self.compute_box();
+            self.compute_box_serial()
         }
-        self.abox.as_ref().unwrap()
+    }
+    //..............................................................................................
+
+    fn box_cache(&mut self) -> &mut Option<ABox<D>>
+    {
+        &mut self.abox
     }
 }
 //..................................................................................................
 //}}}
-
-
 //-------------------------------------------------------------------------------------------------
 //{{{ mod: tests
 #[cfg(test)]
@@ -151,6 +281,8 @@ mod tests
     use super::*;
     use approx::{assert_abs_diff_eq, assert_relative_eq, ulps_eq, AbsDiff};
 
+    use crate::common::Vector;
+    use crate::geometry::BcurveDescriptor;
     use crate::test_utils::test_bcurve::{TestData, load_bcurve};
 
 
@@ -177,5 +309,185 @@ mod tests
 
     }
     //..............................................................................................
+
+    #[test]
+    fn abox_2d_test()
+    {
+        // a loop with a single extreme in each direction, each inside a knot span
+        let mut bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.2, 0.4, 0.6, 0.8, 1.0, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vector::<2>::new(3.0, 0.0),
+                Vector::<2>::new(6.0, -1.0),
+                Vector::<2>::new(7.0, 2.0),
+                Vector::<2>::new(5.0, 5.0),
+                Vector::<2>::new(1.0, 5.0),
+                Vector::<2>::new(-1.0, 2.0),
+                Vector::<2>::new(1.0, 0.5),
+                Vector::<2>::new(3.5, 0.5),
+            ],
+            cweights: vec![1.0, 1.0, 2.0, 1.0, 1.0, 0.5, 1.0, 1.0],
+        });
+        let abox = bcurve.get_box().clone();
+
+        // every sample lies in the box, and the box is no larger than the samples need
+        let n = 1000;
+        let mut lo = [f64::MAX; 2];
+        let mut hi = [f64::MIN; 2];
+        for i in 0..=n
+        {
+            let point = bcurve.eval(i as f64 / n as f64);
+            for j in 0..2
+            {
+                assert!(point[j] >= abox.min(j) - 1e-6 && point[j] <= abox.max(j) + 1e-6);
+                lo[j] = lo[j].min(point[j]);
+                hi[j] = hi[j].max(point[j]);
+            }
+        }
+        for j in 0..2
+        {
+            assert_relative_eq!(abox.min(j), lo[j], epsilon = 1e-4);
+            assert_relative_eq!(abox.max(j), hi[j], epsilon = 1e-4);
+        }
+    }
+    //..............................................................................................
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_box_test()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let serial = bcurve.compute_box_serial();
+        let parallel = bcurve.compute_box_parallel();
+        for j in 0..3
+        {
+            assert_eq!(serial.min(j), parallel.min(j));
+            assert_eq!(serial.max(j), parallel.max(j));
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn obb_test()
+    {
+        // a curve wiggling about the diagonal of the unit cube
+        let cpoints: Vec<Vector<3>> = (0..8)
+            .map(|i| {
+                let t = i as f64;
+                Vector::<3>::new(t, t, t) + Vector::<3>::new(1.0, -1.0, 0.0) * 0.2 * (-1.0f64).powi(i)
+            })
+            .collect();
+        let mut bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.2, 0.4, 0.6, 0.8, 1.0, 1.0, 1.0, 1.0],
+            cweights: vec![1.0; 8],
+            cpoints,
+        });
+
+        let (rotation, obb) = bcurve.obb();
+        assert_relative_eq!(rotation.transpose() * rotation, Mat3::identity(), epsilon = 1e-12);
+        assert_relative_eq!(rotation.determinant(), 1.0, epsilon = 1e-12);
+        assert!(obb.measure() < 0.1 * bcurve.get_box().measure());
+
+        for i in 0..=200
+        {
+            let x = rotation.transpose() * bcurve.eval(i as f64 / 200.0);
+            for j in 0..3
+            {
+                assert!(x[j] >= obb.min(j) - 1e-9 && x[j] <= obb.max(j) + 1e-9);
+            }
+        }
+
+        // the corners of the oriented box rotated into place bound the curve in world coordinates
+        let world = ABox::<3>::from_points(&obb.corners().map(|c| rotation * c));
+        for i in 0..=200
+        {
+            let x = bcurve.eval(i as f64 / 200.0);
+            for j in 0..3
+            {
+                assert!(x[j] >= world.min(j) - 1e-9 && x[j] <= world.max(j) + 1e-9);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn invalidate_box_test()
+    {
+        let test_data = TestData::new();
+        let mut bcurve = load_bcurve::<3>(3, &test_data);
+        let xmax = bcurve.get_box().xmax();
+
+        // a stale cache is returned until it is invalidated
+        bcurve.abox = Some(ABox::<3>::new([0.0; 3], [1.0; 3]));
+        assert_eq!(bcurve.get_box().xmax(), 1.0);
+        bcurve.invalidate_box();
+        assert_relative_eq!(bcurve.get_box().xmax(), xmax, epsilon = 1e-12);
+
+        // moving a control point invalidates the box, pulling an interior one far out moves the
+        // largest x inside the curve
+        let i = bcurve.cpoints().len() / 2;
+        let mut cpoint = bcurve.cpoints()[i];
+        cpoint[0] = xmax + 5.0;
+        bcurve.set_cpoint(i, &cpoint);
+        assert!(bcurve.abox.is_none());
+
+        // the new largest x, by Newton's method on the x derivative from the best of the samples
+        let n = 1000;
+        let mut u = (0..=n)
+            .map(|k| k as f64 / n as f64)
+            .fold(0.0, |best, u| if bcurve.eval(u)[0] > bcurve.eval(best)[0] { u } else { best });
+        for _ in 0..20
+        {
+            u -= bcurve.eval_diff(u, 1)[0] / bcurve.eval_diff(u, 2)[0];
+        }
+        let expected = bcurve.eval(u)[0];
+        assert!(expected > xmax && 0.0 < u && u < 1.0);
+        assert_relative_eq!(bcurve.get_box().xmax(), expected, epsilon = 1e-10);
+        assert_relative_eq!(bcurve.recompute_box().xmax(), expected, epsilon = 1e-10);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn transform_box_test()
+    {
+        let test_data = TestData::new();
+        let mut bcurve = load_bcurve::<3>(3, &test_data);
+        bcurve.get_box();
+
+        let (s, c) = 0.3f64.sin_cos();
+        let m = Mat4::new(
+            c,  -s,  0.0, 1.0,
+            s,   c,  0.0, -2.0,
+            0.0, 0.0, 1.0, 0.5,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        let samples_before: Vec<Vector<3>> = test_data.u.values.iter().map(|u| bcurve.eval(*u)).collect();
+        bcurve.transform(&m);
+
+        // the conservative box is available without recomputation
+        let conservative = bcurve.abox.clone().unwrap();
+        for (u, x) in test_data.u.values.iter().zip(samples_before.iter())
+        {
+            let y = bcurve.eval(*u);
+            assert_relative_eq!(y, m.transform_point(&(*x).into()).coords, epsilon = 1e-12);
+            for i in 0..3
+            {
+                assert!(y[i] >= conservative.min(i) - 1e-12 && y[i] <= conservative.max(i) + 1e-12);
+            }
+        }
+
+        // the tight box is contained within the conservative one
+        let tight = bcurve.recompute_box().clone();
+        for i in 0..3
+        {
+            assert!(tight.min(i) >= conservative.min(i) - 1e-9);
+            assert!(tight.max(i) <= conservative.max(i) + 1e-9);
+        }
+        assert!(tight.measure() < conservative.measure());
+    }
+    //..............................................................................................
 }
 //}}}
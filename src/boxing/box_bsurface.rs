@@ -0,0 +1,181 @@
+//! This module contains the bounding boxes of B-spline surfaces.
+//!
+//! The box is found by subdividing the surface. By the convex hull property the control points of
+//! a patch bound it, and they converge onto the patch as it shrinks, while the corners of a clamped
+//! patch lie on the surface. So in each dimension the extreme control point of a patch bounds the
+//! extreme of the patch from outside, its corners bound it from inside, and only those patches
+//! whose outer bound improves on the best corner found so far need to be subdivided further.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::common::{ABox, ABoxable};
+use crate::geometry::{Bsurface, BSURFACE_DER_MAX};
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Tolerance, relative to the diameter of the control box, to which the extents are found
+const BOX_SUBDIV_TOL: f64 = 1e-6;
+/// Maximum depth of the subdivision in `extent_subdivided`
+const BOX_SUBDIV_DEPTH_MAX: usize = 48;
+//}}}
+//{{{ impl<const D: usize> Bsurface<D>
+impl<const D: usize> Bsurface<D>
+where
+    [(); D + 1]:,
+    [(); D * BSURFACE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    //{{{ fun: extent_subdivided
+    /// Computes the minimum over the surface of `sign` times its coordinate in dimension `j`, so
+    /// that a `sign` of $-1$ gives the negated maximum.
+    ///
+    /// The result is a lower bound which lies within `tol` of the true minimum, it is the smallest
+    /// control point bound of the patches at which the subdivision stopped.
+    fn extent_subdivided(
+        &self,
+        j: usize,
+        sign: f64,
+        tol: f64,
+    ) -> f64
+    {
+        let outer = |patch: &Bsurface<D>| {
+            patch.cpoints().iter().map(|x| sign * x[j]).fold(f64::MAX, f64::min)
+        };
+        let inner = |patch: &Bsurface<D>| {
+            let (r, s) = patch.net_dims();
+            let cpoints = patch.cpoints();
+            [0, r - 1, r * (s - 1), r * s - 1].iter().map(|i| sign * cpoints[*i][j]).fold(f64::MAX, f64::min)
+        };
+
+        let mut best = inner(self);
+        let mut bound = f64::MAX;
+        let mut stack = vec![(self.clone(), 0)];
+        while let Some((patch, depth)) = stack.pop()
+        {
+            let lower = outer(&patch);
+            if lower >= best - tol || depth == BOX_SUBDIV_DEPTH_MAX
+            {
+                bound = bound.min(lower);
+                continue;
+            }
+
+            // split across the u and v directions in turn
+            let (left, right) = if depth % 2 == 0
+            {
+                let (u0, u1) = patch.param_range_u();
+                patch.split_u(0.5 * (u0 + u1))
+            }
+            else
+            {
+                let (v0, v1) = patch.param_range_v();
+                patch.split_v(0.5 * (v0 + v1))
+            };
+            for half in [left, right]
+            {
+                best = best.min(inner(&half));
+                stack.push((half, depth + 1));
+            }
+        }
+        bound
+    }
+    //}}}
+}
+//}}}
+//{{{ impl<const D: usize> ABoxable<D> for Bsurface<D>
+impl<const D: usize> ABoxable<D> for Bsurface<D>
+where
+    [(); D + 1]:,
+    [(); D * BSURFACE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    /// Computes a box containing the surface which is tight to within a small fraction of the
+    /// size of its control box, [Bsurface::control_box] is the cheaper but looser alternative.
+    fn compute_box(&self) -> ABox<D>
+    {
+        let tol = BOX_SUBDIV_TOL * self.control_box().diameter();
+        let mut min_vals = [0.0; D];
+        let mut max_vals = [0.0; D];
+        for j in 0..D
+        {
+            min_vals[j] = self.extent_subdivided(j, 1.0, tol);
+            max_vals[j] = -self.extent_subdivided(j, -1.0, tol);
+        }
+        ABox::new(min_vals, max_vals)
+    }
+    //..............................................................................................
+
+    fn box_cache(&mut self) -> &mut Option<ABox<D>>
+    {
+        &mut self.abox
+    }
+}
+//}}}
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::Vector;
+    use crate::geometry::{BsurfaceDescriptor, Surface};
+
+    #[test]
+    fn abox_test()
+    {
+        // a rational bicubic patch with a bump rising from a saddle
+        let cpoints: Vec<Vector<3>> = (0..36)
+            .map(|idx| {
+                let (x, y) = ((idx % 6) as f64, (idx / 6) as f64);
+                let bump = if (2..4).contains(&(idx % 6)) && (2..4).contains(&(idx / 6)) { 3.0 } else { 0.0 };
+                Vector::<3>::new(x, y, 0.1 * (x - 2.5) * (y - 2.5) + bump)
+            })
+            .collect();
+        let knots = vec![0.0, 0.0, 0.0, 0.0, 0.4, 0.6, 1.0, 1.0, 1.0, 1.0];
+        let mut bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 3,
+            q: 3,
+            knots_u: knots.clone(),
+            knots_v: knots,
+            cpoints,
+            cweights: (0..36).map(|idx| 1.0 + 0.2 * (idx % 4) as f64).collect(),
+        });
+
+        let control = bsurf.control_box();
+        let tight = bsurf.get_box().clone();
+        let tol = 1e-6 * control.diameter();
+
+        // the tight box lies within the control box, and is tighter over the bump
+        for j in 0..3
+        {
+            assert!(tight.min(j) >= control.min(j) && tight.max(j) <= control.max(j));
+        }
+        assert!(tight.zmax() < control.zmax() - 0.1);
+
+        // and contains the surface while reaching its extremes, up to the spacing of the samples
+        let mut sampled = ([f64::MAX; 3], [f64::MIN; 3]);
+        for i in 0..=100
+        {
+            for k in 0..=100
+            {
+                let x = bsurf.eval(i as f64 / 100.0, k as f64 / 100.0);
+                for j in 0..3
+                {
+                    assert!(x[j] >= tight.min(j) - 1e-12 && x[j] <= tight.max(j) + 1e-12);
+                    sampled.0[j] = sampled.0[j].min(x[j]);
+                    sampled.1[j] = sampled.1[j].max(x[j]);
+                }
+            }
+        }
+        for j in 0..3
+        {
+            assert!(sampled.0[j] - tight.min(j) < tol + 1e-3);
+            assert!(tight.max(j) - sampled.1[j] < tol + 1e-3);
+        }
+    }
+}
+//}}}
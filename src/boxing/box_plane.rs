@@ -0,0 +1,77 @@
+use crate::boxing::common::{ABox, ABoxable};
+use crate::geometry::Plane;
+
+//{{{ struct: BoundedPlane
+/// A plane restricted to a rectangle of its parameter space.
+///
+/// A [Plane] is infinite and so has no bounding box of its own, this gives it the bounds needed
+/// to take part in scene boxes and spatial trees.
+pub struct BoundedPlane<'a>
+{
+    pub plane: &'a Plane,
+    pub u_range: (f64, f64),
+    pub v_range: (f64, f64),
+    abox: Option<ABox<3>>,
+}
+//}}}
+//{{{ impl BoundedPlane
+impl<'a> BoundedPlane<'a>
+{
+    pub fn new(plane: &'a Plane, u_range: (f64, f64), v_range: (f64, f64)) -> Self
+    {
+        Self {
+            plane,
+            u_range,
+            v_range,
+            abox: None,
+        }
+    }
+}
+//}}}
+//{{{ impl ABoxable<3> for BoundedPlane
+impl ABoxable<3> for BoundedPlane<'_>
+{
+    fn compute_box(&self) -> ABox<3>
+    {
+        self.plane.box_over(self.u_range, self.v_range)
+    }
+    //..............................................................................................
+
+    fn box_cache(&mut self) -> &mut Option<ABox<3>>
+    {
+        &mut self.abox
+    }
+}
+//..................................................................................................
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::Vec3;
+    use crate::geometry::{PlaneDescriptor, Surface};
+
+    #[test]
+    fn bounded_plane_box_test()
+    {
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(1.0, 0.0, 0.0),
+            x: Vec3::new(1.0, 0.0, 0.0),
+            y: Vec3::new(0.0, 1.0, 0.0),
+        });
+        let mut bounded = BoundedPlane::new(&plane, (0.0, 2.0), (-1.0, 3.0));
+        let abox = bounded.get_box().clone();
+        assert_eq!(abox.min(0), plane.eval(0.0, -1.0).x);
+        assert_eq!(abox.max(0), plane.eval(2.0, 3.0).x);
+        assert_eq!(abox.min(1), -1.0);
+        assert_eq!(abox.max(1), 3.0);
+        assert_eq!(abox.length(2), 0.0);
+
+        bounded.u_range = (0.0, 4.0);
+        assert_eq!(bounded.recompute_box().max(0), 5.0);
+    }
+}
+//}}}
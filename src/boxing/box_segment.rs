@@ -0,0 +1,81 @@
+//! This module contains the bounding boxes of line segments.
+//!
+//! A segment is the convex hull of its end points, so its box is exactly the box of the two.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::common::{ABox, ABoxable};
+use crate::geometry::Segment;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ impl<const D: usize> ABoxable<D> for Segment<D>
+impl<const D: usize> ABoxable<D> for Segment<D>
+{
+    fn compute_box(&self) -> ABox<D>
+    {
+        ABox::from_points(&[self.start(), self.end()])
+    }
+    //..............................................................................................
+
+    fn box_cache(&mut self) -> &mut Option<ABox<D>>
+    {
+        &mut self.abox
+    }
+}
+//}}}
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::{Mat4, Vec3};
+    use crate::geometry::{Line, LineDescriptor, SegmentDescriptor};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn segment_box_test()
+    {
+        let mut segment = Segment::new(&SegmentDescriptor {
+            start: Vec3::new(1.0, -2.0, 3.0),
+            end: Vec3::new(-1.0, 4.0, 3.5),
+        });
+        let abox = segment.get_box().clone();
+        assert_eq!((abox.xmin(), abox.ymin(), abox.zmin()), (-1.0, -2.0, 3.0));
+        assert_eq!((abox.xmax(), abox.ymax(), abox.zmax()), (1.0, 4.0, 3.5));
+
+        // the box follows the segment when it is transformed
+        let m = Mat4::new(
+            0.0, -1.0, 0.0, 2.0,
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, -1.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        segment.transform(&m);
+        let abox = segment.get_box().clone();
+        let expected = ABox::<3>::from_points(&[segment.start(), segment.end()]);
+        for i in 0..3
+        {
+            assert_eq!(abox.min(i), expected.min(i));
+            assert_eq!(abox.max(i), expected.max(i));
+        }
+        assert_relative_eq!(abox.xmin(), -2.0, epsilon = 1e-14);
+        assert_relative_eq!(abox.ymax(), 1.0, epsilon = 1e-14);
+        assert_relative_eq!(abox.zmax(), 2.5, epsilon = 1e-14);
+
+        // an unbounded line is boxed through a segment over a parameter range
+        let line = Line::new(&LineDescriptor {
+            origin: Vec3::new(0.0, 0.0, 0.0),
+            dir: Vec3::new(0.6, 0.0, -0.8),
+        });
+        let mut piece = line.segment(-5.0, 5.0);
+        assert_relative_eq!(piece.get_box().xmax(), 3.0, epsilon = 1e-14);
+        assert_relative_eq!(piece.get_box().zmin(), -4.0, epsilon = 1e-14);
+    }
+}
+//}}}
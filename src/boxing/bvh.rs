@@ -0,0 +1,195 @@
+//! This module contains a bounding volume hierarchy over a set of axis-aligned boxes.
+//!
+//! The hierarchy is a binary tree whose nodes each store the box containing all of the items
+//! beneath them, queries descend only into nodes whose box passes a test, so that the items which
+//! cannot pass it are culled in bulk. It is used to accelerate ray casting and overlap queries
+//! against collections of objects such as the faces of a mesh.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::common::ABox;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Maximum number of items stored in a leaf of the hierarchy
+const BVH_LEAF_SIZE: usize = 4;
+//}}}
+//{{{ enum: BvhNode
+#[derive(Debug, Clone)]
+enum BvhNode<const D: usize>
+{
+    /// A leaf holding the items `items[start..start + count]` of the hierarchy
+    Leaf
+    {
+        abox: ABox<D>,
+        start: usize,
+        count: usize,
+    },
+    /// An inner node with the indices of its two children
+    Inner
+    {
+        abox: ABox<D>,
+        left: usize,
+        right: usize,
+    },
+}
+//}}}
+//{{{ struct: Bvh
+/// A bounding volume hierarchy over a set of boxes, each identified by its index in the slice
+/// the hierarchy was built from.
+#[derive(Debug, Clone)]
+pub struct Bvh<const D: usize>
+{
+    nodes: Vec<BvhNode<D>>,
+    items: Vec<usize>,
+    boxes: Vec<ABox<D>>,
+}
+//}}}
+//{{{ impl: Bvh
+impl<const D: usize> Bvh<D>
+{
+    //{{{ fun: new
+    /// Builds the hierarchy over `boxes` top-down, splitting each node at the median of the box
+    /// centers along the dimension in which the centers are most spread.
+    pub fn new(boxes: &[ABox<D>]) -> Self
+    {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            items: (0..boxes.len()).collect(),
+            boxes: boxes.to_vec(),
+        };
+        if !boxes.is_empty()
+        {
+            bvh.build(boxes, 0, boxes.len());
+        }
+        bvh
+    }
+    //}}}
+    //{{{ fun: build
+    /// Builds the node over the items `items[start..end]` and returns its index.
+    fn build(
+        &mut self,
+        boxes: &[ABox<D>],
+        start: usize,
+        end: usize,
+    ) -> usize
+    {
+        let abox = self.items[start + 1..end]
+            .iter()
+            .fold(boxes[self.items[start]].clone(), |acc, i| acc.merge(&boxes[*i]));
+
+        if end - start <= BVH_LEAF_SIZE
+        {
+            self.nodes.push(BvhNode::Leaf { abox, start, count: end - start });
+            return self.nodes.len() - 1;
+        }
+
+        //{{{ com: split at the median center along the dimension of greatest spread
+        let centers = ABox::from_points(
+            &self.items[start..end].iter().map(|i| boxes[*i].center()).collect::<Vec<_>>(),
+        );
+        let dim = (0..D)
+            .max_by(|a, b| centers.length(*a).partial_cmp(&centers.length(*b)).unwrap())
+            .unwrap();
+        let mid = (start + end) / 2;
+        self.items[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            boxes[*a].center()[dim].partial_cmp(&boxes[*b].center()[dim]).unwrap()
+        });
+        //}}}
+
+        // reserve the slot of this node before its children are pushed
+        let node = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf { abox: abox.clone(), start, count: 0 });
+        let left = self.build(boxes, start, mid);
+        let right = self.build(boxes, mid, end);
+        self.nodes[node] = BvhNode::Inner { abox, left, right };
+        node
+    }
+    //}}}
+    //{{{ fun: query
+    /// Returns the indices of the boxes which pass `test`, found by descending only into the nodes
+    /// which pass it.
+    ///
+    /// The test must be conservative, any box which contains a box passing the test must also pass
+    /// it, as is the case for overlap and ray intersection tests.
+    pub fn query<F: Fn(&ABox<D>) -> bool>(&self, test: F) -> Vec<usize>
+    {
+        let mut found = Vec::new();
+        if self.nodes.is_empty()
+        {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop()
+        {
+            match &self.nodes[node]
+            {
+                BvhNode::Leaf { abox, start, count } =>
+                {
+                    if test(abox)
+                    {
+                        found.extend(
+                            self.items[*start..*start + *count].iter().filter(|i| test(&self.boxes[**i])),
+                        );
+                    }
+                }
+                BvhNode::Inner { abox, left, right } =>
+                {
+                    if test(abox)
+                    {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        }
+        found
+    }
+    //}}}
+    //{{{ fun: len
+    /// Returns the number of boxes in the hierarchy
+    pub fn len(&self) -> usize { self.items.len() }
+    //}}}
+    //{{{ fun: is_empty
+    pub fn is_empty(&self) -> bool { self.items.is_empty() }
+    //}}}
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn query_test()
+    {
+        // a 10 x 10 grid of unit boxes with unit gaps between them
+        let boxes: Vec<ABox<2>> = (0..100)
+            .map(|k| {
+                let (i, j) = ((k % 10) as f64, (k / 10) as f64);
+                ABox::new([2.0 * i, 2.0 * j], [2.0 * i + 1.0, 2.0 * j + 1.0])
+            })
+            .collect();
+        let bvh = Bvh::new(&boxes);
+        assert_eq!(bvh.len(), 100);
+
+        let window = ABox::<2>::new([2.5, 4.5], [6.5, 6.5]);
+        let mut found = bvh.query(|abox| abox.distance(&window) == 0.0);
+        found.sort();
+        let expected: Vec<usize> = (0..100).filter(|k| boxes[*k].distance(&window) == 0.0).collect();
+        assert_eq!(found, expected);
+        assert_eq!(found.len(), 6);
+
+        assert!(Bvh::<2>::new(&[]).query(|_| true).is_empty());
+    }
+}
+//}}}
@@ -4,7 +4,8 @@
 //--------------------------------------------------------------------------------------------------
 
 //{{{ crate imports 
-use crate::common::Vector;
+use crate::common::{Mat3, Mat4, Vector};
+use crate::geometry::SignedDistance;
 //}}}
 //{{{ std imports 
 //}}}
@@ -33,6 +34,20 @@ impl<const D: usize>  ABox<D>
         }
     }
     //}}}
+    //{{{ fun: from_points
+    /// Constructs the smallest box containing all of the given points.
+    pub fn from_points(points: &[Vector<D>]) -> Self
+    {
+        debug_assert!(!points.is_empty());
+
+        let mut abox = Self { min: [f64::MAX; D], max: [f64::MIN; D] };
+        for point in points
+        {
+            abox.expand_to_include(point);
+        }
+        abox
+    }
+    //}}}
     //{{{ fun min
     pub fn min(&self, i: usize) -> f64 { self.min[i] }  
     //}}}
@@ -67,8 +82,105 @@ impl<const D: usize>  ABox<D>
         origin
     }
     //}}}
+    //{{{ fun: distance
+    /// Computes the minimum distance between the points of this box and those of `other`, this is
+    /// zero if the boxes overlap.
+    pub fn distance(&self, other: &ABox<D>) -> f64
+    {
+        let mut dist = 0.0f64;
+        for i in 0..D
+        {
+            let gap = (self.min[i] - other.max[i]).max(other.min[i] - self.max[i]).max(0.0);
+            dist += gap * gap;
+        }
+        dist.sqrt()
+    }
+    //}}}
+    //{{{ fun: intersects
+    /// Returns true if this box and `other` overlap, boxes which only touch are considered to
+    /// overlap.
+    pub fn intersects(&self, other: &ABox<D>) -> bool
+    {
+        (0..D).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+    //}}}
+    //{{{ fun: corners
+    /// Returns the $2^{D}$ corners of the box, corner `k` takes the max value in dimension `i` if
+    /// bit `i` of `k` is set and the min value otherwise.
+    pub fn corners(&self) -> [Vector<D>; 1 << D]
+    where
+        [(); 1 << D]:,
+    {
+        std::array::from_fn(|k| {
+            let mut corner = Vector::<D>::zeros();
+            for i in 0..D
+            {
+                corner[i] = if (k >> i) & 1 == 1 { self.max[i] } else { self.min[i] };
+            }
+            corner
+        })
+    }
+    //}}}
+    //{{{ fun: merge
+    /// Returns the smallest box containing both this box and `other`.
+    pub fn merge(&self, other: &ABox<D>) -> ABox<D>
+    {
+        let mut merged = self.clone();
+        for i in 0..D
+        {
+            merged.min[i] = self.min[i].min(other.min[i]);
+            merged.max[i] = self.max[i].max(other.max[i]);
+        }
+        merged
+    }
+    //}}}
+    //{{{ fun: expand_to_include
+    /// Grows the box in place so that it contains the point `p`, allowing a box to be built up
+    /// incrementally as points arrive.
+    pub fn expand_to_include(&mut self, p: &Vector<D>)
+    {
+        for i in 0..D
+        {
+            self.min[i] = self.min[i].min(p[i]);
+            self.max[i] = self.max[i].max(p[i]);
+        }
+    }
+    //}}}
+    //{{{ fun: intersect_ray
+    /// Intersects the ray $\mathbf{o} + t \mathbf{d}, t \geq 0$ with the box by clipping it 
+    /// against the slab between the faces of the box in each dimension.
+    ///
+    /// # Returns
+    /// The range of ray parameters `(t_enter, t_exit)` within the box, or `None` if the ray misses.
+    pub fn intersect_ray(&self, origin: &Vector<D>, dir: &Vector<D>) -> Option<(f64, f64)>
+    {
+        let mut t_enter = 0.0f64;
+        let mut t_exit = f64::MAX;
+        for i in 0..D
+        {
+            if dir[i] == 0.0
+            {
+                if origin[i] < self.min[i] || origin[i] > self.max[i]
+                {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (self.min[i] - origin[i]) / dir[i];
+            let t2 = (self.max[i] - origin[i]) / dir[i];
+            t_enter = t_enter.max(t1.min(t2));
+            t_exit = t_exit.min(t1.max(t2));
+            if t_enter > t_exit
+            {
+                return None;
+            }
+        }
+        Some((t_enter, t_exit))
+    }
+    //}}}
     //{{{ fun: center
-    fn center(&self) -> Vector<D>
+    /// Returns the centre of the box, the midpoint of its extent in each dimension.
+    pub fn center(&self) -> Vector<D>
     {
         let mut center = Vector::<D>::zeros();
         for i in 0..D {
@@ -83,6 +195,18 @@ impl<const D: usize>  ABox<D>
 //{{{ impl ABox<2>
 impl ABox<2>
 {
+    //{{{ fun: transform
+    /// Computes the box containing this box after it has been transformed by the affine 
+    /// transformation `m`, given in homogeneous coordinates.
+    ///
+    /// This is the box of the transformed corners, so it is conservative but not in general tight 
+    /// for the transformed contents of the box.
+    pub fn transform(&self, m: &Mat3) -> ABox<2>
+    {
+        let corners = self.corners().map(|c| m.transform_point(&c.into()).coords);
+        ABox::<2>::from_points(&corners)
+    }
+    //}}}
 
     pub fn xmin(&self) -> f64 { self.min[0] }
     pub fn xmax(&self) -> f64 { self.max[0] }
@@ -93,6 +217,18 @@ impl ABox<2>
 //{{{ impl ABox<3>
 impl ABox<3> 
 {
+    //{{{ fun: transform
+    /// Computes the box containing this box after it has been transformed by the affine 
+    /// transformation `m`, given in homogeneous coordinates.
+    ///
+    /// This is the box of the transformed corners, so it is conservative but not in general tight 
+    /// for the transformed contents of the box.
+    pub fn transform(&self, m: &Mat4) -> ABox<3>
+    {
+        let corners = self.corners().map(|c| m.transform_point(&c.into()).coords);
+        ABox::<3>::from_points(&corners)
+    }
+    //}}}
 
     pub fn xmin(&self) -> f64 { self.min[0] }
     pub fn xmax(&self) -> f64 { self.max[0] }
@@ -102,12 +238,268 @@ impl ABox<3>
     pub fn zmax(&self) -> f64 { self.max[2] }
 }
 //}}}
+//{{{ impl: SignedDistance for ABox<D>
+impl<const D: usize> SignedDistance<D> for ABox<D>
+{
+    /// The exact signed distance to the surface of the box.
+    ///
+    /// Outside the box this is the distance to the nearest face, edge or corner, inside the box it
+    /// is minus the distance to the nearest face.
+    fn sdf(&self, p: &Vector<D>) -> f64
+    {
+        let mut outside = 0.0f64;
+        let mut inside = f64::MIN;
+        for i in 0..D
+        {
+            let half = 0.5 * (self.max[i] - self.min[i]);
+            let qi = (p[i] - 0.5 * (self.max[i] + self.min[i])).abs() - half;
+            outside += qi.max(0.0).powi(2);
+            inside = inside.max(qi);
+        }
+        outside.sqrt() + inside.min(0.0)
+    }
+}
+//}}}
 //{{{ impl ABoxable
 /// This trait defines boxable types. Meaning types with a presence in 2D or 3D space for which 
 /// the limits of their extent can be computed and stored in a bounding box.
 /// 
 /// Types that implement this trait are expected to have a ``Option<Box>`` field that is 
-/// lazily evaluated
+/// lazily evaluated. Implementors provide access to this field through `box_cache` and the 
+/// computation of the box through `compute_box`, the caching logic is provided by the trait.
+///
+/// Any method which changes the geometry of an implementor must call `invalidate_box` so that the
+/// box is recomputed on the next call to `get_box`.
 pub trait ABoxable<const D: usize> {
-    fn get_box(&mut self) -> &ABox<D>;
+    //{{{ fun: compute_box
+    /// Computes the bounding box, without consulting or updating the cache.
+    fn compute_box(&self) -> ABox<D>;
+    //}}}
+    //{{{ fun: box_cache
+    /// Gives access to the cached bounding box.
+    fn box_cache(&mut self) -> &mut Option<ABox<D>>;
+    //}}}
+    //{{{ fun: get_box
+    /// Returns the bounding box, computing and caching it if it is not already cached.
+    fn get_box(&mut self) -> &ABox<D>
+    {
+        if self.box_cache().is_none()
+        {
+            let abox = self.compute_box();
+            *self.box_cache() = Some(abox);
+        }
+        self.box_cache().as_ref().unwrap()
+    }
+    //}}}
+    //{{{ fun: invalidate_box
+    /// Clears the cached bounding box, it will be recomputed on the next call to `get_box`.
+    fn invalidate_box(&mut self)
+    {
+        *self.box_cache() = None;
+    }
+    //}}}
+    //{{{ fun: recompute_box
+    /// Forces the bounding box to be recomputed and returns it.
+    fn recompute_box(&mut self) -> &ABox<D>
+    {
+        self.invalidate_box();
+        self.get_box()
+    }
+    //}}}
 }//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// Computes the distance from `p` to the surface of the box by clamping `p` onto each face.
+    fn brute_force_distance(abox: &ABox<3>, p: &Vector<3>) -> f64
+    {
+        let mut dist = f64::MAX;
+        for i in 0..3
+        {
+            for bound in [abox.min(i), abox.max(i)]
+            {
+                let mut q = *p;
+                for j in 0..3
+                {
+                    q[j] = q[j].clamp(abox.min(j), abox.max(j));
+                }
+                q[i] = bound;
+                dist = dist.min((p - q).norm());
+            }
+        }
+        let inside = (0..3).all(|i| p[i] > abox.min(i) && p[i] < abox.max(i));
+        if inside { -dist } else { dist }
+    }
+
+    #[test]
+    fn sdf_test()
+    {
+        let abox = ABox::<3>::new([-1.0, 0.0, 2.0], [1.0, 3.0, 2.5]);
+
+        // simple linear congruential generator for reproducible pseudo-random points
+        let mut seed = 12345u64;
+        let mut rand = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        for _ in 0..1000
+        {
+            let p = Vector::<3>::new(
+                -3.0 + 6.0 * rand(),
+                -2.0 + 7.0 * rand(),
+                0.0 + 5.0 * rand(),
+            );
+            assert_relative_eq!(abox.sdf(&p), brute_force_distance(&abox, &p), epsilon = 1e-12);
+        }
+
+        // corner distance
+        let p = Vector::<3>::new(2.0, 4.0, 3.5);
+        assert_relative_eq!(abox.sdf(&p), 3.0f64.sqrt(), epsilon = 1e-12);
+        // centre distance
+        let p = Vector::<3>::new(0.0, 1.5, 2.25);
+        assert_relative_eq!(abox.sdf(&p), -0.25, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn transform_test()
+    {
+        let abox = ABox::<3>::new([0.0, 0.0, 0.0], [2.0, 1.0, 1.0]);
+        assert_eq!(abox.corners().len(), 8);
+
+        // rotation by 90 degrees about z followed by a translation
+        let m = Mat4::new(
+            0.0, -1.0, 0.0, 5.0,
+            1.0,  0.0, 0.0, 0.0,
+            0.0,  0.0, 1.0, 1.0,
+            0.0,  0.0, 0.0, 1.0,
+        );
+        let abox2 = abox.transform(&m);
+        assert_relative_eq!(abox2.xmin(), 4.0, epsilon = 1e-14);
+        assert_relative_eq!(abox2.xmax(), 5.0, epsilon = 1e-14);
+        assert_relative_eq!(abox2.ymin(), 0.0, epsilon = 1e-14);
+        assert_relative_eq!(abox2.ymax(), 2.0, epsilon = 1e-14);
+        assert_relative_eq!(abox2.zmin(), 1.0, epsilon = 1e-14);
+        assert_relative_eq!(abox2.zmax(), 2.0, epsilon = 1e-14);
+
+        // rotation by 45 degrees in 2D grows the box
+        let (s, c) = std::f64::consts::FRAC_PI_4.sin_cos();
+        let m = Mat3::new(
+            c, -s, 0.0,
+            s,  c, 0.0,
+            0.0, 0.0, 1.0,
+        );
+        let abox3 = ABox::<2>::new([-1.0, -1.0], [1.0, 1.0]).transform(&m);
+        assert_relative_eq!(abox3.xmax(), 2.0f64.sqrt(), epsilon = 1e-14);
+        assert_relative_eq!(abox3.ymin(), -2.0f64.sqrt(), epsilon = 1e-14);
+    }
+
+    #[test]
+    fn distance_test()
+    {
+        let abox1 = ABox::<2>::from_points(&[Vector::<2>::new(0.0, 0.0), Vector::<2>::new(1.0, 2.0)]);
+        let abox2 = ABox::<2>::new([4.0, 6.0], [5.0, 7.0]);
+        let abox3 = ABox::<2>::new([0.5, -1.0], [3.0, 0.5]);
+        assert_relative_eq!(abox1.distance(&abox2), 5.0, epsilon = 1e-12);
+        assert_relative_eq!(abox2.distance(&abox1), 5.0, epsilon = 1e-12);
+        assert_eq!(abox1.distance(&abox3), 0.0);
+    }
+
+    #[test]
+    fn intersect_ray_test()
+    {
+        let abox = ABox::<3>::new([0.0, 0.0, 0.0], [2.0, 1.0, 1.0]);
+        let (t1, t2) = abox.intersect_ray(&Vector::<3>::new(-1.0, 0.5, 0.5), &Vector::<3>::new(1.0, 0.0, 0.0)).unwrap();
+        assert_relative_eq!(t1, 1.0, epsilon = 1e-14);
+        assert_relative_eq!(t2, 3.0, epsilon = 1e-14);
+
+        // a ray starting inside enters at its origin
+        let (t1, t2) = abox.intersect_ray(&Vector::<3>::new(1.0, 0.5, 0.5), &Vector::<3>::new(0.0, 0.0, -2.0)).unwrap();
+        assert_eq!(t1, 0.0);
+        assert_relative_eq!(t2, 0.25, epsilon = 1e-14);
+
+        // rays which miss, pass beside the box or point away from it
+        assert!(abox.intersect_ray(&Vector::<3>::new(-1.0, 2.0, 0.5), &Vector::<3>::new(1.0, 0.0, 0.0)).is_none());
+        assert!(abox.intersect_ray(&Vector::<3>::new(-1.0, 0.5, 0.5), &Vector::<3>::new(-1.0, 0.0, 0.0)).is_none());
+        assert!(abox.intersect_ray(&Vector::<3>::new(-1.0, -1.0, 0.5), &Vector::<3>::new(1.0, 3.0, 0.0)).is_none());
+
+        let merged = abox.merge(&ABox::<3>::new([-1.0, 0.5, 0.5], [0.5, 3.0, 0.7]));
+        assert_eq!((merged.min(0), merged.min(1), merged.min(2)), (-1.0, 0.0, 0.0));
+        assert_eq!((merged.max(0), merged.max(1), merged.max(2)), (2.0, 3.0, 1.0));
+    }
+
+    #[test]
+    fn intersects_test()
+    {
+        let abox = ABox::<2>::new([0.0, 0.0], [2.0, 1.0]);
+        assert!(abox.intersects(&ABox::new([1.0, 0.5], [3.0, 4.0])));
+        assert!(abox.intersects(&ABox::new([0.5, 0.25], [1.0, 0.5])));
+        assert!(abox.intersects(&ABox::new([2.0, 1.0], [3.0, 2.0])));
+        assert!(!abox.intersects(&ABox::new([2.5, 0.0], [3.0, 1.0])));
+        assert!(!abox.intersects(&ABox::new([0.0, -2.0], [2.0, -0.5])));
+        for other in [ABox::new([1.0, 0.5], [3.0, 4.0]), ABox::new([2.5, 0.0], [3.0, 1.0])]
+        {
+            assert_eq!(abox.intersects(&other), abox.distance(&other) == 0.0);
+        }
+    }
+
+    #[test]
+    fn corners_test()
+    {
+        let abox = ABox::<3>::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        let corners: [Vector<3>; 8] = abox.corners();
+        let expected = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 1.0, 1.0],
+        ];
+        for (corner, x) in corners.iter().zip(expected.iter())
+        {
+            assert_eq!(*corner, Vector::<3>::new(x[0], x[1], x[2]));
+        }
+        assert_eq!(abox.center(), Vector::<3>::new(0.5, 0.5, 0.5));
+        assert_eq!(corners.iter().sum::<Vector<3>>() / 8.0, abox.center());
+    }
+
+    #[test]
+    fn expand_to_include_test()
+    {
+        let points = [
+            Vector::<3>::new(0.5, -1.0, 2.0),
+            Vector::<3>::new(-3.0, 0.25, 1.0),
+            Vector::<3>::new(1.5, 4.0, -2.5),
+            Vector::<3>::new(0.0, 0.0, 0.0),
+        ];
+
+        // starting from the degenerate box of the first point
+        let mut abox = ABox::<3>::new([0.5, -1.0, 2.0], [0.5, -1.0, 2.0]);
+        assert_eq!(abox.measure(), 0.0);
+        for p in &points[1..]
+        {
+            abox.expand_to_include(p);
+        }
+        let expected = ABox::<3>::from_points(&points);
+        for i in 0..3
+        {
+            assert_eq!(abox.min(i), expected.min(i));
+            assert_eq!(abox.max(i), expected.max(i));
+        }
+        assert_eq!((abox.xmin(), abox.ymax(), abox.zmin()), (-3.0, 4.0, -2.5));
+
+        // a point already inside leaves the box unchanged
+        abox.expand_to_include(&Vector::<3>::new(0.0, 1.0, 0.5));
+        assert_eq!((abox.min(1), abox.max(1)), (-1.0, 4.0));
+    }
+}
+//}}}
@@ -5,8 +5,16 @@
 // misc
 mod common;
 pub use common::{ABoxable, ABox};
+mod bvh;
+pub use bvh::Bvh;
 //..................................................................................................
 // curves
 mod box_bcurve;
+mod box_segment;
+//..................................................................................................
+// surfaces
+mod box_bsurface;
+mod box_plane;
+pub use box_plane::BoundedPlane;
 //..................................................................................................
 
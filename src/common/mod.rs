@@ -3,6 +3,7 @@
 //--------------------------------------------------------------------------------------------------
 
 //{{{ crate imports 
+pub use crate::utilities::NDArray;
 //}}}
 //{{{ std imports 
 use std::ops::{Add, Index, IndexMut, Mul, Sub};
@@ -22,6 +23,12 @@ pub type Vec2 = Vector<2>;
 pub type Vec3 = Vector<3>;
 pub type Vec4 = Vector<4>;
 //}}}
+//{{{ collection: Matrix types
+pub type Matrix<const R: usize, const C: usize> = na::SMatrix<f64, R, C>;
+pub type Mat2 = Matrix<2, 2>;
+pub type Mat3 = Matrix<3, 3>;
+pub type Mat4 = Matrix<4, 4>;
+//}}}
 //{{{ collection: VectorOps
 //{{{ trait: VectorOps
 /// This trait provides a set of operations defined for nalgebra vecotrs vectors. This is purely
@@ -109,6 +116,50 @@ pub enum DescriptorError
 {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+    /// A degree or order exceeds the limit supported by the crate, `limit` names the quantity
+    #[error("{limit} {value} exceeds the maximum of {max}")]
+    LimitExceeded
+    {
+        limit: &'static str,
+        value: usize,
+        max: usize,
+    },
+}
+//}}}
+//{{{ enum: ModellerError
+/// The error type of the fallible operations of the crate.
+#[derive(Error, Debug)]
+pub enum ModellerError
+{
+    /// The input to an operation is malformed, for example an invalid descriptor
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    /// A parameter lies outside the domain of the object it is applied to
+    #[error("Parameter {0} is outside the domain")]
+    OutOfDomain(f64),
+    /// An iterative method failed to converge
+    #[error("Failed to converge: {0}")]
+    NonConvergence(String),
+    /// The topological data structure is inconsistent
+    #[error("Topology violation: {0}")]
+    TopologyViolation(String),
+    /// A homogeneous point has a zero, near-zero or negative weight, so it has no finite
+    /// Euclidean counterpart
+    #[error("Degenerate homogeneous weight {0}")]
+    DegenerateWeight(f64),
+}
+//}}}
+//{{{ impl: From<DescriptorError> for ModellerError
+impl From<DescriptorError> for ModellerError
+{
+    fn from(err: DescriptorError) -> Self
+    {
+        match err
+        {
+            DescriptorError::InvalidInput(msg) => ModellerError::InvalidInput(msg),
+            DescriptorError::LimitExceeded { .. } => ModellerError::InvalidInput(err.to_string()),
+        }
+    }
 }
 //}}}
 //{{{ trait: Descriptor
@@ -194,6 +245,131 @@ pub fn vec_orthogonal<const D: usize>(a: &Vector<D>, b: &Vector<D>, mut tol: f64
     cos_angle <= tol
 }
 //}}}
+//{{{ fun: to_vectors
+/// Converts rows of coordinates, such as points read from JSON or CSV, into vectors.
+///
+/// # Arguments
+/// * `data` - The rows of coordinates, arrays `[f64; D]` or `Vec<f64>` of length `D`
+///
+/// # Returns
+/// One vector per row, or [ModellerError::InvalidInput] naming the first row whose length is not
+/// `D`.
+pub fn to_vectors<const D: usize, T: AsRef<[f64]>>(data: &[T]) -> Result<Vec<Vector<D>>, ModellerError>
+{
+    data.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let row = row.as_ref();
+            if row.len() != D
+            {
+                return Err(ModellerError::InvalidInput(format!(
+                    "row {} has {} coordinates, expected {}",
+                    i,
+                    row.len(),
+                    D
+                )));
+            }
+            Ok(Vector::<D>::from_column_slice(row))
+        })
+        .collect()
+}
+//}}}
+//{{{ fun: from_vectors
+/// Converts vectors into rows of coordinates, the inverse of [to_vectors].
+pub fn from_vectors<const D: usize>(vectors: &[Vector<D>]) -> Vec<[f64; D]>
+{
+    vectors.iter().map(|v| (*v).into()).collect()
+}
+//}}}
+
+//{{{ fun: inv_homog
+/// Performs the perspective map (inverse of homogeneous map) from homogeneous coordinates to
+/// Euclidean coordinates.
+///
+/// The weight is not checked, a zero weight gives infinite or NaN coordinates, see [try_inv_homog].
+pub fn inv_homog<const N: usize>(point_w: &Vector<{ N + 1 }>) -> Vector<{ N }>
+where
+    [(); N + 1]:,
+{
+    let mut point = Vector::<{ N }>::from_element(0.0);
+    let w = point_w[N];
+    for i in 0..N
+    {
+        point[i] = point_w[i] / w;
+    }
+    point
+}
+//}}}
+//{{{ fun: try_inv_homog
+/// Performs the perspective map from homogeneous coordinates to Euclidean coordinates as
+/// [inv_homog], checking first that the weight is positive.
+///
+/// # Returns
+/// The point in Euclidean coordinates, or [ModellerError::DegenerateWeight] if the weight is
+/// negative or smaller than the linear resolution, where [inv_homog] would give infinite or NaN
+/// coordinates.
+pub fn try_inv_homog<const N: usize>(point_w: &Vector<{ N + 1 }>) -> Result<Vector<{ N }>, ModellerError>
+where
+    [(); N + 1]:,
+{
+    let w = point_w[N];
+    if !(w >= f64::RES_LINEAR)
+    {
+        return Err(ModellerError::DegenerateWeight(w));
+    }
+    Ok(inv_homog(point_w))
+}
+//}}}
+//{{{ fun: homog
+/// Performs the inverse perspective map (homogeneous map) from Euclidean coordinates to
+/// homogeneous coordinates.
+pub fn homog<const N: usize>(
+    point: &Vector<N>,
+    weight: f64,
+) -> Vector<{ N + 1 }>
+{
+    let mut point_w = Vector::<{ N + 1 }>::from_element(0.0);
+    for i in 0..N
+    {
+        point_w[i] = weight * point[i];
+    }
+    point_w[N] = weight;
+    point_w
+}
+//}}}
+
+//{{{ collection: Homogeneous
+//{{{ trait: Homogeneous
+/// Conversions of Euclidean points to and from homogeneous coordinates, as methods on the
+/// vector types.
+pub trait Homogeneous<const N: usize>
+where
+    [(); N + 1]:,
+{
+    /// Maps the point to homogeneous coordinates with the given weight, see [homog].
+    fn to_homog(&self, weight: f64) -> Vector<{ N + 1 }>;
+
+    /// Maps a point in homogeneous coordinates back to Euclidean coordinates, see [inv_homog].
+    fn from_homog(point_w: &Vector<{ N + 1 }>) -> Self;
+}
+//}}}
+//{{{ impl: Homogeneous for Vector<N>
+impl<const N: usize> Homogeneous<N> for Vector<N>
+where
+    [(); N + 1]:,
+{
+    fn to_homog(&self, weight: f64) -> Vector<{ N + 1 }>
+    {
+        homog(self, weight)
+    }
+
+    fn from_homog(point_w: &Vector<{ N + 1 }>) -> Self
+    {
+        inv_homog(point_w)
+    }
+}
+//}}}
+//}}}
 
 //-------------------------------------------------------------------------------------------------
 //{{{ mod: tests
@@ -239,5 +415,59 @@ mod tests
         assert!(!vec_orthogonal(&a, &c, 1.0e-10));
     }
 
+    #[test]
+    fn test_homog_round_trip() {
+        let mut seed = 12345u64;
+        let mut rand = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        for _ in 0..100
+        {
+            let p = Vec3::new(20.0 * rand() - 10.0, 20.0 * rand() - 10.0, 20.0 * rand() - 10.0);
+            let w = 0.1 + 5.0 * rand();
+            let pw = homog(&p, w);
+            assert_eq!(pw[3], w);
+            assert!((inv_homog(&pw) - p).norm() <= 1e-14 * p.norm());
+            assert_eq!(Vec3::from_homog(&p.to_homog(w)), inv_homog(&pw));
+            assert_eq!(try_inv_homog::<3>(&pw).unwrap(), inv_homog(&pw));
+        }
+    }
+
+    #[test]
+    fn test_to_from_vectors() {
+        let rows = [[1.0, 2.0, 3.0], [-0.5, 0.0, 4.25], [1e-3, 7.0, -2.0]];
+        let points = to_vectors::<3, _>(&rows).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1], Vec3::new(-0.5, 0.0, 4.25));
+        assert_eq!(from_vectors(&points), rows.to_vec());
+
+        // rows of the wrong length are reported rather than truncated or padded
+        let rows = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0]];
+        match to_vectors::<3, _>(&rows)
+        {
+            Err(ModellerError::InvalidInput(msg)) => assert!(msg.starts_with("row 1")),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+        assert!(to_vectors::<3, Vec<f64>>(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_try_inv_homog() {
+        // a zero weight maps to infinity, or NaN at the origin
+        let pw = Vec4::new(1.0, 2.0, 3.0, 0.0);
+        assert!(!inv_homog::<3>(&pw).iter().all(|x| x.is_finite()));
+        assert!(matches!(try_inv_homog::<3>(&pw), Err(ModellerError::DegenerateWeight(w)) if w == 0.0));
+        assert!(matches!(try_inv_homog::<3>(&Vec4::zeros()), Err(ModellerError::DegenerateWeight(_))));
+
+        // near-zero and negative weights are flagged too
+        let pw = Vec4::new(1.0, 2.0, 3.0, 1e-12);
+        assert!(matches!(try_inv_homog::<3>(&pw), Err(ModellerError::DegenerateWeight(_))));
+        let pw = homog(&Vec3::new(1.0, 2.0, 3.0), -2.0);
+        assert!(matches!(try_inv_homog::<3>(&pw), Err(ModellerError::DegenerateWeight(w)) if w == -2.0));
+        let pw = Vec4::new(1.0, 2.0, 3.0, f64::NAN);
+        assert!(try_inv_homog::<3>(&pw).is_err());
+    }
+
 }
 //}}}
\ No newline at end of file
@@ -3,7 +3,7 @@
 //--------------------------------------------------------------------------------------------------
 
 //{{{ crate imports 
-use crate::common::{Vector, VectorOps, ResConstants};
+use crate::common::{ModellerError, Vector, VectorOps, ResConstants};
 //}}}
 //{{{ std imports 
 //}}}
@@ -14,8 +14,26 @@ use topohedral_integrate::gauss;
 //--------------------------------------------------------------------------------------------------
 
 //{{{ constants
-const MIN_PARAM: f64 = f64::MIN;
-const MAX_PARAM: f64 = f64::MAX;
+/// Lower end of the parameter range of unbounded curves, finite so that arithmetic on the range
+/// does not produce NaNs
+pub(crate) const MIN_PARAM: f64 = f64::MIN;
+/// Upper end of the parameter range of unbounded curves
+pub(crate) const MAX_PARAM: f64 = f64::MAX;
+/// Number of points of the Gauss-Legendre rule used by [gauss_legendre]
+const GAUSS_LEGENDRE_NQP: usize = 8;
+//}}}
+//{{{ fun: gauss_legendre
+/// Integrates `f` over $[a, b]$ with the 8-point Gauss-Legendre rule of the integration crate,
+/// which is exact for polynomials of degree up to 15.
+///
+/// Integrands with kinks, such as those of spline curves at their knots, should be integrated one
+/// knot span at a time.
+pub(crate) fn gauss_legendre<F: Fn(f64) -> f64>(f: F, a: f64, b: f64) -> f64
+{
+    gauss::get_legendre_points()
+        .gauss_quad_from_nqp(GAUSS_LEGENDRE_NQP)
+        .integrate(f, a, b)
+}
 //}}}
 //{{{ struct: CurveMinValOpts
 pub struct CurveMinValOpts
@@ -57,6 +75,21 @@ pub trait Curve
         u: f64,
     ) -> Self::Vector;
     //}}}
+    //{{{ fun: try_eval
+    /// Evaluates the curve at the parameter value $u$, checking first that $u$ lies in the domain
+    /// of the curve.
+    ///
+    /// # Returns
+    /// The point on the curve, or [ModellerError::OutOfDomain] if $u$ is not a member of the
+    /// domain.
+    fn try_eval(
+        &self,
+        u: f64,
+    ) -> Result<Self::Vector, ModellerError>
+    {
+        if self.is_member(u) { Ok(self.eval(u)) } else { Err(ModellerError::OutOfDomain(u)) }
+    }
+    //}}}
     //{{{ fun: eval_diff
     /// Evalutes the $m$'th derivative of the curve:
     /// $$
@@ -73,6 +106,21 @@ pub trait Curve
         m: usize,
     ) -> Self::Vector;
     //}}}
+    //{{{ fun: try_eval_diff
+    /// Evaluates the $m$'th derivative of the curve at the parameter value $u$, checking first
+    /// that $u$ lies in the domain of the curve.
+    ///
+    /// # Returns
+    /// The derivative, or [ModellerError::OutOfDomain] if $u$ is not a member of the domain.
+    fn try_eval_diff(
+        &self,
+        u: f64,
+        m: usize,
+    ) -> Result<Self::Vector, ModellerError>
+    {
+        if self.is_member(u) { Ok(self.eval_diff(u, m)) } else { Err(ModellerError::OutOfDomain(u)) }
+    }
+    //}}}
     //{{{ fun: eval_diff_all
     /// Evalutes the $0$'th to the $m$'th derivative of the curve:
     /// $$
@@ -110,6 +158,11 @@ pub trait Curve
     //}}}
     //{{{ fun: eval_normal 
     /// Evaluates the normal to the curve at the parameter value `u`.
+    ///
+    /// In 2D this is the tangent turned a quarter turn counterclockwise. In 3D it is the principal
+    /// normal, which points towards the centre of curvature and is zero where the curvature 
+    /// vanishes, so that it is not normalised there. Curves in other dimensions have no normal and
+    /// the zero vector is returned.
     fn eval_normal(
         &self,
         u: f64,
@@ -152,12 +205,16 @@ pub trait Curve
                     normal
                 }
             }
-            _=> panic!("dim mux be 2 or 3"),
+            _ => Self::Vector::zeros(),
         }
     }
     //}}}
     //{{{ fun: eval_binormal
     /// Evaluates the binormal to the cure at the parameter value `u`.
+    ///
+    /// This is the cross product of the tangent and the normal in 3D, and like the normal it is 
+    /// zero where the curvature vanishes. Curves in other dimensions have no binormal and the zero 
+    /// vector is returned.
     fn eval_binormal(
         &self,
         u: f64,
@@ -173,13 +230,13 @@ pub trait Curve
                 let tan = self.eval_tangent(u, false);
                 let normal = self.eval_normal(u, false);
                 let mut binorm = tan.cross(&normal);
-                if normalise
+                if normalise && binorm.norm() > f64::RES_LINEAR
                 {
                     binorm = binorm.normalize();
                 }
                 binorm
             }, 
-            _ => {panic!("D must be 2 or 3")}   
+            _ => Self::Vector::zeros(),
         }
     }
     //}}}
@@ -258,13 +315,47 @@ pub trait Curve
     //{{{ fun: param_range
     /// Returns the valid range of the parameter space for the curve. 
     /// 
-    /// This defaults to ``(MIN_PARAM, MAX_PARAM)``, where ``MIN_PARAM`` and ``MAX_PARAM`` are
-    /// value
+    /// This defaults to `(MIN_PARAM, MAX_PARAM)`, the most negative and most positive finite
+    /// values, which stand in for the unbounded range of curves such as lines.
     fn param_range(&self) -> (f64, f64)
     {
         (MIN_PARAM, MAX_PARAM)
     }
     //}}}
+    //{{{ fun: sample_params
+    /// Returns `n + 1` parameters evenly spaced over the [Curve::param_range], including both ends.
+    ///
+    /// The parameters are interpolated between the ends of the range, rather than stepped from the
+    /// first, so that they stay finite for the unbounded default range and the last is exactly
+    /// the end of the range. An `n` of 0 is taken as 1, so that at least the two ends are returned.
+    fn sample_params(
+        &self,
+        n: usize,
+    ) -> Vec<f64>
+    {
+        let n = n.max(1);
+        let (u1, u2) = self.param_range();
+        (0..n + 1)
+            .map(|i| {
+                let t = i as f64 / n as f64;
+                (1.0 - t) * u1 + t * u2
+            })
+            .collect()
+    }
+    //}}}
+    //{{{ fun: sample
+    /// Samples the curve at the `n + 1` parameters of [Curve::sample_params].
+    ///
+    /// # Returns
+    /// The pairs of parameter and point, in order of increasing parameter.
+    fn sample(
+        &self,
+        n: usize,
+    ) -> Vec<(f64, Self::Vector)>
+    {
+        self.sample_params(n).into_iter().map(|u| (u, self.eval(u))).collect()
+    }
+    //}}}
     //{{{ fun: min_value_scalar
     /// Finds the minimum value of a scalar function `f` over an optional parameter range.
     ///
@@ -281,6 +372,8 @@ pub trait Curve
     /// A tuple `(f64, f64)` where the second element is the minimum value of `f` and the first element
     /// is the parameter value at which the minimum occurs.
     fn min_value_scalar<F: Fn(f64) -> f64>(&self, f: F, opts: &CurveMinValOpts) -> (f64, f64)
+    where
+        Self: Sized,
     {
         let bounds = match opts.bounds{
             Some(range) => range,
@@ -314,6 +407,8 @@ pub trait Curve
     /// A tuple `(f64, f64)` where the first value is the parameter value at which the mininum occurs 
     /// and the second value is the minimum value of the function `f` over the specified parameter range.
     fn min_value_vector<F: Fn(Self::Vector) -> f64>(&self, f: F, opts: &CurveMinValOpts) -> (f64, f64)
+    where
+        Self: Sized,
     {
         let bounds = match opts.bounds{
             Some(range) => range,
@@ -339,6 +434,8 @@ pub trait Curve
     //}}}
     //{{{ fun: integrate_scalar
     fn integrate_scalar<F: Fn(f64) -> f64>(&self, f: F, param_range: Option<(f64, f64)>) -> f64
+    where
+        Self: Sized,
     {
         // let leg = get_legendre_points();
         // let leg5 = leg.gauss_quad_from_nqp(5);
@@ -361,6 +458,8 @@ pub trait Curve
     /// # Returns
     /// The integral of the function `f` over the specified parameter range.
     fn integrate_vector<F: Fn(Self::Vector) -> f64>(&self, f: F, param_range: Option<(f64, f64)>) -> f64
+    where
+        Self: Sized,
     {
         todo!();
     }
@@ -387,6 +486,30 @@ pub trait Surface
         u: f64,
         v: f64) -> Self::Vector;
     //}}}
+    //{{{ fun: try_eval
+    /// Evaluates a point on the surface, checking first that $(u, v)$ lies in the domain of the
+    /// surface.
+    ///
+    /// # Returns
+    /// The point on the surface, or [ModellerError::OutOfDomain] holding the first of $u$ and $v$
+    /// which is not a member of its range.
+    fn try_eval(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> Result<Self::Vector, ModellerError>
+    {
+        if !self.is_member_u(u)
+        {
+            return Err(ModellerError::OutOfDomain(u));
+        }
+        if !self.is_member_v(v)
+        {
+            return Err(ModellerError::OutOfDomain(v));
+        }
+        Ok(self.eval(u, v))
+    }
+    //}}}
     //{{{ fun: eval_diff_u
     /// Evaluates the ``nu``'th partial derivative of the surface with respect to ``u`` and the
     /// ``nv``'th partial derivative with respect to ``v``.
@@ -494,45 +617,127 @@ pub trait Surface
     
 }
 //}}}
-//{{{ fun: inv_homog
-/// Performs the perspective map (inverse of homogeneuos map) from homogeneous coordinates to
-/// Euclidean coordinates.
-pub fn inv_homog<const N: usize>(point_w: &Vector<{ N + 1 }>) -> Vector<{ N }>
-where
-    [(); N + 1]:,
-{
-    let mut point = Vector::<{ N }>::from_element(0.0);
-    let w = point_w[N];
-    for i in 0..N
-    {
-        point[i] = point_w[i] / w;
-    }
-    point
-}
-//}}}
-//{{{ fun: homog
-/// Performs the inverse perspective map (homogeneous map) from Euclidean coordinates to
-/// Homogeneious coordinates.
-pub fn homog<const N: usize>(
-    point: &Vector<N>,
-    weight: f64,
-) -> Vector<{ N + 1 }>
+//{{{ trait: SignedDistance
+/// This trait models entities which partition space into an inside and an outside and so have a
+/// signed distance function.
+pub trait SignedDistance<const D: usize>
 {
-    let mut point_w = Vector::<{ N + 1 }>::from_element(0.0);
-    for i in 0..N
-    {
-        point_w[i] = weight * point[i];
-    }
-    point_w[N] = weight;
-    point_w
+    //{{{ fun: sdf
+    /// Evaluates the signed distance from the point `p` to the boundary of the entity.
+    ///
+    /// The distance is negative for points inside the entity and positive for points outside.
+    fn sdf(&self, p: &Vector<D>) -> f64;
+    //}}}
 }
 //}}}
-
 //-------------------------------------------------------------------------------------------------
 //{{{ mod: tests
 #[cfg(test)]
 mod tests
 {
-  
+    use super::*;
+
+    #[test]
+    fn gauss_legendre_test()
+    {
+        // exact for each monomial up to degree 15, to rounding
+        let (a, b): (f64, f64) = (-0.5, 2.0);
+        for k in 0..16
+        {
+            let exact = (b.powi(k + 1) - a.powi(k + 1)) / (k + 1) as f64;
+            let quad = gauss_legendre(|x: f64| x.powi(k), a, b);
+            assert!((quad - exact).abs() <= 1e-14 * exact.abs(), "degree {}: {} != {}", k, quad, exact);
+        }
+
+        // but not for degree 16, so that the rule has exactly 8 points
+        let exact = (b.powi(17) - a.powi(17)) / 17.0;
+        let quad = gauss_legendre(|x: f64| x.powi(16), a, b);
+        assert!((quad - exact).abs() > 1e-10 * exact.abs());
+    }
+
+    #[test]
+    fn eval_normal_test()
+    {
+        use crate::geometry::{Bcurve, BcurveDescriptor};
+
+        // in 2D the normal is the tangent turned counterclockwise and there is no binormal
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vector::<2>::new(0.0, 0.0), Vector::<2>::new(1.0, 1.0), Vector::<2>::new(2.0, 0.0)],
+            cweights: vec![1.0; 3],
+        });
+        let tangent = bcurve.eval_tangent(0.25, true);
+        assert_eq!(bcurve.eval_normal(0.25, true), Vector::<2>::new(-tangent[1], tangent[0]));
+        assert_eq!(bcurve.eval_binormal(0.25, true), Vector::<2>::zeros());
+
+        // in 3D the normal points to the inside of a bend and the frame is orthonormal
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vector::<3>::new(0.0, 0.0, 0.0), Vector::<3>::new(1.0, 1.0, 0.0), Vector::<3>::new(2.0, 0.0, 0.0)],
+            cweights: vec![1.0; 3],
+        });
+        let tangent = bcurve.eval_tangent(0.25, true);
+        let normal = bcurve.eval_normal(0.25, true);
+        let binormal = bcurve.eval_binormal(0.25, true);
+        assert!(normal[1] < 0.0);
+        assert!((normal.norm() - 1.0).abs() < 1e-12 && (binormal.norm() - 1.0).abs() < 1e-12);
+        assert!(normal.dot(&tangent).abs() < 1e-12 && binormal.dot(&tangent).abs() < 1e-12);
+        assert!((binormal - Vector::<3>::new(0.0, 0.0, -1.0)).norm() < 1e-12);
+
+        // a straight curve has no curvature, its normal and binormal are zero rather than NaN
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vector::<3>::new(0.0, 0.0, 0.0), Vector::<3>::new(1.0, 2.0, 3.0), Vector::<3>::new(2.0, 4.0, 6.0)],
+            cweights: vec![1.0; 3],
+        });
+        for u in [0.0, 0.5, 1.0]
+        {
+            assert_eq!(bcurve.eval_normal(u, true), Vector::<3>::zeros());
+            assert_eq!(bcurve.eval_binormal(u, true), Vector::<3>::zeros());
+        }
+
+        // and in higher dimensions neither is defined
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 1,
+            knots: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints: vec![Vector::<4>::zeros(), Vector::<4>::new(1.0, 2.0, 3.0, 4.0)],
+            cweights: vec![1.0; 2],
+        });
+        assert_eq!(bcurve.eval_normal(0.5, true), Vector::<4>::zeros());
+        assert_eq!(bcurve.eval_binormal(0.5, true), Vector::<4>::zeros());
+    }
+
+    #[test]
+    fn param_range_test()
+    {
+        use crate::geometry::{
+            Bcurve, BcurveDescriptor, Line, LineDescriptor, Polyline, PolylineDescriptor,
+        };
+
+        let points = vec![Vector::<2>::new(0.0, 0.0), Vector::<2>::new(3.0, 4.0), Vector::<2>::new(3.0, 6.0)];
+        let curves: Vec<Box<dyn Curve<Vector = Vector<2>>>> = vec![
+            Box::new(Line::new(&LineDescriptor {
+                origin: Vector::<2>::new(1.0, 1.0),
+                dir: Vector::<2>::new(1.0, 0.0),
+            })),
+            Box::new(Bcurve::new(&BcurveDescriptor {
+                p: 2,
+                knots: vec![0.5, 0.5, 0.5, 2.0, 2.0, 2.0],
+                cpoints: points.clone(),
+                cweights: vec![1.0; 3],
+            })),
+            Box::new(Polyline::new(&PolylineDescriptor { points })),
+        ];
+
+        let ranges: Vec<(f64, f64)> = curves.iter().map(|curve| curve.param_range()).collect();
+        assert_eq!(ranges, vec![(MIN_PARAM, MAX_PARAM), (0.5, 2.0), (0.0, 7.0)]);
+        for (curve, (u1, u2)) in curves.iter().zip(ranges.iter())
+        {
+            assert!(curve.is_member(*u1) && curve.is_member(*u2));
+        }
+    }
 }
 //}}}
\ No newline at end of file
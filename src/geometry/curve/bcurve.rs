@@ -5,20 +5,55 @@
 //--------------------------------------------------------------------------------------------------
 
 //{{{ crate imports 
-use crate::common::{Vec3, Vector};
-use crate::geometry::common::{homog, inv_homog, Curve};
+use crate::common::{homog, inv_homog, try_inv_homog, Descriptor, DescriptorError, Mat4, ModellerError, Vec2, Vec3, Vec4, Vector, ResConstants};
+use crate::geometry::common::{gauss_legendre, Curve, CurveMinValOpts, SignedDistance};
+use crate::geometry::Plane;
 use crate::splines::{self as spl, knot_eq};
 use crate::utilities::{lower_bound, NDArrayWrapper};
-use crate::boxing::ABox;
+use crate::boxing::{ABox, ABoxable};
 //}}}
 //{{{ std imports 
 //}}}
 //{{{ dep imports 
+use arrayvec::ArrayVec;
+use nalgebra as na;
+use topohedral_tracing::*;
 //}}}
 //--------------------------------------------------------------------------------------------------
 
 //{{{ constants
+/// Number of derivatives, including the zeroth, for which [Bcurve::eval_diff_all] uses fixed-size
+/// stack buffers.
+///
+/// This is not an upper limit on the order of derivative which can be evaluated, requests for the
+/// derivatives of order `BCURVE_DER_MAX` and above, or on curves of order `p >= BCURVE_DER_MAX`, 
+/// fall back to heap-allocated buffers and return correct values up to any order.
 pub const BCURVE_DER_MAX: usize = 5;
+/// Size of the sub-curves, relative to the larger curve, at which subdivision stops in
+/// `min_distance`
+const MIN_DIST_LEAF_RATIO: f64 = 1e-2;
+/// Maximum subdivision depth in `min_distance`
+const MIN_DIST_DEPTH_MAX: usize = 40;
+/// Maximum number of Newton iterations in `min_distance`
+const MIN_DIST_NEWTON_ITER_MAX: usize = 50;
+/// Number of curvature samples per knot span used to bracket the maximum in `max_curvature`
+const MAX_CURV_SAMPLES_PER_SPAN: usize = 8;
+/// Number of signed curvature samples per knot span used to bracket sign changes in
+/// `inflection_points`
+const INFLECTION_SAMPLES_PER_SPAN: usize = 8;
+/// Maximum number of bisection steps used to refine each inflection point
+const INFLECTION_BISECT_ITER_MAX: usize = 100;
+/// Maximum number of safeguarded Newton steps used to invert the arc length in `arc_length_param`
+const ARCLEN_ITER_MAX: usize = 100;
+/// Number of distance samples per knot span used to bracket the minima in `closest_point_to_ray`
+const RAY_SAMPLES_PER_SPAN: usize = 8;
+/// Degree of the curve fitted in `align_parameterization`
+const ALIGN_FIT_DEGREE: usize = 3;
+/// Number of spans of the fitted curve in `align_parameterization` per span of the input curves
+const ALIGN_SPANS_PER_SPAN: usize = 16;
+/// Curvature, relative to the inverse size of the control box, below which the torsion is taken
+/// to be undefined in `torsion_profile`
+const TORSION_CURVATURE_MIN: f64 = 1e-6;
 //}}}
 //{{{ struct: BcurveDescriptor
 pub struct BcurveDescriptor<const D: usize>
@@ -29,6 +64,155 @@ pub struct BcurveDescriptor<const D: usize>
     pub cweights: Vec<f64>,
 }
 //}}}
+//{{{ impl: Descriptor for BcurveDescriptor<D>
+impl<const D: usize> Descriptor for BcurveDescriptor<D>
+{
+    fn is_valid(&self) -> Result<(), DescriptorError>
+    {
+        spl::check_degrees(&[("degree", self.p)], &[])?;
+        if self.cpoints.len() < self.p + 1
+        {
+            return Err(DescriptorError::InvalidInput(format!(
+                "{} control points are too few for order {}",
+                self.cpoints.len(),
+                self.p
+            )));
+        }
+        if self.cweights.len() != self.cpoints.len()
+        {
+            return Err(DescriptorError::InvalidInput(format!(
+                "{} weights given for {} control points",
+                self.cweights.len(),
+                self.cpoints.len()
+            )));
+        }
+        if self.knots.len() != self.cpoints.len() + self.p + 1
+        {
+            return Err(DescriptorError::InvalidInput(format!(
+                "{} knots given, expected {} for {} control points of order {}",
+                self.knots.len(),
+                self.cpoints.len() + self.p + 1,
+                self.cpoints.len(),
+                self.p
+            )));
+        }
+        if !spl::is_sorted_tol(&self.knots)
+        {
+            return Err(DescriptorError::InvalidInput("knots not sorted".to_string()));
+        }
+        if self.cweights.iter().any(|&w| !(w >= 0.0))
+        {
+            return Err(DescriptorError::InvalidInput(
+                "weights must be non-negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+//}}}
+//{{{ struct: BcurveBuilder
+/// Assembles the control points, weights and knots of a [Bcurve] one step at a time, keeping them
+/// consistent with one another.
+///
+/// The degree defaults to 3, and unless knots are given the uniform clamped knot vector on
+/// $[0, 1]$ is generated for the control points added.
+pub struct BcurveBuilder<const D: usize>
+{
+    p: usize,
+    cpoints: Vec<Vector<D>>,
+    cweights: Vec<f64>,
+    knots: Option<Vec<f64>>,
+}
+//}}}
+//{{{ impl: BcurveBuilder<D>
+impl<const D: usize> BcurveBuilder<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    /// Starts a curve of degree 3 with no control points.
+    pub fn new() -> Self
+    {
+        Self {
+            p: 3,
+            cpoints: Vec::new(),
+            cweights: Vec::new(),
+            knots: None,
+        }
+    }
+    //..............................................................................................
+
+    /// Sets the degree of the curve.
+    pub fn degree(mut self, p: usize) -> Self
+    {
+        self.p = p;
+        self
+    }
+    //..............................................................................................
+
+    /// Appends a control point with its weight, a weight of 1 for every point gives a
+    /// non-rational curve.
+    pub fn add_control_point(mut self, point: Vector<D>, weight: f64) -> Self
+    {
+        self.cpoints.push(point);
+        self.cweights.push(weight);
+        self
+    }
+    //..............................................................................................
+
+    /// Sets the knot vector, which must have as many knots as the control points plus the order.
+    pub fn knots(mut self, knots: Vec<f64>) -> Self
+    {
+        self.knots = Some(knots);
+        self
+    }
+    //..............................................................................................
+
+    /// Discards any knots given, so that the uniform clamped knot vector is generated.
+    pub fn auto_open_knots(mut self) -> Self
+    {
+        self.knots = None;
+        self
+    }
+    //..............................................................................................
+
+    /// Builds the curve.
+    ///
+    /// # Returns
+    /// The curve, or the [DescriptorError] of [Bcurve::try_new] if the pieces are inconsistent,
+    /// such as when there are too few control points for the degree.
+    pub fn build(self) -> Result<Bcurve<D>, DescriptorError>
+    {
+        let knots = match self.knots
+        {
+            Some(knots) => knots,
+            // leave the knots empty for the descriptor to report too few control points
+            None if self.cpoints.len() < self.p + 1 => Vec::new(),
+            None => spl::uniform_knots(self.cpoints.len(), self.p),
+        };
+        Bcurve::try_new(&BcurveDescriptor {
+            p: self.p,
+            knots,
+            cpoints: self.cpoints,
+            cweights: self.cweights,
+        })
+    }
+}
+//..................................................................................................
+
+impl<const D: usize> Default for BcurveBuilder<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+//}}}
 //{{{ collection: Bcurve
 //{{{ struct: Bcurve
 #[derive(Clone)]
@@ -60,15 +244,40 @@ where
     [(); D * BCURVE_DER_MAX]:,
     [(); D * 3]:,
 {
+    /// The maximum degree of a curve
+    pub const PMAX: usize = spl::PMAX;
+    /// The order of derivative below which evaluation uses stack buffers, see [BCURVE_DER_MAX]
+    pub const DER_MAX: usize = BCURVE_DER_MAX;
+    //..............................................................................................
 
     /// Standard constructor of the Bcurve.
+    ///
+    /// # Panics
+    /// If the descriptor is invalid, use [Bcurve::try_new] to handle this as an error instead.
     pub fn new(bcd: &BcurveDescriptor<D>) -> Self
     {
-        debug_assert!(bcd.p <= spl::PMAX, "Order too large");
-        debug_assert!(bcd.knots.is_sorted(), "knots not sorted");
-        debug_assert!(bcd.cweights.iter().all(|&x| x >= 0.0));
-        debug_assert!(bcd.cweights.len() == bcd.cpoints.len());
-        debug_assert!(bcd.knots.len() == bcd.cpoints.len() + bcd.p + 1);
+        match Self::try_new(bcd)
+        {
+            Ok(bcurve) => bcurve,
+            Err(err) => panic!("Invalid Bcurve descriptor: {}", err),
+        }
+    }
+    //..............................................................................................
+
+    /// Fallible constructor of the Bcurve, the descriptor is validated in all builds.
+    ///
+    /// # Returns
+    /// The curve, or a [DescriptorError] describing the first problem found with the descriptor.
+    pub fn try_new(bcd: &BcurveDescriptor<D>) -> Result<Self, DescriptorError>
+    {
+        bcd.is_valid()?;
+
+        if bcd.cpoints.windows(2).any(|w| (w[1] - w[0]).norm() < f64::RES_LINEAR)
+        {
+            //{{{ trace
+            warn!("Bcurve has coincident consecutive control points, see dedup_control_points");
+            //}}}
+        }
 
         let mut points_w = vec![Vector::<{ D + 1 }>::zeros(); bcd.cpoints.len()];
 
@@ -77,12 +286,117 @@ where
             points_w[i] = homog(&bcd.cpoints[i], bcd.cweights[i]);
         }
 
-        Self {
+        Ok(Self {
             p: bcd.p,
             knots: bcd.knots.clone(),
             cpoints_w: points_w,
             knot_multiplicites: spl::multiplicites(&bcd.knots),
             abox: None,
+        })
+    }
+    //..............................................................................................
+
+    /// Interpolates a sequence of points with a non-rational curve of degree `p`.
+    ///
+    /// The points are parameterised by chord length on $[0, 1]$ and the knots are found by
+    /// averaging the parameters, this is the global interpolation of The NURBS Book, section
+    /// 9.2.1.
+    ///
+    /// # Arguments
+    /// * `points` - The points to interpolate, at least two and with no two consecutive points
+    ///              coincident.
+    /// * `p` - The degree of the curve, reduced to `points.len() - 1` if there are too few points.
+    pub fn interpolate(points: &[Vector<D>], p: usize) -> Self
+    {
+        debug_assert!(points.len() >= 2, "Too few points to interpolate");
+        debug_assert!(p >= 1, "Invalid degree");
+        spl::check_degrees(&[("degree", p)], &[]).unwrap_or_else(|err| panic!("{}", err));
+
+        let n = points.len();
+        let p = p.min(n - 1);
+
+        let mut params = vec![0.0; n];
+        for i in 1..n
+        {
+            params[i] = params[i - 1] + (points[i] - points[i - 1]).norm();
+        }
+        let total = params[n - 1];
+        for i in 1..n
+        {
+            params[i] /= total;
+        }
+        params[n - 1] = 1.0;
+
+        let mut knots = vec![0.0; n + p + 1];
+        for j in 1..n - p
+        {
+            knots[j + p] = params[j..j + p].iter().sum::<f64>() / p as f64;
+        }
+        for j in 0..p + 1
+        {
+            knots[n + j] = 1.0;
+        }
+
+        Self::interpolate_at(points, &params, knots, p)
+    }
+    //..............................................................................................
+
+    /// Interpolates the points at the given parameters with a non-rational curve of degree `p`
+    /// on the knot vector `knots`, which must have one basis function per point and satisfy the
+    /// Schoenberg-Whitney condition for the parameters.
+    fn interpolate_at(
+        points: &[Vector<D>],
+        params: &[f64],
+        knots: Vec<f64>,
+        p: usize,
+    ) -> Self
+    {
+        let n = points.len();
+        let mut basis = na::DMatrix::<f64>::zeros(n, n);
+        let mut scratch = spl::BasisScratch::new();
+        for (i, u) in params.iter().enumerate()
+        {
+            let (start, end, _) = spl::non_zero_basis(&knots, *u, p);
+            let basis_funs = spl::eval_with(&mut scratch, &knots, *u, p);
+            for j in start..end
+            {
+                basis[(i, j)] = basis_funs[j - start];
+            }
+        }
+        let mut rhs = na::DMatrix::<f64>::zeros(n, D);
+        for (i, point) in points.iter().enumerate()
+        {
+            for k in 0..D
+            {
+                rhs[(i, k)] = point[k];
+            }
+        }
+        let sol = basis.lu().solve(&rhs).expect("interpolation system is singular");
+
+        let cpoints = (0..n)
+            .map(|i| Vector::<D>::from_fn(|k, _| sol[(i, k)]))
+            .collect();
+
+        Self::new(&BcurveDescriptor {
+            p,
+            knots,
+            cpoints,
+            cweights: vec![1.0; n],
+        })
+    }
+    //..............................................................................................
+
+    /// Reconstructs the descriptor of the curve, the inverse of [Bcurve::new].
+    ///
+    /// The weights are the last coordinates of the homogeneous control points and the control 
+    /// points are recovered by projecting out of homogeneous space.
+    pub fn to_descriptor(&self) -> BcurveDescriptor<D>
+    {
+        BcurveDescriptor {
+            p: self.p,
+            knots: self.knots.clone(),
+            cpoints: self.cpoints(),
+            cweights: self.cpoints_w.iter().map(|pw| pw[D]).collect(),
         }
     }
     //..............................................................................................
@@ -101,6 +415,42 @@ where
     }
     //..............................................................................................
 
+    /// Returns the number of control points of the curve
+    pub fn num_control_points(&self) -> usize
+    {
+        self.cpoints_w.len()
+    }
+    //..............................................................................................
+
+    /// Returns the number of knots of the curve, counted with multiplicity
+    pub fn num_knots(&self) -> usize
+    {
+        self.knots.len()
+    }
+    //..............................................................................................
+
+    /// Returns the number of non-empty knot spans in the parameter range of the curve, each of which
+    /// is a single polynomial, or rational, segment.
+    pub fn num_spans(&self) -> usize
+    {
+        let n = self.knots.len() - self.p - 1;
+        spl::multiplicites(&self.knots[self.p..n + 1]).len() - 1
+    }
+    //..............................................................................................
+
+    /// Iterates over the non-empty knot spans of the curve, as the parameters `(u_left, u_right)`
+    /// at either end of each, so that spans between repeated knots are skipped. The spans are in
+    /// increasing order and cover the knots $u_{p}, \ldots, u_{n}$, where $n$ is the number of
+    /// control points, which is the parameter range of a clamped curve. The knots past the ends of
+    /// an unclamped curve do not bound spans, so there are [Bcurve::num_spans] of them.
+    pub fn spans(&self) -> impl Iterator<Item = (f64, f64)> + '_
+    {
+        let n = self.knots.len() - self.p - 1;
+        let mults = spl::multiplicites(&self.knots[self.p..n + 1]);
+        (1..mults.len()).map(move |i| (mults[i - 1].0, mults[i].0))
+    }
+    //..............................................................................................
+
     /// Accessor to the control points in homogeneous coordinates
     pub fn cpoints_w(&self) -> &Vec<Vector<{ D + 1 }>>
     {
@@ -111,662 +461,3609 @@ where
     /// Computor of the control points in real coordinates.
     pub fn cpoints(&self) -> Vec<Vector<D>> 
     {
-        self.cpoints_w.iter().cloned().map(|v| inv_homog(&v)).collect()
+        self.cpoints_iter().collect()
     }
     //..............................................................................................
 
-    /// Returns whether the bcurve is rational and so is a NURBS curve, or is merely a non-rational
-    /// Bcurve
-    pub fn is_rational(&self) -> bool
+    /// Iterates over the control points in real coordinates, converting each one as it is reached
+    /// rather than collecting them as [Bcurve::cpoints] does.
+    pub fn cpoints_iter(&self) -> impl Iterator<Item = Vector<D>> + '_
     {
-        let w = self.cpoints_w[0][D];
-        let is_rat = self.cpoints_w.iter().any(|v| v[D] != w);  
-        is_rat
+        self.cpoints_w.iter().map(|v| inv_homog(v))
     }
     //..............................................................................................
 
-    pub fn multiplicity(&self, u: f64) -> usize {
-
-        let knot_mult_result = self.knot_multiplicites.iter().find(|&x| spl::knot_eq(x.0, u));
-        let mult = match knot_mult_result {
-            Some(knot_mult) => knot_mult.1,
-            None => 0,
-        };  
-        mult
+    /// Returns the point at the start of the curve, the first control point of the clamped curve.
+    pub fn start_point(&self) -> Vector<D>
+    {
+        debug_assert!(self.is_clamped_start(), "curve is not clamped");
+        inv_homog(&self.cpoints_w[0])
     }
     //..............................................................................................
 
-    /// Returns the curvature function as a function object which does not borrow the calling object.
-    pub fn curvature_fn(&self) -> impl Fn(f64) -> f64 {
-        let self_clone = self.clone();
-        move |u| self_clone.eval_curvature(u)
+    /// Returns the point at the end of the curve, the last control point of the clamped curve.
+    pub fn end_point(&self) -> Vector<D>
+    {
+        debug_assert!(self.is_clamped_end(), "curve is not clamped");
+        inv_homog(self.cpoints_w.last().unwrap())
     }
-}
-//}}}
-//{{{ impl: Curve for  Bcurve
-impl<const D: usize> Curve for Bcurve<D>
-where
-    [(); D + 1]:,
-    [(); D * BCURVE_DER_MAX]:,
-    [(); D * 3]:,
-{
-    //{{{ type: Vector
-    type Vector = Vector<D>;
-    //}}}
-    //{{{ fun: eval
-    fn eval(
-        &self,
-        u: f64,
-    ) -> Vector<D>
+    //..............................................................................................
+
+    /// Returns the first derivative of the curve at its start, this is not normalised.
+    ///
+    /// For the clamped curve this is 
+    /// $\frac{p}{u_{p+1} - u_{0}} \frac{w_{1}}{w_{0}} (P_{1} - P_{0})$.
+    pub fn start_tangent(&self) -> Vector<D>
     {
-        debug_assert!(spl::is_member(&self.knots, u));
+        debug_assert!(self.is_clamped_start(), "curve is not clamped");
+        let (pw0, pw1) = (&self.cpoints_w[0], &self.cpoints_w[1]);
+        let scale = self.p as f64 / (self.knots[self.p + 1] - self.knots[0]);
+        (inv_homog(pw1) - inv_homog(pw0)) * (scale * pw1[D] / pw0[D])
+    }
+    //..............................................................................................
 
-        let mut pointw_tmp = Vector::<{ D + 1 }>::from_element(0.0);
-        let (start, end, _nb) = spl::non_zero_basis(&self.knots, u, self.p);
+    /// Returns the first derivative of the curve at its end, this is not normalised.
+    ///
+    /// For the clamped curve, with $n + 1$ control points and $m + 1$ knots, this is 
+    /// $\frac{p}{u_{m} - u_{m-p-1}} \frac{w_{n-1}}{w_{n}} (P_{n} - P_{n-1})$.
+    pub fn end_tangent(&self) -> Vector<D>
+    {
+        debug_assert!(self.is_clamped_end(), "curve is not clamped");
+        let n = self.cpoints_w.len() - 1;
+        let m = self.knots.len() - 1;
+        let (pw0, pw1) = (&self.cpoints_w[n - 1], &self.cpoints_w[n]);
+        let scale = self.p as f64 / (self.knots[m] - self.knots[m - self.p - 1]);
+        (inv_homog(pw1) - inv_homog(pw0)) * (scale * pw0[D] / pw1[D])
+    }
+    //..............................................................................................
 
-        let mut basis_funs = [0.0; spl::PMAX];
-        spl::eval(&self.knots, u, self.p, &mut basis_funs);
+    /// Whether the first knot has multiplicity $p + 1$, so the curve starts at its first control 
+    /// point.
+    fn is_clamped_start(&self) -> bool
+    {
+        self.knot_multiplicites[0].1 == self.p + 1
+    }
+    //..............................................................................................
 
-        for i in start..end
-        {
-            pointw_tmp += basis_funs[i - start] * self.cpoints_w[i];
-        }
-        let point = inv_homog(&pointw_tmp);
-        point
+    /// Whether the last knot has multiplicity $p + 1$, so the curve ends at its last control 
+    /// point.
+    fn is_clamped_end(&self) -> bool
+    {
+        self.knot_multiplicites.last().unwrap().1 == self.p + 1
     }
     //..............................................................................................
-    //}}}
-    //{{{ fun: eval_diff
-    fn eval_diff(
-        &self,
-        u: f64,
-        m: usize,
-    ) -> Vector<D>
+
+    /// Clamps `u` onto the ends of the parameter range if it lies just outside of it due to 
+    /// floating point noise, see [spl::clamp_to_domain].
+    pub fn clamp_to_domain(&self, u: f64) -> f64
     {
-        debug_assert!(spl::is_member(&self.knots, u));
+        spl::clamp_to_domain(&self.knots, u)
+    }
+    //..............................................................................................
 
-        if m == 0
+    /// Brings any parameter `u` into the parameter range $[u_{1}, u_{2}]$, so that code stepping
+    /// along the curve by `u + du` can pass over the seam of a closed curve.
+    ///
+    /// For a periodic curve `u` is wrapped into $[u_{1}, u_{2})$ modulo the period $u_{2} - u_{1}$,
+    /// otherwise it is clamped onto the nearest end of the range. Whether the curve is periodic is
+    /// passed in, so that loops stepping along the curve find it once with [Bcurve::is_periodic]
+    /// rather than at every step.
+    ///
+    /// # Arguments
+    /// * `u` - The parameter to bring into the range.
+    /// * `periodic` - Whether the curve is periodic, as found by [Bcurve::is_periodic].
+    pub fn wrap_param(&self, u: f64, periodic: bool) -> f64
+    {
+        let (u1, u2) = self.param_range();
+        if periodic
         {
-            self.eval(u)
+            u1 + (u - u1).rem_euclid(u2 - u1)
         }
         else
         {
-            let mut diff_loc = [Vector::<D>::zeros(); BCURVE_DER_MAX];
-            self.eval_diff_all(u, m, &mut diff_loc);
-            diff_loc[m]
+            u.clamp(u1, u2)
         }
     }
     //..............................................................................................
-    //}}}
-    //{{{ fun: eval_diff_all 
-    fn eval_diff_all(
-        &self,
-        u: f64,
-        k: usize,
-        ders: &mut [Vector<D>],
-    )
+
+    /// Locates `u` within the knot spans of the curve.
+    ///
+    /// The span is the one found by [spl::find_span], so a knot belongs to the span on its right
+    /// except for the end of the parameter range, which belongs to the last non-empty span.
+    ///
+    /// # Returns
+    /// A tuple of the span index $i$ and the local parameter $(u - u_{i}) / (u_{i+1} - u_{i})$,
+    /// which lies in $[0, 1)$, or is $1$ at the end of the parameter range.
+    pub fn local_param(&self, u: f64) -> (usize, f64)
     {
+        let u = self.clamp_to_domain(u);
+        debug_assert!(spl::is_member(&self.knots, u), "parameter outside the domain");
+
+        let span = spl::find_span(&self.knots, u, self.p);
+        let (left, right) = (self.knots[span], self.knots[span + 1]);
+        (span, ((u - left) / (right - left)).clamp(0.0, 1.0))
+    }
+    //..............................................................................................
+
+    /// Evaluates the homogeneous point of the curve at `u`, shared by [Curve::eval] and
+    /// [Curve::try_eval] which differ only in how they project it.
+    fn eval_pointw(&self, u: f64) -> Vector<{ D + 1 }>
+    {
+        let u = self.clamp_to_domain(u);
         debug_assert!(spl::is_member(&self.knots, u));
-        debug_assert!(ders.len() >= k + 1);
 
-        if k == 0
-        {
-            ders[0] = self.eval(u);
-        }
-        else
-        {
-            let dim = k + 1;
-            let mut dersw = [Vector::<{ D + 1 }>::zeros(); BCURVE_DER_MAX];
-            let (start, _, num_basis) = spl::non_zero_basis(&self.knots, u, self.p);
+        let basis = spl::basis_at(&self.knots, self.p, u);
+        basis.combine(|i| self.cpoints_w[i])
+    }
+    //..............................................................................................
 
-            let mut basis_ders = [0.0; BCURVE_DER_MAX * BCURVE_DER_MAX];
-            spl::eval_diff_all(&self.knots, u, self.p, k, &mut basis_ders);
-            let basis_ders_arr = NDArrayWrapper::<'_, f64, 2>::new(&mut basis_ders, &[num_basis, k + 1]);
+    /// Evaluates the point of the curve and its first `k` derivatives at `u` in a single pass,
+    /// as [Curve::eval_diff_all] does but without a buffer to size.
+    ///
+    /// Panics if `k` exceeds [BCURVE_DER_MAX] or [Curve::max_der] at `u`.
+    ///
+    /// # Returns
+    /// The `k + 1` vectors $\mathbf{C}^{(0)}(u), ..., \mathbf{C}^{(k)}(u)$.
+    pub fn eval_with_ders(&self, u: f64, k: usize) -> ArrayVec<Vector<D>, { BCURVE_DER_MAX + 1 }>
+    {
+        spl::check_degrees(&[], &[("derivative order", k)]).unwrap_or_else(|err| panic!("{}", err));
+        let max_der = self.max_der(u);
+        assert!(k <= max_der, "derivative order {} exceeds the maximum of {} at {}", k, max_der, u);
+
+        let mut ders: ArrayVec<Vector<D>, { BCURVE_DER_MAX + 1 }> =
+            (0..k + 1).map(|_| Vector::<D>::zeros()).collect();
+        self.eval_diff_all(u, k, &mut ders);
+        ders
+    }
+    //..............................................................................................
 
-            for m in 0..k + 1
-            // loop over derivatives
+    /// Merges consecutive control points which are closer together than `tol`.
+    ///
+    /// Coincident control points give zero length tangents at the ends of a clamped curve and
+    /// degenerate boxes. For each merged pair one control point is removed along with one knot,
+    /// the interior knot nearest the middle of its support. Where there is no interior knot left
+    /// the degree is lowered instead, so that the curve stays clamped. The end points of the curve
+    /// are preserved, but in general the rest of the curve changes shape, so `tol` should only be
+    /// large enough to catch points which are coincident up to noise.
+    ///
+    /// # Returns
+    /// The number of control points removed.
+    pub fn dedup_control_points(&mut self, tol: f64) -> usize
+    {
+        let mut num_removed = 0;
+        let mut i = 0;
+        while i + 1 < self.cpoints_w.len()
+        {
+            let dist = (inv_homog(&self.cpoints_w[i + 1]) - inv_homog(&self.cpoints_w[i])).norm();
+            if dist >= tol || self.cpoints_w.len() <= 2
             {
-                for j in 0..num_basis
-                {
-                    let nj = basis_ders_arr[&[j, m]];
-                    let pwj = self.cpoints_w[start + j];
-                    dersw[m] += nj * pwj;
-                }
+                i += 1;
+                continue;
             }
 
-            let mut binom = [0.0; BCURVE_DER_MAX * BCURVE_DER_MAX];
-            binom_coeff(k, &mut binom);
-            let binom_arr = NDArrayWrapper::<'_, f64, 2>::new(&mut binom, &[dim, dim]);
-
-            let mut ders_loc = [Vector::<D>::zeros(); BCURVE_DER_MAX];
-            let w0 = dersw[0][D];
-            let mut v = Vector::<D>::zeros();
+            // keep the end points in place by removing the inner point of the pair
+            let r = if i + 2 == self.cpoints_w.len() { i } else { i + 1 };
+            self.cpoints_w.remove(r);
 
-            for m in 0..k + 1
+            let m = self.knots.len() - 1;
+            if m + 1 > 2 * (self.p + 1)
             {
-                v.fill(0.0);
-                v.copy_from(&dersw[m].rows(0, D));
-
-                for j in 1..m + 1
-                {
-                    let wj = dersw[j][D];
-                    let bmj = binom_arr[&[m, j]];
-                    v -= bmj * wj * ders_loc[m - j];
-                }
-                ders_loc[m] = v / w0;
-                ders[m] = ders_loc[m];
+                let k = (r + (self.p + 1) / 2).clamp(self.p + 1, m - self.p - 1);
+                self.knots.remove(k);
+            }
+            else
+            {
+                self.p -= 1;
+                self.knots.remove(m);
+                self.knots.remove(0);
             }
+            num_removed += 1;
+        }
+
+        if num_removed > 0
+        {
+            self.knot_multiplicites = spl::multiplicites(&self.knots);
+            self.invalidate_box();
         }
+        num_removed
     }
     //..............................................................................................
-    //}}}
-    //{{{ eval_arclen
-    fn eval_arclen(
-        &self,
-        u1: f64,
-        u2: f64,
-    ) -> f64
+
+    /// Moves the `i`'th control point to `cpoint`, keeping its weight.
+    ///
+    /// This invalidates the cached bounding box.
+    pub fn set_cpoint(&mut self, i: usize, cpoint: &Vector<D>)
     {
-        let out = 0.0;
-        out
+        let w = self.cpoints_w[i][D];
+        self.cpoints_w[i] = homog(cpoint, w);
+        self.invalidate_box();
     }
     //..............................................................................................
-    //}}}
-    //{{{ is_member
-    fn is_member(
-            &self,
-            u: f64,
-        ) -> bool {
-        spl::is_member(&self.knots, u)
+
+    /// Sets the weight of the `i`'th control point, keeping its position.
+    ///
+    /// This invalidates the cached bounding box.
+    pub fn set_weight(&mut self, i: usize, w: f64)
+    {
+        debug_assert!(w >= 0.0);
+        let cpoint = inv_homog(&self.cpoints_w[i]);
+        self.cpoints_w[i] = homog(&cpoint, w);
+        self.invalidate_box();
     }
     //..............................................................................................
-    //}}}
-    //{{{ fun: dim
-    fn dim(&self) -> usize {
-        D
-    }
+
+    /// Returns whether the end of the curve meets its start, to within the linear resolution
+    pub fn is_closed(&self) -> bool
+    {
+        let (u1, u2) = self.param_range();
+        (self.eval(u2) - self.eval(u1)).norm() < f64::RES_LINEAR
+    }
     //..............................................................................................
-    //}}}
-    //{{{ fun: max_der
-    fn max_der(&self, u: f64) -> usize {
-        if self.is_rational() 
+
+    /// Returns whether the curve is periodic, that is it is closed and its first $p - 1$
+    /// derivatives agree across the seam, as for a curve built from a periodic knot vector.
+    pub fn is_periodic(&self) -> bool
+    {
+        let (u1, u2) = self.param_range();
+        self.is_closed()
+            && (1..self.p).all(|k| {
+                let (d1, d2) = (self.eval_diff(u1, k), self.eval_diff(u2, k));
+                (d2 - d1).norm() <= f64::RES_LINEAR * d1.norm().max(1.0)
+            })
+    }
+    //..............................................................................................
+
+    /// Returns whether the bcurve is rational and so is a NURBS curve, or is merely a non-rational
+    /// Bcurve
+    pub fn is_rational(&self) -> bool
+    {
+        let w = self.cpoints_w[0][D];
+        let is_rat = self.cpoints_w.iter().any(|v| v[D] != w);  
+        is_rat
+    }
+    //..............................................................................................
+
+    /// Returns the knot which `u` coincides with, to within [spl::knot_eq], and its multiplicity,
+    /// or `None` if `u` is not at a knot.
+    pub fn is_at_knot(&self, u: f64) -> Option<(f64, usize)>
+    {
+        self.knot_multiplicites.iter().find(|x| spl::knot_eq(x.0, u)).copied()
+    }
+    //..............................................................................................
+
+    pub fn multiplicity(&self, u: f64) -> usize {
+
+        let knot_mult_result = self.is_at_knot(u);
+        let mult = match knot_mult_result {
+            Some(knot_mult) => knot_mult.1,
+            None => 0,
+        };  
+        mult
+    }
+    //..............................................................................................
+
+    /// Returns the order of continuity $k$ of the curve at the parameter `u`, meaning the curve is
+    /// $C^{k}$ there.
+    ///
+    /// This is $p - m$ where $m$ is the multiplicity of `u` in the knot vector, so away from the
+    /// knots this is `p`, although the curve is infinitely differentiable there. A knot of 
+    /// multiplicity $p + 1$ makes the curve discontinuous, this is also reported as 0.
+    pub fn continuity_at(&self, u: f64) -> usize
+    {
+        debug_assert!(self.is_member(u));
+        self.p.saturating_sub(self.multiplicity(u))
+    }
+    //..............................................................................................
+
+    /// Returns the indices of the control points which influence the curve at `u`, those whose 
+    /// basis functions are non-zero on the knot span containing `u`, as found by 
+    /// [spl::non_zero_basis].
+    ///
+    /// Moving any other control point leaves the curve unchanged at `u`. The range always holds
+    /// $p + 1$ points, at the ends of a clamped curve the first or last $p + 1$, although only the 
+    /// end point itself is then weighted.
+    pub fn active_control_points(&self, u: f64) -> std::ops::Range<usize>
+    {
+        let u = self.clamp_to_domain(u);
+        let (start, end, _) = spl::non_zero_basis(&self.knots, u, self.p);
+        start..end
+    }
+    //..............................................................................................
+
+    /// Computes cheap bounds on the arc length of the curve without integrating it.
+    ///
+    /// The lower bound is the chord between the end points. The upper bound is the length of the
+    /// control polygon, since knot insertion replaces polygon edges by shorter paths along them and
+    /// the polygon converges to the curve, this holds for rational curves with positive weights too.
+    ///
+    /// # Returns
+    /// A tuple `(lower, upper)` bracketing the arc length.
+    pub fn length_bounds(&self) -> (f64, f64)
+    {
+        let cpoints = self.cpoints();
+        let chord = (cpoints[cpoints.len() - 1] - cpoints[0]).norm();
+        let polygon = cpoints.windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+        (chord, polygon)
+    }
+    //..............................................................................................
+
+    /// Finds the parameter at which the arc length measured from the start of the curve is `s`.
+    ///
+    /// The knot span containing the point is located first and the arc length is then inverted 
+    /// within it by Newton's method, safeguarded by bisection.
+    ///
+    /// # Arguments
+    /// * `s` - The arc length from the start of the curve, clamped to the length of the curve.
+    pub fn arc_length_param(&self, s: f64) -> f64
+    {
+        let (u_start, u_end) = self.param_range();
+        if s <= 0.0
         {
-            BCURVE_DER_MAX
+            return u_start;
         }
-        else 
+
+        //{{{ com: locate the knot span [a, b] containing the point, at arc length s_a from the start 
+        let mut a = u_start;
+        let mut s_a = 0.0;
+        let mut b = u_end;
+        let mut span_length = 0.0;
+        for (knot, _) in self.knot_multiplicites.iter().skip(1)
         {
-            self.p
+            span_length = self.eval_arclen(a, *knot);
+            if s_a + span_length >= s
+            {
+                b = *knot;
+                break;
+            }
+            s_a += span_length;
+            a = *knot;
+        }
+        if a >= u_end
+        {
+            return u_end;
+        }
+        //}}}
+        //{{{ com: invert the arc length within the span
+        let target = s - s_a;
+        let (mut lo, mut hi) = (a, b);
+        let mut u = a + (b - a) * target / span_length;
+        for _ in 0..ARCLEN_ITER_MAX
+        {
+            let f = self.eval_arclen(a, u) - target;
+            if f.abs() < f64::RES_LINEAR
+            {
+                break;
+            }
+            if f > 0.0 { hi = u; } else { lo = u; }
+
+            let next = u - f / self.eval_diff(u, 1).norm();
+            u = if next > lo && next < hi { next } else { 0.5 * (lo + hi) };
         }
+        //}}}
+        u
     }
-    //}}}
-    //{{{ fun: param_range
-    fn param_range(&self) -> (f64, f64) {
-        (self.knots[0], self.knots[self.knots.len() - 1])
+    //..............................................................................................
+
+    /// Returns `n + 1` parameters which divide the curve into `n` pieces of equal arc length,
+    /// including the ends of the parameter range. An `n` of 0 is taken as 1.
+    pub fn arc_length_params(&self, n: usize) -> Vec<f64>
+    {
+        let n = n.max(1);
+        let (u1, u2) = self.param_range();
+        let length = self.eval_arclen(u1, u2);
+        let mut params: Vec<f64> = (0..=n)
+            .map(|i| self.arc_length_param(length * i as f64 / n as f64))
+            .collect();
+        params[0] = u1;
+        params[n] = u2;
+        params
     }
-    //}}}
-}
-//}}}
-//}}}
-//{{{ fun: binom_coeff 
-fn binom_coeff(
-    n: usize,
-    binom: &mut [f64],
-)
-{
-    debug_assert!(binom.len() >= (n + 1) * (n + 1));
+    //..............................................................................................
 
-    binom.fill(0.0);
-    let mut binom_arr = NDArrayWrapper::<'_, f64, 2>::new(binom, &[n + 1, n + 1]);
+    /// Returns parameters which divide each non-empty knot span into `n` pieces of equal parameter
+    /// length, so that every span is sampled equally however wide it is. The parameters are in
+    /// increasing order and include the knots and the ends of the parameter range, there are
+    /// `n * num_spans + 1` of them. An `n` of 0 is taken as 1.
+    pub fn per_span_params(&self, n: usize) -> Vec<f64>
+    {
+        let n = n.max(1);
+        let mut params = Vec::with_capacity(n * self.num_spans() + 1);
+        for (a, b) in self.spans()
+        {
+            params.extend((0..n).map(|i| a + (b - a) * i as f64 / n as f64));
+        }
+        params.push(self.param_range().1);
+        params
+    }
+    //..............................................................................................
 
-    for i in 0..n + 1
+    /// Returns the curvature function as a function object which does not borrow the calling object.
+    pub fn curvature_fn(&self) -> impl Fn(f64) -> f64 {
+        let self_clone = self.clone();
+        move |u| self_clone.eval_curvature(u)
+    }
+    //..............................................................................................
+
+    /// Finds the point of greatest curvature on the curve.
+    ///
+    /// The curvature is sampled uniformly within each non-empty knot span, every local maximum of
+    /// the samples is bracketed by its neighbouring samples and refined by minimising the negated
+    /// curvature. Since the curvature may be discontinuous at the knots, the maxima are sought on 
+    /// each side of them. Where the curve is straight throughout the curvature is zero everywhere
+    /// and the start of the curve is returned.
+    ///
+    /// # Returns
+    /// A tuple `(u, kappa)` of the parameter at which the curvature is greatest and the curvature
+    /// there.
+    pub fn max_curvature(&self) -> (f64, f64)
     {
-        binom_arr[&[i, i]] = 1.0;
-        binom_arr[&[i, 0]] = 1.0;
+        let (u0, _) = self.param_range();
+        let mut params = Vec::new();
+        for (a, b) in self.spans()
+        {
+            for i in 0..MAX_CURV_SAMPLES_PER_SPAN + 1
+            {
+                params.push(a + (b - a) * i as f64 / MAX_CURV_SAMPLES_PER_SPAN as f64);
+            }
+        }
+        let kappas: Vec<f64> = params.iter().map(|&u| self.eval_curvature(u)).collect();
+
+        let mut best = (u0, 0.0);
+        for (i, &kappa) in kappas.iter().enumerate()
+        {
+            if kappa > best.1
+            {
+                best = (params[i], kappa);
+            }
+        }
+        if best.1 <= 0.0
+        {
+            return best;
+        }
+
+        let mut opts = CurveMinValOpts::default();
+        let n = params.len();
+        for i in 0..n
+        {
+            let is_peak = (i == 0 || kappas[i] >= kappas[i - 1])
+                && (i == n - 1 || kappas[i] >= kappas[i + 1]);
+            if !is_peak || kappas[i] <= 0.0
+            {
+                continue;
+            }
+            // samples come in one run per span, so the bracket is kept within the span of the peak
+            let lo = if i > 0 && params[i - 1] < params[i] { params[i - 1] } else { params[i] };
+            let hi = if i < n - 1 && params[i + 1] > params[i] { params[i + 1] } else { params[i] };
+            if hi <= lo
+            {
+                continue;
+            }
+            opts.bounds = Some((lo, hi));
+            let (u, neg_kappa) = self.min_value_scalar(|u| -self.eval_curvature(u), &opts);
+            if -neg_kappa > best.1
+            {
+                best = (u, -neg_kappa);
+            }
+        }
+        best
     }
+    //..............................................................................................
 
-    for n2 in 2..n + 1
+    /// Inserts the knot `u` into the curve `r` times, without changing the shape or
+    /// parameterisation of the curve.
+    ///
+    /// This is algorithm A5.1 of The NURBS Book, applied to the homogeneous control points.
+    ///
+    /// # Arguments
+    /// * `u` - The knot value to insert, must be strictly inside the parameter range.
+    /// * `r` - The number of times to insert `u`, the final multiplicity must not exceed `p`.
+    pub fn insert_knot(&self, u: f64, r: usize) -> Bcurve<D>
     {
-        for k2 in 1..n2
+        let (knots, cpoints_w) = spl::insert_knot(&self.knots, self.p, &self.cpoints_w, u, r);
+        Bcurve {
+            p: self.p,
+            knot_multiplicites: spl::multiplicites(&knots),
+            knots,
+            cpoints_w,
+            abox: None,
+        }
+    }
+    //..............................................................................................
+
+    /// Splits the curve at the parameter value `u` into two curves, the first covering
+    /// $[u_{0}, u]$ and the second covering $[u, u_{m}]$.
+    ///
+    /// Both halves retain the parameterisation of the original curve.
+    pub fn split(&self, u: f64) -> (Bcurve<D>, Bcurve<D>)
+    {
+        let ((knots_left, cpoints_left), (knots_right, cpoints_right)) = 
+            spl::split(&self.knots, self.p, &self.cpoints_w, u);
+
+        let left = Bcurve {
+            p: self.p,
+            knot_multiplicites: spl::multiplicites(&knots_left),
+            knots: knots_left,
+            cpoints_w: cpoints_left,
+            abox: None,
+        };
+        let right = Bcurve {
+            p: self.p,
+            knot_multiplicites: spl::multiplicites(&knots_right),
+            knots: knots_right,
+            cpoints_w: cpoints_right,
+            abox: None,
+        };
+        (left, right)
+    }
+    //..............................................................................................
+
+    /// Evaluates the blossom of the polynomial piece of the curve over the knot span `span` at the
+    /// arguments `args`, one per degree, by the de Boor algorithm with argument `args[r - 1]` at
+    /// level `r`.
+    ///
+    /// # Returns
+    /// The homogeneous point $\mathbf{P}(t_{1}, ..., t_{p})$.
+    fn blossom(&self, span: usize, args: &[f64]) -> Vector<{ D + 1 }>
+    {
+        let p = self.p;
+        debug_assert!(args.len() == p, "one argument is needed per degree");
+
+        let mut points = [Vector::<{ D + 1 }>::zeros(); spl::PMAX + 1];
+        points[..=p].copy_from_slice(&self.cpoints_w[span - p..=span]);
+        for r in 1..=p
         {
-            binom_arr[&[n2, k2]] = binom_arr[&[n2 - 1, k2 - 1]] + binom_arr[&[n2 - 1, k2]];
+            let u = args[r - 1];
+            for k in (r..=p).rev()
+            {
+                let (a, b) = (self.knots[span + k - p], self.knots[span + 1 + k - r]);
+                let alpha = (u - a) / (b - a);
+                points[k] = points[k - 1] * (1.0 - alpha) + points[k] * alpha;
+            }
         }
+        points[p]
     }
-}
-//}}}
+    //..............................................................................................
 
-//-------------------------------------------------------------------------------------------------
-//{{{ mod: tests
-#[cfg(test)]
-mod tests
-{
-  
-    use approx::{assert_abs_diff_eq, assert_relative_eq, ulps_eq, AbsDiff};
-    use serde::Deserialize;
-    use std::fs;
+    /// Returns the homogeneous Bézier control points of the non-empty knot span `span`, the
+    /// blossoms $\mathbf{P}(a, ..., a, b, ..., b)$ of the span $[a, b]$. Only the $p + 1$ control
+    /// points of the span are read.
+    ///
+    /// # Returns
+    /// The first $p + 1$ entries hold the Bézier control points.
+    fn bezier_points(&self, span: usize) -> [Vector<{ D + 1 }>; spl::PMAX + 1]
+    {
+        debug_assert!(self.knots[span] < self.knots[span + 1], "span is empty");
 
-    use crate::geometry::common::Curve;
-    use crate::test_utils::test_bcurve::load_bcurve;
-    use crate::utilities::NDArrayWrapper;
-    use crate::test_utils::{test_bcurve::TestData, convert, de_noise};
+        let p = self.p;
+        let (a, b) = (self.knots[span], self.knots[span + 1]);
+        let mut args = [a; spl::PMAX];
+        let mut points = [Vector::<{ D + 1 }>::zeros(); spl::PMAX + 1];
+        for i in 0..=p
+        {
+            points[i] = self.blossom(span, &args[..p]);
+            if i < p
+            {
+                args[p - 1 - i] = b;
+            }
+        }
+        points
+    }
+    //..............................................................................................
 
-    use super::*;
+    /// Splits the curve into its Bézier segments, one for each non-empty knot span, each keeps the
+    /// parameterisation of the curve over its span.
+    pub fn to_bezier_segments(&self) -> Vec<Bcurve<D>>
+    {
+        debug_assert!(self.is_clamped_start() && self.is_clamped_end(), "curve is not clamped");
 
+        let spans = self.spans();
+        spl::decompose(&self.knots, self.p, &self.cpoints_w)
+            .into_iter()
+            .zip(spans)
+            .map(|(cpoints_w, (a, b))| {
+                let mut knots = vec![a; self.p + 1];
+                knots.extend(vec![b; self.p + 1]);
+                Bcurve {
+                    p: self.p,
+                    knot_multiplicites: spl::multiplicites(&knots),
+                    knots,
+                    cpoints_w,
+                    abox: None,
+                }
+            })
+            .collect()
+    }
+    //..............................................................................................
 
-    #[test]
-    fn binomcoeff()
+    /// Evaluates the curve at `u` by the de Casteljau algorithm on the Bézier segment of the knot
+    /// span containing `u`.
+    ///
+    /// The repeated convex combinations of de Casteljau are numerically more stable than the sum
+    /// of the basis functions used by [Curve::eval], and reproduce the end points of each segment
+    /// exactly. Only the Bézier control points of the span containing `u` are formed.
+    pub fn eval_de_casteljau(&self, u: f64) -> Vector<D>
     {
-        let mut binom = [0.0; 36];
-        binom_coeff(5, &mut binom);
-        let binom_arr = NDArrayWrapper::<'_, f64, 2>::new(&mut binom, &[6, 6]);
-        assert!(ulps_eq!(binom_arr[&[5, 0]], 1.0, max_ulps = 4));
-        assert!(ulps_eq!(binom_arr[&[5, 1]], 5.0, max_ulps = 4));
-        assert!(ulps_eq!(binom_arr[&[5, 2]], 10.0, max_ulps = 4));
-        assert!(ulps_eq!(binom_arr[&[5, 3]], 10.0, max_ulps = 4));
-        assert!(ulps_eq!(binom_arr[&[5, 4]], 5.0, max_ulps = 4));
-        assert!(ulps_eq!(binom_arr[&[5, 5]], 1.0, max_ulps = 4));
+        let (span, t) = self.local_param(u);
+        let mut points = self.bezier_points(span);
+        for k in 1..self.p + 1
+        {
+            for i in 0..self.p + 1 - k
+            {
+                points[i] = points[i] * (1.0 - t) + points[i + 1] * t;
+            }
+        }
+        inv_homog(&points[0])
     }
+    //..............................................................................................
 
-    #[test]
-    fn construction()
+    /// Evaluates the curve at `u` by the de Boor algorithm, the repeated convex combinations of
+    /// the homogeneous control points of the knot span containing `u`.
+    ///
+    /// Unlike [Curve::eval] no basis functions are formed, whose evaluation divides by differences
+    /// of knots which become small near coincident knots, and unlike
+    /// [Bcurve::eval_de_casteljau] the span is not first converted to Bézier form.
+    pub fn eval_de_boor(&self, u: f64) -> Vector<D>
     {
-        let test_data = TestData::new();
-        let bcurve = load_bcurve::<3>(3, &test_data);
+        let u = self.clamp_to_domain(u);
+        let j = spl::find_span(&self.knots, u, self.p);
+        inv_homog(&self.blossom(j, &[u; spl::PMAX][..self.p]))
     }
     //..............................................................................................
 
-    macro_rules! eval {
-        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $points: ident, $dim: expr, $order:expr) => {
-            #[test]
-            fn $test_name()
+    /// Reparameterises the curve onto $[u_{1}, u_{2}]$ by an affine map of its knots, the geometry 
+    /// of the curve is unchanged.
+    pub fn set_param_range(&mut self, u1: f64, u2: f64)
+    {
+        debug_assert!(u2 > u1);
+        let (a, b) = self.param_range();
+        let scale = (u2 - u1) / (b - a);
+        for knot in self.knots.iter_mut()
+        {
+            *knot = u1 + (*knot - a) * scale;
+        }
+        let n = self.knots.len();
+        self.knots[0] = u1;
+        self.knots[n - 1] = u2;
+        self.knot_multiplicites = spl::multiplicites(&self.knots);
+    }
+    //..............................................................................................
+
+    /// Raises the degree of the curve by `t` without changing its shape or parameterisation.
+    ///
+    /// The homogeneous control points are elevated by [spl::elevate_degree], so the new knot
+    /// vector is that of [spl::elevate_knots]. The curve must be continuous, with no interior knot
+    /// of multiplicity greater than $p$.
+    pub fn elevate_degree(&self, t: usize) -> Bcurve<D>
+    {
+        spl::check_degrees(&[("elevated degree", self.p + t)], &[])
+            .unwrap_or_else(|err| panic!("{}", err));
+        debug_assert!(
+            self.knot_multiplicites[1..self.knot_multiplicites.len() - 1].iter().all(|(_, m)| *m <= self.p),
+            "curve is discontinuous"
+        );
+        if t == 0
+        {
+            return self.clone();
+        }
+
+        let (knots, cpoints_w) = spl::elevate_degree(&self.knots, self.p, &self.cpoints_w, t);
+        Bcurve {
+            p: self.p + t,
+            knot_multiplicites: spl::multiplicites(&knots),
+            knots,
+            cpoints_w,
+            abox: None,
+        }
+    }
+    //..............................................................................................
+
+    /// Reparameterises the curve so that at each parameter $u$ of `reference` it is at the same
+    /// fraction of its arc length as `reference` is, after which the curve has the parameter range
+    /// of `reference`.
+    ///
+    /// The reparameterised curve is not in general a B-spline, so it is approximated by a
+    /// non-rational cubic interpolating the curve at the Greville abscissae of a uniform knot
+    /// vector, with several spans for each span of either curve. In particular the exact rational
+    /// form of a conic is replaced by the fit. The fit is measured against the reparameterised
+    /// curve midway between the interpolated parameters, and is only accepted if it is within
+    /// `tol` there.
+    ///
+    /// # Arguments
+    /// * `reference` - The curve whose parameterisation is matched, usually of the same geometry.
+    /// * `tol` - The largest distance allowed between the fit and the reparameterised curve.
+    ///
+    /// # Returns
+    /// The measured distance between the fit and the reparameterised curve, or
+    /// [ModellerError::NonConvergence] if it exceeds `tol`, in which case the curve is unchanged.
+    pub fn align_parameterization(&mut self, reference: &Bcurve<D>, tol: f64) -> Result<f64, ModellerError>
+    {
+        let (r1, r2) = reference.param_range();
+        let (u1, u2) = self.param_range();
+        let ref_length = reference.eval_arclen(r1, r2);
+        let length = self.eval_arclen(u1, u2);
+        let target = |u: f64| {
+            let fraction = reference.eval_arclen(r1, u) / ref_length;
+            self.eval(self.arc_length_param(fraction * length))
+        };
+
+        let spans = ALIGN_SPANS_PER_SPAN
+            * (self.knot_multiplicites.len() + reference.knot_multiplicites.len() - 2);
+        let mut knots = vec![r1; ALIGN_FIT_DEGREE + 1];
+        knots.extend((1..spans).map(|i| r1 + (r2 - r1) * i as f64 / spans as f64));
+        knots.extend(vec![r2; ALIGN_FIT_DEGREE + 1]);
+        let params = spl::greville(&knots, ALIGN_FIT_DEGREE);
+
+        let points: Vec<Vector<D>> = params.iter().map(|u| target(*u)).collect();
+        let fit = Self::interpolate_at(&points, &params, knots, ALIGN_FIT_DEGREE);
+
+        let error = params
+            .windows(2)
+            .map(|w| {
+                let u = 0.5 * (w[0] + w[1]);
+                (fit.eval(u) - target(u)).norm()
+            })
+            .fold(0.0, f64::max);
+        if error > tol
+        {
+            return Err(ModellerError::NonConvergence(format!(
+                "fit error {} exceeds the tolerance {}",
+                error, tol
+            )));
+        }
+        *self = fit;
+        Ok(error)
+    }
+    //..............................................................................................
+
+    /// Blends this curve with `other`, interpolating linearly between their homogeneous control 
+    /// points once the two curves are made compatible with [spl::make_compatible].
+    ///
+    /// The blended curve is parameterised on $[0, 1]$. For non-rational curves the point at $s$ is
+    /// $(1 - t) \mathbf{C}_{a}(s) + t \mathbf{C}_{b}(s)$ where both curves are reparameterised 
+    /// onto $[0, 1]$.
+    ///
+    /// # Arguments
+    /// * `other` - The curve to blend towards.
+    /// * `t` - The blending parameter, `0.0` gives this curve and `1.0` gives `other`.
+    pub fn blend(&self, other: &Bcurve<D>, t: f64) -> Bcurve<D>
+    {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        spl::make_compatible(&mut a, &mut b);
+
+        for (pw_a, pw_b) in a.cpoints_w.iter_mut().zip(b.cpoints_w.iter())
+        {
+            *pw_a = *pw_a * (1.0 - t) + *pw_b * t;
+        }
+        a.abox = None;
+        a
+    }
+    //..............................................................................................
+
+    /// Computes the power basis coefficients of the non-rational curve on the knot span 
+    /// $[u_{i}, u_{i+1})$, so that within the span
+    /// $$
+    /// \mathbf{C}(u) = \sum_{k=0}^{p} \mathbf{a}_{k} (u - u_{i})^{k}
+    /// $$
+    /// The coefficients are the Taylor coefficients $\mathbf{a}_{k} = \mathbf{C}^{(k)}(u_{i}) / k!$ 
+    /// of the polynomial piece, with the derivatives taken from the right of $u_{i}$.
+    ///
+    /// # Arguments
+    /// * `span` - The index $i$ of the knot span, which must be non-empty, with $p \leq i < n$.
+    ///
+    /// # Returns
+    /// The coefficients $\mathbf{a}_{0}, \ldots, \mathbf{a}_{p}$, the remaining entries are zero.
+    pub fn span_polynomial(&self, span: usize) -> [Vector<D>; spl::PMAX + 1]
+    {
+        debug_assert!(!self.is_rational(), "curve is rational");
+        debug_assert!(span >= self.p && span < self.cpoints_w.len(), "span out of range");
+        debug_assert!(self.knots[span] < self.knots[span + 1], "span is empty");
+
+        let mut ders = vec![Vector::<D>::zeros(); self.p + 1];
+        self.eval_diff_all(self.knots[span], self.p, &mut ders);
+
+        let mut coeffs = [Vector::<D>::zeros(); spl::PMAX + 1];
+        let mut factorial = 1.0;
+        for k in 0..=self.p
+        {
+            if k > 0
             {
-                let d = $dim;
-                let p = $order;
-                let test_data = TestData::new();
-                let bcurve = load_bcurve::<$dim>(p, &test_data);
+                factorial *= k as f64;
+            }
+            coeffs[k] = ders[k] / factorial;
+        }
+        coeffs
+    }
+    //..............................................................................................
 
-                let points = test_data.$points.values;
-                for (idx, u) in test_data.u.values.iter().enumerate()
+    /// Computes the box containing the control points of the curve, by the convex hull property
+    /// this also contains the curve.
+    pub fn control_box(&self) -> ABox<D>
+    {
+        ABox::<D>::from_points(&self.cpoints())
+    }
+    //..............................................................................................
+
+    /// Computes the principal component analysis of the control points, the eigen decomposition of
+    /// their covariance matrix.
+    ///
+    /// # Returns
+    /// A tuple of the centroid of the control points, the principal axes as orthonormal vectors,
+    /// and the variance of the control points along each axis, with the axes ordered by decreasing
+    /// variance.
+    pub fn control_point_pca(&self) -> (Vector<D>, [Vector<D>; D], [f64; D])
+    {
+        let cpoints = self.cpoints();
+        let n = cpoints.len() as f64;
+        let mean = cpoints.iter().sum::<Vector<D>>() / n;
+        let mut covariance = na::DMatrix::<f64>::zeros(D, D);
+        for x in cpoints.iter()
+        {
+            let r = x - mean;
+            for i in 0..D
+            {
+                for j in 0..D
                 {
-                    let point1 = points[idx].clone();
-                    let point2 = bcurve.eval(*u);   
-                    for i in 0..d
-                    {
-                        assert_relative_eq!(point1[i], point2[i], max_relative = 1e-12);
-                    }
+                    covariance[(i, j)] += r[i] * r[j] / n;
                 }
             }
-        };
+        }
+
+        let eigen = covariance.symmetric_eigen();
+        let mut order: Vec<usize> = (0..D).collect();
+        order.sort_by(|a, b| eigen.eigenvalues[*b].total_cmp(&eigen.eigenvalues[*a]));
+        let mut axes = [Vector::<D>::zeros(); D];
+        let mut variances = [0.0; D];
+        for (k, idx) in order.iter().enumerate()
+        {
+            axes[k] = Vector::<D>::from_iterator(eigen.eigenvectors.column(*idx).iter().copied());
+            variances[k] = eigen.eigenvalues[*idx];
+        }
+        (mean, axes, variances)
     }
-    eval!(
-        eval_d2_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d2_p1,
-        points_d2_p1,
-        2,
-        1
-    );
-    eval!(
-        eval_d2_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d2_p2,
-        points_d2_p2,
-        2,
-        2
-    );
-    eval!(
-        eval_d2_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d2_p3,
-        points_d2_p3,
-        2,
-        3
-    );
-    eval!(
-        eval_d2_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d2_p4,
-        points_d2_p4,
-        2,
-        4
-    );
-    eval!(
-        eval_d3_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d3_p1,
-        points_d3_p1,
-        3,
-        1
-    );
-    eval!(
-        eval_d3_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d3_p2,
-        points_d3_p2,
-        3,
-        2
-    );
-    eval!(
-        eval_d3_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d3_p3,
-        points_d3_p3,
-        3,
-        3
-    );
-    eval!(
-        eval_d3_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d3_p4,
-        points_d3_p4,
-        3,
-        4
-    );
     //..............................................................................................
 
-    macro_rules! eval_diff {
-        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $ders: ident, $dim: expr, $order:expr) => {
-            #[test]
-            fn $test_name()
+    /// Computes the minimum distance between this curve and `other`.
+    ///
+    /// Candidate pairs of sub-curves are found by recursively splitting the curves and discarding
+    /// those pairs whose control point boxes are further apart than the best distance found so far.
+    /// Each remaining pair is then refined by Newton iteration on the squared distance between the
+    /// two curves.
+    ///
+    /// # Returns
+    /// A tuple `(u, v, dist)` where `u` is the parameter on this curve, `v` the parameter on
+    /// `other` and `dist` the minimum separation.
+    pub fn min_distance(&self, other: &Bcurve<D>) -> (f64, f64, f64)
+    {
+        //{{{ locals
+        let range1 = self.param_range();
+        let range2 = other.param_range();
+        let leaf_diam = MIN_DIST_LEAF_RATIO * self.control_box().diameter().max(other.control_box().diameter());
+        let mut best = (range1.0, range2.0, f64::MAX);
+        //}}}
+        //{{{ com: seed the upper bound with the end points of both curves
+        for u in [range1.0, range1.1]
+        {
+            for v in [range2.0, range2.1]
             {
-                let d = $dim;
-                let p = $order;
-                let test_data = TestData::new();
-                let bcurve = load_bcurve::<$dim>(p, &test_data);
-                let ders = test_data.$ders.values;
-
-                for (idx, u) in test_data.u.values.iter().enumerate()
+                let dist = (self.eval(u) - other.eval(v)).norm();
+                if dist < best.2
                 {
-                    let mut ders_all_1 = ders[idx].clone();
-                    for k in 0..p + 1
-                    {
-                        let mut ders1 = &mut ders_all_1[(k * d)..((k + 1) * d)];
-                        de_noise(ders1);
-                        let mut ders2 = bcurve.eval_diff(*u, k);
-                        de_noise(ders2.as_mut_slice());
-                        for i in 0..d
-                        {
-                            assert_relative_eq!(ders1[i], ders2[i], max_relative = 1e-12);
-                        }
-                    }
+                    best = (u, v, dist);
                 }
             }
-        };
+        }
+        //}}}
+        //{{{ com: subdivide pairs of sub-curves, pruning those which cannot contain the minimum 
+        self.refine_pairs(other, leaf_diam, MIN_DIST_DEPTH_MAX, best.2 - f64::RES_LINEAR, |u, v, dist| {
+            if dist < best.2
+            {
+                best = (u, v, dist);
+            }
+            best.2 - f64::RES_LINEAR
+        });
+        //}}}
+        best
     }
-    eval_diff!(
-        eval_diff_d2_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d2_p1,
-        ders_d2_p1,
-        2,
-        1
-    );
-    eval_diff!(
-        eval_diff_d2_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d2_p2,
-        ders_d2_p2,
-        2,
-        2
-    );
-    eval_diff!(
-        eval_diff_d2_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d2_p3,
-        ders_d2_p3,
-        2,
-        3
-    );
-    eval_diff!(
-        eval_diff_d2_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d2_p4,
-        ders_d2_p4,
-        2,
-        4
-    );
-    eval_diff!(
-        eval_diff_d3_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d3_p1,
-        ders_d3_p1,
-        3,
-        1
-    );
-    eval_diff!(
-        eval_diff_d3_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d3_p2,
-        ders_d3_p2,
-        3,
-        2
-    );
-    eval_diff!(
-        eval_diff_d3_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d3_p3,
-        ders_d3_p3,
-        3,
-        3
-    );
-    eval_diff!(
-        eval_diff_d3_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d3_p4,
-        ders_d3_p4,
-        3,
-        4
-    );
     //..............................................................................................
 
-    macro_rules! tangent {
-        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $tangents: ident, $dim: expr, $order:expr) => {
-            #[test]
-            fn $test_name()
+    /// Recursively subdivides this curve and `other` into pairs of sub-curves, and refines the
+    /// pairs which are left when subdivision stops by Newton iteration on the distance between the
+    /// curves, see [Bcurve::min_distance_newton].
+    ///
+    /// A pair is discarded when its control point boxes are further apart than the cull distance,
+    /// otherwise the larger of the two is split in half until both are smaller than `leaf_diam` or
+    /// `depth_max` splits have been made.
+    ///
+    /// # Arguments
+    /// * `leaf_diam` - The diameter of the control boxes below which subdivision stops.
+    /// * `depth_max` - The maximum number of splits of a pair.
+    /// * `cull` - The initial cull distance.
+    /// * `leaf` - Called with the parameters `(u, v)` and distance found from each leaf pair, it
+    ///            returns the cull distance for the pairs which are still to be examined.
+    pub(super) fn refine_pairs<F>(
+        &self,
+        other: &Bcurve<D>,
+        leaf_diam: f64,
+        depth_max: usize,
+        mut cull: f64,
+        mut leaf: F,
+    )
+    where
+        F: FnMut(f64, f64, f64) -> f64,
+    {
+        let mut stack = vec![(self.clone(), other.clone(), 0usize)];
+        while let Some((c1, c2, depth)) = stack.pop()
+        {
+            let box1 = c1.control_box();
+            let box2 = c2.control_box();
+            if box1.distance(&box2) > cull
             {
-                let d = $dim;
-                let p = $order;
-                let test_data = TestData::new();
-                let bcurve = load_bcurve::<$dim>(p, &test_data);
-                let tangents = test_data.$tangents.values;
+                continue;
+            }
 
-                for (idx, u) in test_data.u.values.iter().enumerate()
+            let diam1 = box1.diameter();
+            let diam2 = box2.diameter();
+            if (diam1 < leaf_diam && diam2 < leaf_diam) || depth >= depth_max
+            {
+                let (u, v, dist) = self.min_distance_newton(other, c1.param_range(), c2.param_range());
+                cull = leaf(u, v, dist);
+                continue;
+            }
+
+            if diam1 >= diam2
+            {
+                let (a, b) = c1.param_range();
+                let (left, right) = c1.split(0.5 * (a + b));
+                stack.push((left, c2.clone(), depth + 1));
+                stack.push((right, c2, depth + 1));
+            }
+            else
+            {
+                let (a, b) = c2.param_range();
+                let (left, right) = c2.split(0.5 * (a + b));
+                stack.push((c1.clone(), left, depth + 1));
+                stack.push((c1, right, depth + 1));
+            }
+        }
+    }
+    //..............................................................................................
+
+    /// Newton iteration for the minimum of $\frac{1}{2} \lVert C_{1}(u) - C_{2}(v) \rVert^{2}$,
+    /// started from the midpoints of the given parameter ranges and clamped to the full parameter
+    /// ranges of the curves.
+    fn min_distance_newton(&self, other: &Bcurve<D>, range1: (f64, f64), range2: (f64, f64)) -> (f64, f64, f64)
+    {
+        let full1 = self.param_range();
+        let full2 = other.param_range();
+        let mut u = 0.5 * (range1.0 + range1.1);
+        let mut v = 0.5 * (range2.0 + range2.1);
+        let mut ders1 = [Vector::<D>::zeros(); 3];
+        let mut ders2 = [Vector::<D>::zeros(); 3];
+
+        for _ in 0..MIN_DIST_NEWTON_ITER_MAX
+        {
+            self.eval_diff_all(u, 2, &mut ders1);
+            other.eval_diff_all(v, 2, &mut ders2);
+            let r = ders1[0] - ders2[0];
+
+            let g0 = r.dot(&ders1[1]);
+            let g1 = -r.dot(&ders2[1]);
+            let h00 = ders1[1].dot(&ders1[1]) + r.dot(&ders1[2]);
+            let h01 = -ders1[1].dot(&ders2[1]);
+            let h11 = ders2[1].dot(&ders2[1]) - r.dot(&ders2[2]);
+            let det = h00 * h11 - h01 * h01;
+
+            // fall back to gradient descent when the Hessian is not positive definite
+            let (du, dv) = if det > f64::RES_LINEAR && h00 > 0.0
+            {
+                ((h11 * g0 - h01 * g1) / det, (h00 * g1 - h01 * g0) / det)
+            }
+            else
+            {
+                let scale = 1.0 / (h00.abs() + h11.abs()).max(1.0);
+                (scale * g0, scale * g1)
+            };
+
+            let f0 = r.norm_squared();
+            let mut step = 1.0;
+            let mut accepted = false;
+            while step > 1e-6
+            {
+                let u_new = (u - step * du).clamp(full1.0, full1.1);
+                let v_new = (v - step * dv).clamp(full2.0, full2.1);
+                if (self.eval(u_new) - other.eval(v_new)).norm_squared() <= f0
                 {
-                    let mut tangent1 = tangents[idx].clone();
-                    de_noise(tangent1.as_mut_slice());
-                    let mut tangent2 = bcurve.eval_tangent(*u, false);
-                    de_noise(tangent2.as_mut_slice());
-                    for i in 0..d
-                    {
-                        assert_relative_eq!(tangent1[i], tangent2[i], max_relative = 1e-12);
-                    }
+                    accepted = (u_new - u).abs() + (v_new - v).abs() > f64::RES_LINEAR * 1e-2;
+                    u = u_new;
+                    v = v_new;
+                    break;
                 }
+                step *= 0.5;
             }
-        };
+            if !accepted
+            {
+                break;
+            }
+        }
+        (u, v, (self.eval(u) - other.eval(v)).norm())
     }
-    tangent!(
-        tangent_d2_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d2_p1,
-        tangent_d2_p1,
-        2,
-        1
-    );
-    tangent!(
-        tangent_d2_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d2_p2,
-        tangent_d2_p2,
-        2,
-        2
-    );
-    tangent!(
-        tangent_d2_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d2_p3,
-        tangent_d2_p3,
-        2,
-        3
-    );
-    tangent!(
-        tangent_d2_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d2_p4,
-        tangent_d2_p4,
-        2,
-        4
-    );
-    tangent!(
-        tangent_d3_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d3_p1,
-        tangent_d3_p1,
-        3,
-        1
-    );
-    tangent!(
-        tangent_d3_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d3_p2,
-        tangent_d3_p2,
-        3,
-        2
-    );
-    tangent!(
-        tangent_d3_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d3_p3,
-        tangent_d3_p3,
-        3,
-        3
-    );
-    tangent!(
-        tangent_d3_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d3_p4,
-        tangent_d3_p4,
-        3,
-        4
-    );
     //..............................................................................................
 
-    macro_rules! normal {
-        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $tangents: ident, $dim: expr, $order:expr) => {
-            #[test]
-            fn $test_name()
+    /// Finds the minimum of the function `f` of the parameter of the curve.
+    ///
+    /// `f` is sampled `samples_per_span` times over each knot span, and each local minimum of the
+    /// samples is refined by a bounded scalar minimisation between its neighbours. The minimum is
+    /// only found if the samples are dense enough to bracket it.
+    ///
+    /// # Returns
+    /// A tuple `(u, f(u))` of the parameter at which `f` is least and its value there.
+    pub(super) fn min_over_spans<F: Fn(f64) -> f64>(&self, f: F, samples_per_span: usize) -> (f64, f64)
+    {
+        let mut params = Vec::new();
+        for (a, b) in self.spans()
+        {
+            for i in 0..samples_per_span + 1
             {
-                let p = $order;
-                let test_data = TestData::new();
-                let bcurve = load_bcurve::<$dim>(p, &test_data);
-                let tangents = test_data.$tangents.values;
+                params.push(a + (b - a) * i as f64 / samples_per_span as f64);
+            }
+        }
+        let vals: Vec<f64> = params.iter().map(|&u| f(u)).collect();
 
-                for (idx, u) in test_data.u.values.iter().enumerate()
+        let mut best = (params[0], vals[0]);
+        let mut opts = CurveMinValOpts::default();
+        let n = params.len();
+        for i in 0..n
+        {
+            let is_trough =
+                (i == 0 || vals[i] <= vals[i - 1]) && (i == n - 1 || vals[i] <= vals[i + 1]);
+            if !is_trough
+            {
+                continue;
+            }
+            if vals[i] < best.1
+            {
+                best = (params[i], vals[i]);
+            }
+            let lo = params[i.saturating_sub(1)];
+            let hi = params[(i + 1).min(n - 1)];
+            if hi <= lo
+            {
+                continue;
+            }
+            opts.bounds = Some((lo, hi));
+            let (u, val) = self.min_value_scalar(&f, &opts);
+            if val < best.1
+            {
+                best = (u, val);
+            }
+        }
+        best
+    }
+    //..............................................................................................
+
+    /// Integrates `f` over $[u_1, u_2]$ one knot span at a time, since functions of the curve are 
+    /// only smooth within each span.
+    fn integrate_spans<F: Fn(f64) -> f64>(&self, f: F, u1: f64, u2: f64) -> f64
+    {
+        let mut integral = 0.0;
+        let mut a = u1;
+        for (knot, _) in self.knot_multiplicites.iter()
+        {
+            if *knot > u1 && *knot < u2
+            {
+                integral += gauss_legendre(&f, a, *knot);
+                a = *knot;
+            }
+        }
+        integral + gauss_legendre(&f, a, u2)
+    }
+    //..............................................................................................
+
+    /// Evaluates the derivatives $0..k$ of the curve into `ders`, using the caller-provided work 
+    /// buffers.
+    ///
+    /// # Arguments
+    /// * `dersw` - Buffer of length $\geq k + 1$ for the derivatives of the homogeneous curve
+    /// * `basis_ders` - Buffer of length $\geq (p + 1)(k + 1)$ for the basis function derivatives
+    /// * `binom` - Buffer of length $\geq (k + 1)^{2}$ for the binomial coefficients
+    fn eval_diff_all_impl(
+        &self,
+        u: f64,
+        k: usize,
+        ders: &mut [Vector<D>],
+        dersw: &mut [Vector<{ D + 1 }>],
+        basis_ders: &mut [f64],
+        binom: &mut [f64],
+    )
+    {
+        let dim = k + 1;
+        let (start, _, num_basis) = spl::non_zero_basis(&self.knots, u, self.p);
+
+        spl::eval_diff_all(&self.knots, u, self.p, k, basis_ders);
+        let basis_ders_arr = NDArrayWrapper::<'_, f64, 2>::new(basis_ders, &[num_basis, k + 1]);
+
+        for m in 0..k + 1
+        // loop over derivatives
+        {
+            dersw[m].fill(0.0);
+            for j in 0..num_basis
+            {
+                let nj = basis_ders_arr[&[j, m]];
+                let pwj = self.cpoints_w[start + j];
+                dersw[m] += nj * pwj;
+            }
+        }
+
+        binom_coeff(k, binom);
+        let binom_arr = NDArrayWrapper::<'_, f64, 2>::new(binom, &[dim, dim]);
+
+        let w0 = dersw[0][D];
+        let mut v = Vector::<D>::zeros();
+
+        for m in 0..k + 1
+        {
+            v.fill(0.0);
+            v.copy_from(&dersw[m].rows(0, D));
+
+            for j in 1..m + 1
+            {
+                let wj = dersw[j][D];
+                let bmj = binom_arr[&[m, j]];
+                v -= bmj * wj * ders[m - j];
+            }
+            ders[m] = v / w0;
+        }
+    }
+}
+//}}}
+//{{{ impl: Bcurve<2>
+impl Bcurve<2>
+{
+    /// Evaluates the signed curvature of the planar curve at `u`.
+    ///
+    /// This is $(C' \times C'') / |C'|^{3}$, where the cross product of two planar vectors is the
+    /// scalar $x' y'' - y' x''$. It is positive where the curve turns anticlockwise.
+    pub fn eval_signed_curvature(&self, u: f64) -> f64
+    {
+        let mut ders = [Vector::<2>::zeros(); 3];
+        self.eval_diff_all(u, 2, &mut ders);
+        let (ve, acc) = (ders[1], ders[2]);
+        let ve_norm = ve.norm();
+        (ve.x * acc.y - ve.y * acc.x) / (ve_norm * ve_norm * ve_norm)
+    }
+    //..............................................................................................
+
+    /// Finds the inflection points of the planar curve, where its signed curvature changes sign.
+    ///
+    /// The signed curvature is sampled uniformly within each non-empty knot span and each sign
+    /// change between neighbouring samples is refined by bisection. Stretches of zero curvature,
+    /// such as straight segments, are not reported as inflections.
+    ///
+    /// # Returns
+    /// The parameters of the inflection points in increasing order.
+    pub fn inflection_points(&self) -> Vec<f64>
+    {
+        let mut params = Vec::new();
+        for (a, b) in self.spans()
+        {
+            for i in 0..INFLECTION_SAMPLES_PER_SPAN
+            {
+                params.push(a + (b - a) * i as f64 / INFLECTION_SAMPLES_PER_SPAN as f64);
+            }
+        }
+        params.push(self.param_range().1);
+
+        // the sign of each sample, with samples of negligible curvature taking no sign
+        let signs: Vec<(f64, f64)> = params
+            .iter()
+            .map(|&u| (u, self.eval_signed_curvature(u)))
+            .filter(|(_, kappa)| kappa.abs() > f64::RES_LINEAR)
+            .collect();
+
+        let mut inflections = Vec::new();
+        for w in signs.windows(2)
+        {
+            let ((mut lo, kappa_lo), (mut hi, _)) = (w[0], w[1]);
+            if kappa_lo.signum() == w[1].1.signum()
+            {
+                continue;
+            }
+            for _ in 0..INFLECTION_BISECT_ITER_MAX
+            {
+                let mid = 0.5 * (lo + hi);
+                if hi - lo < f64::RES_LINEAR
                 {
-                    let mut tangent = Vector::<$dim>::from_row_slice(&tangents[idx]);
-                    tangent = tangent.normalize();
-                    let normal = bcurve.eval_normal(*u, true);
-                    let dot_product = normal.dot(&tangent);
-                    assert_abs_diff_eq!(dot_product, 0.0, epsilon = 1e-9);
+                    break;
+                }
+                if self.eval_signed_curvature(mid).signum() == kappa_lo.signum()
+                {
+                    lo = mid;
+                }
+                else
+                {
+                    hi = mid;
                 }
             }
+            inflections.push(0.5 * (lo + hi));
+        }
+        inflections
+    }
+    //..............................................................................................
+
+    /// Computes the centroid and area of the region enclosed by the closed planar curve.
+    ///
+    /// By Green's theorem the area and first moments of the region are the boundary integrals
+    /// $$
+    /// A = \frac{1}{2} \oint x \, dy - y \, dx, \quad
+    /// M_{y} = \frac{1}{2} \oint x^{2} \, dy, \quad
+    /// M_{x} = -\frac{1}{2} \oint y^{2} \, dx
+    /// $$
+    /// which are evaluated by Gauss quadrature over each knot span. The result does not depend on
+    /// the orientation of the curve.
+    ///
+    /// # Returns
+    /// A tuple of the centroid $(M_{y}, M_{x}) / A$ and the area $|A|$.
+    pub fn region_centroid(&self) -> (Vec2, f64)
+    {
+        debug_assert!(self.is_closed(), "curve is not closed");
+        let (u1, u2) = self.param_range();
+        let moments = |u: f64| {
+            let mut ders = [Vector::<2>::zeros(); 2];
+            self.eval_diff_all(u, 1, &mut ders);
+            let (x, dx) = (ders[0], ders[1]);
+            Vec3::new(
+                x.x * dx.y - x.y * dx.x,
+                x.x * x.x * dx.y,
+                -x.y * x.y * dx.x,
+            ) * 0.5
         };
+        let area = self.integrate_spans(|u| moments(u)[0], u1, u2);
+        let my = self.integrate_spans(|u| moments(u)[1], u1, u2);
+        let mx = self.integrate_spans(|u| moments(u)[2], u1, u2);
+        (Vec2::new(my / area, mx / area), area.abs())
+    }
+    //..............................................................................................
+
+    /// Computes the second moments of area about the centroid of the region enclosed by the
+    /// closed planar curve.
+    ///
+    /// With $(x, y)$ measured from the centroid, Green's theorem gives the second moments as the
+    /// boundary integrals
+    /// $$
+    /// I_{xx} = -\frac{1}{3} \oint y^{3} \, dx, \quad
+    /// I_{yy} = \frac{1}{3} \oint x^{3} \, dy, \quad
+    /// I_{xy} = \frac{1}{2} \oint x^{2} y \, dy
+    /// $$
+    /// which are evaluated by Gauss quadrature over each knot span, and negated if the curve is
+    /// clockwise so that the result does not depend on its orientation.
+    ///
+    /// # Returns
+    /// A tuple `(Ixx, Iyy, Ixy)` of the second moments $\int y^{2} dA$, $\int x^{2} dA$ and the
+    /// product moment $\int x y \, dA$.
+    pub fn second_moments(&self) -> (f64, f64, f64)
+    {
+        let (centroid, _) = self.region_centroid();
+        let (u1, u2) = self.param_range();
+        let moments = |u: f64| {
+            let mut ders = [Vector::<2>::zeros(); 2];
+            self.eval_diff_all(u, 1, &mut ders);
+            let (x, dx) = (ders[0] - centroid, ders[1]);
+            Vec4::new(
+                0.5 * (x.x * dx.y - x.y * dx.x),
+                -x.y * x.y * x.y * dx.x / 3.0,
+                x.x * x.x * x.x * dx.y / 3.0,
+                0.5 * x.x * x.x * x.y * dx.y,
+            )
+        };
+        let sign = self.integrate_spans(|u| moments(u)[0], u1, u2).signum();
+        let ixx = self.integrate_spans(|u| moments(u)[1], u1, u2);
+        let iyy = self.integrate_spans(|u| moments(u)[2], u1, u2);
+        let ixy = self.integrate_spans(|u| moments(u)[3], u1, u2);
+        (sign * ixx, sign * iyy, sign * ixy)
+    }
+}
+//}}}
+//{{{ impl: Bcurve<3>
+impl Bcurve<3>
+{
+    /// Transforms the curve by the affine transformation `m`, given in homogeneous coordinates.
+    ///
+    /// If the bounding box is cached it is replaced by the transformed box, which is conservative
+    /// and costs only the transformation of its corners. The tight box can be recomputed on demand 
+    /// with [ABoxable::recompute_box].
+    pub fn transform(&mut self, m: &Mat4)
+    {
+        // the control points are stored as (w x, w), so applying m scales the translation by w
+        for pw in self.cpoints_w.iter_mut()
+        {
+            *pw = m * *pw;
+        }
+        self.abox = self.abox.as_ref().map(|abox| abox.transform(m));
+    }
+    //..............................................................................................
+
+    /// Determines whether reflecting the curve across `plane` maps it onto itself.
+    ///
+    /// The reflected control points are compared with the original ones, either in the same order
+    /// or, if the knot vector is symmetric about the middle of the domain, in reverse order with
+    /// the weights reversed too. A curve whose control net is not itself symmetric, for example
+    /// because of an asymmetric knot vector, is not recognised.
+    ///
+    /// # Arguments
+    /// * `plane` - The plane of symmetry.
+    /// * `tol` - The distance within which reflected control points must match.
+    pub fn is_symmetric_about(&self, plane: &Plane, tol: f64) -> bool
+    {
+        let normal = plane.x().cross(&plane.y());
+        let cpoints: Vec<(Vec3, f64)> = self.cpoints_w.iter().map(|pw| (inv_homog(pw), pw[3])).collect();
+        let reflected: Vec<(Vec3, f64)> = cpoints
+            .iter()
+            .map(|(x, w)| (x - normal * (2.0 * plane.sdf(x)), *w))
+            .collect();
+        let matches = |a: &(Vec3, f64), b: &(Vec3, f64)| {
+            (a.0 - b.0).norm() <= tol && (a.1 - b.1).abs() <= f64::RES_LINEAR * a.1.abs().max(1.0)
+        };
+
+        if reflected.iter().zip(cpoints.iter()).all(|(a, b)| matches(a, b))
+        {
+            return true;
+        }
+
+        let (u1, u2) = self.param_range();
+        let n = self.knots.len();
+        let knots_symmetric = (0..n).all(|i| knot_eq(self.knots[i] - u1, u2 - self.knots[n - 1 - i]));
+        knots_symmetric && reflected.iter().zip(cpoints.iter().rev()).all(|(a, b)| matches(a, b))
+    }
+    //..............................................................................................
+
+    /// Finds the plane containing the curve, if it is planar.
+    ///
+    /// The normal of the plane is the principal axis of least variance of the control points, and
+    /// the plane passes through their centroid. The curve is taken to be planar when every control
+    /// point is within `tol` of this plane, by the convex hull property the curve is then too. For
+    /// a straight curve any plane through it would do, the one returned is arbitrary.
+    ///
+    /// # Arguments
+    /// * `tol` - The distance from the plane within which the control points must lie.
+    ///
+    /// # Returns
+    /// The plane containing the curve, or `None` if the curve is not planar.
+    pub fn fit_plane(&self, tol: f64) -> Option<Plane>
+    {
+        let (centroid, axes, _) = self.control_point_pca();
+        let normal = axes[2];
+        let is_planar = self.cpoints_iter().all(|x| (x - centroid).dot(&normal).abs() <= tol);
+        is_planar.then(|| Plane::from_point_normal(&centroid, &normal))
+    }
+    //..............................................................................................
+
+    /// Computes the tangent indicatrix of the curve, which is the unit tangent traced out on the
+    /// unit sphere as the curve is traversed.
+    ///
+    /// # Arguments
+    /// * `samples` - The number of points, uniformly spaced in parameter space, at which to
+    ///               evaluate the tangent. Must be at least 2.
+    pub fn tangent_indicatrix(&self, samples: usize) -> Vec<Vec3>
+    {
+        debug_assert!(samples >= 2);
+
+        let (u1, u2) = self.param_range();
+        let du = (u2 - u1) / (samples - 1) as f64;
+        (0..samples)
+            .map(|i| {
+                let u = if i == samples - 1 { u2 } else { u1 + i as f64 * du };
+                self.eval_tangent(u, true)
+            })
+            .collect()
+    }
+    //..............................................................................................
+
+    /// Samples the torsion of the curve, which measures how fast it twists out of its osculating
+    /// plane.
+    ///
+    /// The torsion is undefined where the curve is straight, since the osculating plane is then not
+    /// defined, and it is dominated by rounding where the curve is nearly so. At samples where the
+    /// curvature is below a small fraction of the inverse size of the curve it is returned as NaN.
+    ///
+    /// # Arguments
+    /// * `samples` - The number of parameters, those of [Curve::sample_params], at which to
+    ///               evaluate the torsion. Fewer than 2 are treated as 2, the ends of the domain.
+    ///
+    /// # Returns
+    /// The pairs of parameter and torsion, in order of increasing parameter.
+    pub fn torsion_profile(&self, samples: usize) -> Vec<(f64, f64)>
+    {
+        let kappa_min = TORSION_CURVATURE_MIN / self.control_box().diameter();
+        self.sample_params(samples.saturating_sub(1))
+            .into_iter()
+            .map(|u| {
+                let tau = if self.eval_curvature(u) > kappa_min { self.eval_torsion(u) } else { f64::NAN };
+                (u, tau)
+            })
+            .collect()
     }
-    normal!(
-        normal_d2_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d2_p1,
-        tangent_d2_p1,
-        2,
-        1
-    );
-    normal!(
-        normal_d2_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d2_p2,
-        tangent_d2_p2,
-        2,
-        2
-    );
-    normal!(
-        normal_d2_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d2_p3,
-        tangent_d2_p3,
-        2,
-        3
-    );
-    normal!(
-        normal_d2_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d2_p4,
-        tangent_d2_p4,
-        2,
-        4
-    );
-    normal!(
-        normal_d3_p1,
-        knots_p1,
-        weights_p1,
-        cpoints_d3_p1,
-        tangent_d3_p1,
-        3,
-        1
-    );
-    normal!(
-        normal_d3_p2,
-        knots_p2,
-        weights_p2,
-        cpoints_d3_p2,
-        tangent_d3_p2,
-        3,
-        2
-    );
-    normal!(
-        normal_d3_p3,
-        knots_p3,
-        weights_p3,
-        cpoints_d3_p3,
-        tangent_d3_p3,
-        3,
-        3
-    );
-    normal!(
-        normal_d3_p4,
-        knots_p4,
-        weights_p4,
-        cpoints_d3_p4,
-        tangent_d3_p4,
-        3,
-        4
-    );
     //..............................................................................................
 
+    /// Finds the point of the curve closest to the ray $\mathbf{o} + t \mathbf{d}, t \geq 0$.
+    ///
+    /// The distance to the ray is sampled over each knot span, and each local minimum of the
+    /// samples is refined by a bounded scalar minimisation between its neighbours.
+    ///
+    /// # Arguments
+    /// * `origin` - The origin of the ray.
+    /// * `dir` - The direction of the ray, which need not be normalised.
+    ///
+    /// # Returns
+    /// A tuple `(u, t, dist)` of the curve parameter of the closest point, the ray parameter of
+    /// the point on the ray closest to it and the distance between them.
+    pub fn closest_point_to_ray(
+        &self,
+        origin: &Vec3,
+        dir: &Vec3,
+    ) -> (f64, f64, f64)
+    {
+        let dir_sq = dir.norm_squared();
+        let ray_param = |x: &Vec3| ((x - origin).dot(dir) / dir_sq).max(0.0);
+        let dist = |u: f64| {
+            let x = self.eval(u);
+            (x - origin - dir * ray_param(&x)).norm()
+        };
+
+        let best = self.min_over_spans(dist, RAY_SAMPLES_PER_SPAN);
+        (best.0, ray_param(&self.eval(best.0)), best.1)
+    }
+}
+//}}}
+//{{{ impl: Curve for  Bcurve
+impl<const D: usize> Curve for Bcurve<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    //{{{ type: Vector
+    type Vector = Vector<D>;
+    //}}}
+    //{{{ fun: eval
+    fn eval(
+        &self,
+        u: f64,
+    ) -> Vector<D>
+    {
+        inv_homog(&self.eval_pointw(u))
+    }
+    //..............................................................................................
+    //}}}
+    //{{{ fun: try_eval
+    /// Evaluates the curve at `u`, checking that `u` is in the domain and that the weight of the
+    /// point is positive, since zero weights are allowed on the control points.
+    ///
+    /// # Returns
+    /// The point on the curve, [ModellerError::OutOfDomain] if `u` is not a member of the domain or
+    /// [ModellerError::DegenerateWeight] if the point is at infinity.
+    fn try_eval(
+        &self,
+        u: f64,
+    ) -> Result<Vector<D>, ModellerError>
+    {
+        if !self.is_member(u)
+        {
+            return Err(ModellerError::OutOfDomain(u));
+        }
+        try_inv_homog(&self.eval_pointw(u))
+    }
+    //}}}
+    //{{{ fun: eval_diff
+    fn eval_diff(
+        &self,
+        u: f64,
+        m: usize,
+    ) -> Vector<D>
+    {
+        let u = self.clamp_to_domain(u);
+        debug_assert!(spl::is_member(&self.knots, u));
+
+        if m == 0
+        {
+            self.eval(u)
+        }
+        else if m < BCURVE_DER_MAX
+        {
+            let mut diff_loc = [Vector::<D>::zeros(); BCURVE_DER_MAX];
+            self.eval_diff_all(u, m, &mut diff_loc);
+            diff_loc[m]
+        }
+        else
+        {
+            let mut diff_loc = vec![Vector::<D>::zeros(); m + 1];
+            self.eval_diff_all(u, m, &mut diff_loc);
+            diff_loc[m]
+        }
+    }
+    //..............................................................................................
+    //}}}
+    //{{{ fun: eval_diff_all 
+    fn eval_diff_all(
+        &self,
+        u: f64,
+        k: usize,
+        ders: &mut [Vector<D>],
+    )
+    {
+        let u = self.clamp_to_domain(u);
+        debug_assert!(spl::is_member(&self.knots, u));
+        debug_assert!(ders.len() >= k + 1);
+
+        if k == 0
+        {
+            ders[0] = self.eval(u);
+        }
+        else if k < BCURVE_DER_MAX && self.p < BCURVE_DER_MAX
+        {
+            let mut dersw = [Vector::<{ D + 1 }>::zeros(); BCURVE_DER_MAX];
+            let mut basis_ders = [0.0; BCURVE_DER_MAX * BCURVE_DER_MAX];
+            let mut binom = [0.0; BCURVE_DER_MAX * BCURVE_DER_MAX];
+            self.eval_diff_all_impl(u, k, ders, &mut dersw, &mut basis_ders, &mut binom);
+        }
+        else
+        {
+            // heap-backed path for high order derivatives or high order curves
+            let mut dersw = vec![Vector::<{ D + 1 }>::zeros(); k + 1];
+            let mut basis_ders = vec![0.0; (self.p + 1) * (k + 1)];
+            let mut binom = vec![0.0; (k + 1) * (k + 1)];
+            self.eval_diff_all_impl(u, k, ders, &mut dersw, &mut basis_ders, &mut binom);
+        }
+    }
+    //..............................................................................................
+    //}}}
+    //{{{ eval_arclen
+    fn eval_arclen(
+        &self,
+        u1: f64,
+        u2: f64,
+    ) -> f64
+    {
+        debug_assert!(u2 >= u1);
+        self.integrate_spans(|u: f64| self.eval_diff(u, 1).norm(), u1, u2)
+    }
+    //..............................................................................................
+    //}}}
+    //{{{ is_member
+    fn is_member(
+            &self,
+            u: f64,
+        ) -> bool {
+        spl::is_member(&self.knots, u)
+    }
+    //..............................................................................................
+    //}}}
+    //{{{ fun: dim
+    fn dim(&self) -> usize {
+        D
+    }
+    //..............................................................................................
+    //}}}
+    //{{{ fun: max_der
+    fn max_der(&self, u: f64) -> usize {
+        if self.is_rational() 
+        {
+            BCURVE_DER_MAX
+        }
+        else 
+        {
+            self.p
+        }
+    }
+    //}}}
+    //{{{ fun: param_range
+    fn param_range(&self) -> (f64, f64) {
+        (self.knots[0], self.knots[self.knots.len() - 1])
+    }
+    //}}}
+}
+//}}}
+//}}}
+//{{{ fun: junction_continuity
+/// Determines the geometric continuity of the junction where the end of `a` meets the start of 
+/// `b`, as used to assess the quality of joins and fillets.
+///
+/// The end points are compared for $G^{0}$, the unit tangents for $G^{1}$ and the curvature
+/// vectors $\kappa \mathbf{N}$ for $G^{2}$. Comparing the curvature vectors rather than the 
+/// curvatures distinguishes a smooth join from an inflection, where the curvature has the same 
+/// magnitude on both sides but bends the other way.
+///
+/// # Arguments
+/// * `a` - The curve whose end is at the junction.
+/// * `b` - The curve whose start is at the junction.
+/// * `tol` - The tolerance on the distance between the end points and between the unit tangents,
+///           the curvature vectors are compared relative to the larger of them and one.
+///
+/// Panics if the end points are further apart than `tol`, since the curves must share the
+/// junction.
+///
+/// # Returns
+/// The order of continuity, 0 if the curves only meet, 1 if their tangents also agree and 2 if
+/// their curvatures also agree.
+pub fn junction_continuity<const D: usize>(
+    a: &Bcurve<D>,
+    b: &Bcurve<D>,
+    tol: f64,
+) -> u8
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    let mut ders_a = [Vector::<D>::zeros(); 3];
+    let mut ders_b = [Vector::<D>::zeros(); 3];
+    a.eval_diff_all(a.param_range().1, 2, &mut ders_a);
+    b.eval_diff_all(b.param_range().0, 2, &mut ders_b);
+
+    let gap = (ders_a[0] - ders_b[0]).norm();
+    assert!(gap <= tol, "curves do not meet at the junction, their ends are {} apart", gap);
+
+    let (tangent_a, tangent_b) = (ders_a[1].normalize(), ders_b[1].normalize());
+    if !((tangent_a - tangent_b).norm() <= tol)
+    {
+        return 0;
+    }
+
+    // the component of the acceleration normal to the tangent, over the speed squared
+    let curvature = |ders: &[Vector<D>; 3], tangent: &Vector<D>| {
+        (ders[2] - tangent * ders[2].dot(tangent)) / ders[1].norm_squared()
+    };
+    let (kappa_a, kappa_b) = (curvature(&ders_a, &tangent_a), curvature(&ders_b, &tangent_b));
+    let scale = kappa_a.norm().max(kappa_b.norm()).max(1.0);
+    if (kappa_a - kappa_b).norm() <= tol * scale { 2 } else { 1 }
+}
+//}}}
+//{{{ fun: binom_coeff 
+fn binom_coeff(
+    n: usize,
+    binom: &mut [f64],
+)
+{
+    debug_assert!(binom.len() >= (n + 1) * (n + 1));
+
+    binom.fill(0.0);
+    let mut binom_arr = NDArrayWrapper::<'_, f64, 2>::new(binom, &[n + 1, n + 1]);
+
+    for i in 0..n + 1
+    {
+        binom_arr[&[i, i]] = 1.0;
+        binom_arr[&[i, 0]] = 1.0;
+    }
+
+    for n2 in 2..n + 1
+    {
+        for k2 in 1..n2
+        {
+            binom_arr[&[n2, k2]] = binom_arr[&[n2 - 1, k2 - 1]] + binom_arr[&[n2 - 1, k2]];
+        }
+    }
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+  
+    use approx::{assert_abs_diff_eq, assert_relative_eq, ulps_eq, AbsDiff};
+    use serde::Deserialize;
+    use std::fs;
+
+    use crate::common::ModellerError;
+    use crate::geometry::common::Curve;
+    use crate::geometry::{BsurfaceDescriptor, Bsurface, PlaneDescriptor};
+    use crate::test_utils::test_bcurve::{circle, load_bcurve};
+    use crate::utilities::NDArrayWrapper;
+    use crate::test_utils::{test_bcurve::TestData, convert, de_noise};
+
+    use super::*;
+
+
+    #[test]
+    fn binomcoeff()
+    {
+        let mut binom = [0.0; 36];
+        binom_coeff(5, &mut binom);
+        let binom_arr = NDArrayWrapper::<'_, f64, 2>::new(&mut binom, &[6, 6]);
+        assert!(ulps_eq!(binom_arr[&[5, 0]], 1.0, max_ulps = 4));
+        assert!(ulps_eq!(binom_arr[&[5, 1]], 5.0, max_ulps = 4));
+        assert!(ulps_eq!(binom_arr[&[5, 2]], 10.0, max_ulps = 4));
+        assert!(ulps_eq!(binom_arr[&[5, 3]], 10.0, max_ulps = 4));
+        assert!(ulps_eq!(binom_arr[&[5, 4]], 5.0, max_ulps = 4));
+        assert!(ulps_eq!(binom_arr[&[5, 5]], 1.0, max_ulps = 4));
+    }
+
+    #[test]
+    fn construction()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+    }
+    //..............................................................................................
+
+    macro_rules! eval {
+        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $points: ident, $dim: expr, $order:expr) => {
+            #[test]
+            fn $test_name()
+            {
+                let d = $dim;
+                let p = $order;
+                let test_data = TestData::new();
+                let bcurve = load_bcurve::<$dim>(p, &test_data);
+
+                let points = test_data.$points.values;
+                for (idx, u) in test_data.u.values.iter().enumerate()
+                {
+                    let point1 = points[idx].clone();
+                    let point2 = bcurve.eval(*u);   
+                    for i in 0..d
+                    {
+                        assert_relative_eq!(point1[i], point2[i], max_relative = 1e-12);
+                    }
+                }
+            }
+        };
+    }
+    eval!(
+        eval_d2_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d2_p1,
+        points_d2_p1,
+        2,
+        1
+    );
+    eval!(
+        eval_d2_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d2_p2,
+        points_d2_p2,
+        2,
+        2
+    );
+    eval!(
+        eval_d2_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d2_p3,
+        points_d2_p3,
+        2,
+        3
+    );
+    eval!(
+        eval_d2_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d2_p4,
+        points_d2_p4,
+        2,
+        4
+    );
+    eval!(
+        eval_d3_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d3_p1,
+        points_d3_p1,
+        3,
+        1
+    );
+    eval!(
+        eval_d3_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d3_p2,
+        points_d3_p2,
+        3,
+        2
+    );
+    eval!(
+        eval_d3_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d3_p3,
+        points_d3_p3,
+        3,
+        3
+    );
+    eval!(
+        eval_d3_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d3_p4,
+        points_d3_p4,
+        3,
+        4
+    );
+    //..............................................................................................
+
+    macro_rules! eval_diff {
+        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $ders: ident, $dim: expr, $order:expr) => {
+            #[test]
+            fn $test_name()
+            {
+                let d = $dim;
+                let p = $order;
+                let test_data = TestData::new();
+                let bcurve = load_bcurve::<$dim>(p, &test_data);
+                let ders = test_data.$ders.values;
+
+                for (idx, u) in test_data.u.values.iter().enumerate()
+                {
+                    let mut ders_all_1 = ders[idx].clone();
+                    for k in 0..p + 1
+                    {
+                        let mut ders1 = &mut ders_all_1[(k * d)..((k + 1) * d)];
+                        de_noise(ders1);
+                        let mut ders2 = bcurve.eval_diff(*u, k);
+                        de_noise(ders2.as_mut_slice());
+                        for i in 0..d
+                        {
+                            assert_relative_eq!(ders1[i], ders2[i], max_relative = 1e-12);
+                        }
+                    }
+                }
+            }
+        };
+    }
+    eval_diff!(
+        eval_diff_d2_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d2_p1,
+        ders_d2_p1,
+        2,
+        1
+    );
+    eval_diff!(
+        eval_diff_d2_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d2_p2,
+        ders_d2_p2,
+        2,
+        2
+    );
+    eval_diff!(
+        eval_diff_d2_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d2_p3,
+        ders_d2_p3,
+        2,
+        3
+    );
+    eval_diff!(
+        eval_diff_d2_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d2_p4,
+        ders_d2_p4,
+        2,
+        4
+    );
+    eval_diff!(
+        eval_diff_d3_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d3_p1,
+        ders_d3_p1,
+        3,
+        1
+    );
+    eval_diff!(
+        eval_diff_d3_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d3_p2,
+        ders_d3_p2,
+        3,
+        2
+    );
+    eval_diff!(
+        eval_diff_d3_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d3_p3,
+        ders_d3_p3,
+        3,
+        3
+    );
+    eval_diff!(
+        eval_diff_d3_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d3_p4,
+        ders_d3_p4,
+        3,
+        4
+    );
+    //..............................................................................................
+
+    macro_rules! tangent {
+        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $tangents: ident, $dim: expr, $order:expr) => {
+            #[test]
+            fn $test_name()
+            {
+                let d = $dim;
+                let p = $order;
+                let test_data = TestData::new();
+                let bcurve = load_bcurve::<$dim>(p, &test_data);
+                let tangents = test_data.$tangents.values;
+
+                for (idx, u) in test_data.u.values.iter().enumerate()
+                {
+                    let mut tangent1 = tangents[idx].clone();
+                    de_noise(tangent1.as_mut_slice());
+                    let mut tangent2 = bcurve.eval_tangent(*u, false);
+                    de_noise(tangent2.as_mut_slice());
+                    for i in 0..d
+                    {
+                        assert_relative_eq!(tangent1[i], tangent2[i], max_relative = 1e-12);
+                    }
+                }
+            }
+        };
+    }
+    tangent!(
+        tangent_d2_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d2_p1,
+        tangent_d2_p1,
+        2,
+        1
+    );
+    tangent!(
+        tangent_d2_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d2_p2,
+        tangent_d2_p2,
+        2,
+        2
+    );
+    tangent!(
+        tangent_d2_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d2_p3,
+        tangent_d2_p3,
+        2,
+        3
+    );
+    tangent!(
+        tangent_d2_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d2_p4,
+        tangent_d2_p4,
+        2,
+        4
+    );
+    tangent!(
+        tangent_d3_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d3_p1,
+        tangent_d3_p1,
+        3,
+        1
+    );
+    tangent!(
+        tangent_d3_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d3_p2,
+        tangent_d3_p2,
+        3,
+        2
+    );
+    tangent!(
+        tangent_d3_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d3_p3,
+        tangent_d3_p3,
+        3,
+        3
+    );
+    tangent!(
+        tangent_d3_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d3_p4,
+        tangent_d3_p4,
+        3,
+        4
+    );
+    //..............................................................................................
+
+    macro_rules! normal {
+        ($test_name: ident, $knots: ident, $weights: ident, $cpoints:ident, $tangents: ident, $dim: expr, $order:expr) => {
+            #[test]
+            fn $test_name()
+            {
+                let p = $order;
+                let test_data = TestData::new();
+                let bcurve = load_bcurve::<$dim>(p, &test_data);
+                let tangents = test_data.$tangents.values;
+
+                for (idx, u) in test_data.u.values.iter().enumerate()
+                {
+                    let mut tangent = Vector::<$dim>::from_row_slice(&tangents[idx]);
+                    tangent = tangent.normalize();
+                    let normal = bcurve.eval_normal(*u, true);
+                    let dot_product = normal.dot(&tangent);
+                    assert_abs_diff_eq!(dot_product, 0.0, epsilon = 1e-9);
+                }
+            }
+        };
+    }
+    normal!(
+        normal_d2_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d2_p1,
+        tangent_d2_p1,
+        2,
+        1
+    );
+    normal!(
+        normal_d2_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d2_p2,
+        tangent_d2_p2,
+        2,
+        2
+    );
+    normal!(
+        normal_d2_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d2_p3,
+        tangent_d2_p3,
+        2,
+        3
+    );
+    normal!(
+        normal_d2_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d2_p4,
+        tangent_d2_p4,
+        2,
+        4
+    );
+    normal!(
+        normal_d3_p1,
+        knots_p1,
+        weights_p1,
+        cpoints_d3_p1,
+        tangent_d3_p1,
+        3,
+        1
+    );
+    normal!(
+        normal_d3_p2,
+        knots_p2,
+        weights_p2,
+        cpoints_d3_p2,
+        tangent_d3_p2,
+        3,
+        2
+    );
+    normal!(
+        normal_d3_p3,
+        knots_p3,
+        weights_p3,
+        cpoints_d3_p3,
+        tangent_d3_p3,
+        3,
+        3
+    );
+    normal!(
+        normal_d3_p4,
+        knots_p4,
+        weights_p4,
+        cpoints_d3_p4,
+        tangent_d3_p4,
+        3,
+        4
+    );
+    //..............................................................................................
+
+    #[test]
+    fn eval_domain_boundary()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let (u0, u1) = bcurve.param_range();
+
+        assert_eq!(bcurve.clamp_to_domain(u1.next_up()), u1);
+        assert_eq!(bcurve.eval(u1.next_up()), bcurve.eval(u1));
+        assert_eq!(bcurve.eval(u0.next_down()), bcurve.eval(u0));
+        assert_eq!(bcurve.eval_diff(u1.next_up(), 2), bcurve.eval_diff(u1, 2));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn sample()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let (u1, u2) = bcurve.param_range();
+
+        let samples = bcurve.sample(10);
+        assert_eq!(samples.len(), 11);
+        assert_eq!(samples[0].0, u1);
+        assert_eq!(samples[10].0, u2);
+        assert_relative_eq!(samples[10].1, bcurve.end_point(), epsilon = 1e-12);
+        for (i, (u, point)) in samples.iter().enumerate()
+        {
+            assert_relative_eq!(*u, u1 + (u2 - u1) * i as f64 / 10.0, epsilon = 1e-14);
+            assert_eq!(*point, bcurve.eval(*u));
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn eval_de_casteljau()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let (u1, u2) = bcurve.param_range();
+
+        // each segment reproduces the curve over its span
+        let segments = bcurve.to_bezier_segments();
+        assert_eq!(segments.len(), spl::multiplicites(bcurve.knots()).len() - 1);
+        for segment in segments.iter()
+        {
+            assert_eq!(segment.cpoints_w().len(), bcurve.p() + 1);
+            let (a, b) = segment.param_range();
+            for i in 0..=10
+            {
+                let u = a + (b - a) * i as f64 / 10.0;
+                assert_relative_eq!(segment.eval(u), bcurve.eval(u), epsilon = 1e-13);
+            }
+        }
+
+        // de Casteljau agrees with the basis function sum in the interior
+        for i in 1..200
+        {
+            let u = u1 + (u2 - u1) * i as f64 / 200.0;
+            assert_relative_eq!(bcurve.eval_de_casteljau(u), bcurve.eval(u), epsilon = 1e-13);
+        }
+
+        // and is at least as accurate at the ends, where the curve meets its end control points
+        let cpoints = bcurve.cpoints();
+        for (u, exact) in [(u1, cpoints[0]), (u2, cpoints[cpoints.len() - 1])]
+        {
+            let err = (bcurve.eval_de_casteljau(u) - exact).norm();
+            assert!(err <= (bcurve.eval(u) - exact).norm());
+            assert!(err < 1e-15);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn eval_de_boor()
+    {
+        let test_data = TestData::new();
+        for p in 1..5
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            for u in test_data.u.values.iter()
+            {
+                assert_relative_eq!(bcurve.eval_de_boor(*u), bcurve.eval(*u), epsilon = 1e-13);
+            }
+
+            // raising interior knots to multiplicity p makes the curve only continuous there
+            let mut multiple = bcurve.clone();
+            let (u1, u2) = bcurve.param_range();
+            for u in [u1 + 0.3 * (u2 - u1), u1 + 0.7 * (u2 - u1)]
+            {
+                let r = p - multiple.multiplicity(u).min(p);
+                if r > 0
+                {
+                    multiple = multiple.insert_knot(u, r);
+                }
+                assert!(multiple.multiplicity(u) >= p);
+                assert_relative_eq!(multiple.eval_de_boor(u), bcurve.eval(u), epsilon = 1e-13);
+            }
+            for u in test_data.u.values.iter()
+            {
+                assert_relative_eq!(multiple.eval_de_boor(*u), multiple.eval(*u), epsilon = 1e-13);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn wrap_param()
+    {
+        // a circle of a periodic cylinder is a periodic curve
+        let n = 8;
+        let cpoints: Vec<Vector<3>> = (0..2 * n)
+            .map(|idx| {
+                let theta = 2.0 * std::f64::consts::PI * (idx % n) as f64 / n as f64;
+                Vector::<3>::new(2.0 * theta.cos(), 2.0 * theta.sin(), 3.0 * (idx / n) as f64)
+            })
+            .collect();
+        let cylinder = Bsurface::<3>::new_periodic_u(&BsurfaceDescriptor {
+            p: 3,
+            q: 1,
+            knots_u: vec![0.0, 1.0, 2.5, 3.0, 4.0, 5.5, 6.0, 7.0, 8.0],
+            knots_v: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints,
+            cweights: vec![1.0; 2 * n],
+        });
+        let circle = cylinder.iso_v(0.5);
+        let periodic = circle.is_periodic();
+        assert!(periodic);
+
+        let eps = 0.125;
+        assert_relative_eq!(circle.wrap_param(8.0 + eps, periodic), eps, epsilon = 1e-14);
+        assert_relative_eq!(circle.wrap_param(-eps, periodic), 8.0 - eps, epsilon = 1e-14);
+        assert_relative_eq!(circle.wrap_param(3.0 + 2.0 * 8.0, periodic), 3.0, epsilon = 1e-13);
+        assert_eq!(circle.wrap_param(8.0, periodic), 0.0);
+
+        // stepping over the seam continues smoothly onto the start of the curve
+        let mut last = circle.eval(circle.wrap_param(8.0 - 1e-6, periodic));
+        for i in 1..=10
+        {
+            let x = circle.eval(circle.wrap_param(8.0 - 1e-6 + 2e-7 * i as f64, periodic));
+            assert!((x - last).norm() < 1e-6);
+            last = x;
+        }
+
+        // a clamped open curve is not periodic, so parameters are clamped onto its range
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let (u1, u2) = bcurve.param_range();
+        assert!(!bcurve.is_periodic());
+        assert_eq!(bcurve.wrap_param(u2 + eps, false), u2);
+        assert_eq!(bcurve.wrap_param(u1 - eps, false), u1);
+        assert_eq!(bcurve.wrap_param(0.5 * (u1 + u2), false), 0.5 * (u1 + u2));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn builder()
+    {
+        let bcurve = BcurveBuilder::<2>::new()
+            .degree(2)
+            .add_control_point(Vector::<2>::new(0.0, 0.0), 1.0)
+            .add_control_point(Vector::<2>::new(1.0, 2.0), 1.0)
+            .add_control_point(Vector::<2>::new(3.0, 2.0), 2.0)
+            .add_control_point(Vector::<2>::new(4.0, 0.0), 1.0)
+            .build()
+            .unwrap();
+        assert_eq!(bcurve.p(), 2);
+        assert_eq!(bcurve.knots(), &[0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0]);
+        assert!(bcurve.is_rational());
+
+        // clamped, so the curve starts and ends at its end control points
+        assert_eq!(bcurve.param_range(), (0.0, 1.0));
+        assert_relative_eq!(bcurve.start_point(), Vector::<2>::new(0.0, 0.0), epsilon = 1e-14);
+        assert_relative_eq!(bcurve.end_point(), Vector::<2>::new(4.0, 0.0), epsilon = 1e-14);
+
+        // given knots are used as they are, and inconsistent ones are reported
+        let builder = BcurveBuilder::<2>::new()
+            .degree(1)
+            .add_control_point(Vector::<2>::new(0.0, 0.0), 1.0)
+            .add_control_point(Vector::<2>::new(1.0, 0.0), 1.0);
+        let line = builder.knots(vec![0.0, 0.0, 2.0, 2.0]).build().unwrap();
+        assert_eq!(line.param_range(), (0.0, 2.0));
+        assert!(BcurveBuilder::<2>::new()
+            .add_control_point(Vector::<2>::new(0.0, 0.0), 1.0)
+            .knots(vec![0.0, 1.0])
+            .auto_open_knots()
+            .build()
+            .is_err());
+        assert!(BcurveBuilder::<2>::new()
+            .degree(1)
+            .add_control_point(Vector::<2>::new(0.0, 0.0), 1.0)
+            .add_control_point(Vector::<2>::new(1.0, 0.0), 1.0)
+            .knots(vec![0.0, 1.0, 1.0])
+            .build()
+            .is_err());
+    }
+    //..............................................................................................
+
+    #[test]
+    fn control_point_pca()
+    {
+        // control points spread along a direction, with small offsets across it
+        let dir = Vector::<3>::new(1.0, 2.0, 2.0) / 3.0;
+        let across = Vector::<3>::new(2.0, -1.0, 0.0).normalize();
+        let origin = Vector::<3>::new(1.0, -1.0, 0.5);
+        let cpoints: Vec<Vector<3>> = (0..7)
+            .map(|i| origin + dir * (i as f64 - 3.0) + across * 0.1 * (-1.0f64).powi(i))
+            .collect();
+        let mean = cpoints.iter().sum::<Vector<3>>() / 7.0;
+        let (centroid, axes, variances) = make_bcurve(cpoints).control_point_pca();
+
+        assert_relative_eq!(centroid, mean, epsilon = 1e-14);
+        assert_relative_eq!(axes[0].dot(&dir).abs(), 1.0, epsilon = 1e-6);
+        assert_relative_eq!(variances[0], 4.0, epsilon = 1e-2);
+        assert!(variances[0] >= variances[1] && variances[1] >= variances[2]);
+        assert!(variances[2].abs() < 1e-12);
+        for i in 0..3
+        {
+            for j in 0..3
+            {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert_relative_eq!(axes[i].dot(&axes[j]), expected, epsilon = 1e-12);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn spans()
+    {
+        // a double knot at 0.5 and a triple knot at 0.75 leave empty spans between their copies
+        let cpoints: Vec<Vector<2>> = (0..10).map(|i| Vector::<2>::new(i as f64, (i % 3) as f64)).collect();
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 0.75, 0.75, 0.75, 1.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights: vec![1.0; 10],
+        });
+        let spans: Vec<(f64, f64)> = bcurve.spans().collect();
+        assert_eq!(spans, vec![(0.0, 0.25), (0.25, 0.5), (0.5, 0.75), (0.75, 1.0)]);
+        assert_eq!(spans.len(), bcurve.num_spans());
+
+        // sampling per span skips the empty spans and starts a run of samples at each knot
+        let params = bcurve.per_span_params(2);
+        assert_eq!(params, vec![0.0, 0.125, 0.25, 0.375, 0.5, 0.625, 0.75, 0.875, 1.0]);
+
+        // the spans are non-empty and cover the parameter range without gaps
+        let test_data = TestData::new();
+        for p in 1..5
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            let spans: Vec<(f64, f64)> = bcurve.spans().collect();
+            assert_eq!(spans.len(), bcurve.num_spans());
+            assert_eq!(spans[0].0, bcurve.param_range().0);
+            assert_eq!(spans[spans.len() - 1].1, bcurve.param_range().1);
+            assert!(spans.iter().all(|(a, b)| a < b));
+            assert!(spans.windows(2).all(|w| w[0].1 == w[1].0));
+        }
+
+        // the knots past the ends of an unclamped curve bound no spans
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 1.0, 1.5, 2.0],
+            cpoints: (0..6).map(|i| Vector::<2>::new(i as f64, (i % 2) as f64)).collect(),
+            cweights: vec![1.0; 6],
+        });
+        let spans: Vec<(f64, f64)> = bcurve.spans().collect();
+        assert_eq!(spans, vec![(0.0, 0.25), (0.25, 0.5), (0.5, 1.0)]);
+        assert_eq!(spans.len(), bcurve.num_spans());
+    }
+    //..............................................................................................
+
+    #[test]
+    fn is_at_knot()
+    {
+        let cpoints: Vec<Vector<2>> = (0..7).map(|i| Vector::<2>::new(i as f64, (i % 2) as f64)).collect();
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights: vec![1.0; 7],
+        });
+        assert_eq!(bcurve.is_at_knot(0.5), Some((0.5, 2)));
+        assert_eq!(bcurve.is_at_knot(0.5 + 4.0 * f64::EPSILON), Some((0.5, 2)));
+        assert_eq!(bcurve.is_at_knot(0.25), Some((0.25, 1)));
+        assert_eq!(bcurve.is_at_knot(1.0), Some((1.0, 4)));
+        assert_eq!(bcurve.is_at_knot(0.4), None);
+        assert_eq!(bcurve.multiplicity(0.4), 0);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn local_param()
+    {
+        // a double knot at 0.5 leaves an empty span between its copies
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0],
+            cpoints: (0..7).map(|i| Vector::<2>::new(i as f64, (i % 2) as f64)).collect(),
+            cweights: vec![1.0; 7],
+        });
+
+        for (span, left, right) in [(3, 0.0, 0.25), (4, 0.25, 0.5), (6, 0.5, 1.0)]
+        {
+            assert_eq!(bcurve.local_param(left), (span, 0.0));
+            assert_eq!(bcurve.local_param(0.5 * (left + right)), (span, 0.5));
+            let (i, t) = bcurve.local_param(right - 1e-12);
+            assert_eq!(i, span);
+            assert_relative_eq!(t, 1.0, epsilon = 1e-10);
+        }
+
+        // the end of the range lies at the end of the last span, even past it by noise
+        assert_eq!(bcurve.local_param(1.0), (6, 1.0));
+        assert_eq!(bcurve.local_param(1.0f64.next_up()), (6, 1.0));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn eval_with_ders()
+    {
+        let test_data = TestData::new();
+        for p in 1..=4
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            for u in test_data.u.values.iter()
+            {
+                for k in 0..=p
+                {
+                    let ders = bcurve.eval_with_ders(*u, k);
+                    assert_eq!(ders.len(), k + 1);
+                    for (m, der) in ders.iter().enumerate()
+                    {
+                        let expected = bcurve.eval_diff(*u, m);
+                        assert_relative_eq!(*der, expected, epsilon = 1e-12 * expected.norm().max(1.0));
+                    }
+                }
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    #[should_panic(expected = "derivative order 3 exceeds the maximum of 2")]
+    fn eval_with_ders_limit()
+    {
+        // a non-rational quadratic has no derivatives past the second
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vector::<2>::new(0.0, 0.0), Vector::<2>::new(1.0, 2.0), Vector::<2>::new(2.0, 0.0)],
+            cweights: vec![1.0; 3],
+        });
+        bcurve.eval_with_ders(0.5, 3);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn num_spans()
+    {
+        let test_data = TestData::new();
+        for p in 1..=4
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            assert_eq!(bcurve.num_control_points() + bcurve.p() + 1, bcurve.num_knots());
+            assert_eq!(bcurve.num_spans(), bcurve.to_bezier_segments().len());
+        }
+
+        // repeated interior knots and the knots past an unclamped end do not add spans
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 1.0, 1.5, 2.0],
+            cpoints: (0..6).map(|i| Vector::<2>::new(i as f64, (i % 2) as f64)).collect(),
+            cweights: vec![1.0; 6],
+        });
+        assert_eq!(bcurve.num_control_points(), 6);
+        assert_eq!(bcurve.num_knots(), 9);
+        assert_eq!(bcurve.num_spans(), 3);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn cpoints_iter()
+    {
+        let cpoints: Vec<Vector<2>> = (0..7).map(|i| Vector::<2>::new(i as f64, (i % 3) as f64)).collect();
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0],
+            cpoints: cpoints.clone(),
+            cweights: (0..7).map(|i| 0.5 + 0.25 * i as f64).collect(),
+        });
+
+        let streamed: Vec<Vector<2>> = bcurve.cpoints_iter().collect();
+        assert_eq!(streamed, bcurve.cpoints());
+        assert_eq!(streamed.len(), cpoints.len());
+        for (a, b) in streamed.iter().zip(cpoints.iter())
+        {
+            assert_relative_eq!(*a, *b, epsilon = 1e-14);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn continuity_at()
+    {
+        let knots = vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0];
+        let n = knots.len() - 4;
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots,
+            cweights: vec![1.0; n],
+            cpoints: (0..n).map(|i| Vector::<2>::new(i as f64, (i * i) as f64)).collect(),
+        });
+        assert_eq!(bcurve.continuity_at(0.25), 2);
+        assert_eq!(bcurve.continuity_at(0.5), 0);
+        assert_eq!(bcurve.continuity_at(0.75), 2);
+        assert_eq!(bcurve.continuity_at(0.6), 3);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn tangent_indicatrix()
+    {
+        // a planar curve in the plane through the origin with normal n
+        let n = Vector::<3>::new(1.0, 2.0, 2.0) / 3.0;
+        let e1 = Vector::<3>::new(2.0, -1.0, 0.0).normalize();
+        let e2 = n.cross(&e1);
+        let cpoints: Vec<Vector<3>> = [(0.0, 0.0), (1.0, 2.0), (2.0, -1.0), (4.0, 1.5), (5.0, 0.0)]
+            .iter()
+            .map(|(a, b)| *a * e1 + *b * e2)
+            .collect();
+        let bcurve = make_bcurve(cpoints);
+
+        let indicatrix = bcurve.tangent_indicatrix(50);
+        assert_eq!(indicatrix.len(), 50);
+        for t in indicatrix.iter()
+        {
+            assert_relative_eq!(t.norm(), 1.0, epsilon = 1e-12);
+            assert!(t.dot(&n).abs() < 1e-12);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn torsion_profile()
+    {
+        // the planar curve of the tangent indicatrix test does not twist
+        let n = Vector::<3>::new(1.0, 2.0, 2.0) / 3.0;
+        let e1 = Vector::<3>::new(2.0, -1.0, 0.0).normalize();
+        let e2 = n.cross(&e1);
+        let cpoints: Vec<Vector<3>> = [(0.0, 0.0), (1.0, 2.0), (2.0, -1.0), (4.0, 1.5), (5.0, 0.0)]
+            .iter()
+            .map(|(a, b)| *a * e1 + *b * e2)
+            .collect();
+        let profile = make_bcurve(cpoints).torsion_profile(50);
+        assert_eq!(profile.len(), 50);
+        assert!(profile.iter().all(|(_, tau)| tau.abs() < 1e-8));
+
+        // a helix of radius a and pitch 2 pi b has the torsion b / (a^2 + b^2), the interpolant of
+        // points along two turns of it follows this closely away from its ends
+        let (a, b) = (2.0, 0.5);
+        let points: Vec<Vector<3>> = (0..=80)
+            .map(|i| {
+                let t = 4.0 * std::f64::consts::PI * i as f64 / 80.0;
+                Vector::<3>::new(a * t.cos(), a * t.sin(), b * t)
+            })
+            .collect();
+        let helix = Bcurve::interpolate(&points, 5);
+        let tau = b / (a * a + b * b);
+        let profile = helix.torsion_profile(101);
+        assert_eq!(profile.first().unwrap().0, 0.0);
+        assert_eq!(profile.last().unwrap().0, 1.0);
+        for (_, t) in profile[10..91].iter()
+        {
+            assert_relative_eq!(*t, tau, max_relative = 1e-3);
+        }
+
+        // a straight curve has no torsion to speak of
+        let line = make_bcurve((0..5).map(|i| Vector::<3>::new(i as f64, 2.0 * i as f64, 0.5)).collect());
+        assert!(line.torsion_profile(10).iter().all(|(_, tau)| tau.is_nan()));
+
+        // too few samples are treated as the two ends of the domain
+        for samples in [0, 1, 2]
+        {
+            let ends: Vec<f64> = helix.torsion_profile(samples).iter().map(|(u, _)| *u).collect();
+            assert_eq!(ends, vec![0.0, 1.0]);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn split()
+    {
+        let test_data = TestData::new();
+        for p in 1..5
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            let (u0, u1) = bcurve.param_range();
+            let us = [u0 + 0.3 * (u1 - u0), bcurve.knots()[p + 1]];
+            for usplit in us
+            {
+                let (left, right) = bcurve.split(usplit);
+                assert_eq!(left.param_range(), (u0, usplit));
+                assert_eq!(right.param_range(), (usplit, u1));
+                for u in test_data.u.values.iter()
+                {
+                    let point1 = bcurve.eval(*u);
+                    let point2 = if *u <= usplit { left.eval(*u) } else { right.eval(*u) };
+                    assert_relative_eq!(point1, point2, epsilon = 1e-12);
+                }
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn active_control_points()
+    {
+        let test_data = TestData::new();
+        for p in 1..5
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            let n = bcurve.num_control_points();
+            let (u0, u1) = bcurve.param_range();
+            assert_eq!(bcurve.active_control_points(u0), 0..p + 1);
+            assert_eq!(bcurve.active_control_points(u1), n - p - 1..n);
+
+            // moving a point just outside the range leaves the curve where it was
+            for u in test_data.u.values.iter()
+            {
+                let range = bcurve.active_control_points(*u);
+                assert_eq!(range.len(), p + 1);
+                for i in [range.start.wrapping_sub(1), range.end]
+                {
+                    if i < n
+                    {
+                        let mut moved = bcurve.clone();
+                        moved.set_cpoint(i, &(bcurve.cpoints()[i] + Vector::<3>::new(1.0, 2.0, 3.0)));
+                        assert_relative_eq!(moved.eval(*u), bcurve.eval(*u), epsilon = 1e-12);
+                    }
+                }
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn junction_continuity()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let (u0, u1) = bcurve.param_range();
+        let (left, right) = bcurve.split(u0 + 0.4 * (u1 - u0));
+        assert_eq!(super::junction_continuity(&left, &right, 1e-9), 2);
+
+        // a cubic arch continued along its end tangent by a straight line is only G1
+        let arch = make_bcurve(vec![
+            Vector::<2>::new(0.0, 0.0),
+            Vector::<2>::new(1.0, 1.0),
+            Vector::<2>::new(2.0, 1.0),
+            Vector::<2>::new(3.0, 0.0),
+        ]);
+        let end = arch.end_point();
+        let dir = arch.end_tangent().normalize();
+        let line = make_bcurve(vec![end, end + dir, end + 2.0 * dir, end + 3.0 * dir]);
+        assert_eq!(super::junction_continuity(&arch, &line, 1e-9), 1);
+
+        // an S-bend has curvature of the same magnitude either side of the junction but of
+        // opposite direction
+        let mirrored = make_bcurve(arch.cpoints().iter().map(|p| 2.0 * end - p).rev().collect());
+        assert_eq!(super::junction_continuity(&arch, &mirrored, 1e-9), 1);
+
+        // arbitrarily joined curves meet but turn a corner
+        let corner = make_bcurve(vec![
+            end,
+            end + Vector::<2>::new(0.0, 1.0),
+            end + Vector::<2>::new(1.0, 2.0),
+            end + Vector::<2>::new(2.0, 2.0),
+        ]);
+        assert_eq!(super::junction_continuity(&arch, &corner, 1e-9), 0);
+    }
+    //..............................................................................................
+
+    #[test]
+    #[should_panic(expected = "curves do not meet at the junction")]
+    fn junction_continuity_apart()
+    {
+        let a = make_bcurve((0..4).map(|i| Vector::<2>::new(i as f64, 0.0)).collect());
+        let b = make_bcurve((0..4).map(|i| Vector::<2>::new(i as f64, 1.0)).collect());
+        super::junction_continuity(&a, &b, 1e-9);
+    }
+    //..............................................................................................
+
+    fn make_bcurve<const D: usize>(cpoints: Vec<Vector<D>>) -> Bcurve<D>
+    where
+        [(); D + 1]:,
+        [(); D * BCURVE_DER_MAX]:,
+        [(); D * 3]:,
+    {
+        let n = cpoints.len();
+        let mut knots = vec![0.0; 4];
+        knots.extend((1..n - 3).map(|i| i as f64 / (n - 3) as f64));
+        knots.extend(vec![1.0; 4]);
+        Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots,
+            cweights: vec![1.0; n],
+            cpoints,
+        })
+    }
+    //..............................................................................................
+
+    #[test]
+    fn eval_diff_high_order()
+    {
+        // single segment degree 6 curve, so the top derivative is the constant
+        // p! * sum_i (-1)^(p - i) * C(p, i) * P_i
+        let p = 6;
+        let cpoints: Vec<Vector<3>> = (0..p + 1)
+            .map(|i| {
+                let x = i as f64;
+                Vector::<3>::new(x, (x * 1.3).sin() + 0.1 * x * x, (x * 0.7).cos() * x)
+            })
+            .collect();
+        let mut knots = vec![0.0; p + 1];
+        knots.extend(vec![1.0; p + 1]);
+        let bcurve = Bcurve::new(&BcurveDescriptor {
+            p,
+            knots,
+            cweights: vec![1.0; p + 1],
+            cpoints: cpoints.clone(),
+        });
+
+        let mut binom = [0.0; 49];
+        binom_coeff(p, &mut binom);
+        let binom_arr = NDArrayWrapper::<'_, f64, 2>::new(&mut binom, &[p + 1, p + 1]);
+        let mut top_der = Vector::<3>::zeros();
+        for i in 0..p + 1
+        {
+            let sign = if (p - i) % 2 == 0 { 1.0 } else { -1.0 };
+            top_der += sign * binom_arr[&[p, i]] * cpoints[i];
+        }
+        top_der *= 720.0;
+        assert!(top_der.norm() > 1.0);
+
+        for u in [0.0, 0.3, 0.65, 1.0]
+        {
+            let mut ders = vec![Vector::<3>::zeros(); p + 2];
+            bcurve.eval_diff_all(u, p + 1, &mut ders);
+            assert_relative_eq!(ders[p], top_der, max_relative = 1e-10, epsilon = 1e-8);
+            assert_relative_eq!(ders[p + 1], Vector::<3>::zeros(), epsilon = 1e-8);
+            assert_relative_eq!(bcurve.eval_diff(u, p), top_der, max_relative = 1e-10, epsilon = 1e-8);
+            assert_relative_eq!(ders[0], bcurve.eval(u), max_relative = 1e-12);
+
+            // the low order derivatives agree with central differences of the next lower one
+            let h = 1e-5;
+            let um = (u - h).max(0.0);
+            let up = (u + h).min(1.0);
+            for m in 1..p + 1
+            {
+                let fd = (bcurve.eval_diff(up, m - 1) - bcurve.eval_diff(um, m - 1)) / (up - um);
+                assert_relative_eq!(ders[m], fd, max_relative = 1e-3, epsilon = 1e-3);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn min_distance_parallel()
+    {
+        let cpoints = vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 2.0, 0.0),
+            Vector::<3>::new(2.0, -1.0, 0.0),
+            Vector::<3>::new(3.0, 1.0, 0.0),
+            Vector::<3>::new(4.0, 0.5, 0.0),
+        ];
+        let offset = Vector::<3>::new(0.0, 0.0, 0.75);
+        let bcurve1 = make_bcurve(cpoints.clone());
+        let bcurve2 = make_bcurve(cpoints.iter().map(|x| x + offset).collect());
+
+        let (u, v, dist) = bcurve1.min_distance(&bcurve2);
+        assert_relative_eq!(dist, 0.75, epsilon = 1e-10);
+        assert_relative_eq!((bcurve1.eval(u) - bcurve2.eval(v)).norm(), dist, epsilon = 1e-12);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn min_distance_crossing()
+    {
+        let bcurve1 = make_bcurve(vec![
+            Vector::<2>::new(0.0, 0.0),
+            Vector::<2>::new(1.0, 1.5),
+            Vector::<2>::new(2.0, -0.5),
+            Vector::<2>::new(3.0, 1.0),
+            Vector::<2>::new(4.0, 0.0),
+        ]);
+        let bcurve2 = make_bcurve(vec![
+            Vector::<2>::new(1.0, -2.0),
+            Vector::<2>::new(1.5, -0.5),
+            Vector::<2>::new(2.5, 1.0),
+            Vector::<2>::new(2.0, 3.0),
+        ]);
+
+        let (u, v, dist) = bcurve1.min_distance(&bcurve2);
+        assert!(dist < 1e-10);
+        assert_relative_eq!(bcurve1.eval(u), bcurve2.eval(v), epsilon = 1e-10);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn min_distance_separated()
+    {
+        let bcurve1 = make_bcurve(vec![
+            Vector::<2>::new(0.0, 0.0),
+            Vector::<2>::new(1.0, 1.5),
+            Vector::<2>::new(2.0, -0.5),
+            Vector::<2>::new(3.0, 1.0),
+            Vector::<2>::new(4.0, 0.0),
+        ]);
+        let bcurve2 = make_bcurve(vec![
+            Vector::<2>::new(0.0, 3.0),
+            Vector::<2>::new(1.5, 1.5),
+            Vector::<2>::new(2.5, 2.0),
+            Vector::<2>::new(4.0, 3.5),
+        ]);
+
+        // brute force the minimum over a fine grid of parameter values
+        let num = 1000;
+        let mut brute = f64::MAX;
+        for i in 0..num + 1
+        {
+            let x1 = bcurve1.eval(i as f64 / num as f64);
+            for j in 0..num + 1
+            {
+                brute = brute.min((x1 - bcurve2.eval(j as f64 / num as f64)).norm());
+            }
+        }
+
+        let (_u, _v, dist) = bcurve1.min_distance(&bcurve2);
+        assert!(dist > 0.1);
+        assert!(dist <= brute + 1e-12);
+        assert!(brute - dist < 1e-4);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn interpolate()
+    {
+        let points: Vec<Vector<3>> = (0..9)
+            .map(|i| {
+                let t = i as f64 * 0.4;
+                Vector::<3>::new(t.cos(), t.sin(), 0.2 * t)
+            })
+            .collect();
+        let bcurve = Bcurve::<3>::interpolate(&points, 3);
+
+        // the curve passes through every point at its chord length parameter
+        let total: f64 = points.windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+        let mut u = 0.0;
+        for i in 0..points.len()
+        {
+            if i > 0
+            {
+                u += (points[i] - points[i - 1]).norm() / total;
+            }
+            assert_relative_eq!(bcurve.eval(u.min(1.0)), points[i], epsilon = 1e-10);
+        }
+
+        // two points give a straight line
+        let line = Bcurve::<2>::interpolate(&[Vector::<2>::new(0.0, 0.0), Vector::<2>::new(2.0, 1.0)], 3);
+        assert_eq!(line.p(), 1);
+        assert_relative_eq!(line.eval(0.5), Vector::<2>::new(1.0, 0.5), epsilon = 1e-12);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn max_curvature()
+    {
+        // the parabola y = x^2 has its greatest curvature of 2 at the vertex
+        let parabola = Bcurve::<3>::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vector::<3>::new(-1.0, 1.0, 0.0),
+                Vector::<3>::new(0.0, -1.0, 0.0),
+                Vector::<3>::new(1.0, 1.0, 0.0),
+            ],
+            cweights: vec![1.0; 3],
+        });
+        let (u, kappa) = parabola.max_curvature();
+        assert_relative_eq!(u, 0.5, epsilon = 1e-6);
+        assert_relative_eq!(kappa, 2.0, epsilon = 1e-9);
+
+        // a bump between two straight runs, where the curvature vanishes
+        let bump = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 0.0, 0.0),
+            Vector::<3>::new(2.0, 0.0, 0.0),
+            Vector::<3>::new(3.0, 0.0, 0.0),
+            Vector::<3>::new(4.0, 1.5, 0.0),
+            Vector::<3>::new(5.5, 0.0, 0.0),
+            Vector::<3>::new(6.0, 0.0, 0.0),
+            Vector::<3>::new(7.0, 0.0, 0.0),
+            Vector::<3>::new(8.0, 0.0, 0.0),
+        ]);
+        let num = 20000;
+        let (u_brute, kappa_brute) = (0..=num)
+            .map(|i| {
+                let u = i as f64 / num as f64;
+                (u, bump.eval_curvature(u))
+            })
+            .fold((0.0, 0.0), |a, b| if b.1 > a.1 { b } else { a });
+        let (u, kappa) = bump.max_curvature();
+        assert!(kappa >= kappa_brute - 1e-12);
+        assert_relative_eq!(u, u_brute, epsilon = 1e-4);
+        assert_relative_eq!(bump.eval_curvature(0.1), 0.0, epsilon = 1e-12);
+
+        // a straight line has zero curvature everywhere
+        let line = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 1.0, 0.0),
+            Vector::<3>::new(2.0, 2.0, 0.0),
+            Vector::<3>::new(3.0, 3.0, 0.0),
+        ]);
+        assert_eq!(line.max_curvature(), (0.0, 0.0));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn try_eval()
+    {
+        let test_data = TestData::new();
+        let curve = load_bcurve::<3>(3, &test_data);
+        let (u1, u2) = curve.param_range();
+
+        for u in [u1, 0.5 * (u1 + u2), u2]
+        {
+            assert_eq!(curve.try_eval(u).unwrap(), curve.eval(u));
+        }
+        for u in [u1 - 0.1, u2 + 0.1]
+        {
+            match curve.try_eval(u)
+            {
+                Err(ModellerError::OutOfDomain(v)) => assert_eq!(v, u),
+                other => panic!("expected OutOfDomain, got {:?}", other),
+            }
+            assert!(matches!(curve.try_eval_diff(u, 1), Err(ModellerError::OutOfDomain(_))));
+        }
+        assert!(curve.try_eval(f64::NAN).is_err());
+        assert_eq!(curve.try_eval_diff(0.5 * (u1 + u2), 2).unwrap(), curve.eval_diff(0.5 * (u1 + u2), 2));
+
+        // descriptor errors convert into the crate error type
+        let err: ModellerError = DescriptorError::InvalidInput("bad".to_string()).into();
+        assert!(matches!(err, ModellerError::InvalidInput(msg) if msg == "bad"));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn try_eval_zero_weight()
+    {
+        // a zero weight puts the end of the curve at infinity
+        let curve = Bcurve::new(&BcurveDescriptor {
+            p: 1,
+            knots: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints: vec![Vector::<2>::new(0.0, 0.0), Vector::<2>::new(1.0, 1.0)],
+            cweights: vec![1.0, 0.0],
+        });
+        assert!(curve.eval(1.0).iter().any(|x| !x.is_finite()));
+        assert!(matches!(curve.try_eval(1.0), Err(ModellerError::DegenerateWeight(w)) if w == 0.0));
+        assert_eq!(curve.try_eval(0.5).unwrap(), curve.eval(0.5));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn try_new_invalid()
+    {
+        let valid = || BcurveDescriptor::<2> {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vector::<2>::new(0.0, 0.0),
+                Vector::<2>::new(1.0, 1.0),
+                Vector::<2>::new(2.0, 0.0),
+                Vector::<2>::new(3.0, 1.0),
+            ],
+            cweights: vec![1.0; 4],
+        };
+        let message = |bcd: &BcurveDescriptor<2>| match Bcurve::try_new(bcd)
+        {
+            Err(err) => err.to_string(),
+            Ok(_) => panic!("descriptor accepted"),
+        };
+
+        assert!(Bcurve::try_new(&valid()).is_ok());
+
+        let mut bcd = valid();
+        bcd.p = spl::PMAX + 1;
+        assert!(message(&bcd).contains("exceeds the maximum"));
+        match Bcurve::try_new(&bcd)
+        {
+            Err(DescriptorError::LimitExceeded { limit, value, max }) =>
+            {
+                assert_eq!((limit, value, max), ("degree", Bcurve::<2>::PMAX + 1, Bcurve::<2>::PMAX));
+            }
+            _ => panic!("degree limit not reported"),
+        }
+
+        let mut bcd = valid();
+        bcd.p = 4;
+        bcd.knots = vec![0.0; 9];
+        assert!(message(&bcd).contains("too few for order"));
+
+        let mut bcd = valid();
+        bcd.cweights.pop();
+        assert!(message(&bcd).contains("weights given for"));
+
+        let mut bcd = valid();
+        bcd.knots.pop();
+        assert!(message(&bcd).contains("knots given, expected"));
+
+        let mut bcd = valid();
+        bcd.knots.swap(3, 4);
+        assert!(message(&bcd).contains("knots not sorted"));
+
+        // knots out of order only by floating noise are accepted
+        let mut bcd = valid();
+        bcd.knots[4] = 1.0f64.next_up();
+        let noisy = Bcurve::try_new(&bcd).unwrap();
+        let exact = Bcurve::new(&valid());
+        for u in [0.0, 0.3, 0.5, 0.8, 1.0]
+        {
+            assert_relative_eq!(noisy.eval(u), exact.eval(u), epsilon = 1e-12);
+        }
+
+        let mut bcd = valid();
+        bcd.cweights[1] = -1.0;
+        assert!(message(&bcd).contains("non-negative"));
+    }
+    //..............................................................................................
+
+    #[test]
+    #[should_panic(expected = "Invalid Bcurve descriptor")]
+    fn new_invalid()
+    {
+        Bcurve::new(&BcurveDescriptor::<2> {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0],
+            cpoints: vec![Vector::<2>::new(0.0, 0.0); 3],
+            cweights: vec![1.0; 3],
+        });
+    }
+    //..............................................................................................
+
+    #[test]
+    fn inflection_points()
+    {
+        // an S-shaped cubic, symmetric about its midpoint, inflects at u = 1/2
+        let s_curve = Bcurve::<2>::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vector::<2>::new(0.0, 0.0),
+                Vector::<2>::new(1.0, 1.0),
+                Vector::<2>::new(2.0, -1.0),
+                Vector::<2>::new(3.0, 0.0),
+            ],
+            cweights: vec![1.0; 4],
+        });
+        let inflections = s_curve.inflection_points();
+        assert_eq!(inflections.len(), 1);
+        assert_relative_eq!(inflections[0], 0.5, epsilon = 1e-9);
+        assert!(s_curve.eval_signed_curvature(0.25) < 0.0);
+        assert!(s_curve.eval_signed_curvature(0.75) > 0.0);
+
+        // a convex arc, with straight ends, has none
+        let arc = make_bcurve(vec![
+            Vector::<2>::new(0.0, 0.0),
+            Vector::<2>::new(1.0, 0.0),
+            Vector::<2>::new(2.0, 0.0),
+            Vector::<2>::new(3.0, 0.0),
+            Vector::<2>::new(4.0, 1.0),
+            Vector::<2>::new(4.0, 2.0),
+        ]);
+        assert!(arc.inflection_points().is_empty());
+    }
+    //..............................................................................................
+
+    #[test]
+    fn end_accessors()
+    {
+        // a rational clamped curve with a repeated interior knot
+        let bcurve = Bcurve::<3>::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.3, 0.6, 0.6, 2.0, 2.0, 2.0, 2.0],
+            cpoints: vec![
+                Vector::<3>::new(0.0, 0.0, 0.0),
+                Vector::<3>::new(1.0, 2.0, 0.5),
+                Vector::<3>::new(2.0, -1.0, 1.0),
+                Vector::<3>::new(3.0, 0.5, -1.0),
+                Vector::<3>::new(4.0, 1.0, 0.0),
+                Vector::<3>::new(5.0, -2.0, 2.0),
+                Vector::<3>::new(6.0, 0.0, 1.0),
+            ],
+            cweights: vec![1.0, 0.5, 2.0, 1.0, 1.5, 0.8, 1.2],
+        });
+        let (u0, u1) = bcurve.param_range();
+        assert_eq!(bcurve.start_point(), Vector::<3>::new(0.0, 0.0, 0.0));
+        assert_eq!(bcurve.end_point(), Vector::<3>::new(6.0, 0.0, 1.0));
+        assert_relative_eq!(bcurve.start_point(), bcurve.eval(u0), epsilon = 1e-12);
+        assert_relative_eq!(bcurve.end_point(), bcurve.eval(u1), epsilon = 1e-12);
+        assert_relative_eq!(bcurve.start_tangent(), bcurve.eval_tangent(u0, false), epsilon = 1e-12);
+        assert_relative_eq!(bcurve.end_tangent(), bcurve.eval_tangent(u1, false), epsilon = 1e-12);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn dedup_control_points()
+    {
+        let a = Vector::<3>::new(0.0, 0.0, 0.0);
+        let mut bcurve = make_bcurve(vec![
+            a,
+            a,
+            Vector::<3>::new(1.0, 1.0, 0.0),
+            Vector::<3>::new(2.0, 1.0, 1.0),
+            Vector::<3>::new(2.0, 1.0, 1.0),
+            Vector::<3>::new(3.0, 0.0, 1.0),
+        ]);
+        let (start, end) = (bcurve.eval(0.0), bcurve.eval(1.0));
+        assert!(bcurve.eval_tangent(0.0, true).iter().any(|x| x.is_nan()));
+
+        assert_eq!(bcurve.dedup_control_points(1e-9), 2);
+        assert_eq!(bcurve.cpoints().len(), 4);
+        assert_eq!(bcurve.knots().len(), bcurve.cpoints().len() + bcurve.p() + 1);
+        assert_relative_eq!(bcurve.eval(0.0), start, epsilon = 1e-14);
+        assert_relative_eq!(bcurve.eval(1.0), end, epsilon = 1e-14);
+        for i in 0..=100
+        {
+            let t = bcurve.eval_tangent(i as f64 / 100.0, true);
+            assert!(t.iter().all(|x| x.is_finite()));
+        }
+        assert_eq!(bcurve.dedup_control_points(1e-9), 0);
+
+        // a Bezier curve has no interior knots, so its degree is lowered instead
+        let mut bezier = Bcurve::<2>::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vector::<2>::new(0.0, 0.0), Vector::<2>::new(0.0, 0.0), Vector::<2>::new(1.0, 1.0)],
+            cweights: vec![1.0; 3],
+        });
+        assert_eq!(bezier.dedup_control_points(1e-9), 1);
+        assert_eq!(bezier.p(), 1);
+        assert_relative_eq!(bezier.eval(0.5), Vector::<2>::new(0.5, 0.5), epsilon = 1e-14);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn to_descriptor()
+    {
+        let test_data = TestData::new();
+        let rational = load_bcurve::<3>(3, &test_data);
+        assert!(rational.is_rational());
+        let non_rational = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 2.0, 0.0),
+            Vector::<3>::new(2.0, -1.0, 1.0),
+            Vector::<3>::new(3.0, 0.0, 2.0),
+            Vector::<3>::new(4.0, 1.0, 0.0),
+        ]);
+        assert!(!non_rational.is_rational());
+
+        for bcurve in [rational, non_rational]
+        {
+            let bcd = bcurve.to_descriptor();
+            assert!(bcd.is_valid().is_ok());
+            assert_eq!(bcd.p, bcurve.p());
+            assert_eq!(bcd.knots, bcurve.knots());
+
+            let copy = Bcurve::new(&bcd);
+            for (pw1, pw2) in copy.cpoints_w().iter().zip(bcurve.cpoints_w().iter())
+            {
+                assert_relative_eq!(pw1, pw2, epsilon = 1e-12);
+            }
+            let (u1, u2) = bcurve.param_range();
+            for i in 0..=100
+            {
+                let u = u1 + (u2 - u1) * i as f64 / 100.0;
+                assert_relative_eq!(copy.eval(u), bcurve.eval(u), epsilon = 1e-12);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn arc_length()
+    {
+        // a straight line with uneven parameterisation
+        let line = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vector::<3>::new(0.0, 0.0, 0.0),
+                Vector::<3>::new(0.5, 0.0, 0.0),
+                Vector::<3>::new(3.0, 0.0, 0.0),
+            ],
+            cweights: vec![1.0; 3],
+        });
+        assert_relative_eq!(line.eval_arclen(0.0, 1.0), 3.0, epsilon = 1e-12);
+        assert_relative_eq!(line.eval_arclen(0.0, 0.5), line.eval(0.5)[0], epsilon = 1e-12);
+        for s in [0.0, 0.3, 1.0, 2.2, 3.0]
+        {
+            assert_relative_eq!(line.eval(line.arc_length_param(s))[0], s, epsilon = 1e-9);
+        }
+
+        // the upper semicircle of a circle of radius 2, on u in [0, 0.5]
+        let (circle, _) = circle(&Vector::<3>::zeros(), 2.0).split(0.5);
+        assert_relative_eq!(circle.eval_arclen(0.0, 0.5), 2.0 * std::f64::consts::PI, epsilon = 1e-6);
+
+        // equal arc lengths subtend equal chords on a circle
+        let params = circle.arc_length_params(12);
+        assert_eq!(params.len(), 13);
+        let chord = 4.0 * (std::f64::consts::PI / 24.0).sin();
+        for w in params.windows(2)
+        {
+            assert_relative_eq!((circle.eval(w[1]) - circle.eval(w[0])).norm(), chord, epsilon = 1e-6);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn region_centroid()
+    {
+        // an off-center circle
+        let (radius, center) = (1.5, Vec2::new(2.0, -1.0));
+        let circle = circle(&center, radius);
+        assert!(circle.is_closed());
+
+        let (centroid, area) = circle.region_centroid();
+        assert_relative_eq!(centroid, center, epsilon = 1e-8);
+        assert_relative_eq!(area, std::f64::consts::PI * radius * radius, epsilon = 1e-8);
+
+        // reversing the curve does not change the result
+        let mut bcd = circle.to_descriptor();
+        bcd.cpoints.reverse();
+        bcd.cweights.reverse();
+        let (centroid, area) = Bcurve::new(&bcd).region_centroid();
+        assert_relative_eq!(centroid, center, epsilon = 1e-8);
+        assert_relative_eq!(area, std::f64::consts::PI * radius * radius, epsilon = 1e-8);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn align_parameterization()
+    {
+        // two quarter circles, the second reparameterised by scaling the weights geometrically
+        let (quarter, _) = circle(&Vector::<3>::zeros(), 1.0).split(0.25);
+        let c = 3.0;
+        let arc = |c: f64, u2: f64| {
+            let mut bcd = quarter.to_descriptor();
+            bcd.knots = vec![0.0, 0.0, 0.0, u2, u2, u2];
+            bcd.cweights = bcd.cweights.iter().enumerate().map(|(i, w)| w * c.powi(i as i32)).collect();
+            Bcurve::new(&bcd)
+        };
+        let reference = arc(c, 2.0);
+        let mut curve = arc(1.0, 1.0);
+
+        // before alignment the curves are at different points for the same parameter
+        assert!((curve.eval(1.0) - reference.eval(1.0)).norm() > 0.1);
+
+        // a tolerance the fit cannot meet leaves the curve as it was
+        let original = curve.clone();
+        assert!(matches!(
+            curve.align_parameterization(&reference, 1e-14),
+            Err(ModellerError::NonConvergence(_))
+        ));
+        assert_eq!(curve.cpoints_w(), original.cpoints_w());
+        assert_eq!(curve.knots(), original.knots());
+
+        let error = curve.align_parameterization(&reference, 1e-5).unwrap();
+        assert!(error > 0.0 && error <= 1e-5);
+        assert_eq!(curve.param_range(), (0.0, 2.0));
+        let ref_length = reference.eval_arclen(0.0, 2.0);
+        let length = curve.eval_arclen(0.0, 2.0);
+        assert_relative_eq!(length, ref_length, epsilon = 1e-5);
+        for i in 0..=50
+        {
+            let u = 2.0 * i as f64 / 50.0;
+            let ref_fraction = reference.eval_arclen(0.0, u) / ref_length;
+            let fraction = curve.eval_arclen(0.0, u) / length;
+            assert_relative_eq!(fraction, ref_fraction, epsilon = 1e-5);
+            assert_relative_eq!(curve.eval(u), reference.eval(u), epsilon = 1e-5);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn length_bounds()
+    {
+        let test_data = TestData::new();
+        let mut curves: Vec<Bcurve<3>> = (2..5).map(|p| load_bcurve::<3>(p, &test_data)).collect();
+        curves.push(make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 2.0, -1.0),
+            Vector::<3>::new(2.5, -1.0, 1.0),
+            Vector::<3>::new(3.0, 0.5, 2.0),
+            Vector::<3>::new(4.0, 1.0, 0.0),
+        ]));
+        for curve in curves.iter()
+        {
+            let (lower, upper) = curve.length_bounds();
+            let (u1, u2) = curve.param_range();
+            let length = curve.eval_arclen(u1, u2);
+            assert!(lower <= length + 1e-10 && length <= upper + 1e-10);
+            assert!(lower < upper);
+        }
+
+        // the bounds are tight for a straight line
+        let line = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 1.0, 1.0),
+            Vector::<3>::new(2.0, 2.0, 2.0),
+            Vector::<3>::new(3.0, 3.0, 3.0),
+        ]);
+        let (lower, upper) = line.length_bounds();
+        assert_relative_eq!(lower, 27.0f64.sqrt(), epsilon = 1e-14);
+        assert_relative_eq!(upper, 27.0f64.sqrt(), epsilon = 1e-14);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn is_symmetric_about()
+    {
+        // a rational quadratic quarter circle is symmetric about the plane bisecting it, which
+        // maps it onto itself reversed
+        let (arc, _) = circle(&Vector::<3>::zeros(), 1.0).split(0.25);
+        let bisector = Plane::new(&PlaneDescriptor {
+            origin: Vector::<3>::zeros(),
+            x: Vector::<3>::new(1.0, 1.0, 0.0).normalize(),
+            y: Vector::<3>::new(0.0, 0.0, 1.0),
+        });
+        assert!(arc.is_symmetric_about(&bisector, 1e-12));
+
+        // a planar curve is symmetric about its own plane without reversal
+        let xy = Plane::new(&PlaneDescriptor {
+            origin: Vector::<3>::zeros(),
+            x: Vector::<3>::new(1.0, 0.0, 0.0),
+            y: Vector::<3>::new(0.0, 1.0, 0.0),
+        });
+        assert!(arc.is_symmetric_about(&xy, 1e-12));
+
+        // an asymmetric cubic is not symmetric about its mid-plane
+        let cubic = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 2.0, 0.0),
+            Vector::<3>::new(3.0, 1.0, 0.0),
+            Vector::<3>::new(4.0, 0.0, 0.0),
+        ]);
+        let mid_plane = Plane::new(&PlaneDescriptor {
+            origin: Vector::<3>::new(2.0, 0.0, 0.0),
+            x: Vector::<3>::new(0.0, 1.0, 0.0),
+            y: Vector::<3>::new(0.0, 0.0, 1.0),
+        });
+        assert!(!cubic.is_symmetric_about(&mid_plane, 1e-6));
+        assert!(!arc.is_symmetric_about(&mid_plane, 1e-6));
+
+        // whereas moving its inner control points into symmetric positions makes it so
+        let mut symmetric = cubic;
+        symmetric.set_cpoint(2, &Vector::<3>::new(3.0, 2.0, 0.0));
+        assert!(symmetric.is_symmetric_about(&mid_plane, 1e-12));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn fit_plane()
+    {
+        // a full circle of radius 2, tilted out of the xy plane
+        let mut circle = circle(&Vector::<3>::zeros(), 2.0);
+        let m = Mat4::new_rotation(Vector::<3>::new(0.3, -0.5, 0.2))
+            .append_translation(&Vector::<3>::new(1.0, 2.0, -3.0));
+        circle.transform(&m);
+        let expected = m.fixed_view::<3, 3>(0, 0) * Vector::<3>::z();
+
+        let plane = circle.fit_plane(1e-9).unwrap();
+        let normal = plane.x().cross(&plane.y());
+        assert_relative_eq!(normal.dot(&expected).abs(), 1.0, epsilon = 1e-12);
+        for i in 0..=20
+        {
+            assert!(plane.sdf(&circle.eval(i as f64 / 20.0)).abs() < 1e-9);
+        }
+
+        // a helix rises out of every plane
+        let points: Vec<Vector<3>> = (0..=40)
+            .map(|i| {
+                let t = 4.0 * std::f64::consts::PI * i as f64 / 40.0;
+                Vector::<3>::new(2.0 * t.cos(), 2.0 * t.sin(), 0.5 * t)
+            })
+            .collect();
+        let helix = Bcurve::interpolate(&points, 3);
+        assert!(helix.fit_plane(1e-3).is_none());
+    }
+    //..............................................................................................
+
+    #[test]
+    fn second_moments()
+    {
+        let polygon = |corners: &[(f64, f64)]| {
+            let n = corners.len();
+            let mut cpoints: Vec<Vec2> = corners.iter().map(|(x, y)| Vec2::new(*x, *y)).collect();
+            cpoints.push(cpoints[0]);
+            let mut knots = vec![0.0];
+            knots.extend((0..=n).map(|i| i as f64 / n as f64));
+            knots.push(1.0);
+            Bcurve::new(&BcurveDescriptor { p: 1, knots, cpoints, cweights: vec![1.0; n + 1] })
+        };
+
+        // an off-center rectangle of breadth b and height h
+        let (b, h) = (3.0, 2.0);
+        let rect = polygon(&[(1.0, -1.0), (1.0 + b, -1.0), (1.0 + b, -1.0 + h), (1.0, -1.0 + h)]);
+        let (ixx, iyy, ixy) = rect.second_moments();
+        assert_relative_eq!(ixx, b * h * h * h / 12.0, epsilon = 1e-10);
+        assert_relative_eq!(iyy, h * b * b * b / 12.0, epsilon = 1e-10);
+        assert_relative_eq!(ixy, 0.0, epsilon = 1e-10);
+
+        // a clockwise right triangle with legs b and h has a non-zero product moment
+        let tri = polygon(&[(0.0, 0.0), (0.0, h), (b, 0.0)]);
+        let (ixx, iyy, ixy) = tri.second_moments();
+        assert_relative_eq!(ixx, b * h * h * h / 36.0, epsilon = 1e-10);
+        assert_relative_eq!(iyy, h * b * b * b / 36.0, epsilon = 1e-10);
+        assert_relative_eq!(ixy, -b * b * h * h / 72.0, epsilon = 1e-10);
+
+        // a circle of radius r has I = pi r^4 / 4 about every axis through its centre
+        let r = 1.5;
+        let circle = circle(&Vec2::new(4.0, 0.0), r);
+        let (ixx, iyy, ixy) = circle.second_moments();
+        let expected = std::f64::consts::PI * r.powi(4) / 4.0;
+        assert_relative_eq!(ixx, expected, max_relative = 1e-6);
+        assert_relative_eq!(iyy, expected, max_relative = 1e-6);
+        assert_relative_eq!(ixy, 0.0, epsilon = 1e-6);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn span_polynomial()
+    {
+        let cubic = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 2.0, 0.0),
+            Vector::<3>::new(2.0, -1.0, 1.0),
+            Vector::<3>::new(3.0, 0.0, 2.0),
+            Vector::<3>::new(4.0, 1.0, 0.0),
+            Vector::<3>::new(5.0, 3.0, -1.0),
+        ]);
+        let points: Vec<Vector<2>> = (0..9)
+            .map(|i| Vector::<2>::new(i as f64, (i as f64).sin() * 2.0))
+            .collect();
+        let quartic = Bcurve::interpolate(&points, 4);
+
+        fn check<const D: usize>(bcurve: &Bcurve<D>)
+        where
+            [(); D + 1]:,
+            [(); D * BCURVE_DER_MAX]:,
+            [(); D * 3]:,
+        {
+            let knots = bcurve.knots();
+            for span in bcurve.p()..knots.len() - bcurve.p() - 1
+            {
+                let (a, b) = (knots[span], knots[span + 1]);
+                let coeffs = bcurve.span_polynomial(span);
+                assert!(coeffs[bcurve.p() + 1..].iter().all(|c| c.norm() == 0.0));
+                for i in 0..=10
+                {
+                    let u = a + (b - a) * i as f64 / 10.0;
+                    // Horner's scheme
+                    let x = coeffs[..=bcurve.p()]
+                        .iter()
+                        .rev()
+                        .fold(Vector::<D>::zeros(), |acc, c| acc * (u - a) + c);
+                    assert_relative_eq!(x, bcurve.eval(u), epsilon = 1e-11);
+                }
+            }
+        }
+        check(&cubic);
+        check(&quartic);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn elevate_degree()
+    {
+        let test_data = TestData::new();
+        // the fixture of degree 1 has knots of full multiplicity, so is discontinuous
+        for p in 2..5
+        {
+            let bcurve = load_bcurve::<3>(p, &test_data);
+            let elevated = bcurve.elevate_degree(2);
+            assert_eq!(elevated.p(), p + 2);
+            for (u, m) in elevated.knot_multiplicites.iter()
+            {
+                assert_eq!(*m, bcurve.multiplicity(*u) + 2);
+            }
+            for u in test_data.u.values.iter()
+            {
+                assert_relative_eq!(elevated.eval(*u), bcurve.eval(*u), epsilon = 1e-11);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn blend()
+    {
+        let test_data = TestData::new();
+        let a = load_bcurve::<3>(3, &test_data);
+        let mut b = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 2.0, 0.0),
+            Vector::<3>::new(2.0, -1.0, 1.0),
+            Vector::<3>::new(3.0, 0.0, 2.0),
+            Vector::<3>::new(4.0, 1.0, 0.0),
+        ]);
+        b.set_param_range(-1.0, 3.0);
+
+        let blend0 = a.blend(&b, 0.0);
+        let blend1 = a.blend(&b, 1.0);
+        for i in 0..=100
+        {
+            let s = i as f64 / 100.0;
+            assert_relative_eq!(blend0.eval(s), a.eval(s), epsilon = 1e-11);
+            assert_relative_eq!(blend1.eval(s), b.eval(-1.0 + 4.0 * s), epsilon = 1e-11);
+        }
+
+        // between two non-rational curves the blend is the pointwise interpolation
+        let c = make_bcurve(vec![
+            Vector::<3>::new(0.0, 5.0, 0.0),
+            Vector::<3>::new(1.0, 5.0, 1.0),
+            Vector::<3>::new(3.0, 6.0, 1.0),
+            Vector::<3>::new(4.0, 5.0, 0.0),
+        ]);
+        let mid = b.blend(&c, 0.25);
+        for i in 0..=100
+        {
+            let s = i as f64 / 100.0;
+            let expected = b.eval(-1.0 + 4.0 * s) * 0.75 + c.eval(s) * 0.25;
+            assert_relative_eq!(mid.eval(s), expected, epsilon = 1e-11);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn closest_point_to_ray()
+    {
+        // a straight segment along x at height 2, crossing under a ray along -z at x = 1.5
+        let line = make_bcurve(vec![
+            Vector::<3>::new(0.0, 0.0, 2.0),
+            Vector::<3>::new(1.0, 0.0, 2.0),
+            Vector::<3>::new(2.0, 0.0, 2.0),
+            Vector::<3>::new(3.0, 0.0, 2.0),
+        ]);
+        let origin = Vector::<3>::new(1.5, 0.5, 10.0);
+        let dir = Vector::<3>::new(0.0, 0.0, -2.0);
+        let (u, t, dist) = line.closest_point_to_ray(&origin, &dir);
+        assert_relative_eq!(line.eval(u), Vector::<3>::new(1.5, 0.0, 2.0), epsilon = 1e-6);
+        assert_relative_eq!(t, 4.0, epsilon = 1e-6);
+        assert_relative_eq!(dist, 0.5, epsilon = 1e-9);
+
+        // a ray pointing away from the curve is closest to it at its origin
+        let (u, t, dist) = line.closest_point_to_ray(&origin, &-dir);
+        assert_eq!(t, 0.0);
+        assert_relative_eq!(dist, (line.eval(u) - origin).norm(), epsilon = 1e-12);
+        assert_relative_eq!(dist, (8.0f64 * 8.0 + 0.25).sqrt(), epsilon = 1e-6);
+
+        // the closest point found agrees with dense sampling
+        let test_data = TestData::new();
+        let curve = load_bcurve::<3>(3, &test_data);
+        let origin = Vector::<3>::new(0.1, -0.2, 3.0);
+        let dir = Vector::<3>::new(0.3, 0.2, -1.0);
+        let (_, _, dist) = curve.closest_point_to_ray(&origin, &dir);
+        let (u1, u2) = curve.param_range();
+        for i in 0..=1000
+        {
+            let x = curve.eval(u1 + (u2 - u1) * i as f64 / 1000.0);
+            let t = ((x - origin).dot(&dir) / dir.norm_squared()).max(0.0);
+            assert!(dist <= (x - origin - dir * t).norm() + 1e-9);
+        }
+    }
 }
 //}}}
@@ -0,0 +1,246 @@
+//! This module contains the intersection of pairs of B-spline curves.
+//!
+//! Candidate intersections are found by recursively subdividing both curves, pairs of sub-curves
+//! whose control boxes touch are refined onto the intersection by Newton iteration on the distance
+//! between the curves, and the end points of each curve are projected onto the other. Between
+//! consecutive candidates the curves are sampled to decide whether they run together, such
+//! stretches are reported as overlaps rather than as points.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use super::bcurve::{Bcurve, BCURVE_DER_MAX};
+use crate::common::ResConstants;
+use crate::geometry::common::Curve;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Size of the sub-curves, relative to the larger curve, at which subdivision stops
+const CCI_LEAF_RATIO: f64 = 1.0 / 64.0;
+/// Maximum subdivision depth when searching for intersections
+const CCI_DEPTH_MAX: usize = 40;
+/// Parameter distance, relative to the parameter range, below which two candidates are merged
+const CCI_PARAM_TOL_RATIO: f64 = 1e-7;
+/// Number of samples between consecutive candidates used to detect an overlap
+const CCI_OVERLAP_SAMPLES: usize = 8;
+//}}}
+//{{{ enum: CurveIntersection
+/// The intersection of two curves, as parameters on the first and second curve.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CurveIntersection<const D: usize>
+{
+    /// The curves meet at isolated points, each given as the pair of parameters `(u, v)`
+    Points(Vec<(f64, f64)>),
+    /// The curves coincide along sub-curves, each given as the parameter range `(u1, u2)` on the
+    /// first curve with `u1 < u2` and the parameters `(v1, v2)` of the same two points on the
+    /// second curve, so that `v1 > v2` where the curves run in opposite directions. Any isolated
+    /// points of intersection are included as ranges of zero length.
+    Overlaps(Vec<((f64, f64), (f64, f64))>),
+}
+//}}}
+//{{{ impl: Bcurve<D>
+impl<const D: usize> Bcurve<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    //{{{ fun: intersect
+    /// Computes the intersection of this curve with `other`.
+    ///
+    /// Points are taken to be on both curves when they are closer than the linear resolution,
+    /// relative to the size of the larger curve. Two curves which touch tangentially are found to
+    /// intersect only if the touching point is within this tolerance.
+    ///
+    /// # Arguments
+    /// * `other` - The curve to intersect with.
+    ///
+    /// # Returns
+    /// [CurveIntersection::Overlaps] if the curves coincide anywhere, otherwise the crossing points
+    /// in [CurveIntersection::Points] ordered by the parameter on this curve, which are empty if
+    /// the curves do not meet.
+    pub fn intersect(&self, other: &Bcurve<D>) -> CurveIntersection<D>
+    {
+        //{{{ locals
+        let range1 = self.param_range();
+        let range2 = other.param_range();
+        let size = self.control_box().diameter().max(other.control_box().diameter());
+        let tol = f64::RES_LINEAR * size.max(1.0);
+        let leaf_diam = CCI_LEAF_RATIO * size;
+        let mut candidates: Vec<(f64, f64)> = Vec::new();
+        //}}}
+        //{{{ com: the end points of each curve which lie on the other
+        for u in [range1.0, range1.1]
+        {
+            let (v, dist) = other.closest_point(&self.eval(u));
+            if dist <= tol
+            {
+                candidates.push((u, v));
+            }
+        }
+        for v in [range2.0, range2.1]
+        {
+            let (u, dist) = self.closest_point(&other.eval(v));
+            if dist <= tol
+            {
+                candidates.push((u, v));
+            }
+        }
+        //}}}
+        //{{{ com: subdivide pairs of sub-curves whose boxes touch, refining the leaves
+        self.refine_pairs(other, leaf_diam, CCI_DEPTH_MAX, tol, |u, v, dist| {
+            if dist <= tol
+            {
+                candidates.push((u, v));
+            }
+            tol
+        });
+        //}}}
+        //{{{ com: merge candidates which are the same point
+        let utol = CCI_PARAM_TOL_RATIO * (range1.1 - range1.0);
+        let vtol = CCI_PARAM_TOL_RATIO * (range2.1 - range2.0);
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut points: Vec<(f64, f64)> = Vec::with_capacity(candidates.len());
+        for (u, v) in candidates
+        {
+            let is_new = points.iter().all(|(u0, v0)| (u - u0).abs() > utol || (v - v0).abs() > vtol);
+            if is_new
+            {
+                points.push((u, v));
+            }
+        }
+        //}}}
+        //{{{ com: join consecutive points between which the curves coincide
+        let coincide = |a: &(f64, f64), b: &(f64, f64)| -> bool {
+            let (vmin, vmax) = (a.1.min(b.1), a.1.max(b.1));
+            (1..=CCI_OVERLAP_SAMPLES).all(|k| {
+                let u = a.0 + (b.0 - a.0) * k as f64 / (CCI_OVERLAP_SAMPLES + 1) as f64;
+                let (v, dist) = other.closest_point(&self.eval(u));
+                dist <= tol && v >= vmin - vtol && v <= vmax + vtol
+            })
+        };
+
+        let mut ranges: Vec<((f64, f64), (f64, f64))> = Vec::new();
+        let mut has_overlap = false;
+        for (i, point) in points.iter().enumerate()
+        {
+            let joined = i > 0 && points[i - 1].0 < point.0 - utol && coincide(&points[i - 1], point);
+            match ranges.last_mut()
+            {
+                Some(last) if joined =>
+                {
+                    last.0 .1 = point.0;
+                    last.1 .1 = point.1;
+                    has_overlap = true;
+                }
+                _ => ranges.push(((point.0, point.0), (point.1, point.1))),
+            }
+        }
+        //}}}
+
+        if has_overlap
+        {
+            CurveIntersection::Overlaps(ranges)
+        }
+        else
+        {
+            CurveIntersection::Points(points)
+        }
+    }
+    //}}}
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::Vector;
+    use crate::geometry::BcurveDescriptor;
+    use approx::assert_relative_eq;
+
+    fn make_line(a: Vector<2>, b: Vector<2>) -> Bcurve<2>
+    {
+        Bcurve::new(&BcurveDescriptor {
+            p: 1,
+            knots: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints: vec![a, b],
+            cweights: vec![1.0; 2],
+        })
+    }
+
+    #[test]
+    fn intersect_crossing()
+    {
+        // the parabola y = x^2 - 1 over [-2, 2] crosses the x axis at x = -1 and x = 1
+        let parabola = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vector::<2>::new(-2.0, 3.0), Vector::<2>::new(0.0, -5.0), Vector::<2>::new(2.0, 3.0)],
+            cweights: vec![1.0; 3],
+        });
+        let axis = make_line(Vector::<2>::new(-3.0, 0.0), Vector::<2>::new(3.0, 0.0));
+
+        match parabola.intersect(&axis)
+        {
+            CurveIntersection::Points(points) =>
+            {
+                assert_eq!(points.len(), 2);
+                for ((u, v), x) in points.iter().zip([-1.0, 1.0])
+                {
+                    assert_relative_eq!(*u, 0.25 * (x + 2.0), epsilon = 1e-9);
+                    assert_relative_eq!(*v, (x + 3.0) / 6.0, epsilon = 1e-9);
+                }
+            }
+            other => panic!("expected points, found {:?}", other),
+        }
+
+        // curves which do not meet
+        let above = make_line(Vector::<2>::new(-3.0, 4.0), Vector::<2>::new(3.0, 4.0));
+        assert_eq!(parabola.intersect(&above), CurveIntersection::Points(vec![]));
+    }
+
+    #[test]
+    fn intersect_overlap()
+    {
+        // collinear lines over [0, 2] and [1, 3] share the stretch [1, 2]
+        let line1 = make_line(Vector::<2>::new(0.0, 1.0), Vector::<2>::new(2.0, 1.0));
+        let line2 = make_line(Vector::<2>::new(1.0, 1.0), Vector::<2>::new(3.0, 1.0));
+        match line1.intersect(&line2)
+        {
+            CurveIntersection::Overlaps(ranges) =>
+            {
+                assert_eq!(ranges.len(), 1);
+                let ((u1, u2), (v1, v2)) = ranges[0];
+                assert_relative_eq!(u1, 0.5, epsilon = 1e-9);
+                assert_relative_eq!(u2, 1.0, epsilon = 1e-9);
+                assert_relative_eq!(v1, 0.0, epsilon = 1e-9);
+                assert_relative_eq!(v2, 0.5, epsilon = 1e-9);
+            }
+            other => panic!("expected an overlap, found {:?}", other),
+        }
+
+        // running in the opposite direction the range on the second curve is reversed
+        let line3 = make_line(Vector::<2>::new(3.0, 1.0), Vector::<2>::new(1.0, 1.0));
+        match line1.intersect(&line3)
+        {
+            CurveIntersection::Overlaps(ranges) =>
+            {
+                assert_eq!(ranges.len(), 1);
+                let ((u1, u2), (v1, v2)) = ranges[0];
+                assert_relative_eq!(u1, 0.5, epsilon = 1e-9);
+                assert_relative_eq!(u2, 1.0, epsilon = 1e-9);
+                assert_relative_eq!(v1, 1.0, epsilon = 1e-9);
+                assert_relative_eq!(v2, 0.5, epsilon = 1e-9);
+            }
+            other => panic!("expected an overlap, found {:?}", other),
+        }
+    }
+}
+//}}}
@@ -4,7 +4,8 @@
 
 //{{{ crate imports 
 use crate::common::{vec_unitary, Descriptor, Vector};
-use crate::geometry::common::{Surface, Curve};
+use crate::geometry::common::{Surface, Curve, MIN_PARAM, MAX_PARAM};
+use crate::geometry::curve::segment::{Segment, SegmentDescriptor};
 //}}}
 //{{{ std imports 
 //}}}
@@ -49,6 +50,17 @@ impl<const D: usize> Line<D>
             dir: ld.dir,
         }
     }   
+
+    /// Returns the segment of the line between the parameters `u1` and `u2`.
+    ///
+    /// A line is unbounded and so has no bounding box, a segment over a parameter range is what
+    /// can be boxed.
+    pub fn segment(&self, u1: f64, u2: f64) -> Segment<D> {
+        Segment::new(&SegmentDescriptor {
+            start: self.eval(u1),
+            end: self.eval(u2),
+        })
+    }
 }
 //}}}
 //{{{ impl Curve for Line<D>
@@ -104,11 +116,12 @@ impl<const D: usize> Curve for Line<D>
     }
     //}}}
     //{{{ fun: is_member
+    /// A line is unbounded, so every finite parameter is a member.
     fn is_member(
         &self,
         u: f64,
     ) -> bool {
-        true
+        u.is_finite()
     }
     //}}}
     //{{{ fun: dim
@@ -121,6 +134,13 @@ impl<const D: usize> Curve for Line<D>
         1
     }
     //}}}
+    //{{{ fun: param_range
+    /// A line is unbounded, its range is that of all finite parameters rather than 
+    /// $(-\infty, \infty)$ so that generic samplers stay finite.
+    fn param_range(&self) -> (f64, f64) {
+        (MIN_PARAM, MAX_PARAM)
+    }
+    //}}}
 }
 //}}}
 
@@ -130,6 +150,37 @@ impl<const D: usize> Curve for Line<D>
 mod tests
 {
     use super::*;
+    use crate::common::ModellerError;
+
+    #[test]
+    fn sample_test()
+    {
+        let line = Line::new(&LineDescriptor {
+            origin: Vector::<2>::new(1.0, 2.0),
+            dir: Vector::<2>::new(0.6, 0.8),
+        });
+
+        // the unbounded range of a line is sampled without overflow
+        let samples = line.sample(4);
+        assert_eq!(samples.len(), 5);
+        assert_eq!(samples[0].0, line.param_range().0);
+        assert_eq!(samples[4].0, line.param_range().1);
+        assert_eq!(samples[2], (0.0, Vector::<2>::new(1.0, 2.0)));
+        assert!(samples.iter().all(|(u, point)| u.is_finite() && *point == line.eval(*u)));
+
+        // no divisions is taken as a single one, between the ends of the range
+        for n in [0, 1]
+        {
+            let samples = line.sample(n);
+            assert_eq!(samples.len(), 2);
+            assert_eq!(samples[0].0, line.param_range().0);
+            assert_eq!(samples[1].0, line.param_range().1);
+        }
+
+        // the parameters alone are those of the samples
+        let params = line.sample_params(4);
+        assert_eq!(params, line.sample(4).iter().map(|(u, _)| *u).collect::<Vec<f64>>());
+    }
 
     #[test]
     fn line_new_test() {
@@ -139,5 +190,21 @@ mod tests
         };
         let line = Line::new(&ld);
     }
+
+    #[test]
+    fn try_eval_test() {
+        let line = Line::new(&LineDescriptor {
+            origin: Vector::<3>::new(1.0, 2.0, 3.0),
+            dir: Vector::<3>::new(0.0, 0.6, 0.8),
+        });
+        assert_eq!(line.try_eval(-2.5).unwrap(), line.eval(-2.5));
+        assert_eq!(line.try_eval_diff(4.0, 1).unwrap(), line.eval_diff(4.0, 1));
+
+        for u in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY]
+        {
+            assert!(matches!(line.try_eval(u), Err(ModellerError::OutOfDomain(_))));
+            assert!(matches!(line.try_eval_diff(u, 1), Err(ModellerError::OutOfDomain(_))));
+        }
+    }
 }
 //}}}
\ No newline at end of file
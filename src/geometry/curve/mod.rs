@@ -1,3 +1,6 @@
-
 pub mod line;
-pub mod bcurve;
\ No newline at end of file
+pub mod segment;
+pub mod bcurve;
+pub mod intersect;
+pub mod polyline;
+pub mod projection;
\ No newline at end of file
@@ -0,0 +1,285 @@
+//! This module contains the definition of the Polyline curve.
+//!
+//! A polyline is a chain of straight segments joining a sequence of points. It is parameterised by
+//! arc length, so the parameter at each point is the cumulative chord length up to that point.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::common::{Descriptor, DescriptorError, ResConstants, Vector};
+use crate::geometry::common::Curve;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ struct: PolylineDescriptor
+pub struct PolylineDescriptor<const D: usize>
+{
+    pub points: Vec<Vector<D>>,
+}
+//}}}
+//{{{ impl: Descriptor for PolylineDescriptor<D>
+impl<const D: usize> Descriptor for PolylineDescriptor<D>
+{
+    fn is_valid(&self) -> Result<(), DescriptorError>
+    {
+        if self.points.len() < 2
+        {
+            return Err(DescriptorError::InvalidInput(
+                "polyline needs at least two points".to_string(),
+            ));
+        }
+        if self.points.windows(2).any(|w| (w[1] - w[0]).norm() < f64::RES_LINEAR)
+        {
+            return Err(DescriptorError::InvalidInput(
+                "polyline has a zero length segment".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+//}}}
+//{{{ struct: Polyline
+/// A chain of straight segments in dimension `D`, parameterised by arc length.
+pub struct Polyline<const D: usize>
+{
+    /// The vertices of the polyline
+    points: Vec<Vector<D>>,
+    /// The cumulative chord length at each vertex, starting from 0
+    params: Vec<f64>,
+}
+//}}}
+//{{{ impl: Polyline<D>
+impl<const D: usize> Polyline<D>
+{
+    /// Standard constructor of the Polyline.
+    pub fn new(pd: &PolylineDescriptor<D>) -> Self
+    {
+        debug_assert!(pd.is_valid().is_ok());
+
+        let mut params = Vec::with_capacity(pd.points.len());
+        params.push(0.0);
+        for i in 1..pd.points.len()
+        {
+            let len = (pd.points[i] - pd.points[i - 1]).norm();
+            params.push(params[i - 1] + len);
+        }
+
+        Self {
+            points: pd.points.clone(),
+            params,
+        }
+    }
+    //..............................................................................................
+
+    /// Accessor to the vertices of the polyline
+    pub fn points(&self) -> &[Vector<D>]
+    {
+        &self.points
+    }
+    //..............................................................................................
+
+    /// Accessor to the parameter values at the vertices, these are the cumulative chord lengths.
+    pub fn params(&self) -> &[f64]
+    {
+        &self.params
+    }
+    //..............................................................................................
+
+    /// Returns the number of segments in the polyline
+    pub fn num_segments(&self) -> usize
+    {
+        self.points.len() - 1
+    }
+    //..............................................................................................
+
+    /// Returns the total length of the polyline
+    pub fn length(&self) -> f64
+    {
+        *self.params.last().unwrap()
+    }
+    //..............................................................................................
+
+    /// Finds the index of the segment containing the parameter `u`.
+    ///
+    /// At an interior vertex this is the segment which starts at the vertex, so that derivatives
+    /// at vertices are one-sided from the right. At the end of the polyline it is the last
+    /// segment.
+    fn segment(&self, u: f64) -> usize
+    {
+        let idx = self.params.partition_point(|&x| x <= u);
+        idx.clamp(1, self.num_segments()) - 1
+    }
+}
+//}}}
+//{{{ impl: Curve for Polyline<D>
+impl<const D: usize> Curve for Polyline<D>
+{
+    //{{{ type: Vector
+    type Vector = Vector<D>;
+    //}}}
+    //{{{ fun: eval
+    fn eval(
+        &self,
+        u: f64,
+    ) -> Self::Vector
+    {
+        debug_assert!(self.is_member(u));
+
+        if u >= self.length()
+        {
+            return *self.points.last().unwrap();
+        }
+
+        let i = self.segment(u);
+        let t = (u - self.params[i]) / (self.params[i + 1] - self.params[i]);
+        self.points[i] * (1.0 - t) + self.points[i + 1] * t
+    }
+    //}}}
+    //{{{ fun: eval_diff
+    /// Evaluates the derivatives of the polyline.
+    ///
+    /// The first derivative is the unit direction of the segment containing `u`, at interior
+    /// vertices the direction of the following segment is used. Higher derivatives are zero.
+    fn eval_diff(
+        &self,
+        u: f64,
+        m: usize,
+    ) -> Self::Vector
+    {
+        debug_assert!(self.is_member(u));
+
+        match m
+        {
+            0 => self.eval(u),
+            1 => {
+                let i = self.segment(u);
+                (self.points[i + 1] - self.points[i]) / (self.params[i + 1] - self.params[i])
+            }
+            _ => Vector::<D>::zeros(),
+        }
+    }
+    //}}}
+    //{{{ fun: eval_diff_all
+    fn eval_diff_all(
+        &self,
+        u: f64,
+        m: usize,
+        ders: &mut [Self::Vector],
+    )
+    {
+        debug_assert!(ders.len() >= m + 1, "Output array is not large enough");
+
+        for i in 0..=m
+        {
+            ders[i] = self.eval_diff(u, i);
+        }
+    }
+    //}}}
+    //{{{ fun: eval_arclen
+    fn eval_arclen(
+        &self,
+        u1: f64,
+        u2: f64,
+    ) -> f64
+    {
+        debug_assert!(u2 >= u1);
+        let (umin, umax) = self.param_range();
+        u2.clamp(umin, umax) - u1.clamp(umin, umax)
+    }
+    //}}}
+    //{{{ fun: is_member
+    fn is_member(
+        &self,
+        u: f64,
+    ) -> bool
+    {
+        u >= -f64::RES_LINEAR && u <= self.length() + f64::RES_LINEAR
+    }
+    //}}}
+    //{{{ fun: dim
+    fn dim(&self) -> usize
+    {
+        D
+    }
+    //}}}
+    //{{{ fun: max_der
+    fn max_der(&self, u: f64) -> usize
+    {
+        1
+    }
+    //}}}
+    //{{{ fun: param_range
+    fn param_range(&self) -> (f64, f64)
+    {
+        (0.0, self.length())
+    }
+    //}}}
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn make_polyline() -> Polyline<3>
+    {
+        Polyline::new(&PolylineDescriptor {
+            points: vec![
+                Vector::<3>::new(0.0, 0.0, 0.0),
+                Vector::<3>::new(3.0, 4.0, 0.0),
+                Vector::<3>::new(3.0, 4.0, 2.0),
+                Vector::<3>::new(1.0, 1.0, 1.0),
+            ],
+        })
+    }
+
+    #[test]
+    fn eval_vertices_test()
+    {
+        let polyline = make_polyline();
+        for (u, point) in polyline.params().iter().zip(polyline.points().iter())
+        {
+            assert_eq!(polyline.eval(*u), *point);
+        }
+        assert_relative_eq!(polyline.eval(2.5), Vector::<3>::new(1.5, 2.0, 0.0), epsilon = 1e-14);
+    }
+
+    #[test]
+    fn eval_arclen_test()
+    {
+        let polyline = make_polyline();
+        let chord_sum: f64 = polyline.points().windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+        let (u0, u1) = polyline.param_range();
+        assert_relative_eq!(polyline.eval_arclen(u0, u1), chord_sum, epsilon = 1e-14);
+        assert_relative_eq!(polyline.length(), 5.0 + 2.0 + 14.0f64.sqrt(), epsilon = 1e-14);
+        assert_relative_eq!(polyline.eval_arclen(1.0, 6.0), 5.0, epsilon = 1e-14);
+    }
+
+    #[test]
+    fn eval_diff_test()
+    {
+        let polyline = make_polyline();
+
+        // inside a segment the tangent is the unit segment direction
+        assert_relative_eq!(polyline.eval_diff(1.0, 1), Vector::<3>::new(0.6, 0.8, 0.0), epsilon = 1e-14);
+        // at an interior vertex the tangent is taken from the following segment
+        assert_relative_eq!(polyline.eval_diff(5.0, 1), Vector::<3>::new(0.0, 0.0, 1.0), epsilon = 1e-14);
+        // at the end the tangent is taken from the last segment
+        let last = (Vector::<3>::new(-2.0, -3.0, -1.0)) / 14.0f64.sqrt();
+        assert_relative_eq!(polyline.eval_diff(polyline.length(), 1), last, epsilon = 1e-14);
+        assert_eq!(polyline.eval_diff(1.0, 2), Vector::<3>::zeros());
+
+        let mut ders = [Vector::<3>::zeros(); 3];
+        polyline.eval_diff_all(0.5, 2, &mut ders);
+        assert_relative_eq!(ders[0], Vector::<3>::new(0.3, 0.4, 0.0), epsilon = 1e-14);
+        assert_relative_eq!(polyline.eval_tangent(0.5, true), ders[1], epsilon = 1e-14);
+    }
+}
+//}}}
@@ -0,0 +1,219 @@
+//! This module contains the projection of points onto B-spline curves.
+//!
+//! A point is projected by finding the parameter of the closest point of the curve. The full
+//! projection samples every knot span and refines each local minimum of the distance. For many
+//! points projected onto the same curve, such as when snapping a moving cursor, a table of samples
+//! can be built once, each projection then starts from the nearest sample and needs only a couple
+//! of Newton steps.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use super::bcurve::{Bcurve, BCURVE_DER_MAX};
+use crate::common::Vector;
+use crate::geometry::common::Curve;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Number of distance samples per knot span used to bracket the minima in `closest_point`
+const PROJ_SAMPLES_PER_SPAN: usize = 8;
+/// Maximum number of Newton steps polishing the minimum found in `closest_point`
+const PROJ_NEWTON_ITER_MAX: usize = 10;
+/// Number of Newton steps taken from the nearest sample in `ProjectionLut::project`
+const LUT_NEWTON_STEPS: usize = 2;
+//}}}
+//{{{ struct: ProjectionLut
+/// A table of points sampled uniformly in parameter along a curve, for fast repeated projection
+/// onto it.
+pub struct ProjectionLut<'a, const D: usize>
+where
+    [(); D + 1]:,
+{
+    curve: &'a Bcurve<D>,
+    params: Vec<f64>,
+    points: Vec<Vector<D>>,
+}
+//}}}
+//{{{ impl: Bcurve<D>
+impl<const D: usize> Bcurve<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    //{{{ fun: closest_point
+    /// Finds the point of the curve closest to `p`.
+    ///
+    /// The distance to `p` is sampled over each knot span, and each local minimum of the samples is
+    /// refined by a bounded scalar minimisation between its neighbours. The closest of these is
+    /// then polished by Newton's method.
+    ///
+    /// # Returns
+    /// A tuple `(u, dist)` of the parameter of the closest point and its distance from `p`.
+    pub fn closest_point(&self, p: &Vector<D>) -> (f64, f64)
+    {
+        let dist = |u: f64| (self.eval(u) - p).norm();
+
+        let best = self.min_over_spans(dist, PROJ_SAMPLES_PER_SPAN);
+        newton_project(self, p, best, PROJ_NEWTON_ITER_MAX)
+    }
+    //}}}
+    //{{{ fun: build_projection_lut
+    /// Samples the curve at `samples` parameters, uniformly spaced over its domain, into a table
+    /// for repeated projection.
+    ///
+    /// The table must be dense enough that the nearest sample lies in the basin of the closest
+    /// point, a few samples per knot span are usually sufficient.
+    pub fn build_projection_lut(&self, samples: usize) -> ProjectionLut<'_, D>
+    {
+        debug_assert!(samples >= 2);
+
+        let (u1, u2) = self.param_range();
+        let params: Vec<f64> = (0..samples)
+            .map(|i| if i == samples - 1 { u2 } else { u1 + (u2 - u1) * i as f64 / (samples - 1) as f64 })
+            .collect();
+        let points = params.iter().map(|u| self.eval(*u)).collect();
+        ProjectionLut { curve: self, params, points }
+    }
+    //}}}
+}
+//}}}
+//{{{ impl: ProjectionLut
+impl<const D: usize> ProjectionLut<'_, D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    //{{{ fun: project
+    /// Projects `p` onto the curve, starting from the nearest sample of the table and taking a
+    /// couple of Newton steps from there.
+    ///
+    /// # Returns
+    /// A tuple `(u, dist)` of the parameter of the projection and its distance from `p`.
+    pub fn project(&self, p: &Vector<D>) -> (f64, f64)
+    {
+        let nearest = (0..self.points.len())
+            .min_by(|a, b| {
+                let da = (self.points[*a] - p).norm_squared();
+                let db = (self.points[*b] - p).norm_squared();
+                da.partial_cmp(&db).unwrap()
+            })
+            .unwrap();
+
+        let start = (self.params[nearest], (self.points[nearest] - p).norm());
+        newton_project(self.curve, p, start, LUT_NEWTON_STEPS)
+    }
+    //}}}
+    //{{{ fun: len
+    /// Returns the number of samples in the table
+    pub fn len(&self) -> usize { self.params.len() }
+    //}}}
+    //{{{ fun: is_empty
+    pub fn is_empty(&self) -> bool { self.params.is_empty() }
+    //}}}
+}
+//}}}
+//{{{ fun: newton_project
+/// Refines the projection `start = (u, dist)` of `p` onto the curve by at most `steps` Newton
+/// steps on $\mathbf{C}'(u) \cdot (\mathbf{C}(u) - \mathbf{p}) = 0$. A step which leaves the domain
+/// is clamped to it, and the iteration stops at the first step which does not bring the curve
+/// closer to `p`.
+fn newton_project<const D: usize>(
+    curve: &Bcurve<D>,
+    p: &Vector<D>,
+    start: (f64, f64),
+    steps: usize,
+) -> (f64, f64)
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    let (u1, u2) = curve.param_range();
+    let (mut u, mut dist) = start;
+    for _ in 0..steps
+    {
+        let mut ders = [Vector::<D>::zeros(); 3];
+        curve.eval_diff_all(u, 2, &mut ders);
+        let r = ders[0] - p;
+        let df = ders[2].dot(&r) + ders[1].norm_squared();
+        if df <= 0.0
+        {
+            break;
+        }
+        let u_next = (u - ders[1].dot(&r) / df).clamp(u1, u2);
+        let dist_next = (curve.eval(u_next) - p).norm();
+        if dist_next >= dist
+        {
+            break;
+        }
+        (u, dist) = (u_next, dist_next);
+    }
+    (u, dist)
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test_utils::test_bcurve::{load_bcurve, TestData};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn closest_point_test()
+    {
+        let test_data = TestData::new();
+        let curve = load_bcurve::<3>(3, &test_data);
+        let (u1, u2) = curve.param_range();
+
+        // a point on the curve projects onto itself
+        let u = u1 + 0.37 * (u2 - u1);
+        let (v, dist) = curve.closest_point(&curve.eval(u));
+        assert_relative_eq!(v, u, epsilon = 1e-6);
+        assert!(dist < 1e-9);
+
+        // the closest point found agrees with dense sampling
+        let p = Vector::<3>::new(0.3, -0.4, 0.5);
+        let (_, dist) = curve.closest_point(&p);
+        for i in 0..=1000
+        {
+            let x = curve.eval(u1 + (u2 - u1) * i as f64 / 1000.0);
+            assert!(dist <= (x - p).norm() + 1e-9);
+        }
+    }
+
+    #[test]
+    fn projection_lut_test()
+    {
+        let test_data = TestData::new();
+        let curve = load_bcurve::<3>(3, &test_data);
+        let (u1, u2) = curve.param_range();
+        let lut = curve.build_projection_lut(200);
+        assert_eq!(lut.len(), 200);
+
+        // points displaced a little from the curve along its normal plane
+        let offset = 1e-2 * curve.control_box().diameter();
+        for i in 1..50
+        {
+            let u = u1 + (u2 - u1) * i as f64 / 50.0;
+            let mut ders = [Vector::<3>::zeros(); 2];
+            curve.eval_diff_all(u, 1, &mut ders);
+            let normal = ders[1].cross(&Vector::<3>::new(0.3, -0.2, 1.0)).normalize();
+            let p = ders[0] + normal * offset;
+
+            let (u_lut, dist_lut) = lut.project(&p);
+            let (u_full, dist_full) = curve.closest_point(&p);
+            assert_relative_eq!(dist_lut, dist_full, epsilon = 1e-9);
+            assert_relative_eq!(u_lut, u_full, epsilon = 1e-5);
+        }
+    }
+}
+//}}}
@@ -0,0 +1,221 @@
+//! This module contains the definition of the Segment curve.
+//!
+//! A segment is the straight piece of a line between two points, parameterised on $[0, 1]$ from
+//! its start to its end. Unlike a [Line](super::line::Line) it is bounded, so it has a bounding
+//! box.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::ABox;
+use crate::common::{Descriptor, DescriptorError, Mat4, ResConstants, Vector};
+use crate::geometry::common::Curve;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ struct: SegmentDescriptor
+pub struct SegmentDescriptor<const D: usize>
+{
+    pub start: Vector<D>,
+    pub end: Vector<D>,
+}
+//}}}
+//{{{ impl: Descriptor for SegmentDescriptor<D>
+impl<const D: usize> Descriptor for SegmentDescriptor<D>
+{
+    fn is_valid(&self) -> Result<(), DescriptorError>
+    {
+        if (self.end - self.start).norm() < f64::RES_LINEAR
+        {
+            return Err(DescriptorError::InvalidInput(
+                "segment has zero length".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+//}}}
+//{{{ struct: Segment
+/// A straight segment between two points in dimension `D`, parameterised on $[0, 1]$.
+pub struct Segment<const D: usize>
+{
+    /// The point at parameter 0
+    start: Vector<D>,
+    /// The point at parameter 1
+    end: Vector<D>,
+    /// The cached bounding box
+    pub(crate) abox: Option<ABox<D>>,
+}
+//}}}
+//{{{ impl: Segment<D>
+impl<const D: usize> Segment<D>
+{
+    /// Standard constructor of the Segment.
+    pub fn new(sd: &SegmentDescriptor<D>) -> Self
+    {
+        debug_assert!(sd.is_valid().is_ok());
+        Self {
+            start: sd.start,
+            end: sd.end,
+            abox: None,
+        }
+    }
+    //..............................................................................................
+
+    /// Accessor to the start point of the segment
+    pub fn start(&self) -> Vector<D>
+    {
+        self.start
+    }
+    //..............................................................................................
+
+    /// Accessor to the end point of the segment
+    pub fn end(&self) -> Vector<D>
+    {
+        self.end
+    }
+    //..............................................................................................
+
+    /// Returns the length of the segment
+    pub fn length(&self) -> f64
+    {
+        (self.end - self.start).norm()
+    }
+}
+//}}}
+//{{{ impl: Segment<3>
+impl Segment<3>
+{
+    /// Transforms the segment by the affine transformation `m`, given in homogeneous coordinates.
+    ///
+    /// The cached bounding box is cleared, since the box of the transformed end points is both
+    /// tight and cheap to recompute.
+    pub fn transform(&mut self, m: &Mat4)
+    {
+        self.start = m.transform_point(&self.start.into()).coords;
+        self.end = m.transform_point(&self.end.into()).coords;
+        self.abox = None;
+    }
+}
+//}}}
+//{{{ impl: Curve for Segment<D>
+impl<const D: usize> Curve for Segment<D>
+{
+    //{{{ type: Vector
+    type Vector = Vector<D>;
+    //}}}
+    //{{{ fun: eval
+    fn eval(
+        &self,
+        u: f64,
+    ) -> Self::Vector
+    {
+        self.start * (1.0 - u) + self.end * u
+    }
+    //}}}
+    //{{{ fun: eval_diff
+    fn eval_diff(
+        &self,
+        u: f64,
+        m: usize,
+    ) -> Self::Vector
+    {
+        match m
+        {
+            0 => self.eval(u),
+            1 => self.end - self.start,
+            _ => Vector::<D>::zeros(),
+        }
+    }
+    //}}}
+    //{{{ fun: eval_diff_all
+    fn eval_diff_all(
+        &self,
+        u: f64,
+        m: usize,
+        ders: &mut [Self::Vector],
+    )
+    {
+        debug_assert!(ders.len() >= m + 1, "Output array is not large enough");
+
+        for i in 0..=m
+        {
+            ders[i] = self.eval_diff(u, i);
+        }
+    }
+    //}}}
+    //{{{ fun: eval_arclen
+    fn eval_arclen(
+        &self,
+        u1: f64,
+        u2: f64,
+    ) -> f64
+    {
+        debug_assert!(u2 >= u1);
+        (u2.clamp(0.0, 1.0) - u1.clamp(0.0, 1.0)) * self.length()
+    }
+    //}}}
+    //{{{ fun: is_member
+    fn is_member(
+        &self,
+        u: f64,
+    ) -> bool
+    {
+        u >= -f64::RES_LINEAR && u <= 1.0 + f64::RES_LINEAR
+    }
+    //}}}
+    //{{{ fun: dim
+    fn dim(&self) -> usize
+    {
+        D
+    }
+    //}}}
+    //{{{ fun: max_der
+    fn max_der(&self, _u: f64) -> usize
+    {
+        1
+    }
+    //}}}
+    //{{{ fun: param_range
+    fn param_range(&self) -> (f64, f64)
+    {
+        (0.0, 1.0)
+    }
+    //}}}
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn segment_test()
+    {
+        let sd = SegmentDescriptor {
+            start: Vector::<2>::new(1.0, 2.0),
+            end: Vector::<2>::new(4.0, 6.0),
+        };
+        assert!(sd.is_valid().is_ok());
+        let segment = Segment::new(&sd);
+        assert_eq!(segment.length(), 5.0);
+        assert_eq!(segment.eval(0.0), segment.start());
+        assert_eq!(segment.eval(1.0), segment.end());
+        assert_eq!(segment.eval(0.5), Vector::<2>::new(2.5, 4.0));
+        assert_eq!(segment.eval_diff(0.3, 1), Vector::<2>::new(3.0, 4.0));
+        assert_eq!(segment.eval_arclen(0.25, 0.75), 2.5);
+
+        let degenerate = SegmentDescriptor {
+            start: Vector::<2>::new(1.0, 2.0),
+            end: Vector::<2>::new(1.0, 2.0),
+        };
+        assert!(degenerate.is_valid().is_err());
+    }
+}
+//}}}
@@ -0,0 +1,264 @@
+//! This module contains writers which export geometry to exchange formats read by other CAD
+//! systems.
+//!
+//! Curves are written to IGES as rational B-spline curve entities (type 126). An IGES file is a
+//! sequence of 80 column records split into the Start, Global, Directory Entry, Parameter Data and
+//! Terminate sections, each record ends with the letter of its section and its sequence number
+//! within the section. Every entity has a two record directory entry pointing at its parameter
+//! data, whose fields are written in columns 1 to 64 of as many records as they need.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::common::inv_homog;
+use crate::geometry::{Bcurve, Curve};
+//}}}
+//{{{ std imports
+use std::io::{self, Write};
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Width of an IGES record
+const IGES_LINE_WIDTH: usize = 72;
+/// Width of the data field of a parameter data record
+const IGES_PARAM_WIDTH: usize = 64;
+/// Entity type of a rational B-spline curve
+const IGES_BSPLINE_CURVE: usize = 126;
+//}}}
+//{{{ fun: write_iges_curves
+/// Writes the curves to `w` as an IGES file holding one rational B-spline curve entity per curve.
+///
+/// The entities carry the degree, knots, weights, control points and parameter range of each
+/// curve. The curves are flagged as non-planar, so no normal is written, and the global section
+/// holds only the delimiters, the units (millimetres) and the resolution.
+///
+/// # Arguments
+/// * `curves` - The curves to write
+/// * `w` - The destination of the file
+pub fn write_iges_curves<W: Write>(
+    curves: &[&Bcurve<3>],
+    w: &mut W,
+) -> io::Result<()>
+{
+    let start = ["IGES export of rational B-spline curves"];
+    let global = wrap_params(
+        &[
+            "1H,", "1H;", "", "", "", "", "32", "38", "6", "308", "15", "", "1.0", "2", "2HMM", "1",
+            "1.0", "", "1.0E-10", "", "", "", "11", "0",
+        ],
+        IGES_LINE_WIDTH,
+    );
+
+    //{{{ com: parameter data, one block of records per curve
+    let mut params: Vec<(usize, String)> = Vec::new();
+    let mut directory: Vec<String> = Vec::new();
+    for (i, curve) in curves.iter().enumerate()
+    {
+        let de = 2 * i + 1;
+        let lines = wrap_params(&curve_params(curve), IGES_PARAM_WIDTH);
+        let first = params.len() + 1;
+        directory.push(directory_line(&[IGES_BSPLINE_CURVE, first, 0, 0, 0, 0, 0, 0], "00000000"));
+        directory.push(directory_line(&[IGES_BSPLINE_CURVE, 0, 0, lines.len(), 0], ""));
+        params.extend(lines.into_iter().map(|line| (de, line)));
+    }
+    //}}}
+
+    for (i, line) in start.iter().enumerate()
+    {
+        write_record(w, line, 'S', i + 1)?;
+    }
+    for (i, line) in global.iter().enumerate()
+    {
+        write_record(w, line, 'G', i + 1)?;
+    }
+    for (i, line) in directory.iter().enumerate()
+    {
+        write_record(w, line, 'D', i + 1)?;
+    }
+    for (i, (de, line)) in params.iter().enumerate()
+    {
+        write_record(w, &format!("{:<64}{:>8}", line, de), 'P', i + 1)?;
+    }
+    let terminate = format!(
+        "S{:>7}G{:>7}D{:>7}P{:>7}",
+        start.len(),
+        global.len(),
+        directory.len(),
+        params.len()
+    );
+    write_record(w, &terminate, 'T', 1)
+}
+//}}}
+//{{{ fun: curve_params
+/// Returns the parameter data fields of the type 126 entity of `curve`.
+fn curve_params(curve: &Bcurve<3>) -> Vec<String>
+{
+    let n = curve.cpoints_w().len();
+    let p = curve.p();
+    let flag = |b: bool| if b { "1" } else { "0" }.to_string();
+
+    let mut fields = vec![
+        IGES_BSPLINE_CURVE.to_string(),
+        (n - 1).to_string(),
+        p.to_string(),
+        flag(false),
+        flag(curve.is_closed()),
+        flag(!curve.is_rational()),
+        flag(false),
+    ];
+    fields.extend(curve.knots().iter().map(|u| iges_real(*u)));
+    fields.extend(curve.cpoints_w().iter().map(|pw| iges_real(pw[3])));
+    for pw in curve.cpoints_w()
+    {
+        fields.extend(inv_homog(pw).iter().map(|x| iges_real(*x)));
+    }
+    let (u1, u2) = curve.param_range();
+    fields.extend([iges_real(u1), iges_real(u2)]);
+    fields.extend(["0.0", "0.0", "0.0"].map(String::from));
+    fields
+}
+//}}}
+//{{{ fun: iges_real
+/// Formats a real in the exponent form of IGES, with enough digits to reproduce it exactly.
+fn iges_real(x: f64) -> String
+{
+    format!("{:.16E}", x)
+}
+//}}}
+//{{{ fun: wrap_params
+/// Joins `fields` with the parameter delimiter, terminated by the record delimiter, into lines of
+/// at most `width` characters which break only after a delimiter.
+fn wrap_params(
+    fields: &[impl AsRef<str>],
+    width: usize,
+) -> Vec<String>
+{
+    let mut lines = vec![String::new()];
+    for (i, field) in fields.iter().enumerate()
+    {
+        let delim = if i + 1 == fields.len() { ';' } else { ',' };
+        let token = format!("{}{}", field.as_ref(), delim);
+        if lines.last().unwrap().len() + token.len() > width
+        {
+            lines.push(String::new());
+        }
+        lines.last_mut().unwrap().push_str(&token);
+    }
+    lines
+}
+//}}}
+//{{{ fun: directory_line
+/// Formats one record of a directory entry from its right justified 8 column integer fields,
+/// followed by a final text field.
+fn directory_line(
+    fields: &[usize],
+    last: &str,
+) -> String
+{
+    let mut line: String = fields.iter().map(|f| format!("{:>8}", f)).collect();
+    line.push_str(&format!("{:>8}", last));
+    line
+}
+//}}}
+//{{{ fun: write_record
+/// Writes one 80 column record, the data padded to 72 columns then the section letter and the
+/// sequence number.
+fn write_record<W: Write>(
+    w: &mut W,
+    data: &str,
+    section: char,
+    seq: usize,
+) -> io::Result<()>
+{
+    writeln!(w, "{:<72}{}{:>7}", data, section, seq)
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::Vector;
+    use crate::geometry::BcurveDescriptor;
+    use crate::test_utils::test_bcurve::{load_bcurve, TestData};
+
+    /// Reads back the parameter data fields of each entity in an IGES file.
+    fn read_params(iges: &str) -> Vec<Vec<String>>
+    {
+        let mut entities: Vec<(usize, String)> = Vec::new();
+        for line in iges.lines().filter(|l| &l[72..73] == "P")
+        {
+            let de: usize = line[64..72].trim().parse().unwrap();
+            match entities.last_mut()
+            {
+                Some((last, data)) if *last == de => data.push_str(line[..64].trim_end()),
+                _ => entities.push((de, line[..64].trim_end().to_string())),
+            }
+        }
+        entities
+            .into_iter()
+            .map(|(_, data)| data.trim_end_matches(';').split(',').map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn write_iges_curves_test()
+    {
+        let test_data = TestData::new();
+        let rational = load_bcurve::<3>(3, &test_data);
+        let cubic = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 0.5, 2.0, 2.0, 2.0, 2.0],
+            cpoints: vec![
+                Vector::<3>::new(0.0, 0.0, 0.0),
+                Vector::<3>::new(1.0, 2.0, -1.0),
+                Vector::<3>::new(2.5, -1.0, 1.0),
+                Vector::<3>::new(3.0, 0.5, 2.0),
+                Vector::<3>::new(4.0, 1.0, 0.0),
+            ],
+            cweights: vec![1.0; 5],
+        });
+
+        let mut out = Vec::new();
+        write_iges_curves(&[&rational, &cubic], &mut out).unwrap();
+        let iges = String::from_utf8(out).unwrap();
+        assert!(iges.lines().all(|l| l.len() == 80));
+        assert!(iges.lines().last().unwrap().starts_with("S      1G      1D      4P"));
+
+        let entities = read_params(&iges);
+        assert_eq!(entities.len(), 2);
+        for (curve, fields) in [&rational, &cubic].iter().zip(entities.iter())
+        {
+            let int = |i: usize| fields[i].parse::<usize>().unwrap();
+            let real = |i: usize| fields[i].parse::<f64>().unwrap();
+
+            let n = curve.cpoints_w().len();
+            let nk = curve.knots().len();
+            assert_eq!(int(0), 126);
+            assert_eq!(int(1), n - 1);
+            assert_eq!(int(2), curve.p());
+            assert_eq!(int(5), if curve.is_rational() { 0 } else { 1 });
+            assert_eq!(fields.len(), 7 + nk + 4 * n + 5);
+
+            for (i, u) in curve.knots().iter().enumerate()
+            {
+                assert_eq!(real(7 + i), *u);
+            }
+            let cpoints = curve.cpoints();
+            for (i, pw) in curve.cpoints_w().iter().enumerate()
+            {
+                assert_eq!(real(7 + nk + i), pw[3]);
+                for j in 0..3
+                {
+                    assert_eq!(real(7 + nk + n + 3 * i + j), cpoints[i][j]);
+                }
+            }
+            assert_eq!((real(7 + nk + 4 * n), real(8 + nk + 4 * n)), curve.param_range());
+        }
+    }
+}
+//}}}
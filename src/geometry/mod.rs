@@ -10,14 +10,21 @@ mod common;
 // Curves
 mod curve;
 
-pub use common::{Curve, CurveMinValOpts};
+pub use common::{Curve, CurveMinValOpts, SignedDistance};
 pub use curve::line::{Line, LineDescriptor};
-pub use curve::bcurve::{Bcurve, BcurveDescriptor, BCURVE_DER_MAX};
+pub use curve::segment::{Segment, SegmentDescriptor};
+pub use curve::bcurve::{junction_continuity, Bcurve, BcurveBuilder, BcurveDescriptor, BCURVE_DER_MAX};
+pub use curve::intersect::CurveIntersection;
+pub use curve::polyline::{Polyline, PolylineDescriptor};
+pub use curve::projection::ProjectionLut;
 // .................................................................................................
 // Surfaces
 mod surface;
 
 pub use common::Surface;
 pub use surface::plane::{Plane, PlaneDescriptor};
-pub use surface::bsurface::{Bsurface, BsurfaceDescriptor, BSURFACE_DER_MAX};
+pub use surface::bsurface::{Bsurface, BsurfaceDescriptor, CurvatureKind, SurfaceFrame, BSURFACE_DER_MAX};
+// .................................................................................................
+// Exchange formats
+pub mod export;
 // .................................................................................................
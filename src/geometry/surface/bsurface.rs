@@ -1,23 +1,59 @@
 use crate::boxing::ABox;
-use crate::common::Vector;
-use crate::geometry::common::{homog, inv_homog};
+use crate::common::{homog, inv_homog, try_inv_homog, Mat2, Mat3, Matrix, ModellerError, NDArray, ResConstants, Vec3, Vector};
+use crate::utilities::NDArrayWrapper;
+use crate::geometry::curve::bcurve::{Bcurve, BcurveDescriptor, BCURVE_DER_MAX};
+use crate::mesh::TriMesh;
 use crate::splines as spl;
 
 use crate::geometry::common::Surface;
 
+use nalgebra as na;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
 pub const BSURFACE_DER_MAX: usize = spl::PMAX + 1;
 
 pub struct BsurfaceDescriptor<const D: usize>
 {
-    p: usize,
-    q: usize,
-    knots_u: Vec<f64>,
-    knots_v: Vec<f64>,
-    cpoints: Vec<Vector<D>>,
-    cweights: Vec<f64>,
+    pub p: usize,
+    pub q: usize,
+    pub knots_u: Vec<f64>,
+    pub knots_v: Vec<f64>,
+    pub cpoints: Vec<Vector<D>>,
+    pub cweights: Vec<f64>,
+}
+//..................................................................................................
+
+/// The point of a surface with its tangents and unit normal at a parameter, see 
+/// [Bsurface::surface_frame].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceFrame
+{
+    /// The point $\mathbf{s}(u, v)$
+    pub point: Vector<3>,
+    /// The tangent $\mathbf{s}_{u}$, not normalised
+    pub du: Vector<3>,
+    /// The tangent $\mathbf{s}_{v}$, not normalised
+    pub dv: Vector<3>,
+    /// The unit normal $\mathbf{s}_{u} \times \mathbf{s}_{v} / |\mathbf{s}_{u} \times \mathbf{s}_{v}|$
+    pub normal: Vector<3>,
+}
+//..................................................................................................
+
+/// The curvature sampled by [Bsurface::curvature_field].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurvatureKind
+{
+    /// The Gauss curvature $K = \kappa_{1} \kappa_{2}$
+    Gauss,
+    /// The mean curvature $H = (\kappa_{1} + \kappa_{2}) / 2$
+    Mean,
+    /// The larger principal curvature $\kappa_{1}$
+    MaxPrincipal,
 }
 //..................................................................................................
 
+#[derive(Clone)]
 pub struct Bsurface<const D: usize>
 where
     [(); D + 1]:,
@@ -29,7 +65,9 @@ where
     cpoints_w: Vec<Vector<{ D + 1 }>>,
     r: usize,
     s: usize,
-    abox: Option<ABox<D>>,
+    pub(crate) abox: Option<ABox<D>>,
+    /// Tessellation seeding [Bsurface::intersect_ray], built on the first query
+    pub(crate) ray_mesh: OnceLock<TriMesh<3>>,
 }
 //..................................................................................................
 
@@ -39,11 +77,17 @@ where
     [(); D * BSURFACE_DER_MAX]:,
     [(); D * 3]:,
 {
+    /// The maximum degree of a surface in each direction
+    pub const PMAX: usize = spl::PMAX;
+    /// The order of derivative below which evaluation uses stack buffers
+    pub const DER_MAX: usize = BSURFACE_DER_MAX;
+
     pub fn new(bsd: &BsurfaceDescriptor<D>) -> Self
     {
-        debug_assert!(bsd.p <= spl::PMAX, "Order too large");
-        debug_assert!(bsd.knots_u.is_sorted(), "knots not sorted");
-        debug_assert!(bsd.knots_v.is_sorted(), "knots not sorted");
+        spl::check_degrees(&[("degree in u", bsd.p), ("degree in v", bsd.q)], &[])
+            .unwrap_or_else(|err| panic!("{}", err));
+        debug_assert!(spl::is_sorted_tol(&bsd.knots_u), "knots not sorted");
+        debug_assert!(spl::is_sorted_tol(&bsd.knots_v), "knots not sorted");
         debug_assert!(bsd.cweights.iter().all(|&x| x >= 0.0));
         debug_assert!(bsd.cweights.len() == bsd.cpoints.len());
         debug_assert!(
@@ -66,6 +110,66 @@ where
             r: bsd.knots_u.len() - bsd.p - 1,
             s: bsd.knots_v.len() - bsd.q - 1,
             abox: None,
+            ray_mesh: OnceLock::new(),
+        }
+    }
+
+    /// Constructs a surface which is closed and periodic in the $u$ direction, such as a full
+    /// surface of revolution.
+    ///
+    /// Here `knots_u` are the $n + 1$ distinct knots $u_{0} < \ldots < u_{n}$ of one period and the
+    /// net has $n$ columns, laid out as in [Bsurface::cpoints_w], which wrap around the seam. The
+    /// knots and columns are repeated periodically past either end, the surface is then clamped at
+    /// $u_{0}$ and $u_{n}$ by knot insertion. The result is an ordinary clamped
+    /// surface over $[u_{0}, u_{n}]$ whose position and first $p - 1$ derivatives in $u$ agree
+    /// across the seam.
+    pub fn new_periodic_u(bsd: &BsurfaceDescriptor<D>) -> Self
+    {
+        let p = bsd.p;
+        let n = bsd.knots_u.len() - 1;
+        spl::check_degrees(&[("degree in u", p), ("degree in v", bsd.q)], &[])
+            .unwrap_or_else(|err| panic!("{}", err));
+        debug_assert!(n >= p, "Too few spans for a periodic surface");
+        debug_assert!(bsd.knots_u.windows(2).all(|w| w[0] < w[1]), "knots not distinct");
+        debug_assert!(bsd.cweights.len() == bsd.cpoints.len());
+        debug_assert!(n * (bsd.knots_v.len() - bsd.q - 1) == bsd.cpoints.len());
+
+        // the periodic knots u_{-p}, ..., u_{n + p + 1}, the span past u_{n + p} keeps u_n inside
+        // the domain of the periodic spline so that it can be split there
+        let (u0, un) = (bsd.knots_u[0], bsd.knots_u[n]);
+        let period = un - u0;
+        let knots_periodic: Vec<f64> = (0..n + 2 * p + 2)
+            .map(|k| {
+                let k = k as isize - p as isize;
+                let shift = k.div_euclid(n as isize);
+                bsd.knots_u[k.rem_euclid(n as isize) as usize] + shift as f64 * period
+            })
+            .collect();
+
+        let s = bsd.knots_v.len() - bsd.q - 1;
+        let mut knots_u = Vec::new();
+        let mut points_w = Vec::with_capacity((n + p) * s);
+        for j in 0..s
+        {
+            let row: Vec<Vector<{ D + 1 }>> = (0..n + p + 1)
+                .map(|i| homog(&bsd.cpoints[i % n + j * n], bsd.cweights[i % n + j * n]))
+                .collect();
+            let (_, (knots_right, row_right)) = spl::split(&knots_periodic, p, &row, u0);
+            let ((knots_clamped, row_clamped), _) = spl::split(&knots_right, p, &row_right, un);
+            points_w.extend(row_clamped);
+            knots_u = knots_clamped;
+        }
+
+        Self {
+            p,
+            q: bsd.q,
+            r: knots_u.len() - p - 1,
+            s,
+            knots_u,
+            knots_v: bsd.knots_v.clone(),
+            cpoints_w: points_w,
+            abox: None,
+            ray_mesh: OnceLock::new(),
         }
     }
 
@@ -77,316 +181,2365 @@ where
     {
         &self.cpoints_w[i + j * self.r]
     }
-}
-//..................................................................................................
-
-impl<const D: usize> Surface for Bsurface<D>
-where
-    [(); D + 1]:,
-    [(); D * BSURFACE_DER_MAX]:,
-    [(); D * 3]:,
-{
-    type Vector = Vector<D>;
 
-    fn eval(
+    /// Evaluates the homogeneous point of the surface at $(u, v)$, shared by [Surface::eval] and
+    /// [Surface::try_eval] which differ only in how they project it.
+    fn eval_pointw(
         &self,
         u: f64,
-        v: f64
-    ) -> Vector<D>
+        v: f64,
+    ) -> Vector<{ D + 1 }>
     {
+        let u = spl::clamp_to_domain(&self.knots_u, u);
+        let v = spl::clamp_to_domain(&self.knots_v, v);
         debug_assert!(spl::is_member(&self.knots_u, u));
         debug_assert!(spl::is_member(&self.knots_v, v));
 
-        let mut pointw_tmp = Vector::<{ D + 1 }>::from_element(0.0);
-        let (startu, endu, _) = spl::non_zero_basis(&self.knots_u, u, self.p);
-        let (startv, endv, _) = spl::non_zero_basis(&self.knots_v, v, self.q);
+        // the tensor product is the v combination of the u combinations of the rows of the net
+        let basis_u = spl::basis_at(&self.knots_u, self.p, u);
+        let basis_v = spl::basis_at(&self.knots_v, self.q, v);
+        basis_v.combine(|j| basis_u.combine(|i| *self.pointw(i, j)))
+    }
 
-        let mut basis_funs_u = [0.0; spl::PMAX];
-        spl::eval(&self.knots_u, u, self.p, &mut basis_funs_u);
-        let mut basis_funs_v = [0.0; spl::PMAX];
-        spl::eval(&self.knots_v, v, self.q, &mut basis_funs_v);
+    /// Accessor to the order of the surface in the $u$ direction
+    pub fn p(&self) -> usize
+    {
+        self.p
+    }
 
-        for j in startv..endv
-        {
-            let basis_v_j = basis_funs_v[j - startv];
+    /// Accessor to the order of the surface in the $v$ direction
+    pub fn q(&self) -> usize
+    {
+        self.q
+    }
 
-            for i in startu..endu
-            {
-                let basis_u_i = basis_funs_u[i - startu];
-                let pointw_ij = self.pointw(i, j);
+    /// Accessor to the knots of the surface in the $u$ direction
+    pub fn knots_u(&self) -> &[f64]
+    {
+        &self.knots_u
+    }
 
-                pointw_tmp += (basis_u_i * basis_v_j) * pointw_ij;
-            }
-        }
+    /// Accessor to the knots of the surface in the $v$ direction
+    pub fn knots_v(&self) -> &[f64]
+    {
+        &self.knots_v
+    }
 
-        let point = inv_homog(&pointw_tmp);
-        point
+    /// Returns the valid parameter range of the surface in the $u$ direction
+    pub fn param_range_u(&self) -> (f64, f64)
+    {
+        (self.knots_u[0], self.knots_u[self.knots_u.len() - 1])
     }
 
+    /// Returns the valid parameter range of the surface in the $v$ direction
+    pub fn param_range_v(&self) -> (f64, f64)
+    {
+        (self.knots_v[0], self.knots_v[self.knots_v.len() - 1])
+    }
 
-    fn eval_principle_curvatures(
-        &self,
-        u: f64,
-        v: f64,
-    ) -> (f64, f64)
+    /// Accessor to the control points in homogeneous coordinates, the point $(i, j)$ of the net
+    /// is at index `i + j * r`, see [Bsurface::net_dims].
+    pub fn cpoints_w(&self) -> &[Vector<{ D + 1 }>]
     {
-        todo!()
+        &self.cpoints_w
     }
 
-    fn eval_gauss_curvature(
-        &self,
-        u: f64,
-        v: f64,
-    ) -> f64
+    /// Returns the control points in Euclidean coordinates, laid out as in 
+    /// [Bsurface::cpoints_w].
+    pub fn cpoints(&self) -> Vec<Vector<D>>
     {
-        todo!()
+        self.cpoints_w.iter().map(|pw| inv_homog(pw)).collect()
     }
 
-    fn eval_mean_curvature(
-        &self,
-        u: f64,
-        v: f64,
-    ) -> f64
+    /// Returns the control points in Euclidean coordinates paired with their weights, laid out as
+    /// in [Bsurface::cpoints_w].
+    pub fn cpoints_weighted(&self) -> Vec<(Vector<D>, f64)>
     {
-        todo!()
+        self.cpoints_w.iter().map(|pw| (inv_homog(pw), pw[D])).collect()
     }
-    
-    
-    fn is_member_u(
-        &self,
-        u: f64,
-    ) -> bool {
-        todo!()
+
+    /// Returns the dimensions `(r, s)` of the control net, the number of control points in the 
+    /// $u$ and $v$ directions respectively.
+    pub fn net_dims(&self) -> (usize, usize)
+    {
+        (self.r, self.s)
     }
-    
-    fn is_member_v(
+
+    /// Returns whether the surface is rational, meaning not all of its weights are equal
+    pub fn is_rational(&self) -> bool
+    {
+        let w = self.cpoints_w[0][D];
+        self.cpoints_w.iter().any(|pw| pw[D] != w)
+    }
+
+    /// Computes the box containing the control points of the surface, by the convex hull property
+    /// this also contains the surface.
+    pub fn control_box(&self) -> ABox<D>
+    {
+        ABox::<D>::from_points(&self.cpoints())
+    }
+
+    /// Evaluates the surface on the tensor product of its Greville abscissae in $u$ and $v$, which
+    /// gives a grid of points aligned with the control net.
+    ///
+    /// # Returns
+    /// The grid points laid out as in [Bsurface::cpoints_w], and the dimensions `(r, s)` of the
+    /// grid which are those of the control net.
+    pub fn greville_grid(&self) -> (Vec<Vector<D>>, (usize, usize))
+    {
+        let params_u = spl::greville(&self.knots_u, self.p);
+        let params_v = spl::greville(&self.knots_v, self.q);
+        let points = params_v
+            .iter()
+            .flat_map(|v| params_u.iter().map(move |u| self.eval(*u, *v)))
+            .collect();
+        (points, (self.r, self.s))
+    }
+
+    /// Constructs a surface of the same degrees from homogeneous control points laid out as in
+    /// `cpoints_w`.
+    fn from_parts(
         &self,
-        v: f64,
-    ) -> bool {
-        todo!()
+        knots_u: Vec<f64>,
+        knots_v: Vec<f64>,
+        cpoints_w: Vec<Vector<{ D + 1 }>>,
+    ) -> Self
+    {
+        let r = knots_u.len() - self.p - 1;
+        let s = knots_v.len() - self.q - 1;
+        debug_assert!(r * s == cpoints_w.len());
+        Self {
+            p: self.p,
+            q: self.q,
+            knots_u,
+            knots_v,
+            cpoints_w,
+            r,
+            s,
+            abox: None,
+            ray_mesh: OnceLock::new(),
+        }
     }
-    
-    fn dim(&self) -> usize {
-        todo!()
+
+    /// Splits the surface along the isoparametric line at `u` into two surfaces, the first 
+    /// covering $[u_{0}, u]$ and the second covering $[u, u_{m}]$ in the $u$ direction.
+    ///
+    /// This inserts `u` up to multiplicity `p` in each row of control points, both halves retain
+    /// the parameterisation of the original surface.
+    pub fn split_u(&self, u: f64) -> (Self, Self)
+    {
+        let mut knots = (Vec::new(), Vec::new());
+        let mut cpoints = (Vec::new(), Vec::new());
+        for j in 0..self.s
+        {
+            let row = &self.cpoints_w[j * self.r..(j + 1) * self.r];
+            let ((knots_left, row_left), (knots_right, row_right)) = 
+                spl::split(&self.knots_u, self.p, row, u);
+            cpoints.0.extend(row_left);
+            cpoints.1.extend(row_right);
+            knots = (knots_left, knots_right);
+        }
+        (
+            self.from_parts(knots.0, self.knots_v.clone(), cpoints.0),
+            self.from_parts(knots.1, self.knots_v.clone(), cpoints.1),
+        )
     }
-    
-    fn max_der_u(&self, u: f64) -> usize {
-        todo!()
+
+    /// Splits the surface along the isoparametric line at `v` into two surfaces, the first 
+    /// covering $[v_{0}, v]$ and the second covering $[v, v_{m}]$ in the $v$ direction.
+    ///
+    /// This inserts `v` up to multiplicity `q` in each column of control points, both halves 
+    /// retain the parameterisation of the original surface.
+    pub fn split_v(&self, v: f64) -> (Self, Self)
+    {
+        let mut knots = (Vec::new(), Vec::new());
+        let mut columns = (Vec::new(), Vec::new());
+        for i in 0..self.r
+        {
+            let column: Vec<Vector<{ D + 1 }>> = (0..self.s).map(|j| *self.pointw(i, j)).collect();
+            let ((knots_left, column_left), (knots_right, column_right)) = 
+                spl::split(&self.knots_v, self.q, &column, v);
+            columns.0.push(column_left);
+            columns.1.push(column_right);
+            knots = (knots_left, knots_right);
+        }
+
+        // gather the columns back into the layout of cpoints_w
+        let mut cpoints = (Vec::new(), Vec::new());
+        for j in 0..columns.0[0].len()
+        {
+            for i in 0..self.r
+            {
+                cpoints.0.push(columns.0[i][j]);
+            }
+        }
+        for j in 0..columns.1[0].len()
+        {
+            for i in 0..self.r
+            {
+                cpoints.1.push(columns.1[i][j]);
+            }
+        }
+        (
+            self.from_parts(self.knots_u.clone(), knots.0, cpoints.0),
+            self.from_parts(self.knots_u.clone(), knots.1, cpoints.1),
+        )
     }
-    
-    fn max_der_v(&self, v: f64) -> usize {
-        todo!()
+
+    /// Extracts the isoparametric curve of constant `u`, which runs in the $v$ direction.
+    ///
+    /// The curve has the order and knots of the surface in $v$, its control points are the 
+    /// columns of the control net combined with the $u$ basis functions at `u`.
+    pub fn iso_u(&self, u: f64) -> Bcurve<D>
+    where
+        [(); D * BCURVE_DER_MAX]:,
+    {
+        let u = spl::clamp_to_domain(&self.knots_u, u);
+        debug_assert!(spl::is_member(&self.knots_u, u));
+
+        let basis_u = spl::basis_at(&self.knots_u, self.p, u);
+
+        let mut cpoints = Vec::with_capacity(self.s);
+        let mut cweights = Vec::with_capacity(self.s);
+        for j in 0..self.s
+        {
+            let pointw = basis_u.combine(|i| *self.pointw(i, j));
+            cpoints.push(inv_homog(&pointw));
+            cweights.push(pointw[D]);
+        }
+
+        Bcurve::new(&BcurveDescriptor {
+            p: self.q,
+            knots: self.knots_v.clone(),
+            cpoints,
+            cweights,
+        })
     }
-    
-    fn eval_diff_u(
-        &self,
-        u: f64,
-        v: f64, 
-        nu: usize,
-    ) -> Self::Vector {
-        todo!()
+
+    /// Extracts the isoparametric curve of constant `v`, which runs in the $u$ direction.
+    ///
+    /// The curve has the order and knots of the surface in $u$, its control points are the rows
+    /// of the control net combined with the $v$ basis functions at `v`.
+    pub fn iso_v(&self, v: f64) -> Bcurve<D>
+    where
+        [(); D * BCURVE_DER_MAX]:,
+    {
+        let v = spl::clamp_to_domain(&self.knots_v, v);
+        debug_assert!(spl::is_member(&self.knots_v, v));
+
+        let basis_v = spl::basis_at(&self.knots_v, self.q, v);
+
+        let mut cpoints = Vec::with_capacity(self.r);
+        let mut cweights = Vec::with_capacity(self.r);
+        for i in 0..self.r
+        {
+            let pointw = basis_v.combine(|j| *self.pointw(i, j));
+            cpoints.push(inv_homog(&pointw));
+            cweights.push(pointw[D]);
+        }
+
+        Bcurve::new(&BcurveDescriptor {
+            p: self.p,
+            knots: self.knots_u.clone(),
+            cpoints,
+            cweights,
+        })
     }
-    
-    fn eval_diff_v(
-        &self,
-        u: f64,
-        v: f64, 
-        nv: usize,
-    ) -> Self::Vector {
-        todo!()
+
+    /// Extracts the four boundary curves of the surface, in the order of constant $u$ at the start
+    /// and end of its range, then constant $v$ at the start and end of its range. Each curve keeps
+    /// the parameterisation of the surface along it.
+    pub fn boundary_curves(&self) -> [Bcurve<D>; 4]
+    where
+        [(); D * BCURVE_DER_MAX]:,
+    {
+        let (u0, u1) = self.param_range_u();
+        let (v0, v1) = self.param_range_v();
+        [self.iso_u(u0), self.iso_u(u1), self.iso_v(v0), self.iso_v(v1)]
     }
-    
-    fn eval_diff_all(
-        &self,
-        u: f64,
-        v: f64,
-        nu: usize,
-        nv: usize,
-        ders: &mut [Self::Vector],
-    ) {
-        todo!()
+
+    /// Evaluates corner `i` of the surface, the corners are numbered as the control net is, so 
+    /// that bit 0 of `i` selects the end of the range in $u$ and bit 1 the end in $v$.
+    pub fn corner(&self, i: usize) -> Vector<D>
+    {
+        debug_assert!(i < 4, "a surface has four corners");
+
+        let (u0, u1) = self.param_range_u();
+        let (v0, v1) = self.param_range_v();
+        let u = if i & 1 == 0 { u0 } else { u1 };
+        let v = if i & 2 == 0 { v0 } else { v1 };
+        self.eval(u, v)
     }
-    
-    fn eval_tangent(
+
+    /// Checks whether the surface is degenerate at `(u, v)`, that is whether one of its tangents
+    /// $\mathbf{s}_{u}$, $\mathbf{s}_{v}$ is no longer than `tol`. This happens where a whole 
+    /// isocurve collapses to a point, such as at the poles of a surface of revolution, and there 
+    /// the normal and curvatures are undefined.
+    ///
+    /// The tangents are derivatives with respect to the parameters, so `tol` should be scaled by 
+    /// the size of the surface over the size of its parameter range.
+    pub fn is_degenerate_at(
         &self,
         u: f64,
         v: f64,
-        normalise: bool
-    ) -> (Self::Vector, Self::Vector) {
-        todo!()
+        tol: f64,
+    ) -> bool
+    {
+        let (du, dv) = self.eval_tangent(u, v, false);
+        du.norm() <= tol || dv.norm() <= tol
     }
-    
-    fn eval_normal(
-        &self,
-        u: f64,
-        v: f64,
-        normalise: bool,
-    ) -> Self::Vector {
-        todo!()
+
+    /// Reverses the direction of the $u$ parameter, so that the point at $u$ moves to
+    /// $u_{0} + u_{1} - u$ over the same parameter range $[u_{0}, u_{1}]$. The geometry of the 
+    /// surface is unchanged but its normal $\mathbf{s}_{u} \times \mathbf{s}_{v}$ is flipped.
+    pub fn reverse_u(&mut self)
+    {
+        let (u0, u1) = self.param_range_u();
+        self.knots_u = self.knots_u.iter().rev().map(|k| u0 + u1 - k).collect();
+        for row in self.cpoints_w.chunks_mut(self.r)
+        {
+            row.reverse();
+        }
+        self.ray_mesh = OnceLock::new();
     }
 }
+//..................................................................................................
 
-//------------------------------------------- tests ----------------------------------------------//
-
-#[cfg(test)]
-mod tests
+impl Bsurface<3>
 {
+    /// Orients the surface so that its normal at the centre of the parameter domain points to the
+    /// same side as `outward_reference`, reversing the $u$ direction if it points away from it.
+    ///
+    /// Orienting each patch of a shell against its outward direction makes the normals of adjacent
+    /// patches agree, as needed to export the shell as a consistently oriented mesh.
+    pub fn set_normal_convention(
+        &mut self,
+        outward_reference: &Vec3,
+    )
+    {
+        let (u0, u1) = self.param_range_u();
+        let (v0, v1) = self.param_range_v();
+        let normal = self.eval_normal(0.5 * (u0 + u1), 0.5 * (v0 + v1), false);
+        if normal.dot(outward_reference) < 0.0
+        {
+            self.reverse_u();
+        }
+    }
 
-    use approx::{assert_abs_diff_eq, assert_relative_eq, ulps_eq};
-    use serde::Deserialize;
-    use std::fs;
+    /// Constructs a blend surface between two edges, which interpolates both edges and the given
+    /// cross-boundary tangents along them, as used for fillets and blends between surfaces.
+    ///
+    /// The surface is cubic Hermite in $v$: with the curves reparameterised onto $[0, 1]$ and made
+    /// compatible, its rows of control points are $\mathbf{A}$, $\mathbf{A} + \mathbf{T}_{a} / 3$,
+    /// $\mathbf{B} - \mathbf{T}_{b} / 3$ and $\mathbf{B}$. The rows next to each edge take the
+    /// weights of that edge, so the edges are always reproduced exactly, while the cross tangents
+    /// are reproduced exactly where the curves are non-rational.
+    ///
+    /// # Arguments
+    /// * `edge_a` - The edge $\mathbf{A}(u)$ at $v = 0$.
+    /// * `cross_a` - The cross-boundary tangent $\mathbf{T}_{a}(u) = \mathbf{s}_{v}(u, 0)$.
+    /// * `edge_b` - The edge $\mathbf{B}(u)$ at $v = 1$.
+    /// * `cross_b` - The cross-boundary tangent $\mathbf{T}_{b}(u) = \mathbf{s}_{v}(u, 1)$, which
+    ///               points away from `edge_a` like `cross_a`.
+    ///
+    /// # Returns
+    /// The blend surface on $[0, 1] \times [0, 1]$, of cubic degree in $v$.
+    pub fn blend_surface(
+        edge_a: &Bcurve<3>,
+        cross_a: &Bcurve<3>,
+        edge_b: &Bcurve<3>,
+        cross_b: &Bcurve<3>,
+    ) -> Bsurface<3>
+    {
+        let mut curves = [edge_a.clone(), cross_a.clone(), edge_b.clone(), cross_b.clone()];
+        spl::make_all_compatible(&mut curves);
+        let [a, ta, b, tb] = &curves;
 
-    use crate::geometry::common::Surface;
-    use crate::test_utils::{convert, de_noise};
-    use crate::utilities::NDArrayWrapper;
+        let n = a.num_control_points();
+        let (a_points, b_points) = (a.cpoints(), b.cpoints());
+        let (ta_points, tb_points) = (ta.cpoints(), tb.cpoints());
+        let mut cpoints = Vec::with_capacity(4 * n);
+        cpoints.extend(a_points.iter().copied());
+        cpoints.extend((0..n).map(|i| a_points[i] + ta_points[i] / 3.0));
+        cpoints.extend((0..n).map(|i| b_points[i] - tb_points[i] / 3.0));
+        cpoints.extend(b_points.iter().copied());
 
-    use super::*;
+        let a_weights: Vec<f64> = a.cpoints_w().iter().map(|pw| pw[3]).collect();
+        let b_weights: Vec<f64> = b.cpoints_w().iter().map(|pw| pw[3]).collect();
+        let cweights = [&a_weights[..], &a_weights[..], &b_weights[..], &b_weights[..]].concat();
 
-    #[derive(Deserialize)]
-    struct ParamData
+        Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: a.p(),
+            q: 3,
+            knots_u: a.knots().to_vec(),
+            knots_v: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights,
+        })
+    }
+
+    /// Constructs the bilinearly blended Coons patch bounded by four curves, the sum of the ruled
+    /// surfaces between opposite pairs of curves less the bilinear surface through the corners.
+    ///
+    /// Each opposite pair is made compatible with [spl::make_compatible], after which the patch is
+    /// exactly representable on the knots of the pairs: by linear precision the linear blends 
+    /// across each pair take the Greville abscissae of the other direction as weights.
+    ///
+    /// # Arguments
+    /// * `c_u0` - The boundary at $u = 0$, running in $v$.
+    /// * `c_u1` - The boundary at $u = 1$, running in $v$.
+    /// * `c_v0` - The boundary at $v = 0$, running in $u$.
+    /// * `c_v1` - The boundary at $v = 1$, running in $u$.
+    ///
+    /// # Returns
+    /// The patch on $[0, 1] \times [0, 1]$ with each curve reparameterised onto $[0, 1]$, or 
+    /// [ModellerError::InvalidInput] if a curve is rational or the ends of the curves do not meet
+    /// at the four corners.
+    pub fn coons_patch(
+        c_u0: &Bcurve<3>,
+        c_u1: &Bcurve<3>,
+        c_v0: &Bcurve<3>,
+        c_v1: &Bcurve<3>,
+    ) -> Result<Bsurface<3>, ModellerError>
     {
-        description: String,
-        values: Vec<Vec<f64>>,
+        let curves = [c_u0, c_u1, c_v0, c_v1];
+        if curves.iter().any(|c| c.is_rational())
+        {
+            return Err(ModellerError::InvalidInput("boundary curves must be non-rational".to_string()));
+        }
+
+        //{{{ com: check the corners
+        let size = curves.iter().map(|c| c.control_box().diameter()).fold(1.0, f64::max);
+        let tol = f64::RES_LINEAR * size;
+        let corners = [
+            (c_v0.start_point(), c_u0.start_point()),
+            (c_v0.end_point(), c_u1.start_point()),
+            (c_v1.start_point(), c_u0.end_point()),
+            (c_v1.end_point(), c_u1.end_point()),
+        ];
+        for (k, (a, b)) in corners.iter().enumerate()
+        {
+            if (a - b).norm() > tol
+            {
+                return Err(ModellerError::InvalidInput(format!(
+                    "boundary curves do not meet at corner {}",
+                    k
+                )));
+            }
+        }
+        //}}}
+
+        let (mut c_u0, mut c_u1) = (c_u0.clone(), c_u1.clone());
+        let (mut c_v0, mut c_v1) = (c_v0.clone(), c_v1.clone());
+        spl::make_compatible(&mut c_u0, &mut c_u1);
+        spl::make_compatible(&mut c_v0, &mut c_v1);
+
+        let (p, q) = (c_v0.p(), c_u0.p());
+        let knots_u = c_v0.knots().to_vec();
+        let knots_v = c_u0.knots().to_vec();
+        let gu = spl::greville(&knots_u, p);
+        let gv = spl::greville(&knots_v, q);
+        let (pu0, pu1) = (c_u0.cpoints(), c_u1.cpoints());
+        let (pv0, pv1) = (c_v0.cpoints(), c_v1.cpoints());
+        let [s00, s10, s01, s11] = corners.map(|(a, _)| a);
+
+        let mut cpoints = Vec::with_capacity(gu.len() * gv.len());
+        for (j, v) in gv.iter().enumerate()
+        {
+            for (i, u) in gu.iter().enumerate()
+            {
+                let ruled_v = pv0[i] * (1.0 - v) + pv1[i] * *v;
+                let ruled_u = pu0[j] * (1.0 - u) + pu1[j] * *u;
+                let bilinear = (s00 * (1.0 - u) + s10 * *u) * (1.0 - v) + (s01 * (1.0 - u) + s11 * *u) * *v;
+                cpoints.push(ruled_v + ruled_u - bilinear);
+            }
+        }
+
+        let n = cpoints.len();
+        Ok(Bsurface::<3>::new(&BsurfaceDescriptor {
+            p,
+            q,
+            knots_u,
+            knots_v,
+            cpoints,
+            cweights: vec![1.0; n],
+        }))
+    }
+
+    /// Fits a tensor-product B-spline surface to a grid of sample points by least squares.
+    ///
+    /// The sample points are parameterised by averaged chord length in each direction. Since the
+    /// least-squares system of a tensor-product basis is separable, the fit is performed as a
+    /// sequence of curve fits: first each row of constant $v$ is fitted in $u$, then the
+    /// resulting intermediate control points are fitted in $v$.
+    ///
+    /// # Arguments
+    /// * `points` - Grid of sample points, `points[&[i, j]]` is the i'th point in $u$ and the j'th
+    ///              point in $v$
+    /// * `p` - Degree in the $u$ direction
+    /// * `q` - Degree in the $v$ direction
+    /// * `nu` - Number of control points in the $u$ direction
+    /// * `nv` - Number of control points in the $v$ direction
+    ///
+    /// # Returns
+    /// A tuple whose first element is the fitted surface and whose second element is the
+    /// root-mean-square distance between the sample points and the surface at their parameters.
+    pub fn fit_grid(
+        points: &NDArray<Vector<3>, 2>,
+        p: usize,
+        q: usize,
+        nu: usize,
+        nv: usize,
+    ) -> (Bsurface<3>, f64)
+    {
+        let [mu, mv] = *points.dims();
+        spl::check_degrees(&[("degree in u", p), ("degree in v", q)], &[])
+            .unwrap_or_else(|err| panic!("{}", err));
+        debug_assert!(nu >= p + 1 && nv >= q + 1, "Too few control points for degree");
+        debug_assert!(mu >= nu && mv >= nv, "Too few sample points for control points");
+
+        let params_u = grid_params(mu, mv, |i, j| points[&[i, j]]);
+        let params_v = grid_params(mv, mu, |j, i| points[&[i, j]]);
+        let knots_u = spl::fit_knots(&params_u, p, nu);
+        let knots_v = spl::fit_knots(&params_v, q, nv);
+        let basis_u = basis_matrix(&knots_u, p, &params_u, nu);
+        let basis_v = basis_matrix(&knots_v, q, &params_v, nv);
+
+        // fit each row of constant v in the u-direction
+        let mut inter = NDArray::<Vector<3>, 2>::new(&[nu, mv], Vector::<3>::zeros());
+        for j in 0..mv
+        {
+            let row: Vec<Vector<3>> = (0..mu).map(|i| points[&[i, j]]).collect();
+            let row_cpoints = lsq_solve(&basis_u, &row);
+            for k in 0..nu
+            {
+                inter[&[k, j]] = row_cpoints[k];
+            }
+        }
+
+        // fit the intermediate control points in the v-direction
+        let mut cpoints = vec![Vector::<3>::zeros(); nu * nv];
+        for k in 0..nu
+        {
+            let col: Vec<Vector<3>> = (0..mv).map(|j| inter[&[k, j]]).collect();
+            let col_cpoints = lsq_solve(&basis_v, &col);
+            for l in 0..nv
+            {
+                cpoints[k + l * nu] = col_cpoints[l];
+            }
+        }
+
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: p,
+            q: q,
+            knots_u: knots_u,
+            knots_v: knots_v,
+            cpoints: cpoints,
+            cweights: vec![1.0; nu * nv],
+        });
+
+        let mut sum_sq = 0.0;
+        for j in 0..mv
+        {
+            for i in 0..mu
+            {
+                sum_sq += (bsurf.eval(params_u[i], params_v[j]) - points[&[i, j]]).norm_squared();
+            }
+        }
+        let residual = (sum_sq / (mu * mv) as f64).sqrt();
+
+        (bsurf, residual)
+    }
+
+    /// Fits a tensor-product B-spline surface to an unstructured cloud of points by least squares,
+    /// the scattered-data analogue of [Bsurface::fit_grid].
+    ///
+    /// The points are parameterised by projecting them onto the base plane spanned by their two
+    /// principal axes, with the projections scaled onto $[0, 1]$ along each axis. So the cloud
+    /// should be a height field over that plane, and cover it densely enough that every knot span
+    /// rectangle holds data, otherwise the least-squares system is singular. The knots in each
+    /// direction are placed by [spl::fit_knots] from the sorted parameters.
+    ///
+    /// # Arguments
+    /// * `points` - The points to fit
+    /// * `p` - Degree in the $u$ direction
+    /// * `q` - Degree in the $v$ direction
+    /// * `nu` - Number of control points in the $u$ direction
+    /// * `nv` - Number of control points in the $v$ direction
+    ///
+    /// # Returns
+    /// A tuple whose first element is the fitted surface and whose second element is the
+    /// root-mean-square distance between the points and the surface at their parameters.
+    pub fn fit_to_mesh(
+        points: &[Vector<3>],
+        p: usize,
+        q: usize,
+        nu: usize,
+        nv: usize,
+    ) -> (Bsurface<3>, f64)
+    {
+        let m = points.len();
+        spl::check_degrees(&[("degree in u", p), ("degree in v", q)], &[])
+            .unwrap_or_else(|err| panic!("{}", err));
+        debug_assert!(nu >= p + 1 && nv >= q + 1, "Too few control points for degree");
+        debug_assert!(m >= nu * nv, "Too few points for control points");
+
+        //{{{ com: parameterise by projection onto the principal plane
+        let mean = points.iter().sum::<Vector<3>>() / m as f64;
+        let covariance = points
+            .iter()
+            .map(|x| (x - mean) * (x - mean).transpose())
+            .sum::<Mat3>() / m as f64;
+        let eigen = covariance.symmetric_eigen();
+        let mut order = [0, 1, 2];
+        order.sort_by(|a, b| eigen.eigenvalues[*b].total_cmp(&eigen.eigenvalues[*a]));
+
+        let project = |axis: usize| -> Vec<f64> {
+            let dir = eigen.eigenvectors.column(order[axis]).into_owned();
+            let coords: Vec<f64> = points.iter().map(|x| (x - mean).dot(&dir)).collect();
+            let lo = coords.iter().copied().fold(f64::MAX, f64::min);
+            let hi = coords.iter().copied().fold(f64::MIN, f64::max);
+            debug_assert!(hi > lo, "Points are collinear");
+            coords.iter().map(|c| (c - lo) / (hi - lo)).collect()
+        };
+        let params_u = project(0);
+        let params_v = project(1);
+        //}}}
+
+        let sorted = |params: &[f64]| -> Vec<f64> {
+            let mut sorted = params.to_vec();
+            sorted.sort_by(f64::total_cmp);
+            sorted
+        };
+        let knots_u = spl::fit_knots(&sorted(&params_u), p, nu);
+        let knots_v = spl::fit_knots(&sorted(&params_v), q, nv);
+        let basis_u = basis_matrix(&knots_u, p, &params_u, nu);
+        let basis_v = basis_matrix(&knots_v, q, &params_v, nv);
+
+        // the tensor-product basis, control point (k, l) is column k + l nu
+        let mut basis = na::DMatrix::<f64>::zeros(m, nu * nv);
+        for i in 0..m
+        {
+            for l in 0..nv
+            {
+                for k in 0..nu
+                {
+                    basis[(i, k + l * nu)] = basis_u[(i, k)] * basis_v[(i, l)];
+                }
+            }
+        }
+        let cpoints = lsq_solve(&basis, points);
+
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: p,
+            q: q,
+            knots_u: knots_u,
+            knots_v: knots_v,
+            cpoints: cpoints,
+            cweights: vec![1.0; nu * nv],
+        });
+
+        let sum_sq: f64 = (0..m)
+            .map(|i| (bsurf.eval(params_u[i], params_v[i]) - points[i]).norm_squared())
+            .sum();
+        let residual = (sum_sq / m as f64).sqrt();
+
+        (bsurf, residual)
+    }
+
+    /// Evaluates the surface on the grid of parameters `us` by `vs`, the point at `(us[i], vs[j])`
+    /// is at index `[i, j]` of the result.
+    ///
+    /// The basis functions in each direction are evaluated once per parameter, and for each $u$
+    /// the rows of the control net are combined once and reused for every $v$. The arithmetic is
+    /// that of [Surface::eval], so the points are identical to those of evaluating one at a time.
+    pub fn eval_grid(
+        &self,
+        us: &[f64],
+        vs: &[f64],
+    ) -> NDArray<Vector<3>, 2>
+    {
+        let basis_v: Vec<spl::BasisAt> = vs
+            .iter()
+            .map(|v| spl::basis_at(&self.knots_v, self.q, spl::clamp_to_domain(&self.knots_v, *v)))
+            .collect();
+
+        let mut points = NDArray::<Vector<3>, 2>::new(&[us.len(), vs.len()], Vector::<3>::zeros());
+        for (i, u) in us.iter().enumerate()
+        {
+            let basis_u = spl::basis_at(&self.knots_u, self.p, spl::clamp_to_domain(&self.knots_u, *u));
+            let rows: Vec<Vector<4>> =
+                (0..self.s).map(|j| basis_u.combine(|k| *self.pointw(k, j))).collect();
+            for (j, basis) in basis_v.iter().enumerate()
+            {
+                points[&[i, j]] = inv_homog(&basis.combine(|l| rows[l]));
+            }
+        }
+        points
+    }
+
+    /// Evaluates the point, both tangents and the unit normal of the surface at `(u, v)` from a 
+    /// single evaluation of the first derivatives, rather than one each for [Surface::eval], 
+    /// [Surface::eval_tangent] and [Surface::eval_normal].
+    ///
+    /// The normal is not defined where the tangents are parallel, such as at the poles of a surface
+    /// of revolution, and is then not finite.
+    pub fn surface_frame(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> SurfaceFrame
+    {
+        let mut ders = [Vector::<3>::zeros(); 4];
+        self.eval_diff_all(u, v, 1, 1, &mut ders);
+        SurfaceFrame {
+            point: ders[0],
+            du: ders[1],
+            dv: ders[2],
+            normal: ders[1].cross(&ders[2]).normalize(),
+        }
+    }
+
+    /// Evaluates the Jacobian of the surface at `(u, v)`, the matrix whose columns are the tangents
+    /// $\mathbf{s}_{u}$ and $\mathbf{s}_{v}$, which maps a step $(\delta u, \delta v)$ in parameter 
+    /// space to the step in space to first order.
+    pub fn uv_jacobian(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> Matrix<3, 2>
+    {
+        let (du, dv) = self.eval_tangent(u, v, false);
+        Matrix::<3, 2>::from_columns(&[du, dv])
+    }
+
+    /// Evaluates the first fundamental form $J^{T} J$ of the surface at `(u, v)`, where $J$ is the
+    /// [Bsurface::uv_jacobian].
+    ///
+    /// A step $\mathbf{d}$ in parameter space has the length $\sqrt{\mathbf{d}^{T} G \mathbf{d}}$ 
+    /// in space, and a parameter space area is scaled by $\sqrt{\det G}$, to first order.
+    pub fn metric(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> Mat2
+    {
+        let jac = self.uv_jacobian(u, v);
+        jac.transpose() * jac
+    }
+
+    /// Samples a curvature of the surface on a uniform `nu` by `nv` grid in parameter space, for 
+    /// false-color display of the surface.
+    ///
+    /// The grid is that of [Bsurface::tessellate_uniform], so the value at index `[i, j]` belongs 
+    /// to the vertex `i + j * (nu + 1)` of the tessellation with the same divisions. The signs of 
+    /// the mean and principal curvatures follow the normal $\mathbf{s}_{u} \times \mathbf{s}_{v}$,
+    /// they are negative where the surface bends away from it.
+    ///
+    /// # Arguments
+    /// * `nu` - Number of divisions in the $u$ direction
+    /// * `nv` - Number of divisions in the $v$ direction
+    /// * `kind` - The curvature to sample
+    ///
+    /// # Returns
+    /// The `nu + 1` by `nv + 1` grid of curvatures, which are NaN at degenerate points such as the 
+    /// poles of a surface of revolution.
+    pub fn curvature_field(
+        &self,
+        nu: usize,
+        nv: usize,
+        kind: CurvatureKind,
+    ) -> NDArray<f64, 2>
+    {
+        debug_assert!(nu >= 1 && nv >= 1, "Number of divisions must be positive");
+
+        let (u0, u1) = self.param_range_u();
+        let (v0, v1) = self.param_range_v();
+
+        let mut field = NDArray::<f64, 2>::new(&[nu + 1, nv + 1], 0.0);
+        for j in 0..nv + 1
+        {
+            let v = v0 + (v1 - v0) * j as f64 / nv as f64;
+            for i in 0..nu + 1
+            {
+                let u = u0 + (u1 - u0) * i as f64 / nu as f64;
+                field[&[i, j]] = match kind
+                {
+                    CurvatureKind::Gauss => self.eval_gauss_curvature(u, v),
+                    CurvatureKind::Mean => self.eval_mean_curvature(u, v),
+                    CurvatureKind::MaxPrincipal => self.eval_principle_curvatures(u, v).0,
+                };
+            }
+        }
+        field
+    }
+
+    /// Tessellates the surface into a triangle mesh on a uniform `nu` by `nv` grid in parameter 
+    /// space.
+    pub fn tessellate_uniform(
+        &self,
+        nu: usize,
+        nv: usize,
+    ) -> TriMesh<3>
+    {
+        debug_assert!(nu >= 1 && nv >= 1, "Number of divisions must be positive");
+
+        let (u0, u1) = (self.knots_u[0], self.knots_u[self.knots_u.len() - 1]);
+        let (v0, v1) = (self.knots_v[0], self.knots_v[self.knots_v.len() - 1]);
+
+        let us: Vec<f64> = (0..nu + 1).map(|i| u0 + (u1 - u0) * i as f64 / nu as f64).collect();
+        let vs: Vec<f64> = (0..nv + 1).map(|j| v0 + (v1 - v0) * j as f64 / nv as f64).collect();
+        let points = self.eval_grid(&us, &vs);
+
+        let mut mesh = TriMesh::<3>::new();
+        for j in 0..nv + 1
+        {
+            for i in 0..nu + 1
+            {
+                mesh.add_vertex_uv(&points[&[i, j]], [us[i], vs[j]]);
+            }
+        }
+
+        for j in 0..nv
+        {
+            for i in 0..nu
+            {
+                let v00 = i + j * (nu + 1);
+                let v10 = v00 + 1;
+                let v01 = v00 + nu + 1;
+                let v11 = v01 + 1;
+                mesh.add_triangle([v00, v10, v11]);
+                mesh.add_triangle([v00, v11, v01]);
+            }
+        }
+        mesh
+    }
+
+    /// Tessellates the surface into a triangle mesh whose density adapts to the local curvature.
+    ///
+    /// Each non-empty knot span rectangle is recursively subdivided in parameter space as a 
+    /// quadtree, a cell is split whenever the surface at its centre or edge midpoints deviates 
+    /// from the bilinear interpolation of its corners by more than `max_chord_error`. The 
+    /// quadtree is then balanced, so that neighbouring cells differ by at most one level, and each
+    /// cell is triangulated including the hanging vertices of its finer neighbours which makes 
+    /// the resulting mesh crack-free.
+    pub fn tessellate_adaptive(
+        &self,
+        max_chord_error: f64,
+    ) -> TriMesh<3>
+    {
+        debug_assert!(max_chord_error > 0.0, "Tolerance must be positive");
+
+        let breaks_u: Vec<f64> = spl::multiplicites(&self.knots_u).iter().map(|x| x.0).collect();
+        let breaks_v: Vec<f64> = spl::multiplicites(&self.knots_v).iter().map(|x| x.0).collect();
+        let to_param = |i: usize, breaks: &[f64]| -> f64 {
+            let span = (i >> TESS_LEVEL_MAX).min(breaks.len() - 2);
+            let frac = (i - (span << TESS_LEVEL_MAX)) as f64 / (1usize << TESS_LEVEL_MAX) as f64;
+            breaks[span] + frac * (breaks[span + 1] - breaks[span])
+        };
+        let to_point = |iu: usize, iv: usize| -> Vector<3> {
+            self.eval(to_param(iu, &breaks_u), to_param(iv, &breaks_v))
+        };
+
+        //{{{ com: refine cells whose centre or edge midpoints deviate from the bilinear patch
+        let base_size = 1usize << TESS_LEVEL_MAX;
+        let mut leaves: Vec<(usize, usize, usize)> = Vec::new();
+        let mut stack: Vec<(usize, usize, usize)> = Vec::new();
+        for j in 0..breaks_v.len() - 1
+        {
+            for i in 0..breaks_u.len() - 1
+            {
+                stack.push((i * base_size, j * base_size, base_size));
+            }
+        }
+
+        while let Some((x, y, size)) = stack.pop()
+        {
+            let p00 = to_point(x, y);
+            let p10 = to_point(x + size, y);
+            let p01 = to_point(x, y + size);
+            let p11 = to_point(x + size, y + size);
+            let half = size / 2;
+            let samples = [
+                (x + half, y + half, (p00 + p10 + p01 + p11) * 0.25),
+                (x + half, y, (p00 + p10) * 0.5),
+                (x + half, y + size, (p01 + p11) * 0.5),
+                (x, y + half, (p00 + p01) * 0.5),
+                (x + size, y + half, (p10 + p11) * 0.5),
+            ];
+            let error = samples
+                .iter()
+                .map(|(iu, iv, approx)| (to_point(*iu, *iv) - approx).norm())
+                .fold(0.0, f64::max);
+
+            if error > max_chord_error && size > 1
+            {
+                split_cell((x, y, size), &mut stack);
+            }
+            else
+            {
+                leaves.push((x, y, size));
+            }
+        }
+        //}}}
+        //{{{ com: balance the quadtree so that adjacent cells differ by at most one level
+        loop
+        {
+            let corners = cell_corners(&leaves);
+            let mut balanced: Vec<(usize, usize, usize)> = Vec::with_capacity(leaves.len());
+            let mut changed = false;
+            for (x, y, size) in leaves.iter().cloned()
+            {
+                let quarter = size / 4;
+                let needs_split = quarter > 0
+                    && [
+                        (x + quarter, y),
+                        (x + 3 * quarter, y),
+                        (x + size, y + quarter),
+                        (x + size, y + 3 * quarter),
+                        (x + quarter, y + size),
+                        (x + 3 * quarter, y + size),
+                        (x, y + quarter),
+                        (x, y + 3 * quarter),
+                    ]
+                    .iter()
+                    .any(|c| corners.contains(c));
+
+                if needs_split
+                {
+                    split_cell((x, y, size), &mut balanced);
+                    changed = true;
+                }
+                else
+                {
+                    balanced.push((x, y, size));
+                }
+            }
+            leaves = balanced;
+            if !changed
+            {
+                break;
+            }
+        }
+        //}}}
+        //{{{ com: triangulate each cell, fanning from its centre if it has hanging vertices
+        let corners = cell_corners(&leaves);
+        let mut mesh = TriMesh::<3>::new();
+        let mut vert_idx: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut get_vert = |mesh: &mut TriMesh<3>, key: (usize, usize)| -> usize {
+            *vert_idx.entry(key).or_insert_with(|| {
+                let u = to_param(key.0, &breaks_u);
+                let v = to_param(key.1, &breaks_v);
+                mesh.add_vertex_uv(&self.eval(u, v), [u, v])
+            })
+        };
+
+        for (x, y, size) in leaves.iter().cloned()
+        {
+            let half = size / 2;
+            let boundary = [
+                (x, y),
+                (x + half, y),
+                (x + size, y),
+                (x + size, y + half),
+                (x + size, y + size),
+                (x + half, y + size),
+                (x, y + size),
+                (x, y + half),
+            ];
+            let ring: Vec<usize> = boundary
+                .iter()
+                .enumerate()
+                .filter(|(k, c)| k % 2 == 0 || (half > 0 && corners.contains(c)))
+                .map(|(_, c)| get_vert(&mut mesh, *c))
+                .collect();
+
+            if ring.len() == 4
+            {
+                mesh.add_triangle([ring[0], ring[1], ring[2]]);
+                mesh.add_triangle([ring[0], ring[2], ring[3]]);
+            }
+            else
+            {
+                let center = get_vert(&mut mesh, (x + half, y + half));
+                for k in 0..ring.len()
+                {
+                    mesh.add_triangle([center, ring[k], ring[(k + 1) % ring.len()]]);
+                }
+            }
+        }
+        //}}}
+        mesh
+    }
+}
+//..................................................................................................
+
+/// Maximum depth of the adaptive tessellation quadtree within a single knot span rectangle.
+const TESS_LEVEL_MAX: usize = 10;
+
+/// Splits a quadtree cell `(x, y, size)` into its four children.
+fn split_cell(
+    cell: (usize, usize, usize),
+    cells: &mut Vec<(usize, usize, usize)>,
+)
+{
+    let (x, y, size) = cell;
+    let half = size / 2;
+    cells.push((x, y, half));
+    cells.push((x + half, y, half));
+    cells.push((x, y + half, half));
+    cells.push((x + half, y + half, half));
+}
+//..................................................................................................
+
+/// Collects the set of corners of the given quadtree cells.
+fn cell_corners(cells: &[(usize, usize, usize)]) -> HashSet<(usize, usize)>
+{
+    let mut corners = HashSet::with_capacity(cells.len() * 4);
+    for (x, y, size) in cells.iter().cloned()
+    {
+        corners.insert((x, y));
+        corners.insert((x + size, y));
+        corners.insert((x, y + size));
+        corners.insert((x + size, y + size));
+    }
+    corners
+}
+//..................................................................................................
+
+/// Computes the averaged chord-length parameterisation of a grid of points along its first index.
+///
+/// `point(i, j)` returns the i'th point along the direction being parameterised in the j'th row.
+fn grid_params<F: Fn(usize, usize) -> Vector<3>>(
+    num_along: usize,
+    num_across: usize,
+    point: F,
+) -> Vec<f64>
+{
+    let mut params = vec![0.0; num_along];
+    let mut num_rows = 0;
+
+    for j in 0..num_across
+    {
+        let mut chords = vec![0.0; num_along];
+        for i in 1..num_along
+        {
+            chords[i] = chords[i - 1] + (point(i, j) - point(i - 1, j)).norm();
+        }
+        let total = chords[num_along - 1];
+        if total > 0.0
+        {
+            for i in 1..num_along
+            {
+                params[i] += chords[i] / total;
+            }
+            num_rows += 1;
+        }
+    }
+
+    for i in 1..num_along
+    {
+        params[i] = if num_rows > 0
+        {
+            params[i] / num_rows as f64
+        }
+        else
+        {
+            i as f64 / (num_along - 1) as f64
+        };
+    }
+    params[num_along - 1] = 1.0;
+    params
+}
+//..................................................................................................
+
+/// Assembles the matrix of basis functions $N_{j}(u_{i})$ for the given parameters.
+fn basis_matrix(
+    knots: &[f64],
+    p: usize,
+    params: &[f64],
+    num_cpoints: usize,
+) -> na::DMatrix<f64>
+{
+    let mut basis = na::DMatrix::<f64>::zeros(params.len(), num_cpoints);
+    let mut scratch = spl::BasisScratch::new();
+    for (i, u) in params.iter().enumerate()
+    {
+        let (start, end, _) = spl::non_zero_basis(knots, *u, p);
+        let basis_funs = spl::eval_with(&mut scratch, knots, *u, p);
+        for j in start..end
+        {
+            basis[(i, j)] = basis_funs[j - start];
+        }
+    }
+    basis
+}
+//..................................................................................................
+
+/// Solves the least-squares problem $N P = Q$ for the control points $P$ via the normal equations.
+fn lsq_solve(
+    basis: &na::DMatrix<f64>,
+    points: &[Vector<3>],
+) -> Vec<Vector<3>>
+{
+    let mut rhs = na::DMatrix::<f64>::zeros(points.len(), 3);
+    for (i, point) in points.iter().enumerate()
+    {
+        for k in 0..3
+        {
+            rhs[(i, k)] = point[k];
+        }
+    }
+
+    let basis_t = basis.transpose();
+    let sol = (&basis_t * basis)
+        .cholesky()
+        .expect("least-squares system is singular")
+        .solve(&(&basis_t * rhs));
+
+    (0..sol.nrows())
+        .map(|i| Vector::<3>::new(sol[(i, 0)], sol[(i, 1)], sol[(i, 2)]))
+        .collect()
+}
+//..................................................................................................
+
+impl<const D: usize> Surface for Bsurface<D>
+where
+    [(); D + 1]:,
+    [(); D * BSURFACE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    type Vector = Vector<D>;
+
+    fn eval(
+        &self,
+        u: f64,
+        v: f64
+    ) -> Vector<D>
+    {
+        inv_homog(&self.eval_pointw(u, v))
+    }
+
+    /// Evaluates a point on the surface, checking that $(u, v)$ is in the domain and that the 
+    /// weight of the point is positive, since zero weights are allowed on the control points.
+    ///
+    /// # Returns
+    /// The point on the surface, [ModellerError::OutOfDomain] holding the first of $u$ and $v$ 
+    /// which is not a member of its range, or [ModellerError::DegenerateWeight] if the point is at
+    /// infinity.
+    fn try_eval(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> Result<Vector<D>, ModellerError>
+    {
+        if !self.is_member_u(u)
+        {
+            return Err(ModellerError::OutOfDomain(u));
+        }
+        if !self.is_member_v(v)
+        {
+            return Err(ModellerError::OutOfDomain(v));
+        }
+        try_inv_homog(&self.eval_pointw(u, v))
+    }
+
+
+    /// Evaluates the principal curvatures $\kappa_{1} \geq \kappa_{2}$, which are NaN where the
+    /// surface is degenerate, see [gauss_and_mean_curvature].
+    fn eval_principle_curvatures(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> (f64, f64)
+    {
+        let (gauss, mean) = gauss_and_mean_curvature(self, u, v);
+        let disc = (mean * mean - gauss).max(0.0).sqrt();
+        (mean + disc, mean - disc)
+    }
+
+    /// Evaluates the Gauss curvature, which is NaN where the surface is degenerate, see 
+    /// [gauss_and_mean_curvature].
+    fn eval_gauss_curvature(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> f64
+    {
+        gauss_and_mean_curvature(self, u, v).0
+    }
+
+    /// Evaluates the mean curvature, which is NaN where the surface is degenerate, see 
+    /// [gauss_and_mean_curvature].
+    fn eval_mean_curvature(
+        &self,
+        u: f64,
+        v: f64,
+    ) -> f64
+    {
+        gauss_and_mean_curvature(self, u, v).1
+    }
+    
+    
+    fn is_member_u(
+        &self,
+        u: f64,
+    ) -> bool {
+        spl::is_member(&self.knots_u, u)
+    }
+    
+    fn is_member_v(
+        &self,
+        v: f64,
+    ) -> bool {
+        spl::is_member(&self.knots_v, v)
+    }
+    
+    fn dim(&self) -> usize {
+        D
+    }
+    
+    fn max_der_u(&self, u: f64) -> usize {
+        if self.is_rational() { BSURFACE_DER_MAX } else { self.p }
+    }
+    
+    fn max_der_v(&self, v: f64) -> usize {
+        if self.is_rational() { BSURFACE_DER_MAX } else { self.q }
+    }
+    
+    fn eval_diff_u(
+        &self,
+        u: f64,
+        v: f64, 
+        nu: usize,
+    ) -> Self::Vector {
+        let mut ders = vec![Vector::<D>::zeros(); nu + 1];
+        self.eval_diff_all(u, v, nu, 0, &mut ders);
+        ders[nu]
+    }
+    
+    fn eval_diff_v(
+        &self,
+        u: f64,
+        v: f64, 
+        nv: usize,
+    ) -> Self::Vector {
+        let mut ders = vec![Vector::<D>::zeros(); nv + 1];
+        self.eval_diff_all(u, v, 0, nv, &mut ders);
+        ders[nv]
+    }
+    
+    /// Computes the partial derivatives of the surface, $\mathbf{s}^{(k, l)}$ is stored in 
+    /// `ders[k + (nu + 1) * l]`.
+    ///
+    /// This is algorithm A4.4 of The NURBS Book, the derivatives of the homogeneous surface are 
+    /// computed from the basis function derivatives and then the quotient rule is applied.
+    fn eval_diff_all(
+        &self,
+        u: f64,
+        v: f64,
+        nu: usize,
+        nv: usize,
+        ders: &mut [Self::Vector],
+    ) {
+        let u = spl::clamp_to_domain(&self.knots_u, u);
+        let v = spl::clamp_to_domain(&self.knots_v, v);
+        debug_assert!(spl::is_member(&self.knots_u, u));
+        debug_assert!(spl::is_member(&self.knots_v, v));
+        debug_assert!(ders.len() >= (nu + 1) * (nv + 1), "Output array is not large enough");
+
+        //{{{ com: basis function derivatives in each direction
+        let (startu, _, num_basis_u) = spl::non_zero_basis(&self.knots_u, u, self.p);
+        let (startv, _, num_basis_v) = spl::non_zero_basis(&self.knots_v, v, self.q);
+
+        let mut basis_ders_u = vec![0.0; (self.p + 1) * (nu + 1)];
+        spl::eval_diff_all(&self.knots_u, u, self.p, nu, &mut basis_ders_u);
+        let basis_ders_u = NDArrayWrapper::<'_, f64, 2>::new(&mut basis_ders_u, &[num_basis_u, nu + 1]);
+
+        let mut basis_ders_v = vec![0.0; (self.q + 1) * (nv + 1)];
+        spl::eval_diff_all(&self.knots_v, v, self.q, nv, &mut basis_ders_v);
+        let basis_ders_v = NDArrayWrapper::<'_, f64, 2>::new(&mut basis_ders_v, &[num_basis_v, nv + 1]);
+        //}}}
+        //{{{ com: derivatives of the homogeneous surface
+        let mut dersw = vec![Vector::<{ D + 1 }>::zeros(); (nu + 1) * (nv + 1)];
+        for l in 0..nv + 1
+        {
+            for k in 0..nu + 1
+            {
+                let mut aw = Vector::<{ D + 1 }>::zeros();
+                for j in 0..num_basis_v
+                {
+                    let nv_jl = basis_ders_v[&[j, l]];
+                    if nv_jl == 0.0
+                    {
+                        continue;
+                    }
+                    for i in 0..num_basis_u
+                    {
+                        aw += (basis_ders_u[&[i, k]] * nv_jl) * self.pointw(startu + i, startv + j);
+                    }
+                }
+                dersw[k + (nu + 1) * l] = aw;
+            }
+        }
+        //}}}
+        //{{{ com: quotient rule
+        let idx = |k: usize, l: usize| k + (nu + 1) * l;
+        let w00 = dersw[0][D];
+        for k in 0..nu + 1
+        {
+            for l in 0..nv + 1
+            {
+                let mut ve: Vector<D> = dersw[idx(k, l)].fixed_rows::<D>(0).into();
+                for j in 1..l + 1
+                {
+                    ve -= spl::binom(l, j) * dersw[idx(0, j)][D] * ders[idx(k, l - j)];
+                }
+                for i in 1..k + 1
+                {
+                    ve -= spl::binom(k, i) * dersw[idx(i, 0)][D] * ders[idx(k - i, l)];
+                    let mut v2 = Vector::<D>::zeros();
+                    for j in 1..l + 1
+                    {
+                        v2 += spl::binom(l, j) * dersw[idx(i, j)][D] * ders[idx(k - i, l - j)];
+                    }
+                    ve -= spl::binom(k, i) * v2;
+                }
+                ders[idx(k, l)] = ve / w00;
+            }
+        }
+        //}}}
+    }
+    
+    fn eval_tangent(
+        &self,
+        u: f64,
+        v: f64,
+        normalise: bool
+    ) -> (Self::Vector, Self::Vector) {
+        let mut ders = [Vector::<D>::zeros(); 4];
+        self.eval_diff_all(u, v, 1, 1, &mut ders);
+        let (mut tu, mut tv) = (ders[1], ders[2]);
+        if normalise
+        {
+            tu = tu.normalize();
+            tv = tv.normalize();
+        }
+        (tu, tv)
+    }
+    
+    /// Evaluates the normal $\mathbf{s}_{u} \times \mathbf{s}_{v}$, this is only defined for 
+    /// surfaces in 3D.
+    fn eval_normal(
+        &self,
+        u: f64,
+        v: f64,
+        normalise: bool,
+    ) -> Self::Vector {
+        let (tu, tv) = self.eval_tangent(u, v, false);
+        let mut normal = tu.cross(&tv);
+        if normalise
+        {
+            normal = normal.normalize();
+        }
+        normal
+    }
+}
+//..................................................................................................
+
+/// Computes the Gauss curvature $K$ and the mean curvature $H$ of the surface at `(u, v)` from its
+/// first fundamental form $(E, F, G)$ and second fundamental form $(L, M, N)$, with respect to the
+/// normal $\mathbf{s}_{u} \times \mathbf{s}_{v}$:
+/// $$
+/// K = \frac{LN - M^{2}}{EG - F^{2}}, \quad H = \frac{EN - 2FM + GL}{2(EG - F^{2})}
+/// $$
+/// Where the tangents are parallel to within the angular resolution, or one of them vanishes, the
+/// normal is undefined and both are NaN.
+fn gauss_and_mean_curvature<const D: usize>(
+    bsurf: &Bsurface<D>,
+    u: f64,
+    v: f64,
+) -> (f64, f64)
+where
+    [(); D + 1]:,
+    [(); D * BSURFACE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    // the derivative (k, l) is at index k + 3 * l
+    let mut ders = [Vector::<D>::zeros(); 9];
+    bsurf.eval_diff_all(u, v, 2, 2, &mut ders);
+    let (su, sv) = (ders[1], ders[3]);
+    let (suu, suv, svv) = (ders[2], ders[4], ders[6]);
+
+    let normal = su.cross(&sv);
+    let area = normal.norm();
+    if area <= f64::RES_ANGULAR * su.norm() * sv.norm()
+    {
+        return (f64::NAN, f64::NAN);
+    }
+    let normal = normal / area;
+
+    let (e, f, g) = (su.dot(&su), su.dot(&sv), sv.dot(&sv));
+    let (l, m, n) = (suu.dot(&normal), suv.dot(&normal), svv.dot(&normal));
+    let det = e * g - f * f;
+    ((l * n - m * m) / det, (e * n - 2.0 * f * m + g * l) / (2.0 * det))
+}
+
+//------------------------------------------- tests ----------------------------------------------//
+
+#[cfg(test)]
+mod tests
+{
+
+    use approx::{assert_abs_diff_eq, assert_relative_eq, ulps_eq};
+    use serde::Deserialize;
+    use std::fs;
+
+    use crate::common::ModellerError;
+    use crate::geometry::common::{Curve, Surface};
+    use crate::test_utils::{convert, de_noise, sphere_patch};
+    use std::collections::HashMap;
+    use crate::utilities::NDArrayWrapper;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct ParamData
+    {
+        description: String,
+        values: Vec<Vec<f64>>,
+    }
+
+    #[derive(Deserialize)]
+    struct KnotData
+    {
+        description: String,
+        values: Vec<f64>,
+    }
+
+    #[derive(Deserialize)]
+    struct WeightData
+    {
+        description: String,
+        values: Vec<f64>,
+    }
+
+    #[derive(Deserialize)]
+    struct CpointData
+    {
+        description: String,
+        values: Vec<Vec<f64>>,
+    }
+
+    #[derive(Deserialize)]
+    struct PointData
+    {
+        description: String,
+        values: Vec<Vec<f64>>,
+    }
+
+    #[derive(Deserialize)]
+    struct DerData
+    {
+        description: String,
+        values: Vec<Vec<f64>>,
+    }
+
+    #[derive(Deserialize)]
+    struct TestData
+    {
+        uv: ParamData,
+        knotsu_p1: KnotData,
+        knotsu_p2: KnotData,
+        knotsu_p3: KnotData,
+        knotsu_p4: KnotData,
+        knotsu_p5: KnotData,
+        knotsv_q2: KnotData,
+        knotsv_q3: KnotData,
+        knotsv_q4: KnotData,
+        knotsv_q5: KnotData,
+        knotsv_q6: KnotData,
+        weights_p1_q2: WeightData,
+        weights_p2_q3: WeightData,
+        weights_p3_q4: WeightData,
+        weights_p4_q5: WeightData,
+        weights_p5_q6: WeightData,
+        cpoints_d2_p1_q2: CpointData,
+        cpoints_d2_p2_q3: CpointData,
+        cpoints_d2_p3_q4: CpointData,
+        cpoints_d2_p4_q5: CpointData,
+        cpoints_d2_p5_q6: CpointData,
+        cpoints_d3_p1_q2: CpointData,
+        cpoints_d3_p2_q3: CpointData,
+        cpoints_d3_p3_q4: CpointData,
+        cpoints_d3_p4_q5: CpointData,
+        cpoints_d3_p5_q6: CpointData,
+        points_d2_p1_q2: PointData,
+        points_d2_p2_q3: PointData,
+        points_d2_p3_q4: PointData,
+        points_d2_p4_q5: PointData,
+        points_d2_p5_q6: PointData,
+        points_d3_p1_q2: PointData,
+        points_d3_p2_q3: PointData,
+        points_d3_p3_q4: PointData,
+        points_d3_p4_q5: PointData,
+        points_d3_p5_q6: PointData,
+        ders_d2_p1_q2: DerData,
+        ders_d2_p2_q3: DerData,
+        ders_d2_p3_q4: DerData,
+        ders_d2_p4_q5: DerData,
+        ders_d2_p5_q6: DerData,
+        ders_d3_p1_q2: DerData,
+        ders_d3_p2_q3: DerData,
+        ders_d3_p3_q4: DerData,
+        ders_d3_p4_q5: DerData,
+        ders_d3_p5_q6: DerData,
+    }
+
+    impl TestData
+    {
+        pub fn new() -> Self
+        {
+            let json_file =
+                fs::read_to_string("assets/geo/bsurface-tests.json").expect("Unable to read file");
+            serde_json::from_str(&json_file).expect("Could not deserialize")
+        }
+    }
+
+    #[test]
+    fn construction_2d()
+    {
+        let test_data = TestData::new();
+        let p = 1;
+        let q = 2;
+        let knotsu = test_data.knotsu_p1.values;
+        let knotsv = test_data.knotsv_q2.values;
+        let cpoints: Vec<Vector<2>> = convert(&test_data.cpoints_d2_p1_q2.values);
+        let cweights = test_data.weights_p1_q2.values;
+
+        let descriptor = BsurfaceDescriptor {
+            p: p,
+            q: q,
+            knots_u: knotsu,
+            knots_v: knotsv,
+            cpoints: cpoints,
+            cweights: cweights,
+        };
+        let bsurf = Bsurface::<2>::new(&descriptor);
+    }
+
+    #[test]
+    fn construction_3d()
+    {
+        let test_data = TestData::new();
+        let p = 1;
+        let q = 2;
+        let knotsu = test_data.knotsu_p1.values;
+        let knotsv = test_data.knotsv_q2.values;
+        let cpoints: Vec<Vector<3>> = convert(&test_data.cpoints_d3_p1_q2.values);
+        let cweights = test_data.weights_p1_q2.values;
+
+        let descriptor = BsurfaceDescriptor {
+            p: p,
+            q: q,
+            knots_u: knotsu,
+            knots_v: knotsv,
+            cpoints: cpoints,
+            cweights: cweights,
+        };
+        let bsurf = Bsurface::<3>::new(&descriptor);
+    }
+
+    #[test]
+    fn fit_grid_test()
+    {
+        let cpoints: Vec<Vector<3>> = (0..16)
+            .map(|idx| {
+                let i = (idx % 4) as f64;
+                let j = (idx / 4) as f64;
+                Vector::<3>::new(i, j, 0.5 * (i - 1.5) * (j - 1.5) + 0.2 * i)
+            })
+            .collect();
+        let bsurf1 = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 3,
+            q: 3,
+            knots_u: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            cpoints: cpoints,
+            cweights: vec![1.0; 16],
+        });
+
+        let (mu, mv) = (30, 25);
+        let mut points = NDArray::<Vector<3>, 2>::new(&[mu, mv], Vector::<3>::zeros());
+        for j in 0..mv
+        {
+            for i in 0..mu
+            {
+                let u = i as f64 / (mu - 1) as f64;
+                let v = j as f64 / (mv - 1) as f64;
+                points[&[i, j]] = bsurf1.eval(u, v);
+            }
+        }
+
+        let (bsurf2, residual) = Bsurface::<3>::fit_grid(&points, 3, 3, 8, 8);
+        assert!(residual < 1e-4, "residual too large: {}", residual);
+        assert_eq!(bsurf2.r, 8);
+        assert_eq!(bsurf2.s, 8);
+    }
+
+    #[test]
+    fn fit_to_mesh_test()
+    {
+        // scattered samples of a height field over a rectangle, z = 0.1 (x - 3)(y - 1.5) + 0.2 x
+        let height = |x: f64, y: f64| 0.1 * (x - 3.0) * (y - 1.5) + 0.2 * x;
+        let points: Vec<Vector<3>> = (0..400)
+            .map(|k| {
+                // a low-discrepancy sequence covers the rectangle evenly without a grid
+                let x = 6.0 * (0.5 + k as f64 * 0.7548776662466927).fract();
+                let y = 3.0 * (0.5 + k as f64 * 0.5698402909980532).fract();
+                Vector::<3>::new(x, y, height(x, y))
+            })
+            .collect();
+
+        let (bsurf, residual) = Bsurface::<3>::fit_to_mesh(&points, 3, 3, 6, 5);
+        assert!(residual < 1e-3, "residual too large: {}", residual);
+        assert_eq!(bsurf.net_dims(), (6, 5));
+
+        // the fitted surface reproduces the height field away from the samples too
+        for i in 0..=10
+        {
+            for j in 0..=10
+            {
+                let x = bsurf.eval(0.05 + 0.09 * i as f64, 0.05 + 0.09 * j as f64);
+                assert!((x.z - height(x.x, x.y)).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "degree in v 9 exceeds the maximum of 8")]
+    fn new_degree_limit()
+    {
+        let q = spl::PMAX + 1;
+        Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q,
+            knots_u: vec![0.0, 0.0, 1.0, 1.0],
+            knots_v: [vec![0.0; q + 1], vec![1.0; q + 1]].concat(),
+            cpoints: vec![Vector::<3>::zeros(); 2 * (q + 1)],
+            cweights: vec![1.0; 2 * (q + 1)],
+        });
+    }
+
+    #[test]
+    fn eval_domain_boundary()
+    {
+        let cpoints: Vec<Vector<3>> = (0..9)
+            .map(|idx| {
+                let i = (idx % 3) as f64;
+                let j = (idx / 3) as f64;
+                Vector::<3>::new(i, j, i * j)
+            })
+            .collect();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 2,
+            knots_u: vec![0.0, 0.0, 0.0, 2.0, 2.0, 2.0],
+            knots_v: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: cpoints,
+            cweights: vec![1.0; 9],
+        });
+
+        assert_eq!(bsurf.eval(2.0f64.next_up(), 1.0f64.next_up()), bsurf.eval(2.0, 1.0));
+        assert_eq!(bsurf.eval(0.0f64.next_down(), 0.5), bsurf.eval(0.0, 0.5));
+
+        // try_eval agrees with eval inside the domain and rejects parameters well outside it
+        assert_eq!(bsurf.try_eval(1.5, 0.25).unwrap(), bsurf.eval(1.5, 0.25));
+        assert!(matches!(bsurf.try_eval(2.5, 0.5), Err(ModellerError::OutOfDomain(u)) if u == 2.5));
+        assert!(matches!(bsurf.try_eval(1.0, -0.5), Err(ModellerError::OutOfDomain(v)) if v == -0.5));
+    }
+
+    #[test]
+    fn try_eval_zero_weight()
+    {
+        // a zero weight on the corner control point puts that corner at infinity
+        let cpoints: Vec<Vector<3>> = (0..9)
+            .map(|idx| {
+                let i = (idx % 3) as f64;
+                let j = (idx / 3) as f64;
+                Vector::<3>::new(i, j, i * j)
+            })
+            .collect();
+        let mut cweights = vec![1.0; 9];
+        cweights[8] = 0.0;
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 2,
+            knots_u: vec![0.0, 0.0, 0.0, 2.0, 2.0, 2.0],
+            knots_v: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights,
+        });
+        assert!(bsurf.eval(2.0, 1.0).iter().any(|x| !x.is_finite()));
+        assert!(matches!(bsurf.try_eval(2.0, 1.0), Err(ModellerError::DegenerateWeight(w)) if w == 0.0));
+        assert_eq!(bsurf.try_eval(1.0, 0.5).unwrap(), bsurf.eval(1.0, 0.5));
+    }
+
+    #[test]
+    fn split_test()
+    {
+        // a rational biquadratic-by-cubic surface with an interior knot in each direction
+        let (r, s) = (4, 5);
+        let cpoints: Vec<Vector<3>> = (0..r * s)
+            .map(|idx| {
+                let i = (idx % r) as f64;
+                let j = (idx / r) as f64;
+                Vector::<3>::new(i, j, (0.7 * i).sin() * (0.4 * j + 0.3).cos())
+            })
+            .collect();
+        let cweights: Vec<f64> = (0..r * s).map(|idx| 1.0 + 0.1 * (idx % 3) as f64).collect();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 3,
+            knots_u: vec![0.0, 0.0, 0.0, 0.4, 1.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights,
+        });
+
+        let samples: Vec<f64> = (0..11).map(|k| k as f64 / 10.0).collect();
+
+        for usplit in [0.3, 0.4]
+        {
+            let (left, right) = bsurf.split_u(usplit);
+            assert_eq!(left.knots_u.len(), left.r + left.p + 1);
+            assert_eq!(right.knots_u.len(), right.r + right.p + 1);
+            assert_eq!(left.s, bsurf.s);
+            assert_eq!(right.s, bsurf.s);
+            assert_eq!(left.r + right.r, bsurf.r + bsurf.p + 1 - bsurf.knots_u.iter().filter(|&&x| x == usplit).count());
+            for v in samples.iter()
+            {
+                let x = bsurf.eval(usplit, *v);
+                assert_relative_eq!(left.eval(usplit, *v), x, epsilon = 1e-11);
+                assert_relative_eq!(right.eval(usplit, *v), x, epsilon = 1e-11);
+                assert_relative_eq!(left.eval(0.5 * usplit, *v), bsurf.eval(0.5 * usplit, *v), epsilon = 1e-11);
+                assert_relative_eq!(right.eval(0.9, *v), bsurf.eval(0.9, *v), epsilon = 1e-11);
+            }
+        }
+
+        for vsplit in [0.5, 0.65]
+        {
+            let (left, right) = bsurf.split_v(vsplit);
+            assert_eq!(left.knots_v.len(), left.s + left.q + 1);
+            assert_eq!(right.knots_v.len(), right.s + right.q + 1);
+            assert_eq!(left.r, bsurf.r);
+            assert_eq!(right.r, bsurf.r);
+            for u in samples.iter()
+            {
+                let x = bsurf.eval(*u, vsplit);
+                assert_relative_eq!(left.eval(*u, vsplit), x, epsilon = 1e-11);
+                assert_relative_eq!(right.eval(*u, vsplit), x, epsilon = 1e-11);
+                assert_relative_eq!(left.eval(*u, 0.2), bsurf.eval(*u, 0.2), epsilon = 1e-11);
+                assert_relative_eq!(right.eval(*u, 0.95), bsurf.eval(*u, 0.95), epsilon = 1e-11);
+            }
+        }
+    }
+
+    #[test]
+    fn new_periodic_u_test()
+    {
+        // a cylinder of radius 2 and height 3, the u direction runs around an octagonal net
+        let n = 8;
+        let knots_u = vec![0.0, 1.0, 2.5, 3.0, 4.0, 5.5, 6.0, 7.0, 8.0];
+        let cpoints: Vec<Vector<3>> = (0..2 * n)
+            .map(|idx| {
+                let theta = 2.0 * std::f64::consts::PI * (idx % n) as f64 / n as f64;
+                Vector::<3>::new(2.0 * theta.cos(), 2.0 * theta.sin(), 3.0 * (idx / n) as f64)
+            })
+            .collect();
+        let cylinder = Bsurface::<3>::new_periodic_u(&BsurfaceDescriptor {
+            p: 3,
+            q: 1,
+            knots_u,
+            knots_v: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints,
+            cweights: (0..2 * n).map(|idx| 1.0 + 0.25 * (idx % 3) as f64).collect(),
+        });
+        assert_eq!(cylinder.net_dims(), (n + 3, 2));
+        assert_eq!(cylinder.param_range_u(), (0.0, 8.0));
+        assert_eq!(&cylinder.knots_u()[..4], &[0.0; 4]);
+        assert_eq!(&cylinder.knots_u()[cylinder.knots_u().len() - 4..], &[8.0; 4]);
+
+        // the position and the first two derivatives in u agree across the seam
+        for v in [0.0, 0.3, 0.7, 1.0]
+        {
+            let mut ders0 = [Vector::<3>::zeros(); 3];
+            let mut ders1 = [Vector::<3>::zeros(); 3];
+            cylinder.eval_diff_all(0.0, v, 2, 0, &mut ders0);
+            cylinder.eval_diff_all(8.0, v, 2, 0, &mut ders1);
+            for k in 0..3
+            {
+                assert_relative_eq!(ders0[k], ders1[k], epsilon = 1e-12);
+            }
+        }
+
+        // on either side of the seam the surface closes up smoothly
+        let eps = 1e-7;
+        let before = cylinder.eval(8.0 - eps, 0.5);
+        let after = cylinder.eval(eps, 0.5);
+        let tangent = cylinder.eval_diff_u(0.0, 0.5, 1);
+        assert_relative_eq!(after - before, tangent * 2.0 * eps, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn greville_grid_test()
+    {
+        let (r, s) = (5, 4);
+        let knots_u = vec![0.0, 0.0, 0.0, 0.25, 0.6, 1.0, 1.0, 1.0];
+        let knots_v = vec![0.0, 0.0, 0.0, 0.0, 2.0, 2.0, 2.0, 2.0];
+        let gu = spl::greville(&knots_u, 2);
+        let gv = spl::greville(&knots_v, 3);
+
+        // by linear precision a net placed at its Greville abscissae reproduces them on the grid
+        let cpoints: Vec<Vector<3>> = (0..r * s)
+            .map(|idx| Vector::<3>::new(gu[idx % r], gv[idx / r], 1.0 + 2.0 * gu[idx % r] - gv[idx / r]))
+            .collect();
+        let flat = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 3,
+            knots_u: knots_u.clone(),
+            knots_v: knots_v.clone(),
+            cpoints: cpoints.clone(),
+            cweights: vec![1.0; r * s],
+        });
+        let (grid, dims) = flat.greville_grid();
+        assert_eq!(dims, (r, s));
+        assert_eq!(grid.len(), r * s);
+        for (point, cpoint) in grid.iter().zip(cpoints.iter())
+        {
+            assert_relative_eq!(point, cpoint, epsilon = 1e-14);
+        }
+
+        // for a general rational surface the grid points lie on the surface
+        let cpoints: Vec<Vector<3>> = (0..r * s)
+            .map(|idx| Vector::<3>::new((idx % r) as f64, (idx / r) as f64, ((idx * 7) % 5) as f64))
+            .collect();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 3,
+            knots_u,
+            knots_v,
+            cpoints,
+            cweights: (0..r * s).map(|idx| 1.0 + 0.5 * (idx % 3) as f64).collect(),
+        });
+        let (grid, dims) = bsurf.greville_grid();
+        assert_eq!(dims, bsurf.net_dims());
+        for j in 0..s
+        {
+            for i in 0..r
+            {
+                assert_relative_eq!(grid[i + j * r], bsurf.eval(gu[i], gv[j]), epsilon = 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn control_net_accessors_test()
+    {
+        let (r, s) = (4, 3);
+        let cpoints: Vec<Vector<3>> = (0..r * s)
+            .map(|idx| Vector::<3>::new((idx % r) as f64, (idx / r) as f64, 0.3 * idx as f64))
+            .collect();
+        let cweights: Vec<f64> = (0..r * s).map(|idx| 1.0 + 0.25 * (idx % 4) as f64).collect();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 2,
+            knots_u: vec![0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: cpoints.clone(),
+            cweights: cweights.clone(),
+        });
+
+        assert_eq!(bsurf.net_dims(), (r, s));
+        assert_eq!(bsurf.cpoints_w().len(), r * s);
+        for (idx, (point, weight)) in bsurf.cpoints_weighted().into_iter().enumerate()
+        {
+            assert_relative_eq!(point, cpoints[idx], epsilon = 1e-14);
+            assert_eq!(weight, cweights[idx]);
+            assert_eq!(point, bsurf.cpoints()[idx]);
+        }
+
+        let (points, weights): (Vec<Vector<3>>, Vec<f64>) = bsurf.cpoints_weighted().into_iter().unzip();
+        let rebuilt = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: bsurf.p(),
+            q: bsurf.q(),
+            knots_u: bsurf.knots_u().to_vec(),
+            knots_v: bsurf.knots_v().to_vec(),
+            cpoints: points,
+            cweights: weights,
+        });
+        assert_eq!(rebuilt.net_dims(), bsurf.net_dims());
+        for (pw1, pw2) in rebuilt.cpoints_w().iter().zip(bsurf.cpoints_w().iter())
+        {
+            assert_relative_eq!(pw1, pw2, epsilon = 1e-14);
+        }
+        for (u, v) in [(0.0, 0.0), (0.3, 0.7), (0.5, 0.5), (1.0, 0.2)]
+        {
+            assert_relative_eq!(rebuilt.eval(u, v), bsurf.eval(u, v), epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn iso_curves_test()
+    {
+        let (r, s) = (4, 5);
+        let cpoints: Vec<Vector<3>> = (0..r * s)
+            .map(|idx| {
+                let i = (idx % r) as f64;
+                let j = (idx / r) as f64;
+                Vector::<3>::new(i, j, (0.7 * i).sin() * (0.4 * j + 0.3).cos())
+            })
+            .collect();
+        let cweights: Vec<f64> = (0..r * s).map(|idx| 1.0 + 0.1 * (idx % 3) as f64).collect();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 3,
+            knots_u: vec![0.0, 0.0, 0.0, 0.4, 1.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights,
+        });
+
+        for c in [0.0, 0.25, 0.4, 0.9, 1.0]
+        {
+            let iso_u = bsurf.iso_u(c);
+            let iso_v = bsurf.iso_v(c);
+            assert_eq!(iso_u.p(), 3);
+            assert_eq!(iso_v.p(), 2);
+            for k in 0..11
+            {
+                let t = k as f64 / 10.0;
+                assert_relative_eq!(iso_u.eval(t), bsurf.eval(c, t), epsilon = 1e-12);
+                assert_relative_eq!(iso_v.eval(t), bsurf.eval(t, c), epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn boundary_curves_test()
+    {
+        let (r, s) = (4, 5);
+        let cpoints: Vec<Vector<3>> = (0..r * s)
+            .map(|idx| {
+                let i = (idx % r) as f64;
+                let j = (idx / r) as f64;
+                Vector::<3>::new(i, j, (0.7 * i).sin() * (0.4 * j + 0.3).cos())
+            })
+            .collect();
+        let cweights: Vec<f64> = (0..r * s).map(|idx| 1.0 + 0.1 * (idx % 3) as f64).collect();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 2,
+            q: 3,
+            knots_u: vec![1.0, 1.0, 1.0, 1.4, 2.0, 2.0, 2.0],
+            knots_v: vec![-1.0, -1.0, -1.0, -1.0, 0.5, 2.0, 2.0, 2.0, 2.0],
+            cpoints: cpoints.clone(),
+            cweights,
+        });
+
+        // the corners of a clamped surface are the corners of its control net
+        let corners: Vec<Vector<3>> = (0..4).map(|i| bsurf.corner(i)).collect();
+        for (i, idx) in [0, r - 1, r * (s - 1), r * s - 1].iter().enumerate()
+        {
+            assert_relative_eq!(corners[i], cpoints[*idx], epsilon = 1e-12);
+        }
+
+        // and each boundary curve runs between the two corners on its side
+        let ends = [(0, 2), (1, 3), (0, 1), (2, 3)];
+        for (curve, (start, end)) in bsurf.boundary_curves().iter().zip(ends.iter())
+        {
+            let (t0, t1) = curve.param_range();
+            assert_relative_eq!(curve.eval(t0), corners[*start], epsilon = 1e-12);
+            assert_relative_eq!(curve.eval(t1), corners[*end], epsilon = 1e-12);
+        }
     }
 
-    #[derive(Deserialize)]
-    struct KnotData
+    #[test]
+    fn is_degenerate_at_test()
     {
-        description: String,
-        values: Vec<f64>,
+        // the octant of the unit sphere between the equator and the north pole, on u in [0, 0.25]
+        // and v in [0.5, 1]
+        let bsurf = sphere_patch(&Vec3::zeros(), 1.0).split_u(0.25).0.split_v(0.5).1;
+        let at = |s: f64, t: f64| (0.25 * s, 0.5 + 0.5 * t);
+
+        // the isocurve at the pole collapses, elsewhere the surface is regular
+        for s in [0.0, 0.3, 1.0]
+        {
+            let (u, v) = at(s, 1.0);
+            assert!(bsurf.is_degenerate_at(u, v, 1e-9));
+            let (u, v) = at(s, 0.0);
+            assert!(!bsurf.is_degenerate_at(u, v, 1e-9));
+            let (u, v) = at(s, 0.99);
+            assert!(!bsurf.is_degenerate_at(u, v, 1e-9));
+        }
+        let (u, v) = at(0.5, 0.5);
+        assert!(!bsurf.is_degenerate_at(u, v, 1e-9));
+        let (u, v) = at(0.3, 1.0);
+        assert_relative_eq!(bsurf.eval(u, v), Vector::<3>::new(0.0, 0.0, 1.0), epsilon = 1e-12);
     }
 
-    #[derive(Deserialize)]
-    struct WeightData
+    #[test]
+    fn curvature_field_test()
     {
-        description: String,
-        values: Vec<f64>,
+        // the octant of the sphere of radius 2 between the equator and the north pole, whose
+        // normal points outward as the net is laid out
+        let radius = 2.0;
+        let bsurf = sphere_patch(&Vec3::zeros(), radius).split_u(0.25).0.split_v(0.5).1;
+        assert!(bsurf.eval_normal(0.125, 0.75, false).dot(&bsurf.eval(0.125, 0.75)) > 0.0);
+
+        let (nu, nv) = (8, 6);
+        let gauss = bsurf.curvature_field(nu, nv, CurvatureKind::Gauss);
+        let mean = bsurf.curvature_field(nu, nv, CurvatureKind::Mean);
+        let max_principal = bsurf.curvature_field(nu, nv, CurvatureKind::MaxPrincipal);
+        assert_eq!(gauss.dims(), &[nu + 1, nv + 1]);
+        for i in 0..nu + 1
+        {
+            for j in 0..nv
+            {
+                assert_relative_eq!(gauss[&[i, j]], 1.0 / (radius * radius), epsilon = 1e-9);
+                assert_relative_eq!(mean[&[i, j]], -1.0 / radius, epsilon = 1e-9);
+                assert_relative_eq!(max_principal[&[i, j]], -1.0 / radius, epsilon = 1e-6);
+            }
+
+            // the isocurve at the pole collapses, there the curvature is undefined
+            assert!(gauss[&[i, nv]].is_nan());
+            assert!(mean[&[i, nv]].is_nan());
+            assert!(max_principal[&[i, nv]].is_nan());
+        }
+
+        // the sign of the mean curvature follows the normal
+        let mut flipped = bsurf.clone();
+        flipped.reverse_u();
+        assert_relative_eq!(flipped.eval_mean_curvature(0.075, 0.7), 1.0 / radius, epsilon = 1e-9);
+        assert_relative_eq!(flipped.eval_gauss_curvature(0.075, 0.7), 1.0 / (radius * radius), epsilon = 1e-9);
     }
 
-    #[derive(Deserialize)]
-    struct CpointData
+    #[test]
+    fn blend_surface_test()
     {
-        description: String,
-        values: Vec<Vec<f64>>,
+        let make_curve = |p: usize, knots: Vec<f64>, cpoints: Vec<Vector<3>>| {
+            let n = cpoints.len();
+            Bcurve::new(&BcurveDescriptor { p, knots, cpoints, cweights: vec![1.0; n] })
+        };
+        // edges of different degree and knots, with cross tangents leaving one edge upwards and
+        // arriving at the other sideways
+        let edge_a = make_curve(3, vec![0.0, 0.0, 0.0, 0.0, 0.4, 1.0, 1.0, 1.0, 1.0], vec![
+            Vector::<3>::new(0.0, 0.0, 0.0),
+            Vector::<3>::new(1.0, 0.2, 0.0),
+            Vector::<3>::new(2.0, -0.1, 0.0),
+            Vector::<3>::new(3.0, 0.3, 0.0),
+            Vector::<3>::new(4.0, 0.0, 0.0),
+        ]);
+        let cross_a = make_curve(1, vec![0.0, 0.0, 1.0, 1.0], vec![
+            Vector::<3>::new(0.0, 0.0, 2.0),
+            Vector::<3>::new(0.0, 0.5, 3.0),
+        ]);
+        let edge_b = make_curve(2, vec![0.0, 0.0, 0.0, 0.7, 1.0, 1.0, 1.0], vec![
+            Vector::<3>::new(0.0, 2.0, 2.0),
+            Vector::<3>::new(1.5, 2.2, 2.1),
+            Vector::<3>::new(2.5, 1.9, 1.8),
+            Vector::<3>::new(4.0, 2.0, 2.0),
+        ]);
+        let cross_b = make_curve(2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0], vec![
+            Vector::<3>::new(0.0, 2.0, 0.0),
+            Vector::<3>::new(0.2, 2.5, 0.1),
+            Vector::<3>::new(0.0, 1.5, 0.0),
+        ]);
+
+        let bsurf = Bsurface::<3>::blend_surface(&edge_a, &cross_a, &edge_b, &cross_b);
+        assert_eq!(bsurf.q(), 3);
+        assert_eq!((bsurf.param_range_u(), bsurf.param_range_v()), ((0.0, 1.0), (0.0, 1.0)));
+        for i in 0..=50
+        {
+            let u = i as f64 / 50.0;
+            assert_relative_eq!(bsurf.eval(u, 0.0), edge_a.eval(u), epsilon = 1e-10);
+            assert_relative_eq!(bsurf.eval(u, 1.0), edge_b.eval(u), epsilon = 1e-10);
+            assert_relative_eq!(bsurf.eval_diff_v(u, 0.0, 1), cross_a.eval(u), epsilon = 1e-10);
+            assert_relative_eq!(bsurf.eval_diff_v(u, 1.0, 1), cross_b.eval(u), epsilon = 1e-10);
+        }
     }
 
-    #[derive(Deserialize)]
-    struct PointData
+    #[test]
+    fn coons_patch_test()
     {
-        description: String,
-        values: Vec<Vec<f64>>,
+        let make_curve = |p: usize, knots: Vec<f64>, cpoints: Vec<Vector<3>>| {
+            let n = cpoints.len();
+            Bcurve::new(&BcurveDescriptor { p, knots, cpoints, cweights: vec![1.0; n] })
+        };
+        let (s00, s10) = (Vector::<3>::new(0.0, 0.0, 0.0), Vector::<3>::new(3.0, 0.0, 0.5));
+        let (s01, s11) = (Vector::<3>::new(0.0, 2.0, 0.0), Vector::<3>::new(3.0, 2.0, 1.0));
+
+        // a loop of curves of different degrees and knots, one on another parameter range
+        let c_v0 = make_curve(3, vec![0.0, 0.0, 0.0, 0.0, 0.3, 1.0, 1.0, 1.0, 1.0], vec![
+            s00,
+            Vector::<3>::new(1.0, -0.5, 0.2),
+            Vector::<3>::new(1.5, 0.3, 0.8),
+            Vector::<3>::new(2.2, -0.2, 0.1),
+            s10,
+        ]);
+        let c_v1 = make_curve(2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0], vec![
+            s01,
+            Vector::<3>::new(1.5, 2.8, -0.5),
+            s11,
+        ]);
+        let c_u0 = make_curve(1, vec![0.0, 0.0, 1.0, 1.0], vec![s00, s01]);
+        let c_u1 = make_curve(2, vec![1.0, 1.0, 1.0, 1.6, 3.0, 3.0, 3.0], vec![
+            s10,
+            Vector::<3>::new(3.4, 0.6, 0.7),
+            Vector::<3>::new(2.7, 1.4, 1.2),
+            s11,
+        ]);
+
+        let bsurf = Bsurface::<3>::coons_patch(&c_u0, &c_u1, &c_v0, &c_v1).unwrap();
+        for i in 0..=50
+        {
+            let t = i as f64 / 50.0;
+            assert_relative_eq!(bsurf.eval(t, 0.0), c_v0.eval(t), epsilon = 1e-10);
+            assert_relative_eq!(bsurf.eval(t, 1.0), c_v1.eval(t), epsilon = 1e-10);
+            assert_relative_eq!(bsurf.eval(0.0, t), c_u0.eval(t), epsilon = 1e-10);
+            assert_relative_eq!(bsurf.eval(1.0, t), c_u1.eval(1.0 + 2.0 * t), epsilon = 1e-10);
+        }
+
+        // the curves must meet at the corners and be non-rational
+        let mut open = c_v1.clone();
+        open.set_cpoint(2, &Vector::<3>::new(3.0, 2.0, 1.1));
+        assert!(matches!(
+            Bsurface::<3>::coons_patch(&c_u0, &c_u1, &c_v0, &open),
+            Err(ModellerError::InvalidInput(_))
+        ));
+        let mut rational = c_v1.clone();
+        rational.set_weight(1, 0.5);
+        assert!(matches!(
+            Bsurface::<3>::coons_patch(&c_u0, &c_u1, &c_v0, &rational),
+            Err(ModellerError::InvalidInput(_))
+        ));
     }
 
-    #[derive(Deserialize)]
-    struct DerData
+    #[test]
+    fn set_normal_convention_test()
     {
-        description: String,
-        values: Vec<Vec<f64>>,
+        // a bicubic dome over the unit square, whose normal points down as the net is laid out
+        let cpoints: Vec<Vector<3>> = (0..16)
+            .map(|idx| {
+                let (i, j) = ((idx / 4) as f64, (idx % 4) as f64);
+                let z = if (1..3).contains(&(idx % 4)) && (1..3).contains(&(idx / 4)) { 1.0 } else { 0.0 };
+                Vector::<3>::new(i / 3.0, j / 3.0, z)
+            })
+            .collect();
+        let knots = vec![0.0, 0.0, 0.0, 0.0, 2.0, 2.0, 2.0, 2.0];
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 3,
+            q: 3,
+            knots_u: knots.clone(),
+            knots_v: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            cpoints,
+            cweights: (0..16).map(|idx| 1.0 + 0.1 * (idx % 3) as f64).collect(),
+        });
+        let up = Vec3::new(0.0, 0.1, 1.0);
+        assert!(bsurf.eval_normal(1.0, 0.5, false).dot(&up) < 0.0);
+
+        let mut oriented = bsurf.clone();
+        oriented.set_normal_convention(&up);
+        assert!(oriented.eval_normal(1.0, 0.5, false).dot(&up) > 0.0);
+        assert_eq!(oriented.param_range_u(), (0.0, 2.0));
+
+        // the geometry is unchanged, with the u direction reversed
+        for (u, v) in [(0.0, 0.0), (0.3, 0.7), (1.2, 0.1), (2.0, 1.0)]
+        {
+            assert_relative_eq!(oriented.eval(2.0 - u, v), bsurf.eval(u, v), epsilon = 1e-13);
+        }
+
+        // a surface already oriented outward is left as it is
+        let before = oriented.cpoints_w().to_vec();
+        oriented.set_normal_convention(&up);
+        assert_eq!(oriented.cpoints_w(), &before[..]);
     }
 
-    #[derive(Deserialize)]
-    struct TestData
+    /// Measures the maximum distance between the surface and a tessellation, sampled at the 
+    /// parameter-space centroid of each triangle.
+    fn tessellation_error(bsurf: &Bsurface<3>, mesh: &TriMesh<3>) -> f64
     {
-        uv: ParamData,
-        knotsu_p1: KnotData,
-        knotsu_p2: KnotData,
-        knotsu_p3: KnotData,
-        knotsu_p4: KnotData,
-        knotsu_p5: KnotData,
-        knotsv_q2: KnotData,
-        knotsv_q3: KnotData,
-        knotsv_q4: KnotData,
-        knotsv_q5: KnotData,
-        knotsv_q6: KnotData,
-        weights_p1_q2: WeightData,
-        weights_p2_q3: WeightData,
-        weights_p3_q4: WeightData,
-        weights_p4_q5: WeightData,
-        weights_p5_q6: WeightData,
-        cpoints_d2_p1_q2: CpointData,
-        cpoints_d2_p2_q3: CpointData,
-        cpoints_d2_p3_q4: CpointData,
-        cpoints_d2_p4_q5: CpointData,
-        cpoints_d2_p5_q6: CpointData,
-        cpoints_d3_p1_q2: CpointData,
-        cpoints_d3_p2_q3: CpointData,
-        cpoints_d3_p3_q4: CpointData,
-        cpoints_d3_p4_q5: CpointData,
-        cpoints_d3_p5_q6: CpointData,
-        points_d2_p1_q2: PointData,
-        points_d2_p2_q3: PointData,
-        points_d2_p3_q4: PointData,
-        points_d2_p4_q5: PointData,
-        points_d2_p5_q6: PointData,
-        points_d3_p1_q2: PointData,
-        points_d3_p2_q3: PointData,
-        points_d3_p3_q4: PointData,
-        points_d3_p4_q5: PointData,
-        points_d3_p5_q6: PointData,
-        ders_d2_p1_q2: DerData,
-        ders_d2_p2_q3: DerData,
-        ders_d2_p3_q4: DerData,
-        ders_d2_p4_q5: DerData,
-        ders_d2_p5_q6: DerData,
-        ders_d3_p1_q2: DerData,
-        ders_d3_p2_q3: DerData,
-        ders_d3_p3_q4: DerData,
-        ders_d3_p4_q5: DerData,
-        ders_d3_p5_q6: DerData,
+        let mut error = 0.0f64;
+        for tri in mesh.triangles()
+        {
+            let mut uv = [0.0; 2];
+            let mut centroid = Vector::<3>::zeros();
+            for k in 0..3
+            {
+                uv[0] += mesh.uvs()[tri[k]][0] / 3.0;
+                uv[1] += mesh.uvs()[tri[k]][1] / 3.0;
+                centroid += mesh.vertices()[tri[k]] / 3.0;
+            }
+            error = error.max((bsurf.eval(uv[0], uv[1]) - centroid).norm());
+        }
+        error
     }
 
-    impl TestData
+    #[test]
+    fn eval_grid_test()
     {
-        pub fn new() -> Self
+        let test_data = TestData::new();
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q: 2,
+            knots_u: test_data.knotsu_p1.values,
+            knots_v: test_data.knotsv_q2.values,
+            cpoints: convert(&test_data.cpoints_d3_p1_q2.values),
+            cweights: test_data.weights_p1_q2.values,
+        });
+        let (u0, u1) = bsurf.param_range_u();
+        let (v0, v1) = bsurf.param_range_v();
+
+        // the parameters need not be ordered or distinct, and include the ends of the domain
+        let us: Vec<f64> = [0.0, 1.0, 0.3, 0.75, 0.3, 0.01].iter().map(|t| u0 + (u1 - u0) * t).collect();
+        let vs: Vec<f64> = [1.0, 0.0, 0.5, 0.999, 0.2].iter().map(|t| v0 + (v1 - v0) * t).collect();
+
+        // nested eval would evaluate the basis in each direction once per grid point, here they
+        // are evaluated once per parameter, yet the points are identical
+        let grid = bsurf.eval_grid(&us, &vs);
+        assert_eq!(grid.dims(), &[us.len(), vs.len()]);
+        for (i, u) in us.iter().enumerate()
         {
-            let json_file =
-                fs::read_to_string("assets/geo/bsurface-tests.json").expect("Unable to read file");
-            serde_json::from_str(&json_file).expect("Could not deserialize")
+            for (j, v) in vs.iter().enumerate()
+            {
+                assert_eq!(grid[&[i, j]], bsurf.eval(*u, *v));
+            }
         }
     }
 
     #[test]
-    fn construction_2d()
+    fn surface_frame_test()
     {
         let test_data = TestData::new();
-        let p = 1;
-        let q = 2;
-        let knotsu = test_data.knotsu_p1.values;
-        let knotsv = test_data.knotsv_q2.values;
-        let cpoints: Vec<Vector<2>> = convert(&test_data.cpoints_d2_p1_q2.values);
-        let cweights = test_data.weights_p1_q2.values;
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q: 2,
+            knots_u: test_data.knotsu_p1.values,
+            knots_v: test_data.knotsv_q2.values,
+            cpoints: convert(&test_data.cpoints_d3_p1_q2.values),
+            cweights: test_data.weights_p1_q2.values,
+        });
+        let (u0, u1) = bsurf.param_range_u();
+        let (v0, v1) = bsurf.param_range_v();
 
-        let descriptor = BsurfaceDescriptor {
-            p: p,
-            q: q,
-            knots_u: knotsu,
-            knots_v: knotsv,
-            cpoints: cpoints,
-            cweights: cweights,
-        };
-        let bsurf = Bsurface::<2>::new(&descriptor);
+        for (s, t) in [(0.0, 0.0), (0.3, 0.7), (0.55, 0.2), (1.0, 1.0)]
+        {
+            let (u, v) = (u0 + (u1 - u0) * s, v0 + (v1 - v0) * t);
+            let frame = bsurf.surface_frame(u, v);
+            let (du, dv) = bsurf.eval_tangent(u, v, false);
+            assert_relative_eq!(frame.point, bsurf.eval(u, v), epsilon = 1e-12);
+            assert_eq!((frame.du, frame.dv), (du, dv));
+            assert_relative_eq!(frame.normal, bsurf.eval_normal(u, v, true), epsilon = 1e-12);
+
+            // the normal is of unit length and orthogonal to both tangents
+            assert_relative_eq!(frame.normal.norm(), 1.0, epsilon = 1e-12);
+            assert!(frame.normal.dot(&frame.du).abs() < 1e-10 * frame.du.norm());
+            assert!(frame.normal.dot(&frame.dv).abs() < 1e-10 * frame.dv.norm());
+        }
     }
 
     #[test]
-    fn construction_3d()
+    fn metric_test()
     {
         let test_data = TestData::new();
-        let p = 1;
-        let q = 2;
-        let knotsu = test_data.knotsu_p1.values;
-        let knotsv = test_data.knotsv_q2.values;
-        let cpoints: Vec<Vector<3>> = convert(&test_data.cpoints_d3_p1_q2.values);
-        let cweights = test_data.weights_p1_q2.values;
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q: 2,
+            knots_u: test_data.knotsu_p1.values,
+            knots_v: test_data.knotsv_q2.values,
+            cpoints: convert(&test_data.cpoints_d3_p1_q2.values),
+            cweights: test_data.weights_p1_q2.values,
+        });
+        let (u0, u1) = bsurf.param_range_u();
+        let (v0, v1) = bsurf.param_range_v();
 
-        let descriptor = BsurfaceDescriptor {
-            p: p,
-            q: q,
-            knots_u: knotsu,
-            knots_v: knotsv,
+        for (s, t) in [(0.1, 0.1), (0.3, 0.7), (0.55, 0.2), (0.9, 0.6)]
+        {
+            let (u, v) = (u0 + (u1 - u0) * s, v0 + (v1 - v0) * t);
+            let jac = bsurf.uv_jacobian(u, v);
+            let metric = bsurf.metric(u, v);
+
+            // the determinant of the first fundamental form is the squared area element
+            let area_sq = jac.column(0).cross(&jac.column(1)).norm_squared();
+            assert_relative_eq!(metric.determinant(), area_sq, max_relative = 1e-10);
+
+            // and a small step in parameter space has the length given by the metric
+            let step = nalgebra::Vector2::new(0.3, -0.7) * 1e-6 * (u1 - u0).min(v1 - v0);
+            let length = (bsurf.eval(u + step[0], v + step[1]) - bsurf.eval(u, v)).norm();
+            assert_relative_eq!(length, (step.transpose() * metric * step)[0].sqrt(), max_relative = 1e-4);
+        }
+    }
+
+    #[test]
+    fn tessellate_adaptive_test()
+    {
+        // a flat 10x10 control net with a single raised control point near one corner
+        let cpoints: Vec<Vector<3>> = (0..100)
+            .map(|idx| {
+                let i = idx % 10;
+                let j = idx / 10;
+                let z = if i == 1 && j == 1 { 2.0 } else { 0.0 };
+                Vector::<3>::new(i as f64, j as f64, z)
+            })
+            .collect();
+        let mut knots = vec![0.0; 4];
+        knots.extend((1..7).map(|i| i as f64 / 7.0));
+        knots.extend(vec![1.0; 4]);
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 3,
+            q: 3,
+            knots_u: knots.clone(),
+            knots_v: knots,
             cpoints: cpoints,
-            cweights: cweights,
-        };
-        let bsurf = Bsurface::<3>::new(&descriptor);
+            cweights: vec![1.0; 100],
+        });
+
+        let adaptive = bsurf.tessellate_adaptive(1e-2);
+        let adaptive_error = tessellation_error(&bsurf, &adaptive);
+
+        // every edge of a crack-free mesh is shared by two triangles or lies on the boundary
+        let mut edge_count: HashMap<(usize, usize), usize> = HashMap::new();
+        for tri in adaptive.triangles()
+        {
+            for k in 0..3
+            {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                *edge_count.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        for ((a, b), count) in edge_count
+        {
+            let (ua, va) = (adaptive.uvs()[a][0], adaptive.uvs()[a][1]);
+            let (ub, vb) = (adaptive.uvs()[b][0], adaptive.uvs()[b][1]);
+            let on_boundary = (ua == ub && (ua == 0.0 || ua == 1.0))
+                || (va == vb && (va == 0.0 || va == 1.0));
+            assert_eq!(count, if on_boundary { 1 } else { 2 });
+        }
+
+        // find the coarsest uniform tessellation with the same error by doubling then bisecting
+        let uniform_error = |n: usize| tessellation_error(&bsurf, &bsurf.tessellate_uniform(n, n));
+        let mut n_hi = 1;
+        while uniform_error(n_hi) > adaptive_error
+        {
+            n_hi *= 2;
+        }
+        let mut n_lo = n_hi / 2;
+        while n_hi - n_lo > 1
+        {
+            let n_mid = (n_lo + n_hi) / 2;
+            if uniform_error(n_mid) > adaptive_error { n_lo = n_mid; } else { n_hi = n_mid; }
+        }
+        let uniform = bsurf.tessellate_uniform(n_hi, n_hi);
+        assert!(adaptive.num_triangles() < uniform.num_triangles());
     }
 
     macro_rules! eval {
@@ -591,137 +2744,150 @@ mod tests
                 let bsurf = Bsurface::<$dim>::new(&descriptor);
 
                 let ders = test_data.$ders.values;
+                let num_ders = (max_deriv + 1) * (max_deriv + 1);
 
                 for (idx, uv) in test_data.uv.values.iter().enumerate()
                 {
                     let u = uv[0];
                     let v = uv[1];
 
-                    let start = (max_deriv * max_deriv) * idx;
-                    let end =  (max_deriv * max_deriv) * (idx+1);
+                    let start = num_ders * idx;
+                    let end =  num_ders * (idx+1);
                     let ders_all_1 = ders[start..end].to_vec();
 
-                    let mut point2 = Vector::<$dim>::zeros();
-                    bsurf.eval(u, v, point2.as_mut_slice());
+                    let mut ders_all_2 = vec![Vector::<$dim>::zeros(); num_ders];
+                    bsurf.eval_diff_all(u, v, max_deriv, max_deriv, &mut ders_all_2);
 
-                    // for i in 0..d
-                    // {
-                    //     assert_relative_eq!(point1[i], point2[i], epsilon = 1e-10);
-                    // }
+                    // round-off in the quotient rule scales with the largest derivative
+                    let scale_all = ders_all_1.iter().flatten().fold(1.0f64, |acc, x| acc.max(x.abs()));
+                    for k in 0..num_ders
+                    {
+                        // geomdl only computes the derivatives of total order k + l <= max_deriv,
+                        // the reference data holds zeros in place of the rest
+                        if k % (max_deriv + 1) + k / (max_deriv + 1) > max_deriv
+                        {
+                            continue;
+                        }
+                        let scale = ders_all_1[k].iter().fold(1.0f64, |acc, x| acc.max(x.abs()));
+                        for i in 0..d
+                        {
+                            assert_abs_diff_eq!(ders_all_1[k][i], ders_all_2[k][i], epsilon = 1e-9 * scale + 1e-10 * scale_all);
+                        }
+                    }
                 }
             }
          };
     }
 
-    // eval_diff!(
-    //     eval_diff_d2_p1_q2, 
-    //     knotsu_p1, 
-    //     knotsv_q2,
-    //     weights_p1_q2,
-    //     cpoints_d2_p1_q2,
-    //     points_d2_p1_q2,
-    //     2, 
-    //     1, 
-    //     2
-    //  );
-    // eval_diff!(
-    //     eval_diff_d2_p2_q3, 
-    //     knotsu_p2, 
-    //     knotsv_q3,
-    //     weights_p2_q3,
-    //     cpoints_d2_p2_q3,
-    //     points_d2_p2_q3,
-    //     2, 
-    //     2, 
-    //     3
-    //  );
-    // eval_diff!(
-    //     eval_diff_d2_p3_q4, 
-    //     knotsu_p3, 
-    //     knotsv_q4,
-    //     weights_p3_q4,
-    //     cpoints_d2_p3_q4,
-    //     points_d2_p3_q4,
-    //     2, 
-    //     3, 
-    //     4
-    //  );
-    // eval_diff!(
-    //     eval_diff_d2_p4_q5, 
-    //     knotsu_p4, 
-    //     knotsv_q5,
-    //     weights_p4_q5,
-    //     cpoints_d2_p4_q5,
-    //     points_d2_p4_q5,
-    //     2, 
-    //     4, 
-    //     5
-    //  );
-    // eval_diff!(
-    //     eval_diff_d2_p5_q6, 
-    //     knotsu_p5, 
-    //     knotsv_q6,
-    //     weights_p5_q6,
-    //     cpoints_d2_p5_q6,
-    //     points_d2_p5_q6,
-    //     2, 
-    //     5, 
-    //     6
-    //  );
-    // eval_diff!(
-    //     eval_diff_d3_p1_q2, 
-    //     knotsu_p1, 
-    //     knotsv_q2,
-    //     weights_p1_q2,
-    //     cpoints_d3_p1_q2,
-    //     points_d3_p1_q2,
-    //     3, 
-    //     1, 
-    //     2
-    //  );
-    // eval_diff!(
-    //     eval_diff_d3_p2_q3, 
-    //     knotsu_p2, 
-    //     knotsv_q3,
-    //     weights_p2_q3,
-    //     cpoints_d3_p2_q3,
-    //     points_d3_p2_q3,
-    //     3, 
-    //     2, 
-    //     3
-    //  );
-    // eval_diff!(
-    //     eval_diff_d3_p3_q4, 
-    //     knotsu_p3, 
-    //     knotsv_q4,
-    //     weights_p3_q4,
-    //     cpoints_d3_p3_q4,
-    //     points_d3_p3_q4,
-    //     3, 
-    //     3, 
-    //     4
-    //  );
-    // eval_diff!(
-    //     eval_diff_d3_p4_q5, 
-    //     knotsu_p4, 
-    //     knotsv_q5,
-    //     weights_p4_q5,
-    //     cpoints_d3_p4_q5,
-    //     points_d3_p4_q5,
-    //     3, 
-    //     4, 
-    //     5
-    //  );
-    // eval_diff!(
-    //     eval_diff_d3_p5_q6, 
-    //     knotsu_p5, 
-    //     knotsv_q6,
-    //     weights_p5_q6,
-    //     cpoints_d3_p5_q6,
-    //     points_d3_p5_q6,
-    //     3, 
-    //     5, 
-    //     6
-    //  );
+    eval_diff!(
+        eval_diff_d2_p1_q2, 
+        knotsu_p1, 
+        knotsv_q2,
+        weights_p1_q2,
+        cpoints_d2_p1_q2,
+        ders_d2_p1_q2,
+        2, 
+        1, 
+        2
+     );
+    eval_diff!(
+        eval_diff_d2_p2_q3, 
+        knotsu_p2, 
+        knotsv_q3,
+        weights_p2_q3,
+        cpoints_d2_p2_q3,
+        ders_d2_p2_q3,
+        2, 
+        2, 
+        3
+     );
+    eval_diff!(
+        eval_diff_d2_p3_q4, 
+        knotsu_p3, 
+        knotsv_q4,
+        weights_p3_q4,
+        cpoints_d2_p3_q4,
+        ders_d2_p3_q4,
+        2, 
+        3, 
+        4
+     );
+    eval_diff!(
+        eval_diff_d2_p4_q5, 
+        knotsu_p4, 
+        knotsv_q5,
+        weights_p4_q5,
+        cpoints_d2_p4_q5,
+        ders_d2_p4_q5,
+        2, 
+        4, 
+        5
+     );
+    eval_diff!(
+        eval_diff_d2_p5_q6, 
+        knotsu_p5, 
+        knotsv_q6,
+        weights_p5_q6,
+        cpoints_d2_p5_q6,
+        ders_d2_p5_q6,
+        2, 
+        5, 
+        6
+     );
+    eval_diff!(
+        eval_diff_d3_p1_q2, 
+        knotsu_p1, 
+        knotsv_q2,
+        weights_p1_q2,
+        cpoints_d3_p1_q2,
+        ders_d3_p1_q2,
+        3, 
+        1, 
+        2
+     );
+    eval_diff!(
+        eval_diff_d3_p2_q3, 
+        knotsu_p2, 
+        knotsv_q3,
+        weights_p2_q3,
+        cpoints_d3_p2_q3,
+        ders_d3_p2_q3,
+        3, 
+        2, 
+        3
+     );
+    eval_diff!(
+        eval_diff_d3_p3_q4, 
+        knotsu_p3, 
+        knotsv_q4,
+        weights_p3_q4,
+        cpoints_d3_p3_q4,
+        ders_d3_p3_q4,
+        3, 
+        3, 
+        4
+     );
+    eval_diff!(
+        eval_diff_d3_p4_q5, 
+        knotsu_p4, 
+        knotsv_q5,
+        weights_p4_q5,
+        cpoints_d3_p4_q5,
+        ders_d3_p4_q5,
+        3, 
+        4, 
+        5
+     );
+    eval_diff!(
+        eval_diff_d3_p5_q6, 
+        knotsu_p5, 
+        knotsv_q6,
+        weights_p5_q6,
+        cpoints_d3_p5_q6,
+        ders_d3_p5_q6,
+        3, 
+        5, 
+        6
+     );
     //.............................................................................................
 }
@@ -0,0 +1,791 @@
+//! This module contains the intersection of pairs of B-spline surfaces, and of B-spline surfaces
+//! with rays.
+//!
+//! Intersection curves are traced by marching. Starting points on each branch of the intersection
+//! are found by recursively subdividing both surfaces, each branch is then followed by stepping
+//! along the tangent of the intersection and correcting back onto both surfaces with Newton's
+//! method. Finally the traced points of each branch are interpolated by a B-spline curve.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use super::bsurface::Bsurface;
+use crate::common::{ResConstants, Vec3};
+use crate::geometry::common::Surface;
+use crate::geometry::curve::bcurve::Bcurve;
+use crate::mesh::intersect_triangle;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+use nalgebra as na;
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Size of the sub-patches, relative to the smaller surface, at which subdivision stops
+const SSI_LEAF_RATIO: f64 = 1.0 / 16.0;
+/// Maximum subdivision depth when searching for starting points
+const SSI_DEPTH_MAX: usize = 24;
+/// Maximum marching step, relative to the smaller surface
+const SSI_STEP_RATIO: f64 = 1.0 / 50.0;
+/// Minimum marching step, relative to the maximum step
+const SSI_STEP_MIN_RATIO: f64 = 1e-4;
+/// Maximum angle, in radians, through which the intersection tangent may turn in one step
+const SSI_ANGLE_MAX: f64 = 0.1;
+/// Maximum number of Newton iterations
+const SSI_NEWTON_ITER_MAX: usize = 30;
+/// Maximum number of marching steps in each direction along a branch
+const SSI_STEP_MAX: usize = 100_000;
+/// Degree of the curves fitted through the traced points
+const SSI_FIT_DEGREE: usize = 3;
+/// Number of divisions in each parameter direction of the grid which seeds ray intersections
+const RAY_SEED_DIV: usize = 32;
+//}}}
+//{{{ type: SsiPoint
+/// A point in the joint parameter space $(u_{1}, v_{1}, u_{2}, v_{2})$ of two surfaces.
+type SsiPoint = na::Vector4<f64>;
+//}}}
+//{{{ enum: StepResult
+/// The outcome of correcting a marching step onto the intersection.
+enum StepResult
+{
+    /// The corrected point lies inside the parameter domains
+    Inside(SsiPoint),
+    /// The iteration left the parameter domains at the given point
+    Outside(SsiPoint),
+    /// The iteration did not converge
+    Failed,
+}
+//}}}
+//{{{ impl: Bsurface<3>
+impl Bsurface<3>
+{
+    /// Computes the curves along which this surface intersects `other`.
+    ///
+    /// Starting points are found by subdividing both surfaces until their control boxes are small,
+    /// each pair of overlapping sub-patches is then refined onto the intersection by a
+    /// Gauss-Newton iteration. From each starting point not already on a traced branch the
+    /// intersection is marched in both directions until it leaves the domain of either surface or
+    /// closes on itself. The step length adapts to the turning of the intersection tangent.
+    ///
+    /// Tangential intersections, where the surface normals are parallel, are not traced.
+    ///
+    /// # Arguments
+    /// * `other` - The surface to intersect with.
+    ///
+    /// # Returns
+    /// One cubic curve per branch of the intersection, parameterised on $[0, 1]$. Closed
+    /// branches start and end at the same point.
+    pub fn intersect(&self, other: &Bsurface<3>) -> Vec<Bcurve<3>>
+    {
+        let domain = joint_domain(self, other);
+        let size = self.control_box().diameter().min(other.control_box().diameter());
+        let h_max = size * SSI_STEP_RATIO;
+
+        let seeds = find_seeds(self, other, &domain, size * SSI_LEAF_RATIO);
+
+        let mut branches: Vec<Vec<Vec3>> = Vec::new();
+        for seed in seeds.iter()
+        {
+            let seed_point = self.eval(seed[0], seed[1]);
+            if branches.iter().any(|b| polyline_distance(b, &seed_point) < 0.1 * h_max)
+            {
+                continue;
+            }
+
+            let (forward, closed) = march(self, other, &domain, seed, 1.0, h_max);
+            let mut params = Vec::with_capacity(forward.len() + 2);
+            if closed
+            {
+                params.push(*seed);
+                params.extend(forward);
+                params.push(*seed);
+            }
+            else
+            {
+                let (backward, _) = march(self, other, &domain, seed, -1.0, h_max);
+                params.extend(backward.into_iter().rev());
+                params.push(*seed);
+                params.extend(forward);
+            }
+
+            let points: Vec<Vec3> = params.iter().map(|x| self.eval(x[0], x[1])).collect();
+            let points = remove_close_points(&points, SSI_STEP_MIN_RATIO * h_max);
+            if points.len() >= 2
+            {
+                branches.push(points);
+            }
+        }
+
+        branches
+            .iter()
+            .map(|points| Bcurve::<3>::interpolate(points, SSI_FIT_DEGREE))
+            .collect()
+    }
+
+    /// Computes the first intersection of the ray $\mathbf{o} + t \mathbf{d}, t \geq 0$ with the
+    /// surface.
+    ///
+    /// The ray is first intersected with a uniform tessellation of the surface, each triangle hit
+    /// seeds a Newton iteration on $S(u, v) - \mathbf{o} - t \mathbf{d}$ from the parameters
+    /// interpolated at the hit. Grazing hits which the tessellation misses are not found. The
+    /// tessellation is built on the first query and kept on the surface for later ones.
+    ///
+    /// # Arguments
+    /// * `origin` - The origin of the ray.
+    /// * `dir` - The direction of the ray.
+    ///
+    /// # Returns
+    /// The ray parameter $t$ and the surface parameters $(u, v)$ of the nearest hit, or `None` if
+    /// the ray misses the surface.
+    pub fn intersect_ray(
+        &self,
+        origin: &Vec3,
+        dir: &Vec3,
+    ) -> Option<(f64, f64, f64)>
+    {
+        let domain = [self.param_range_u(), self.param_range_v()];
+        let mesh = self.ray_mesh.get_or_init(|| self.tessellate_uniform(RAY_SEED_DIV, RAY_SEED_DIV));
+        let (verts, uvs) = (mesh.vertices(), mesh.uvs());
+
+        let mut nearest: Option<(f64, f64, f64)> = None;
+        for tri in mesh.triangles()
+        {
+            let Some((t, b, c)) =
+                intersect_triangle(origin, dir, &verts[tri[0]], &verts[tri[1]], &verts[tri[2]])
+            else
+            {
+                continue;
+            };
+            let a = 1.0 - b - c;
+            let mut x = na::Vector3::new(
+                a * uvs[tri[0]][0] + b * uvs[tri[1]][0] + c * uvs[tri[2]][0],
+                a * uvs[tri[0]][1] + b * uvs[tri[1]][1] + c * uvs[tri[2]][1],
+                t,
+            );
+
+            //{{{ com: refine the seed onto the surface by Newton iteration
+            let mut converged = false;
+            for _ in 0..SSI_NEWTON_ITER_MAX
+            {
+                let f = self.eval(x[0], x[1]) - origin - dir * x[2];
+                if f.norm() < f64::RES_LINEAR
+                {
+                    converged = true;
+                    break;
+                }
+                let jac = na::Matrix3::from_columns(&[
+                    self.eval_diff_u(x[0], x[1], 1),
+                    self.eval_diff_v(x[0], x[1], 1),
+                    -dir,
+                ]);
+                match jac.lu().solve(&f)
+                {
+                    Some(dx) => x -= dx,
+                    None => break,
+                }
+                x[0] = x[0].clamp(domain[0].0, domain[0].1);
+                x[1] = x[1].clamp(domain[1].0, domain[1].1);
+            }
+            //}}}
+
+            if converged && x[2] >= 0.0 && nearest.map_or(true, |n| x[2] < n.0)
+            {
+                nearest = Some((x[2], x[0], x[1]));
+            }
+        }
+        nearest
+    }
+}
+//}}}
+//{{{ fun: joint_domain
+/// Returns the parameter ranges of $(u_{1}, v_{1}, u_{2}, v_{2})$.
+fn joint_domain(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+) -> [(f64, f64); 4]
+{
+    [s1.param_range_u(), s1.param_range_v(), s2.param_range_u(), s2.param_range_v()]
+}
+//}}}
+//{{{ fun: clamp
+fn clamp(
+    x: &SsiPoint,
+    domain: &[(f64, f64); 4],
+) -> SsiPoint
+{
+    SsiPoint::from_fn(|i, _| x[i].clamp(domain[i].0, domain[i].1))
+}
+//}}}
+//{{{ fun: is_inside
+fn is_inside(
+    x: &SsiPoint,
+    domain: &[(f64, f64); 4],
+) -> bool
+{
+    (0..4).all(|i| x[i] >= domain[i].0 - f64::RES_LINEAR && x[i] <= domain[i].1 + f64::RES_LINEAR)
+}
+//}}}
+//{{{ fun: eval_pair
+/// Evaluates both surfaces at `x`.
+///
+/// # Returns
+/// A tuple of the point on each surface and the Jacobian of their difference $S_{1} - S_{2}$
+/// with respect to $(u_{1}, v_{1}, u_{2}, v_{2})$.
+fn eval_pair(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    domain: &[(f64, f64); 4],
+    x: &SsiPoint,
+) -> (Vec3, Vec3, na::Matrix3x4<f64>)
+{
+    let x = clamp(x, domain);
+    let p1 = s1.eval(x[0], x[1]);
+    let p2 = s2.eval(x[2], x[3]);
+    let (su1, sv1) = s1.eval_tangent(x[0], x[1], false);
+    let (su2, sv2) = s2.eval_tangent(x[2], x[3], false);
+    let jac = na::Matrix3x4::from_columns(&[su1, sv1, -su2, -sv2]);
+    (p1, p2, jac)
+}
+//}}}
+//{{{ fun: intersection_tangent
+/// Computes the unit tangent of the intersection at `x`, the cross product of the two unit
+/// normals, or `None` if the surfaces are tangent there.
+fn intersection_tangent(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    x: &SsiPoint,
+) -> Option<Vec3>
+{
+    let n1 = s1.eval_normal(x[0], x[1], false);
+    let n2 = s2.eval_normal(x[2], x[3], false);
+    if n1.norm() < f64::RES_LINEAR || n2.norm() < f64::RES_LINEAR
+    {
+        return None;
+    }
+    let t = n1.normalize().cross(&n2.normalize());
+    if t.norm() < f64::RES_ANGULAR
+    {
+        return None;
+    }
+    Some(t.normalize())
+}
+//}}}
+//{{{ fun: param_step
+/// Finds the parameter increments $(du, dv)$ for which $S_{u} du + S_{v} dv$ best approximates
+/// the displacement `d` in the least-squares sense.
+fn param_step(
+    su: &Vec3,
+    sv: &Vec3,
+    d: &Vec3,
+) -> (f64, f64)
+{
+    let (a, b, c) = (su.dot(su), su.dot(sv), sv.dot(sv));
+    let (r1, r2) = (su.dot(d), sv.dot(d));
+    let det = a * c - b * b;
+    if det.abs() < f64::RES_LINEAR * f64::RES_LINEAR
+    {
+        return (0.0, 0.0);
+    }
+    ((c * r1 - b * r2) / det, (a * r2 - b * r1) / det)
+}
+//}}}
+//{{{ fun: refine_seed
+/// Moves `x0` onto the intersection by a minimum-norm Gauss-Newton iteration on $S_{1} - S_{2}$.
+fn refine_seed(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    domain: &[(f64, f64); 4],
+    x0: &SsiPoint,
+) -> Option<SsiPoint>
+{
+    let mut x = *x0;
+    for _ in 0..SSI_NEWTON_ITER_MAX
+    {
+        let (p1, p2, jac) = eval_pair(s1, s2, domain, &x);
+        let r = p1 - p2;
+        if r.norm() < f64::RES_LINEAR
+        {
+            return Some(x);
+        }
+        let y = (jac * jac.transpose()).lu().solve(&r)?;
+        x = clamp(&(x - jac.transpose() * y), domain);
+    }
+    None
+}
+//}}}
+//{{{ fun: correct
+/// Corrects the predicted point `x0` onto the intersection, constrained to the plane through `q`
+/// normal to the marching direction `t`.
+fn correct(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    domain: &[(f64, f64); 4],
+    x0: &SsiPoint,
+    q: &Vec3,
+    t: &Vec3,
+) -> StepResult
+{
+    let mut x = *x0;
+    for _ in 0..SSI_NEWTON_ITER_MAX
+    {
+        if !is_inside(&x, domain)
+        {
+            return StepResult::Outside(x);
+        }
+        let (p1, p2, jac) = eval_pair(s1, s2, domain, &x);
+        let r = p1 - p2;
+        let f = SsiPoint::new(r[0], r[1], r[2], (p1 - q).dot(t));
+        if f.norm() < f64::RES_LINEAR
+        {
+            return StepResult::Inside(clamp(&x, domain));
+        }
+
+        let mut m = na::Matrix4::<f64>::zeros();
+        m.fixed_view_mut::<3, 4>(0, 0).copy_from(&jac);
+        m[(3, 0)] = jac.column(0).dot(t);
+        m[(3, 1)] = jac.column(1).dot(t);
+        match m.lu().solve(&f)
+        {
+            Some(dx) => x -= dx,
+            None => return StepResult::Failed,
+        }
+    }
+    StepResult::Failed
+}
+//}}}
+//{{{ fun: solve_boundary
+/// Finds the point where the intersection leaves the parameter domains between the inside point
+/// `x_in` and the outside point `x_out`.
+///
+/// The parameter which crosses its boundary first is fixed to that boundary and the remaining
+/// three are found by Newton iteration on $S_{1} - S_{2}$.
+fn solve_boundary(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    domain: &[(f64, f64); 4],
+    x_in: &SsiPoint,
+    x_out: &SsiPoint,
+) -> Option<SsiPoint>
+{
+    let mut t_min = f64::MAX;
+    let mut fixed = 0;
+    let mut bound = 0.0;
+    for i in 0..4
+    {
+        let (lo, hi) = domain[i];
+        let b = if x_out[i] < lo
+        {
+            lo
+        }
+        else if x_out[i] > hi
+        {
+            hi
+        }
+        else
+        {
+            continue;
+        };
+        let t = (b - x_in[i]) / (x_out[i] - x_in[i]);
+        if t < t_min
+        {
+            t_min = t;
+            fixed = i;
+            bound = b;
+        }
+    }
+    if t_min == f64::MAX
+    {
+        return None;
+    }
+
+    let free: Vec<usize> = (0..4).filter(|&i| i != fixed).collect();
+    let mut x = clamp(&(x_in + (x_out - x_in) * t_min), domain);
+    x[fixed] = bound;
+    for _ in 0..SSI_NEWTON_ITER_MAX
+    {
+        let (p1, p2, jac) = eval_pair(s1, s2, domain, &x);
+        let r = p1 - p2;
+        if r.norm() < f64::RES_LINEAR
+        {
+            return Some(x);
+        }
+        let m = na::Matrix3::from_columns(&[
+            jac.column(free[0]).into_owned(),
+            jac.column(free[1]).into_owned(),
+            jac.column(free[2]).into_owned(),
+        ]);
+        let dy = m.lu().solve(&r)?;
+        for k in 0..3
+        {
+            x[free[k]] -= dy[k];
+        }
+        x = clamp(&x, domain);
+    }
+    None
+}
+//}}}
+//{{{ fun: march
+/// Marches along the intersection from `x0` in the direction `sign` times the intersection
+/// tangent.
+///
+/// # Returns
+/// A tuple whose first element is the traced points, excluding `x0`, and whose second element
+/// is true if the branch closed back on `x0`.
+fn march(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    domain: &[(f64, f64); 4],
+    x0: &SsiPoint,
+    sign: f64,
+    h_max: f64,
+) -> (Vec<SsiPoint>, bool)
+{
+    let mut points = Vec::new();
+    let start = s1.eval(x0[0], x0[1]);
+    let h_min = h_max * SSI_STEP_MIN_RATIO;
+    let cos_max = SSI_ANGLE_MAX.cos();
+    let cos_grow = (0.5 * SSI_ANGLE_MAX).cos();
+
+    let mut x = *x0;
+    let mut t = match intersection_tangent(s1, s2, &x)
+    {
+        Some(t) => t * sign,
+        None => return (points, false),
+    };
+    let mut h = h_max;
+
+    for _ in 0..SSI_STEP_MAX
+    {
+        let (p1, _, jac) = eval_pair(s1, s2, domain, &x);
+
+        // the branch has come back round to the starting point
+        let to_start = start - p1;
+        if points.len() >= 3 && to_start.norm() < h && to_start.dot(&t) > 0.0
+        {
+            if to_start.norm() < 0.25 * h
+            {
+                points.pop();
+            }
+            return (points, true);
+        }
+
+        let d = t * h;
+        let (du1, dv1) = param_step(&jac.column(0).into_owned(), &jac.column(1).into_owned(), &d);
+        let (du2, dv2) = param_step(&-jac.column(2).into_owned(), &-jac.column(3).into_owned(), &d);
+        let x_pred = x + SsiPoint::new(du1, dv1, du2, dv2);
+
+        let result = if is_inside(&x_pred, domain)
+        {
+            correct(s1, s2, domain, &x_pred, &(p1 + d), &t)
+        }
+        else
+        {
+            StepResult::Outside(x_pred)
+        };
+
+        match result
+        {
+            StepResult::Failed =>
+            {
+                if h <= h_min
+                {
+                    break;
+                }
+                h *= 0.5;
+            }
+            StepResult::Outside(x_out) =>
+            {
+                // accept the exit point only if it lies ahead within the step, otherwise the
+                // linear estimate of the crossing was poor and the step is shortened
+                if let Some(x_b) = solve_boundary(s1, s2, domain, &x, &x_out)
+                {
+                    let to_exit = s1.eval(x_b[0], x_b[1]) - p1;
+                    if to_exit.norm() <= 2.0 * h && to_exit.dot(&t) >= 0.0
+                    {
+                        points.push(x_b);
+                        return (points, false);
+                    }
+                }
+                if h <= h_min
+                {
+                    break;
+                }
+                h *= 0.5;
+            }
+            StepResult::Inside(x_new) =>
+            {
+                let t_new = match intersection_tangent(s1, s2, &x_new)
+                {
+                    Some(t_new) => t_new * sign,
+                    None =>
+                    {
+                        points.push(x_new);
+                        break;
+                    }
+                };
+                let cos_turn = t_new.dot(&t);
+                if cos_turn < cos_max && h > h_min
+                {
+                    h *= 0.5;
+                    continue;
+                }
+                points.push(x_new);
+                x = x_new;
+                t = t_new;
+                if cos_turn > cos_grow
+                {
+                    h = (2.0 * h).min(h_max);
+                }
+            }
+        }
+    }
+    (points, false)
+}
+//}}}
+//{{{ fun: find_seeds
+/// Finds points on the intersection of the two surfaces by subdividing both until the control
+/// boxes of overlapping sub-patches are smaller than `leaf_size`, and refining the centre of each
+/// such pair onto the intersection.
+fn find_seeds(
+    s1: &Bsurface<3>,
+    s2: &Bsurface<3>,
+    domain: &[(f64, f64); 4],
+    leaf_size: f64,
+) -> Vec<SsiPoint>
+{
+    let mut seeds = Vec::new();
+    let mut stack = vec![(s1.clone(), s2.clone(), 0)];
+
+    while let Some((a, b, depth)) = stack.pop()
+    {
+        let box_a = a.control_box();
+        let box_b = b.control_box();
+        if box_a.distance(&box_b) > f64::RES_LINEAR
+        {
+            continue;
+        }
+
+        let (diam_a, diam_b) = (box_a.diameter(), box_b.diameter());
+        if (diam_a < leaf_size && diam_b < leaf_size) || depth >= SSI_DEPTH_MAX
+        {
+            let (ua, va) = (a.param_range_u(), a.param_range_v());
+            let (ub, vb) = (b.param_range_u(), b.param_range_v());
+            let x0 = SsiPoint::new(
+                0.5 * (ua.0 + ua.1),
+                0.5 * (va.0 + va.1),
+                0.5 * (ub.0 + ub.1),
+                0.5 * (vb.0 + vb.1),
+            );
+            if let Some(x) = refine_seed(s1, s2, domain, &x0)
+            {
+                seeds.push(x);
+            }
+            continue;
+        }
+
+        if diam_a >= diam_b
+        {
+            let (a1, a2) = bisect(&a);
+            stack.push((a1, b.clone(), depth + 1));
+            stack.push((a2, b, depth + 1));
+        }
+        else
+        {
+            let (b1, b2) = bisect(&b);
+            stack.push((a.clone(), b1, depth + 1));
+            stack.push((a, b2, depth + 1));
+        }
+    }
+    seeds
+}
+//}}}
+//{{{ fun: bisect
+/// Splits a surface in half across the parameter direction in which it is longest.
+fn bisect(s: &Bsurface<3>) -> (Bsurface<3>, Bsurface<3>)
+{
+    let (u0, u1) = s.param_range_u();
+    let (v0, v1) = s.param_range_v();
+    let (um, vm) = (0.5 * (u0 + u1), 0.5 * (v0 + v1));
+    let len_u = (s.eval(u1, vm) - s.eval(u0, vm)).norm();
+    let len_v = (s.eval(um, v1) - s.eval(um, v0)).norm();
+    if len_u >= len_v
+    {
+        s.split_u(um)
+    }
+    else
+    {
+        s.split_v(vm)
+    }
+}
+//}}}
+//{{{ fun: polyline_distance
+/// Computes the distance from `p` to the polyline through `points`.
+fn polyline_distance(
+    points: &[Vec3],
+    p: &Vec3,
+) -> f64
+{
+    let mut dist = f64::MAX;
+    for w in points.windows(2)
+    {
+        let seg = w[1] - w[0];
+        let len2 = seg.norm_squared();
+        let t = if len2 > 0.0 { ((p - w[0]).dot(&seg) / len2).clamp(0.0, 1.0) } else { 0.0 };
+        dist = dist.min((w[0] + seg * t - p).norm());
+    }
+    dist
+}
+//}}}
+//{{{ fun: remove_close_points
+/// Removes points closer than `tol` to their predecessor, keeping both end points.
+pub(super) fn remove_close_points(
+    points: &[Vec3],
+    tol: f64,
+) -> Vec<Vec3>
+{
+    let mut out: Vec<Vec3> = Vec::with_capacity(points.len());
+    for (i, p) in points.iter().enumerate()
+    {
+        match out.last()
+        {
+            Some(last) if (p - last).norm() < tol =>
+            {
+                if i == points.len() - 1 && out.len() > 1
+                {
+                    *out.last_mut().unwrap() = *p;
+                }
+            }
+            _ => out.push(*p),
+        }
+    }
+    out
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::geometry::surface::bsurface::BsurfaceDescriptor;
+    use crate::geometry::common::Curve;
+    use approx::assert_relative_eq;
+    use crate::test_utils::sphere_patch;
+
+    fn make_bilinear(cpoints: Vec<Vec3>) -> Bsurface<3>
+    {
+        Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q: 1,
+            knots_u: vec![0.0, 0.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints,
+            cweights: vec![1.0; 4],
+        })
+    }
+
+    #[test]
+    fn intersect_planes()
+    {
+        let plane1 = make_bilinear(vec![
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(2.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+        ]);
+        let plane2 = make_bilinear(vec![
+            Vec3::new(1.0, -0.5, -1.0),
+            Vec3::new(1.0, 0.5, -1.0),
+            Vec3::new(1.0, -0.5, 1.0),
+            Vec3::new(1.0, 0.5, 1.0),
+        ]);
+
+        let curves = plane1.intersect(&plane2);
+        assert_eq!(curves.len(), 1);
+
+        let curve = &curves[0];
+        let (start, end) = (curve.eval(0.0), curve.eval(1.0));
+        assert_relative_eq!(start.y.abs(), 0.5, epsilon = 1e-9);
+        assert_relative_eq!(start.y + end.y, 0.0, epsilon = 1e-9);
+        for i in 0..=100
+        {
+            let x = curve.eval(i as f64 / 100.0);
+            assert_relative_eq!(x.x, 1.0, epsilon = 1e-9);
+            assert_relative_eq!(x.z, 0.0, epsilon = 1e-9);
+            assert!(x.y.abs() <= 0.5 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn intersect_ray_sphere()
+    {
+        let centre = Vec3::new(1.0, 2.0, 3.0);
+        let sphere = sphere_patch(&centre, 1.0);
+
+        // a ray from outside hits the near side of the sphere
+        let origin = centre + Vec3::new(0.3, 0.2, 5.0);
+        let (t, u, v) = sphere.intersect_ray(&origin, &Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        let x = sphere.eval(u, v);
+        assert_relative_eq!((x - centre).norm(), 1.0, epsilon = 1e-9);
+        assert_relative_eq!(x, origin - Vec3::new(0.0, 0.0, t), epsilon = 1e-9);
+        assert!(x.z > centre.z);
+
+        // a ray from the centre hits the sphere at unit distance
+        let (t, _, _) = sphere.intersect_ray(&centre, &Vec3::new(0.0, 0.6, 0.8)).unwrap();
+        assert_relative_eq!(t, 1.0, epsilon = 1e-9);
+
+        assert!(sphere.intersect_ray(&origin, &Vec3::new(0.0, 0.0, 1.0)).is_none());
+        assert!(sphere.intersect_ray(&(centre + Vec3::new(2.0, 0.0, 5.0)), &Vec3::new(0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn intersect_ray_cached_mesh()
+    {
+        let centre = Vec3::new(1.0, 2.0, 3.0);
+        let mut sphere = sphere_patch(&centre, 1.0);
+        let origin = centre + Vec3::new(0.3, 0.2, 5.0);
+        let dir = Vec3::new(0.0, 0.0, -1.0);
+
+        // the seed tessellation is built by the first query and reused by the next
+        assert!(sphere.ray_mesh.get().is_none());
+        let first = sphere.intersect_ray(&origin, &dir).unwrap();
+        let mesh = sphere.ray_mesh.get().unwrap() as *const _;
+        assert_eq!(sphere.intersect_ray(&origin, &dir), Some(first));
+        assert_eq!(sphere.ray_mesh.get().unwrap() as *const _, mesh);
+
+        // reversing u moves the parameters of the tessellation, so it is rebuilt
+        sphere.reverse_u();
+        assert!(sphere.ray_mesh.get().is_none());
+        let (t, u, v) = sphere.intersect_ray(&origin, &dir).unwrap();
+        assert_relative_eq!(t, first.0, epsilon = 1e-9);
+        assert_relative_eq!(sphere.eval(u, v), origin + dir * t, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn intersect_spheres()
+    {
+        let centre1 = Vec3::new(0.0, 0.0, 0.0);
+        let centre2 = Vec3::new(0.0, 1.0, 0.0);
+        let sphere1 = sphere_patch(&centre1, 1.0);
+        let sphere2 = sphere_patch(&centre2, 1.0);
+
+        let curves = sphere1.intersect(&sphere2);
+        assert_eq!(curves.len(), 1);
+
+        // a closed circle of radius sqrt(3)/2 in the plane y = 1/2
+        let curve = &curves[0];
+        assert_relative_eq!(curve.eval(0.0), curve.eval(1.0), epsilon = 1e-9);
+        let axis_point = Vec3::new(0.0, 0.5, 0.0);
+        for i in 0..=200
+        {
+            let x = curve.eval(i as f64 / 200.0);
+            assert_relative_eq!((x - centre1).norm(), 1.0, epsilon = 1e-5);
+            assert_relative_eq!((x - centre2).norm(), 1.0, epsilon = 1e-5);
+            assert_relative_eq!(x.y, 0.5, epsilon = 1e-5);
+            assert_relative_eq!((x - axis_point).norm(), 0.75f64.sqrt(), epsilon = 1e-5);
+        }
+    }
+}
+//}}}
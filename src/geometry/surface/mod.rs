@@ -1,3 +1,5 @@
 
 pub mod plane;
-pub mod bsurface;
\ No newline at end of file
+pub mod bsurface;
+mod intersect;
+mod silhouette;
\ No newline at end of file
@@ -1,8 +1,9 @@
+use crate::boxing::ABox;
 use crate::{common::{
     vec_colinear, vec_orthogonal, vec_unitary, Descriptor, DescriptorError, ResConstants, Vec3,
 }, utilities};
 
-use crate::geometry::{common::Surface, Curve};
+use crate::geometry::{common::{SignedDistance, Surface}, Curve};
 
 pub struct PlaneDescriptor
 {
@@ -60,6 +61,25 @@ impl Plane
         }
     }
 
+    /// Constructs the plane through `point` with unit normal along `normal`.
+    ///
+    /// The in-plane axes are chosen so that $x \times y$ is the normalised `normal`, $x$ is the
+    /// projection onto the plane of the coordinate axis least aligned with the normal.
+    pub fn from_point_normal(point: &Vec3, normal: &Vec3) -> Self {
+        debug_assert!(normal.norm() > f64::RES_LINEAR, "normal has zero length");
+
+        let z = normal.normalize();
+        let k = z.iamin();
+        let mut axis = Vec3::zeros();
+        axis[k] = 1.0;
+        let x = (axis - z * z.dot(&axis)).normalize();
+        Self::new(&PlaneDescriptor {
+            origin: *point,
+            x,
+            y: z.cross(&x),
+        })
+    }
+
     pub fn origin(&self) -> Vec3 {
         self.origin
     }
@@ -71,6 +91,58 @@ impl Plane
     pub fn y(&self) -> Vec3 {
         self.y
     }
+
+    /// Returns the bounding box of the rectangle of the plane over the given parameter ranges.
+    ///
+    /// Since the plane is infinite it can only be boxed over a bounded region, the box is that of
+    /// the four corners of the rectangle.
+    pub fn box_over(&self, u_range: (f64, f64), v_range: (f64, f64)) -> ABox<3> {
+        ABox::<3>::from_points(&[
+            self.eval(u_range.0, v_range.0),
+            self.eval(u_range.1, v_range.0),
+            self.eval(u_range.0, v_range.1),
+            self.eval(u_range.1, v_range.1),
+        ])
+    }
+
+    /// Projects the point `p` orthogonally onto the plane.
+    ///
+    /// # Returns
+    /// The plane parameters $(u, v)$ of the projected point.
+    pub fn project(&self, p: &Vec3) -> (f64, f64) {
+        let rel = p - self.origin;
+        (rel.dot(&self.x), rel.dot(&self.y))
+    }
+
+    /// Finds the point closest to `p` on the rectangle `u_range` by `v_range` of the plane, which 
+    /// is the projection of `p` with its parameters clamped to the rectangle.
+    pub fn closest_point_in_rect(
+        &self,
+        p: &Vec3,
+        u_range: (f64, f64),
+        v_range: (f64, f64),
+    ) -> Vec3 {
+        let (u, v) = self.project(p);
+        self.eval(u.clamp(u_range.0, u_range.1), v.clamp(v_range.0, v_range.1))
+    }
+
+    /// Intersects the ray $\mathbf{o} + t \mathbf{d}, t \geq 0$ with the plane.
+    ///
+    /// # Returns
+    /// The ray parameter $t$ and the plane parameters $(u, v)$ of the hit, or `None` if the ray is
+    /// parallel to the plane or points away from it.
+    pub fn intersect_ray(&self, origin: &Vec3, dir: &Vec3) -> Option<(f64, f64, f64)> {
+        let denom = dir.dot(&self.z);
+        if denom.abs() < f64::RES_LINEAR {
+            return None;
+        }
+        let t = (self.origin - origin).dot(&self.z) / denom;
+        if t < 0.0 {
+            return None;
+        }
+        let (u, v) = self.project(&(origin + dir * t));
+        Some((t, u, v))
+    }
 }
 
 impl Surface for Plane
@@ -188,20 +260,22 @@ impl Surface for Plane
         0.0
     }
 
+    /// A plane is unbounded, so every finite parameter is a member.
     fn is_member_u(
         &self,
         u: f64,
     ) -> bool
     {
-        true
+        u.is_finite()
     }
 
+    /// A plane is unbounded, so every finite parameter is a member.
     fn is_member_v(
         &self,
         v: f64,
     ) -> bool
     {
-        true
+        v.is_finite()
     }
 
     fn dim(&self) -> usize
@@ -226,11 +300,21 @@ impl Surface for Plane
     }
 }
 
+impl SignedDistance<3> for Plane
+{
+    /// The signed distance to the plane, positive on the side to which the normal points.
+    fn sdf(&self, p: &Vec3) -> f64
+    {
+        (p - self.origin).dot(&self.z)
+    }
+}
+
 //-------------------------------------------------------------------------------------------------
 #[cfg(test)]
 mod tests
 {
     use super::*;
+    use crate::common::ModellerError;
 
     #[test]
     fn invalid_descriptor_test1()
@@ -264,4 +348,121 @@ mod tests
         };
         let plane = Plane::new(&pd);
     }
+
+    #[test]
+    fn from_point_normal_test()
+    {
+        let point = Vec3::new(1.0, -2.0, 0.5);
+        for normal in [Vec3::new(0.0, 0.0, 3.0), Vec3::new(1.0, 2.0, -2.0), Vec3::new(-1.0, 0.0, 0.0)]
+        {
+            let plane = Plane::from_point_normal(&point, &normal);
+            assert_eq!(plane.origin(), point);
+            assert!((plane.x().cross(&plane.y()) - normal.normalize()).norm() < 1e-14);
+            assert!(plane.x().dot(&plane.y()).abs() < 1e-14);
+            assert!((plane.x().norm() - 1.0).abs() < 1e-14);
+            assert!(plane.sdf(&(point + normal)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn box_over_test()
+    {
+        // for an axis aligned plane the box corners are the corners of the rectangle
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            x: Vec3::new(0.0, 1.0, 0.0),
+            y: Vec3::new(0.0, 0.0, 1.0),
+        });
+        let abox = plane.box_over((-1.0, 2.0), (0.5, 1.5));
+        let corners = abox.corners();
+        for (u, v) in [(-1.0, 0.5), (2.0, 0.5), (-1.0, 1.5), (2.0, 1.5)]
+        {
+            let x = plane.eval(u, v);
+            assert!(corners.iter().any(|c| (c - x).norm() < 1e-14));
+        }
+
+        // for an inclined plane the box is bounded by the corners in each direction
+        let s = 0.5f64.sqrt();
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(0.0, 0.0, 0.0),
+            x: Vec3::new(s, s, 0.0),
+            y: Vec3::new(0.0, 0.0, 1.0),
+        });
+        let abox = plane.box_over((0.0, 2.0), (-1.0, 1.0));
+        let x_min = plane.eval(0.0, -1.0);
+        let x_max = plane.eval(2.0, 1.0);
+        for i in 0..3
+        {
+            assert!((abox.min(i) - x_min[i]).abs() < 1e-14);
+            assert!((abox.max(i) - x_max[i]).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn plane_sdf_test()
+    {
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            x: Vec3::new(1.0, 0.0, 0.0),
+            y: Vec3::new(0.0, 1.0, 0.0),
+        });
+        assert_eq!(plane.sdf(&Vec3::new(-4.0, 7.0, 5.5)), 2.5);
+        assert_eq!(plane.sdf(&Vec3::new(0.0, 0.0, 1.0)), -2.0);
+        assert_eq!(plane.sdf(&Vec3::new(3.0, -1.0, 3.0)), 0.0);
+    }
+
+    #[test]
+    fn intersect_ray_test()
+    {
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            x: Vec3::new(1.0, 0.0, 0.0),
+            y: Vec3::new(0.0, 1.0, 0.0),
+        });
+        let dir = Vec3::new(1.0, 1.0, -2.0);
+        let (t, u, v) = plane.intersect_ray(&Vec3::new(0.0, 0.0, 7.0), &dir).unwrap();
+        assert!((t - 2.0).abs() < 1e-14);
+        assert!((u - 1.0).abs() < 1e-14 && v.abs() < 1e-14);
+
+        // rays pointing away from or parallel to the plane miss it
+        assert!(plane.intersect_ray(&Vec3::new(0.0, 0.0, 7.0), &-dir).is_none());
+        assert!(plane.intersect_ray(&Vec3::new(0.0, 0.0, 7.0), &Vec3::new(1.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn closest_point_in_rect_test()
+    {
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            x: Vec3::new(0.0, 1.0, 0.0),
+            y: Vec3::new(0.0, 0.0, 1.0),
+        });
+        let (u_range, v_range) = ((-1.0, 2.0), (0.0, 1.0));
+        assert_eq!(plane.project(&Vec3::new(5.0, 2.5, 3.25)), (0.5, 0.25));
+
+        // a point over the rectangle projects into it
+        let x = plane.closest_point_in_rect(&Vec3::new(5.0, 2.5, 3.25), u_range, v_range);
+        assert_eq!(x, Vec3::new(1.0, 2.5, 3.25));
+
+        // beyond one edge the parameter across it is clamped
+        let x = plane.closest_point_in_rect(&Vec3::new(-4.0, 6.0, 3.5), u_range, v_range);
+        assert_eq!(x, plane.eval(2.0, 0.5));
+
+        // beyond a corner both parameters are clamped
+        let x = plane.closest_point_in_rect(&Vec3::new(2.0, -3.0, -1.0), u_range, v_range);
+        assert_eq!(x, plane.eval(-1.0, 0.0));
+    }
+
+    #[test]
+    fn try_eval_test()
+    {
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            x: Vec3::new(0.0, 1.0, 0.0),
+            y: Vec3::new(0.0, 0.0, 1.0),
+        });
+        assert_eq!(plane.try_eval(-3.0, 7.5).unwrap(), plane.eval(-3.0, 7.5));
+        assert!(matches!(plane.try_eval(f64::NAN, 0.0), Err(ModellerError::OutOfDomain(_))));
+        assert!(matches!(plane.try_eval(0.0, f64::INFINITY), Err(ModellerError::OutOfDomain(_))));
+    }
 }
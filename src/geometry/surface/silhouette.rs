@@ -0,0 +1,249 @@
+//! This module contains the silhouette curves of B-spline surfaces.
+//!
+//! The silhouette of a surface seen along a view direction $\mathbf{d}$ is the set of points at
+//! which the surface normal is perpendicular to $\mathbf{d}$, the outline of the surface in a
+//! parallel projection. It is found as the zero set of $g(u, v) = (\mathbf{s}_{u} \times
+//! \mathbf{s}_{v}) \cdot \mathbf{d}$ by marching squares over a uniform parameter grid: the zero
+//! crossings along the grid edges are located by bisection, the crossings are joined across each
+//! grid cell, and the resulting chains of points are interpolated by B-spline curves.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use super::bsurface::Bsurface;
+use super::intersect::remove_close_points;
+use crate::common::Vec3;
+use crate::geometry::common::Surface;
+use crate::geometry::curve::bcurve::Bcurve;
+//}}}
+//{{{ std imports
+use std::collections::{HashMap, HashSet};
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Number of bisection steps locating a zero crossing along a grid edge
+const SIL_BISECT_ITER: usize = 50;
+/// Distance, relative to the size of the surface, below which consecutive points are merged
+const SIL_POINT_MIN_RATIO: f64 = 1e-8;
+/// Degree of the curves fitted through the silhouette points
+const SIL_FIT_DEGREE: usize = 3;
+//}}}
+//{{{ impl: Bsurface<3>
+impl Bsurface<3>
+{
+    /// Computes the silhouette curves of the surface seen along `view_dir`, the curves along which
+    /// the normal of the surface is perpendicular to the view direction.
+    ///
+    /// The parameter domain is divided into a `samples` by `samples` grid, and the silhouette is
+    /// traced through the cells of the grid in which $(\mathbf{s}_{u} \times \mathbf{s}_{v}) \cdot
+    /// \mathbf{d}$ changes sign. Branches of the silhouette which pass through a cell without
+    /// changing the sign at its corners, such as small loops, are not found.
+    ///
+    /// # Arguments
+    /// * `view_dir` - The direction along which the surface is viewed.
+    /// * `samples` - The number of grid divisions in each parameter direction, zero is treated as
+    ///               one.
+    ///
+    /// # Returns
+    /// One cubic curve per branch of the silhouette, parameterised on $[0, 1]$. Open branches end
+    /// on the boundary of the surface, closed branches start and end at the same point.
+    pub fn silhouette(
+        &self,
+        view_dir: &Vec3,
+        samples: usize,
+    ) -> Vec<Bcurve<3>>
+    {
+        let n = samples.max(1);
+        let (u0, u1) = self.param_range_u();
+        let (v0, v1) = self.param_range_v();
+        let us: Vec<f64> = (0..=n).map(|i| u0 + (u1 - u0) * i as f64 / n as f64).collect();
+        let vs: Vec<f64> = (0..=n).map(|j| v0 + (v1 - v0) * j as f64 / n as f64).collect();
+
+        let g = |u: f64, v: f64| self.eval_normal(u, v, false).dot(view_dir);
+        let mut values = vec![0.0; (n + 1) * (n + 1)];
+        for j in 0..=n
+        {
+            for i in 0..=n
+            {
+                values[i + j * (n + 1)] = g(us[i], vs[j]);
+            }
+        }
+        let value = |i: usize, j: usize| values[i + j * (n + 1)];
+
+        //{{{ com: locate the zero crossings along the grid edges
+        // the edges along u are numbered first, then the edges along v
+        let num_edges_u = n * (n + 1);
+        let edge_u = |i: usize, j: usize| i + j * n;
+        let edge_v = |i: usize, j: usize| num_edges_u + i + j * (n + 1);
+
+        let mut crossings: HashMap<usize, (f64, f64)> = HashMap::new();
+        for j in 0..=n
+        {
+            for i in 0..=n
+            {
+                if i < n && (value(i, j) >= 0.0) != (value(i + 1, j) >= 0.0)
+                {
+                    let root = bisect_edge(&g, (us[i], vs[j]), (us[i + 1], vs[j]), value(i, j));
+                    crossings.insert(edge_u(i, j), root);
+                }
+                if j < n && (value(i, j) >= 0.0) != (value(i, j + 1) >= 0.0)
+                {
+                    let root = bisect_edge(&g, (us[i], vs[j]), (us[i], vs[j + 1]), value(i, j));
+                    crossings.insert(edge_v(i, j), root);
+                }
+            }
+        }
+        //}}}
+        //{{{ com: join the crossings across each cell
+        let mut links: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut link = |a: usize, b: usize| {
+            links.entry(a).or_default().push(b);
+            links.entry(b).or_default().push(a);
+        };
+        for j in 0..n
+        {
+            for i in 0..n
+            {
+                // the edges of the cell in order around it, starting from the corner (i, j)
+                let edges = [edge_u(i, j), edge_v(i + 1, j), edge_u(i, j + 1), edge_v(i, j)];
+                let crossed: Vec<usize> = edges.iter().copied().filter(|e| crossings.contains_key(e)).collect();
+                match crossed.len()
+                {
+                    2 => link(crossed[0], crossed[1]),
+                    4 =>
+                    {
+                        // a saddle cell, the sign at the centre decides which pairs of opposite
+                        // corners are connected through it
+                        let centre = g(0.5 * (us[i] + us[i + 1]), 0.5 * (vs[j] + vs[j + 1]));
+                        if (centre >= 0.0) == (value(i, j) >= 0.0)
+                        {
+                            link(edges[0], edges[1]);
+                            link(edges[2], edges[3]);
+                        }
+                        else
+                        {
+                            link(edges[3], edges[0]);
+                            link(edges[1], edges[2]);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        //}}}
+        //{{{ com: follow the chains of linked crossings, open chains first
+        let mut starts: Vec<usize> = links.keys().copied().collect();
+        starts.sort_by_key(|e| (links[e].len(), *e));
+
+        let mut visited: HashSet<usize> = HashSet::new();
+        let mut chains: Vec<Vec<usize>> = Vec::new();
+        for start in starts
+        {
+            if visited.contains(&start)
+            {
+                continue;
+            }
+            let mut chain = vec![start];
+            visited.insert(start);
+            let mut current = start;
+            while let Some(next) = links[&current].iter().find(|e| !visited.contains(*e))
+            {
+                chain.push(*next);
+                visited.insert(*next);
+                current = *next;
+            }
+            if chain.len() > 2 && links[&current].contains(&start)
+            {
+                chain.push(start);
+            }
+            chains.push(chain);
+        }
+        //}}}
+
+        let tol = SIL_POINT_MIN_RATIO * self.control_box().diameter();
+        chains
+            .iter()
+            .map(|chain| {
+                let points: Vec<Vec3> = chain
+                    .iter()
+                    .map(|e| {
+                        let (u, v) = crossings[e];
+                        self.eval(u, v)
+                    })
+                    .collect();
+                remove_close_points(&points, tol)
+            })
+            .filter(|points| points.len() >= 2)
+            .map(|points| Bcurve::<3>::interpolate(&points, SIL_FIT_DEGREE))
+            .collect()
+    }
+}
+//}}}
+//{{{ fun: bisect_edge
+/// Locates the zero of `g` on the parameter space segment from `a` to `b` by bisection, where `ga`
+/// is the value of `g` at `a` and `g` changes sign along the segment.
+fn bisect_edge(
+    g: &impl Fn(f64, f64) -> f64,
+    a: (f64, f64),
+    b: (f64, f64),
+    ga: f64,
+) -> (f64, f64)
+{
+    let at = |t: f64| (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+    let positive = ga >= 0.0;
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..SIL_BISECT_ITER
+    {
+        let mid = 0.5 * (lo + hi);
+        let (u, v) = at(mid);
+        if (g(u, v) >= 0.0) == positive
+        {
+            lo = mid;
+        }
+        else
+        {
+            hi = mid;
+        }
+    }
+    at(0.5 * (lo + hi))
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::geometry::common::Curve;
+    use approx::assert_relative_eq;
+    use crate::test_utils::sphere_patch;
+
+    #[test]
+    fn silhouette_sphere()
+    {
+        // the half of the unit sphere on the side y >= 0
+        let (sphere, _) = sphere_patch(&Vec3::zeros(), 1.0).split_u(0.5);
+        let view_dir = Vec3::new(0.6, 0.0, 0.8);
+
+        let curves = sphere.silhouette(&view_dir, 32);
+        assert_eq!(curves.len(), 1);
+
+        // half of the great circle in the plane normal to the view direction, running between
+        // opposite points on the boundary y = 0
+        let curve = &curves[0];
+        let (start, end) = (curve.eval(0.0), curve.eval(1.0));
+        assert_relative_eq!(start.y, 0.0, epsilon = 1e-9);
+        assert_relative_eq!(start + end, Vec3::zeros(), epsilon = 1e-9);
+        for i in 0..=200
+        {
+            let x = curve.eval(i as f64 / 200.0);
+            assert_relative_eq!(x.norm(), 1.0, epsilon = 1e-4);
+            assert_relative_eq!(x.dot(&view_dir), 0.0, epsilon = 1e-4);
+            assert!(x.y >= -1e-9);
+        }
+    }
+}
+//}}}
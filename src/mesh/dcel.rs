@@ -4,9 +4,11 @@
 //--------------------------------------------------------------------------------------------------
 
 use crate::common::*;
+use crate::boxing::{ABox, Bvh};
 
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::HashMap;
 
 
 const MUID_NULL: usize = usize::MAX;
@@ -163,6 +165,9 @@ pub struct DynMesh<const D: usize>
     vertices: Vec<Vertex<D>>,
     fins: Vec<Fin<D>>,
     faces: Vec<Face<D>>,
+    /// Lazily built hierarchy over the boxes of the bounded faces, with the index into `faces` of 
+    /// each of its items
+    face_bvh: RefCell<Option<(Bvh<D>, Vec<usize>)>>,
 }
 //..................................................................................................
 
@@ -176,9 +181,146 @@ impl<const D: usize> DynMesh<D>
             vertices: Vec::new(),
             fins: Vec::new(),
             faces: Vec::new(),
+            face_bvh: RefCell::new(None),
         }
     }
 
+    /// Builds a mesh from a triangle soup, `triangles` index into `points` and are oriented 
+    /// consistently. Fins shared by two triangles are linked as twins, those on the boundary of the
+    /// soup are left without one.
+    pub fn from_triangles(points: &[Vector<D>], triangles: &[[usize; 3]]) -> Self
+    {
+        let mut mesh = Self::new();
+        let vertices: Vec<Vertex<D>> = points.iter().map(|point| mesh.add_vertex(point)).collect();
+        for triangle in triangles
+        {
+            let loop_vertices: Vec<Vertex<D>> = triangle.iter().map(|i| vertices[*i].clone()).collect();
+            mesh.add_loop_face(&loop_vertices);
+        }
+        for fin in mesh.fins.iter()
+        {
+            let vertex = fin.borrow().vertex.clone().unwrap();
+            vertex.borrow_mut().out_fin.get_or_insert_with(|| fin.clone());
+        }
+        mesh.link_twins();
+        mesh
+    }
+
+    /// Checks the connectivity of the mesh: every fin has a vertex, a face and a next fin, the loop
+    /// of each face closes on itself, twins are mutual and run between the same vertices in 
+    /// opposite directions, and the out fin of each vertex leaves it.
+    ///
+    /// # Returns
+    /// A [ModellerError::TopologyViolation] describing the first inconsistency found.
+    pub fn validate(&self) -> Result<(), ModellerError>
+    {
+        let violation = |msg: String| Err(ModellerError::TopologyViolation(msg));
+        for fin in self.fins.iter()
+        {
+            let fin_ref = fin.borrow();
+            if fin_ref.vertex.is_none() || fin_ref.next.is_none() || fin_ref.face.is_none()
+            {
+                return violation(format!("fin {} is not fully connected", fin_ref.mtag));
+            }
+        }
+        for fin in self.fins.iter()
+        {
+            let Some(twin) = fin.borrow().twin.clone() else { continue };
+            if !twin.borrow().twin.as_ref().is_some_and(|t| Rc::ptr_eq(t, fin))
+            {
+                return violation(format!("twin of fin {} is not mutual", fin.mtag()));
+            }
+            let (from, to) = Self::fin_vertices(fin);
+            if Self::fin_vertices(&twin) != (to, from)
+            {
+                return violation(format!("twin of fin {} joins other vertices", fin.mtag()));
+            }
+        }
+        for face in self.faces.iter()
+        {
+            for start in face.borrow().outer_loops.iter().chain(face.borrow().inner_loops.iter())
+            {
+                let mut fin = start.clone();
+                for _ in 0..=self.fins.len()
+                {
+                    if !fin.borrow().face.as_ref().is_some_and(|f| Rc::ptr_eq(f, face))
+                    {
+                        return violation(format!("fin {} is not on face {}", fin.mtag(), face.mtag()));
+                    }
+                    let next = fin.borrow().next.clone().unwrap();
+                    fin = next;
+                    if Rc::ptr_eq(&fin, start)
+                    {
+                        break;
+                    }
+                }
+                if !Rc::ptr_eq(&fin, start)
+                {
+                    return violation(format!("a loop of face {} does not close", face.mtag()));
+                }
+            }
+        }
+        for vertex in self.vertices.iter()
+        {
+            if let Some(out_fin) = &vertex.borrow().out_fin
+            {
+                if out_fin.borrow().vertex.as_ref().map(|v| v.mtag()) != Some(vertex.mtag())
+                {
+                    return violation(format!("out fin of vertex {} does not leave it", vertex.mtag()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether every fin of the mesh has a twin, so that the mesh has no boundary.
+    pub fn is_watertight(&self) -> bool
+    {
+        self.fins.iter().all(|fin| fin.borrow().twin.is_some())
+    }
+
+    /// Returns the vertices of the mesh in the order in which they were added.
+    pub fn vertices(&self) -> &[Vertex<D>]
+    {
+        &self.vertices
+    }
+
+    /// Returns the faces of the mesh in the order in which they were added, including the 
+    /// unbounded face if there is one.
+    pub fn faces(&self) -> &[Face<D>]
+    {
+        &self.faces
+    }
+
+    /// Returns an iterator over the positions of the vertices, in the order of [DynMesh::vertices].
+    pub fn positions(&self) -> impl Iterator<Item = Vector<D>> + '_
+    {
+        self.vertices.iter().map(Self::vertex_position)
+    }
+
+    /// Returns the position of the vertex `v`.
+    pub fn vertex_position(v: &Vertex<D>) -> Vector<D>
+    {
+        v.borrow().position
+    }
+
+    /// Returns the positions of the vertices and the outer loop of each bounded face as indices
+    /// into them, both in the order in which they were added to the mesh.
+    pub(crate) fn indexed_loops(&self) -> (Vec<Vector<D>>, Vec<Vec<usize>>)
+    {
+        let index: HashMap<usize, usize> = 
+            self.vertices.iter().enumerate().map(|(i, vertex)| (vertex.mtag(), i)).collect();
+        let points: Vec<Vector<D>> = self.positions().collect();
+        let loops: Vec<Vec<usize>> = self
+            .faces
+            .iter()
+            .map(Self::loop_vertices)
+            .filter(|vertices| !vertices.is_empty())
+            .map(|vertices| vertices.iter().map(|vertex| index[&vertex.mtag()]).collect())
+            .collect();
+        (points, loops)
+    }
+
     //...................................
     // Euler Operators
     //...................................
@@ -214,6 +356,7 @@ impl<const D: usize> DynMesh<D>
 
     fn add_fin(&mut self) -> Fin<D>
     {
+        self.invalidate_bvh();
         let new_fin = Fin::create_node();
         new_fin.borrow_mut().mtag = self.get_next_mtag();
         self.fins.push(new_fin.clone());
@@ -222,12 +365,58 @@ impl<const D: usize> DynMesh<D>
 
     fn add_face(&mut self) -> Face<D>
     {
+        self.invalidate_bvh();
         let new_face = Face::create_node();
         new_face.borrow_mut().mtag = self.get_next_mtag();
         self.faces.push(new_face.clone());
         new_face
     }
 
+    /// Creates a face bounded by a single loop of new fins, one leaving each of `vertices` in turn.
+    /// The twins of the fins are not set.
+    fn add_loop_face(&mut self, vertices: &[Vertex<D>]) -> Face<D>
+    {
+        debug_assert!(vertices.len() >= 3, "a loop needs at least three vertices");
+        let face = self.add_face();
+        let fins: Vec<Fin<D>> = vertices.iter().map(|_| self.add_fin()).collect();
+        for (i, fin) in fins.iter().enumerate()
+        {
+            let mut fin_ref = fin.borrow_mut();
+            fin_ref.vertex = Some(vertices[i].clone());
+            fin_ref.next = Some(fins[(i + 1) % fins.len()].clone());
+            fin_ref.face = Some(face.clone());
+        }
+        face.borrow_mut().outer_loops.push(fins[0].clone());
+        face
+    }
+
+    /// Links each fin with the fin running between the same vertices in the opposite direction,
+    /// if there is one.
+    fn link_twins(&mut self)
+    {
+        let mut by_vertices: HashMap<(usize, usize), Fin<D>> = HashMap::new();
+        for fin in self.fins.iter()
+        {
+            by_vertices.insert(Self::fin_vertices(fin), fin.clone());
+        }
+        for fin in self.fins.iter()
+        {
+            let (from, to) = Self::fin_vertices(fin);
+            if let Some(twin) = by_vertices.get(&(to, from))
+            {
+                fin.borrow_mut().twin = Some(twin.clone());
+            }
+        }
+    }
+
+    /// Returns the mtags of the vertices which `fin` leaves and enters.
+    fn fin_vertices(fin: &Fin<D>) -> (usize, usize)
+    {
+        let fin_ref = fin.borrow();
+        let next = fin_ref.next.as_ref().unwrap().borrow();
+        (fin_ref.vertex.as_ref().unwrap().mtag(), next.vertex.as_ref().unwrap().mtag())
+    }
+
     fn get_next_mtag(&mut self) -> usize    
     {
         let next_mtag = self.next_mtag;
@@ -235,8 +424,337 @@ impl<const D: usize> DynMesh<D>
         next_mtag
     }
 
+    //...................................
+    // Spatial queries
+    //...................................
+
+    /// Returns the vertices around the outer loop of `face`, in the direction of the loop, this is
+    /// empty for faces without a loop such as the unbounded face.
+    fn loop_vertices(face: &Face<D>) -> Vec<Vertex<D>>
+    {
+        let mut vertices = Vec::new();
+        let start = match face.borrow().outer_loops.first()
+        {
+            Some(fin) => fin.clone(),
+            None => return vertices,
+        };
+
+        let mut fin = start.clone();
+        loop
+        {
+            let next = {
+                let fin_ref = fin.borrow();
+                vertices.push(fin_ref.vertex.clone().unwrap());
+                fin_ref.next.clone().unwrap()
+            };
+            if Rc::ptr_eq(&next, &start)
+            {
+                break;
+            }
+            fin = next;
+        }
+        vertices
+    }
+
+    /// Returns the positions of the vertices around the outer loop of `face`, this is empty for 
+    /// faces without a loop such as the unbounded face.
+    fn face_polygon(face: &Face<D>) -> Vec<Vector<D>>
+    {
+        Self::loop_vertices(face).iter().map(Self::vertex_position).collect()
+    }
+
+    /// Builds the hierarchy over the boxes of the bounded faces if it is not already built.
+    fn ensure_bvh(&self)
+    {
+        if self.face_bvh.borrow().is_some()
+        {
+            return;
+        }
+        let mut boxes = Vec::new();
+        let mut face_idx = Vec::new();
+        for (i, face) in self.faces.iter().enumerate()
+        {
+            let polygon = Self::face_polygon(face);
+            if !polygon.is_empty()
+            {
+                boxes.push(ABox::from_points(&polygon));
+                face_idx.push(i);
+            }
+        }
+        *self.face_bvh.borrow_mut() = Some((Bvh::new(&boxes), face_idx));
+    }
+
+    /// Discards the face hierarchy, it is rebuilt on the next spatial query.
+    fn invalidate_bvh(&self)
+    {
+        *self.face_bvh.borrow_mut() = None;
+    }
+
 
 }
+//..................................................................................................
+
+impl DynMesh<2>
+{
+    /// Applies the affine transformation `m`, given in homogeneous coordinates, to the positions
+    /// of all the vertices of the mesh in place.
+    pub fn transform(&mut self, m: &Mat3)
+    {
+        self.invalidate_bvh();
+        for vertex in self.vertices.iter()
+        {
+            let mut vertex = vertex.borrow_mut();
+            vertex.position = m.transform_point(&vertex.position.into()).coords;
+        }
+    }
+}
+//..................................................................................................
+
+impl DynMesh<3>
+{
+    /// Applies the affine transformation `m`, given in homogeneous coordinates, to the positions
+    /// of all the vertices of the mesh in place.
+    pub fn transform(&mut self, m: &Mat4)
+    {
+        self.invalidate_bvh();
+        for vertex in self.vertices.iter()
+        {
+            let mut vertex = vertex.borrow_mut();
+            vertex.position = m.transform_point(&vertex.position.into()).coords;
+        }
+    }
+
+    /// Casts the ray $\mathbf{o} + t \mathbf{d}, t \geq 0$ against the faces of the mesh.
+    ///
+    /// The faces whose boxes the ray crosses are found from the face hierarchy, each is split into a
+    /// fan of triangles, so faces are assumed to be planar and convex, and the triangles are
+    /// intersected by the Möller–Trumbore algorithm.
+    ///
+    /// # Returns
+    /// The mtag of the nearest face hit, the ray parameter $t$ and the point of the hit, or `None`
+    /// if the ray misses the mesh.
+    pub fn intersect_ray(&self, origin: &Vector<3>, dir: &Vector<3>) -> Option<(usize, f64, Vector<3>)>
+    {
+        self.ensure_bvh();
+        let face_bvh = self.face_bvh.borrow();
+        let (bvh, face_idx) = face_bvh.as_ref().unwrap();
+
+        let mut nearest: Option<(usize, f64)> = None;
+        for item in bvh.query(|abox| abox.intersect_ray(origin, dir).is_some())
+        {
+            let face = &self.faces[face_idx[item]];
+            let polygon = Self::face_polygon(face);
+            for i in 1..polygon.len() - 1
+            {
+                if let Some((t, _, _)) = intersect_triangle(origin, dir, &polygon[0], &polygon[i], &polygon[i + 1])
+                {
+                    if nearest.map_or(true, |(_, t_near)| t < t_near)
+                    {
+                        nearest = Some((face.mtag(), t));
+                    }
+                }
+            }
+        }
+        nearest.map(|(mtag, t)| (mtag, t, origin + dir * t))
+    }
+
+    /// Performs one step of Loop subdivision of a triangle mesh, the mesh is rebuilt with four
+    /// triangles in place of each one.
+    ///
+    /// A new vertex is placed on every edge, at $\frac{3}{8}(a + b) + \frac{1}{8}(c + d)$ for an
+    /// interior edge $ab$ whose triangles have the opposite vertices $c$ and $d$, or at the midpoint
+    /// of a boundary edge. An interior vertex $v$ with the $n$ neighbours $v_{i}$ moves to 
+    /// $(1 - n \beta) v + \beta \sum v_{i}$ where 
+    /// $\beta = \frac{1}{n} \left(\frac{5}{8} - \left(\frac{3}{8} + \frac{1}{4} \cos \frac{2 \pi}{n}\right)^{2}\right)$,
+    /// and a boundary vertex moves to $\frac{3}{4} v + \frac{1}{8}(b_{0} + b_{1})$ where $b_{0}$ and 
+    /// $b_{1}$ are its neighbours along the boundary, so that the boundary is subdivided as a curve.
+    pub fn subdivide_loop(&mut self)
+    {
+        // the triangles as indices into the vertices
+        let (points, loops) = self.indexed_loops();
+        let triangles: Vec<[usize; 3]> = loops
+            .iter()
+            .map(|indices| {
+                debug_assert!(indices.len() == 3, "face is not a triangle");
+                [indices[0], indices[1], indices[2]]
+            })
+            .collect();
+
+        // the edges in order of first appearance with the vertices opposite them, and the 
+        // neighbours of each vertex
+        let mut edges: Vec<((usize, usize), Vec<usize>)> = Vec::new();
+        let mut edge_idx: HashMap<(usize, usize), usize> = HashMap::new();
+        for [a, b, c] in triangles.iter().copied()
+        {
+            for (from, to, opposite) in [(a, b, c), (b, c, a), (c, a, b)]
+            {
+                let key = (from.min(to), from.max(to));
+                let i = *edge_idx.entry(key).or_insert_with(|| {
+                    edges.push((key, Vec::new()));
+                    edges.len() - 1
+                });
+                edges[i].1.push(opposite);
+            }
+        }
+        let mut neighbours = vec![Vec::new(); points.len()];
+        let mut boundary_neighbours = vec![Vec::new(); points.len()];
+        for ((a, b), opposite) in edges.iter()
+        {
+            let (a, b) = (*a, *b);
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+            if opposite.len() == 1
+            {
+                boundary_neighbours[a].push(b);
+                boundary_neighbours[b].push(a);
+            }
+        }
+
+        // the repositioned vertices followed by the edge vertices, in the order of the edges
+        let mut new_points: Vec<Vector<3>> = (0..points.len())
+            .map(|i| {
+                let v = points[i];
+                if boundary_neighbours[i].len() == 2
+                {
+                    let (b0, b1) = (points[boundary_neighbours[i][0]], points[boundary_neighbours[i][1]]);
+                    return v * 0.75 + (b0 + b1) * 0.125;
+                }
+                if !boundary_neighbours[i].is_empty() || neighbours[i].is_empty()
+                {
+                    // a non-manifold vertex, or one on no triangle, is kept in place
+                    return v;
+                }
+                let n = neighbours[i].len() as f64;
+                let c = 0.375 + 0.25 * (2.0 * std::f64::consts::PI / n).cos();
+                let beta = (0.625 - c * c) / n;
+                let sum: Vector<3> = neighbours[i].iter().map(|j| points[*j]).sum();
+                v * (1.0 - n * beta) + sum * beta
+            })
+            .collect();
+        for ((a, b), opposite) in edges.iter()
+        {
+            let point = match opposite.as_slice()
+            {
+                [c, d] => (points[*a] + points[*b]) * 0.375 + (points[*c] + points[*d]) * 0.125,
+                _ => (points[*a] + points[*b]) * 0.5,
+            };
+            new_points.push(point);
+        }
+
+        let mut new_triangles = Vec::with_capacity(4 * triangles.len());
+        for [a, b, c] in triangles.iter().copied()
+        {
+            let mid = |from: usize, to: usize| points.len() + edge_idx[&(from.min(to), from.max(to))];
+            let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+            new_triangles.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        *self = Self::from_triangles(&new_points, &new_triangles);
+    }
+
+    /// Computes the quality of each triangle of the mesh as $2 r / R$, where $r$ and $R$ are the
+    /// radii of its inscribed and circumscribed circles. This is 1 for an equilateral triangle and
+    /// tends to 0 as the triangle degenerates into a sliver or a needle.
+    ///
+    /// # Returns
+    /// The quality of each bounded face in the order of the faces, faces without a loop such as
+    /// the unbounded face are skipped.
+    pub fn triangle_quality(&self) -> Vec<f64>
+    {
+        self.faces
+            .iter()
+            .map(Self::face_polygon)
+            .filter(|polygon| !polygon.is_empty())
+            .map(|polygon| {
+                debug_assert!(polygon.len() == 3, "face is not a triangle");
+                let (a, b, c) = (
+                    (polygon[1] - polygon[2]).norm(),
+                    (polygon[2] - polygon[0]).norm(),
+                    (polygon[0] - polygon[1]).norm(),
+                );
+                // with the semi-perimeter s and area A, r = A / s and R = abc / 4A
+                let s = 0.5 * (a + b + c);
+                let area_sq = (s * (s - a) * (s - b) * (s - c)).max(0.0);
+                let denom = s * a * b * c;
+                if denom > 0.0 { 8.0 * area_sq / denom } else { 0.0 }
+            })
+            .collect()
+    }
+
+    /// Returns the smallest interior angle, in radians, at any corner of the bounded faces, or
+    /// `None` if the mesh has no bounded faces.
+    pub fn min_angle(&self) -> Option<f64>
+    {
+        self.corner_angles().into_iter().reduce(f64::min)
+    }
+
+    /// Returns the largest interior angle, in radians, at any corner of the bounded faces, or
+    /// `None` if the mesh has no bounded faces.
+    pub fn max_angle(&self) -> Option<f64>
+    {
+        self.corner_angles().into_iter().reduce(f64::max)
+    }
+
+    /// Returns the angles at the corners of the outer loops of all the bounded faces, the angle at a
+    /// corner with a coincident neighbour is taken to be 0.
+    fn corner_angles(&self) -> Vec<f64>
+    {
+        let mut angles = Vec::new();
+        for polygon in self.faces.iter().map(Self::face_polygon)
+        {
+            let n = polygon.len();
+            for i in 0..n
+            {
+                let prev = polygon[(i + n - 1) % n] - polygon[i];
+                let next = polygon[(i + 1) % n] - polygon[i];
+                let angle = if prev.norm() > 0.0 && next.norm() > 0.0 { prev.angle(&next) } else { 0.0 };
+                angles.push(angle);
+            }
+        }
+        angles
+    }
+}
+//..................................................................................................
+
+/// Intersects the ray $\mathbf{o} + t \mathbf{d}, t \geq 0$ with the triangle $(a, b, c)$ by the
+/// Möller–Trumbore algorithm, rays parallel to the plane of the triangle do not hit it.
+///
+/// # Returns
+/// The ray parameter of the hit and the barycentric coordinates $(\beta, \gamma)$ of the hit, 
+/// which is at $(1 - \beta - \gamma) a + \beta b + \gamma c$, or `None` if the ray misses.
+pub(crate) fn intersect_triangle(
+    origin: &Vector<3>,
+    dir: &Vector<3>,
+    a: &Vector<3>,
+    b: &Vector<3>,
+    c: &Vector<3>,
+) -> Option<(f64, f64, f64)>
+{
+    let e1 = b - a;
+    let e2 = c - a;
+    let pvec = dir.cross(&e2);
+    let det = e1.dot(&pvec);
+    if det.abs() < f64::RES_LINEAR
+    {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = origin - a;
+    let u = tvec.dot(&pvec) * inv_det;
+    if u < 0.0 || u > 1.0
+    {
+        return None;
+    }
+    let qvec = tvec.cross(&e1);
+    let v = dir.dot(&qvec) * inv_det;
+    if v < 0.0 || u + v > 1.0
+    {
+        return None;
+    }
+    let t = e2.dot(&qvec) * inv_det;
+    if t < 0.0 { None } else { Some((t, u, v)) }
+}
+//..................................................................................................
 
 
 
@@ -244,5 +762,187 @@ impl<const D: usize> DynMesh<D>
 #[cfg(test)]
 mod tests
 {
-  
+    use super::*;
+    use nalgebra as na;
+
+    #[test]
+    fn transform_test()
+    {
+        let mut mesh = DynMesh::<3>::new();
+        let mut cube = Vec::new();
+        for i in 0..8
+        {
+            let point = Vec3::new((i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64);
+            mesh.add_vertex(&point);
+            cube.push(point);
+        }
+
+        let rotation = na::Rotation3::from_axis_angle(&na::Unit::new_normalize(Vec3::new(1.0, 2.0, -1.0)), 0.7);
+        let m = rotation.to_homogeneous().append_translation(&Vec3::new(1.0, -2.0, 0.5));
+        mesh.transform(&m);
+        assert!((mesh.vertices[7].borrow().position - cube[7]).norm() > 0.1);
+
+        mesh.transform(&m.try_inverse().unwrap());
+        for (vertex, point) in mesh.vertices.iter().zip(cube.iter())
+        {
+            assert!((vertex.borrow().position - point).norm() < 1e-12);
+        }
+
+        let mut mesh = DynMesh::<2>::new();
+        mesh.add_vertex(&Vec2::new(1.0, 0.0));
+        let m = na::Rotation2::new(std::f64::consts::FRAC_PI_2).to_homogeneous();
+        mesh.transform(&m);
+        assert!((mesh.vertices[0].borrow().position - Vec2::new(0.0, 1.0)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn traversal_test()
+    {
+        // a square split into two triangles
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.5),
+        ];
+        let mesh = DynMesh::<3>::from_triangles(&points, &[[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.vertices().len(), 4);
+        assert_eq!(mesh.faces().len(), 2);
+        assert_eq!(mesh.positions().collect::<Vec<_>>(), points.to_vec());
+        for (vertex, point) in mesh.vertices().iter().zip(points.iter())
+        {
+            assert_eq!(DynMesh::<3>::vertex_position(vertex), *point);
+        }
+
+        // the traversal order is that of insertion, which the mesh tags follow
+        let tags: Vec<usize> = mesh.vertices().iter().map(|v| v.mtag()).collect();
+        assert!(tags.windows(2).all(|w| w[0] < w[1]));
+        let tags: Vec<usize> = mesh.faces().iter().map(|f| f.mtag()).collect();
+        assert!(tags.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn intersect_ray_test()
+    {
+        // the unit cube, each face is a quad loop
+        let mut mesh = DynMesh::<3>::new();
+        let vertices: Vec<Vertex<3>> = (0..8)
+            .map(|i| mesh.add_vertex(&Vec3::new((i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64)))
+            .collect();
+        let quads = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+        let faces: Vec<Face<3>> = quads
+            .iter()
+            .map(|q| mesh.add_loop_face(&q.iter().map(|i| vertices[*i].clone()).collect::<Vec<_>>()))
+            .collect();
+
+        // rays along each axis, from either side, hit the face they first meet
+        for axis in 0..3
+        {
+            for (side, face) in [(-1.0, &faces[2 * axis]), (1.0, &faces[2 * axis + 1])]
+            {
+                let mut origin = Vec3::new(0.3, 0.6, 0.7);
+                origin[axis] = 0.5 + side * 3.0;
+                let mut dir = Vec3::zeros();
+                dir[axis] = -side;
+
+                let (mtag, t, point) = mesh.intersect_ray(&origin, &dir).unwrap();
+                assert_eq!(mtag, face.mtag());
+                assert!((t - 2.5).abs() < 1e-12);
+                assert!((point[axis] - (0.5 + side * 0.5)).abs() < 1e-12);
+            }
+        }
+
+        // rays passing beside the cube or pointing away from it
+        assert!(mesh.intersect_ray(&Vec3::new(3.0, 1.5, 0.5), &Vec3::new(-1.0, 0.0, 0.0)).is_none());
+        assert!(mesh.intersect_ray(&Vec3::new(3.0, 0.5, 0.5), &Vec3::new(1.0, 0.0, 0.0)).is_none());
+        assert!(mesh.intersect_ray(&Vec3::new(3.0, 0.5, 0.5), &Vec3::new(-1.0, 2.0, 0.0)).is_none());
+
+        // the hierarchy follows the mesh when it is moved
+        let m = Mat4::new_translation(&Vec3::new(0.0, 0.0, 10.0));
+        mesh.transform(&m);
+        assert!(mesh.intersect_ray(&Vec3::new(3.0, 0.5, 0.5), &Vec3::new(-1.0, 0.0, 0.0)).is_none());
+        let (_, t, _) = mesh.intersect_ray(&Vec3::new(3.0, 0.5, 10.5), &Vec3::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!((t - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn subdivide_loop_test()
+    {
+        // a regular tetrahedron about the origin with outward facing triangles
+        let points = [
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+        ];
+        let mut mesh = DynMesh::<3>::from_triangles(&points, &[[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]]);
+        assert!(mesh.validate().is_ok());
+        assert!(mesh.is_watertight());
+
+        for level in 1..=3
+        {
+            mesh.subdivide_loop();
+            let faces = 4 * 4usize.pow(level);
+            assert_eq!(mesh.faces.len(), faces);
+            assert_eq!(mesh.fins.len(), 3 * faces);
+            assert!(mesh.validate().is_ok());
+            assert!(mesh.is_watertight());
+
+            // the Euler characteristic of a sphere is kept, with E = 3F / 2
+            assert_eq!(mesh.vertices.len() as isize - (3 * faces / 2) as isize + faces as isize, 2);
+        }
+
+        // the surface shrinks towards the centre and stays symmetric about it
+        let radii: Vec<f64> = mesh.vertices.iter().map(|v| v.borrow().position.norm()).collect();
+        assert!(radii.iter().all(|r| *r < 3.0f64.sqrt()));
+        let centroid: Vec3 = mesh.vertices.iter().map(|v| v.borrow().position).sum::<Vec3>() / radii.len() as f64;
+        assert!(centroid.norm() < 1e-12);
+
+        // an open patch keeps its boundary and subdivides it as a curve, the corners of a single
+        // triangle move a quarter of the way towards the midpoint of the other two
+        let points = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 4.0, 0.0)];
+        let mut mesh = DynMesh::<3>::from_triangles(&points, &[[0, 1, 2]]);
+        assert!(!mesh.is_watertight());
+        mesh.subdivide_loop();
+        assert_eq!(mesh.faces.len(), 4);
+        assert!(mesh.validate().is_ok());
+        assert!(!mesh.is_watertight());
+        assert_eq!(mesh.fins.iter().filter(|fin| fin.borrow().twin.is_none()).count(), 6);
+        assert!((mesh.vertices[0].borrow().position - Vec3::new(0.5, 0.5, 0.0)).norm() < 1e-12);
+        assert!((mesh.vertices[3].borrow().position - Vec3::new(2.0, 0.0, 0.0)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn triangle_quality_test()
+    {
+        use std::f64::consts::PI;
+
+        // an equilateral triangle, a right isosceles triangle and a sliver with an apex just off
+        // its base
+        let h = 3.0f64.sqrt() / 2.0;
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.5, h, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.5, 1e-4, 0.0),
+        ];
+        let mesh = DynMesh::<3>::from_triangles(&points, &[[0, 1, 2], [0, 1, 3], [0, 1, 4]]);
+        let quality = mesh.triangle_quality();
+        assert_eq!(quality.len(), 3);
+        assert!((quality[0] - 1.0).abs() < 1e-12);
+        assert!((quality[1] - 2.0 * (2.0f64.sqrt() - 1.0)).abs() < 1e-12);
+        assert!(quality[2] < 1e-3);
+
+        let min_angle = mesh.min_angle().unwrap();
+        let max_angle = mesh.max_angle().unwrap();
+        assert!(min_angle < 1e-3);
+        assert!((max_angle - PI).abs() < 1e-3);
+
+        // the angles of an equilateral triangle alone are all the same
+        let mesh = DynMesh::<3>::from_triangles(&points, &[[0, 1, 2]]);
+        assert!((mesh.min_angle().unwrap() - PI / 3.0).abs() < 1e-12);
+        assert!((mesh.max_angle().unwrap() - PI / 3.0).abs() < 1e-12);
+        assert!(DynMesh::<3>::new().min_angle().is_none());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,7 @@
 
 
-mod dcel;
\ No newline at end of file
+mod dcel;
+mod trimesh;
+pub use dcel::{DynMesh, Face, Vertex};
+pub(crate) use dcel::intersect_triangle;
+pub use trimesh::TriMesh;
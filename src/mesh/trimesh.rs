@@ -0,0 +1,86 @@
+//! This module contains the definition of a simple indexed triangle mesh.
+//!
+//! Unlike the dynamic half-edge mesh, the triangle mesh carries no connectivity beyond the vertex 
+//! indices of each triangle. It is the output format of the surface tessellators.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports 
+use crate::common::Vector;
+//}}}
+//{{{ std imports 
+//}}}
+//{{{ dep imports 
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ struct: TriMesh
+/// An indexed triangle mesh of dimension `D`.
+///
+/// The `TriMesh` struct contains the following fields:
+/// - `vertices`: The positions of the vertices.
+/// - `uvs`: The parameter-space coordinates of the vertices, for meshes generated by tessellating a
+///   surface. This is empty for meshes which have no underlying parameterisation.
+/// - `triangles`: The vertex indices of each triangle, ordered counter-clockwise.
+#[derive(Debug, Clone, Default)]
+pub struct TriMesh<const D: usize>
+{
+    vertices: Vec<Vector<D>>,
+    uvs: Vec<[f64; 2]>,
+    triangles: Vec<[usize; 3]>,
+}
+//}}}
+//{{{ impl: TriMesh
+impl<const D: usize> TriMesh<D>
+{
+    //{{{ fun: new
+    pub fn new() -> Self
+    {
+        TriMesh {
+            vertices: Vec::new(),
+            uvs: Vec::new(),
+            triangles: Vec::new(),
+        }
+    }
+    //}}}
+    //{{{ fun: add_vertex
+    /// Adds a vertex to the mesh and returns its index.
+    pub fn add_vertex(&mut self, point: &Vector<D>) -> usize
+    {
+        self.vertices.push(*point);
+        self.vertices.len() - 1
+    }
+    //}}}
+    //{{{ fun: add_vertex_uv
+    /// Adds a vertex with parameter-space coordinates `uv` to the mesh and returns its index.
+    pub fn add_vertex_uv(&mut self, point: &Vector<D>, uv: [f64; 2]) -> usize
+    {
+        debug_assert!(self.uvs.len() == self.vertices.len(), "mesh has vertices without uvs");
+        self.uvs.push(uv);
+        self.add_vertex(point)
+    }
+    //}}}
+    //{{{ fun: add_triangle
+    /// Adds a triangle defined by the indices of its three vertices.
+    pub fn add_triangle(&mut self, tri: [usize; 3])
+    {
+        debug_assert!(tri.iter().all(|&i| i < self.vertices.len()), "vertex index out of range");
+        self.triangles.push(tri);
+    }
+    //}}}
+    //{{{ fun: vertices
+    pub fn vertices(&self) -> &[Vector<D>] { &self.vertices }
+    //}}}
+    //{{{ fun: uvs
+    pub fn uvs(&self) -> &[[f64; 2]] { &self.uvs }
+    //}}}
+    //{{{ fun: triangles
+    pub fn triangles(&self) -> &[[usize; 3]] { &self.triangles }
+    //}}}
+    //{{{ fun: num_vertices
+    pub fn num_vertices(&self) -> usize { self.vertices.len() }
+    //}}}
+    //{{{ fun: num_triangles
+    pub fn num_triangles(&self) -> usize { self.triangles.len() }
+    //}}}
+}
+//}}}
@@ -0,0 +1,85 @@
+//! This module contains the broad phase culling of boxed geometry against a region of space.
+//!
+//! Culling finds the boxes which overlap a query region, such as the box around the view frustum,
+//! so that geometry lying entirely outside it can be skipped. The direct form tests every box,
+//! the octree form descends only into the octants which overlap the region.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::ABox;
+use crate::spatial::octree::Octree;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ fun: cull
+/// Returns the indices, in ascending order, of the boxes which overlap `region`.
+pub fn cull(
+    boxes: &[ABox<3>],
+    region: &ABox<3>,
+) -> Vec<usize>
+{
+    (0..boxes.len()).filter(|i| boxes[*i].intersects(region)).collect()
+}
+//}}}
+//{{{ fun: cull_octree
+/// Returns the indices, in ascending order, of the boxes in `octree` which overlap `region`.
+pub fn cull_octree(
+    octree: &Octree,
+    region: &ABox<3>,
+) -> Vec<usize>
+{
+    let mut found = octree.query(|abox| abox.intersects(region));
+    found.sort();
+    found
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn cull_test()
+    {
+        // an 8 x 8 x 8 lattice of unit boxes with unit gaps between them
+        let boxes: Vec<ABox<3>> = (0..512)
+            .map(|k| {
+                let (i, j, l) = ((k % 8) as f64, ((k / 8) % 8) as f64, (k / 64) as f64);
+                ABox::new([2.0 * i, 2.0 * j, 2.0 * l], [2.0 * i + 1.0, 2.0 * j + 1.0, 2.0 * l + 1.0])
+            })
+            .collect();
+        let octree = Octree::new(&boxes);
+
+        // the region overlaps boxes 1 to 3 in x, 2 to 3 in y and only 0 in z
+        let region = ABox::<3>::new([2.5, 4.5, -1.0], [6.5, 6.5, 0.5]);
+        let mut expected = Vec::new();
+        for j in 2..4
+        {
+            for i in 1..4
+            {
+                expected.push(i + 8 * j);
+            }
+        }
+        assert_eq!(cull(&boxes, &region), expected);
+        assert_eq!(cull_octree(&octree, &region), expected);
+
+        // a region in the gaps between boxes, and one outside the lattice, overlap nothing
+        let gap = ABox::<3>::new([1.25, 1.25, 1.25], [1.75, 1.75, 1.75]);
+        assert!(cull(&boxes, &gap).is_empty());
+        assert!(cull_octree(&octree, &gap).is_empty());
+        let outside = ABox::<3>::new([20.0, 0.0, 0.0], [21.0, 1.0, 1.0]);
+        assert!(cull_octree(&octree, &outside).is_empty());
+
+        // a region containing the lattice keeps everything
+        let all = ABox::<3>::new([-1.0, -1.0, -1.0], [16.0, 16.0, 16.0]);
+        assert_eq!(cull_octree(&octree, &all), (0..512).collect::<Vec<_>>());
+    }
+}
+//}}}
@@ -5,4 +5,12 @@
 //! 
 //!
 
-mod delaunay;
\ No newline at end of file
+mod cull;
+pub use cull::{cull, cull_octree};
+mod delaunay;
+mod octree;
+pub use octree::Octree;
+mod predicates;
+pub use predicates::{incircle, orient2d, CirclePosition, Orientation};
+mod scene;
+pub use scene::{Hit, Ray, Scene, SceneGeometry};
\ No newline at end of file
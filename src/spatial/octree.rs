@@ -0,0 +1,229 @@
+//! This module contains an octree over a set of axis-aligned boxes in 3D.
+//!
+//! Each node of the tree covers an octant of its parent, and each box is stored in the deepest
+//! node whose octant contains it entirely. Boxes which straddle the boundary between octants are
+//! therefore kept higher in the tree, so that every box is stored exactly once. Queries descend
+//! only into the nodes whose octant passes a test.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::ABox;
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Number of boxes a node may hold before it is split into octants
+const OCTREE_LEAF_SIZE: usize = 8;
+/// Maximum depth of the tree
+const OCTREE_DEPTH_MAX: usize = 12;
+//}}}
+//{{{ struct: OctreeNode
+#[derive(Debug, Clone)]
+struct OctreeNode
+{
+    /// The octant covered by the node
+    abox: ABox<3>,
+    /// Index of the first of the eight consecutive children, if the node has been split
+    children: Option<usize>,
+    /// The boxes which are not contained by a single child
+    items: Vec<usize>,
+}
+//}}}
+//{{{ struct: Octree
+/// An octree over a set of boxes, each identified by its index in the slice the tree was built
+/// from.
+#[derive(Debug, Clone)]
+pub struct Octree
+{
+    nodes: Vec<OctreeNode>,
+    boxes: Vec<ABox<3>>,
+}
+//}}}
+//{{{ impl: Octree
+impl Octree
+{
+    //{{{ fun: new
+    /// Builds the tree over `boxes` top-down, the root covers the box containing all of them and a
+    /// node is split into octants whenever it holds more than a few boxes.
+    pub fn new(boxes: &[ABox<3>]) -> Self
+    {
+        let mut octree = Octree {
+            nodes: Vec::new(),
+            boxes: boxes.to_vec(),
+        };
+        if !boxes.is_empty()
+        {
+            let root = boxes[1..].iter().fold(boxes[0].clone(), |acc, b| acc.merge(b));
+            octree.nodes.push(OctreeNode { abox: root, children: None, items: Vec::new() });
+            octree.build(0, (0..boxes.len()).collect(), 0);
+        }
+        octree
+    }
+    //}}}
+    //{{{ fun: build
+    /// Distributes `items` over the node and its descendants.
+    fn build(
+        &mut self,
+        node: usize,
+        items: Vec<usize>,
+        depth: usize,
+    )
+    {
+        if items.len() <= OCTREE_LEAF_SIZE || depth == OCTREE_DEPTH_MAX
+        {
+            self.nodes[node].items = items;
+            return;
+        }
+
+        let octants = octants(&self.nodes[node].abox);
+        let mut kept = Vec::new();
+        let mut sorted: [Vec<usize>; 8] = Default::default();
+        for i in items
+        {
+            match octants.iter().position(|o| contains(o, &self.boxes[i]))
+            {
+                Some(k) => sorted[k].push(i),
+                None => kept.push(i),
+            }
+        }
+        if sorted.iter().all(|s| s.is_empty())
+        {
+            self.nodes[node].items = kept;
+            return;
+        }
+
+        let first = self.nodes.len();
+        self.nodes[node].children = Some(first);
+        self.nodes[node].items = kept;
+        for abox in octants
+        {
+            self.nodes.push(OctreeNode { abox, children: None, items: Vec::new() });
+        }
+        for (k, sub) in sorted.into_iter().enumerate()
+        {
+            self.build(first + k, sub, depth + 1);
+        }
+    }
+    //}}}
+    //{{{ fun: query
+    /// Returns the indices of the boxes which pass `test`, found by descending only into the nodes
+    /// whose octant passes it.
+    ///
+    /// The test must be conservative, any box which contains a box passing the test must also pass
+    /// it, as is the case for overlap and ray intersection tests.
+    pub fn query<F: Fn(&ABox<3>) -> bool>(&self, test: F) -> Vec<usize>
+    {
+        let mut found = Vec::new();
+        if self.nodes.is_empty()
+        {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop()
+        {
+            let node = &self.nodes[node];
+            if !test(&node.abox)
+            {
+                continue;
+            }
+            found.extend(node.items.iter().filter(|i| test(&self.boxes[**i])));
+            if let Some(first) = node.children
+            {
+                stack.extend(first..first + 8);
+            }
+        }
+        found
+    }
+    //}}}
+    //{{{ fun: len
+    /// Returns the number of boxes in the tree
+    pub fn len(&self) -> usize { self.boxes.len() }
+    //}}}
+    //{{{ fun: is_empty
+    pub fn is_empty(&self) -> bool { self.boxes.is_empty() }
+    //}}}
+}
+//}}}
+//{{{ fun: octants
+/// Splits `abox` at its center into its eight octants.
+fn octants(abox: &ABox<3>) -> [ABox<3>; 8]
+{
+    let c = abox.center();
+    std::array::from_fn(|k| {
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+        for i in 0..3
+        {
+            if k & (1 << i) == 0
+            {
+                (min[i], max[i]) = (abox.min(i), c[i]);
+            }
+            else
+            {
+                (min[i], max[i]) = (c[i], abox.max(i));
+            }
+        }
+        ABox::new(min, max)
+    })
+}
+//}}}
+//{{{ fun: contains
+/// Returns true if `inner` lies entirely within `outer`.
+fn contains(
+    outer: &ABox<3>,
+    inner: &ABox<3>,
+) -> bool
+{
+    (0..3).all(|i| inner.min(i) >= outer.min(i) && inner.max(i) <= outer.max(i))
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn query_test()
+    {
+        // a 10 x 10 x 10 lattice of unit boxes with unit gaps between them, plus one box spanning
+        // the whole lattice which cannot descend below the root
+        let mut boxes: Vec<ABox<3>> = (0..1000)
+            .map(|k| {
+                let (i, j, l) = ((k % 10) as f64, ((k / 10) % 10) as f64, (k / 100) as f64);
+                ABox::new([2.0 * i, 2.0 * j, 2.0 * l], [2.0 * i + 1.0, 2.0 * j + 1.0, 2.0 * l + 1.0])
+            })
+            .collect();
+        boxes.push(ABox::new([0.5, 0.5, 0.5], [18.5, 18.5, 18.5]));
+        let octree = Octree::new(&boxes);
+        assert_eq!(octree.len(), 1001);
+        assert!(octree.nodes.len() > 1);
+
+        let window = ABox::<3>::new([2.5, 4.5, 0.0], [6.5, 6.5, 1.0]);
+        let mut found = octree.query(|abox| abox.distance(&window) == 0.0);
+        found.sort();
+        let expected: Vec<usize> =
+            (0..boxes.len()).filter(|k| boxes[*k].distance(&window) == 0.0).collect();
+        assert_eq!(found, expected);
+        assert_eq!(found.len(), 7);
+
+        // a ray along the x axis through the first row of boxes
+        let origin = crate::common::Vec3::new(-1.0, 0.5, 0.5);
+        let dir = crate::common::Vec3::new(1.0, 0.0, 0.0);
+        let mut found = octree.query(|abox| abox.intersect_ray(&origin, &dir).is_some());
+        found.sort();
+        let mut expected: Vec<usize> = (0..10).collect();
+        expected.push(1000);
+        assert_eq!(found, expected);
+
+        assert!(Octree::new(&[]).query(|_| true).is_empty());
+    }
+}
+//}}}
@@ -0,0 +1,204 @@
+//! This module contains the geometric predicates of 2D triangulation.
+//!
+//! A predicate decides on which side of a line, or of a circle, a point lies from the sign of a
+//! determinant. Evaluated naively in floating point the sign of a determinant near zero is noise,
+//! so that points which are collinear or cocircular up to rounding are classified arbitrarily and
+//! triangulations built on them become inconsistent. Here each determinant is compared with its
+//! permanent, the same expression with every term replaced by its absolute value, which bounds the
+//! magnitude of the rounding error. Determinants which are small relative to their permanent are
+//! reported as degenerate.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::common::{ResConstants, Vec2};
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ constants
+/// Relative size of a determinant to its permanent below which a configuration is degenerate
+const PREDICATE_TOL: f64 = f64::RES_LINEAR;
+//}}}
+//{{{ enum: Orientation
+/// The orientation of three points in the plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation
+{
+    Clockwise,
+    Collinear,
+    CounterClockwise,
+}
+//}}}
+//{{{ enum: CirclePosition
+/// The position of a point relative to the circle through three others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CirclePosition
+{
+    Inside,
+    On,
+    Outside,
+}
+//}}}
+//{{{ fun: orient2d
+/// Finds whether `a`, `b` and `c` turn counterclockwise, clockwise or lie on a line.
+///
+/// This is the sign of the determinant
+/// $$
+/// \begin{vmatrix}
+/// a_{x} - c_{x} & a_{y} - c_{y} \\
+/// b_{x} - c_{x} & b_{y} - c_{y}
+/// \end{vmatrix}
+/// $$
+/// which is twice the signed area of the triangle $abc$, the points are collinear when the
+/// determinant is within tolerance of zero.
+pub fn orient2d(
+    a: &Vec2,
+    b: &Vec2,
+    c: &Vec2,
+) -> Orientation
+{
+    let left = (a[0] - c[0]) * (b[1] - c[1]);
+    let right = (a[1] - c[1]) * (b[0] - c[0]);
+    let det = left - right;
+    let permanent = left.abs() + right.abs();
+
+    if det.abs() <= PREDICATE_TOL * permanent
+    {
+        Orientation::Collinear
+    }
+    else if det > 0.0
+    {
+        Orientation::CounterClockwise
+    }
+    else
+    {
+        Orientation::Clockwise
+    }
+}
+//}}}
+//{{{ fun: incircle
+/// Finds whether `d` lies inside, on or outside the circle through `a`, `b` and `c`.
+///
+/// This is the sign of the determinant
+/// $$
+/// \begin{vmatrix}
+/// a_{x} - d_{x} & a_{y} - d_{y} & |\mathbf{a} - \mathbf{d}|^{2} \\
+/// b_{x} - d_{x} & b_{y} - d_{y} & |\mathbf{b} - \mathbf{d}|^{2} \\
+/// c_{x} - d_{x} & c_{y} - d_{y} & |\mathbf{c} - \mathbf{d}|^{2}
+/// \end{vmatrix}
+/// $$
+/// which is positive when `d` is inside the circle and `a`, `b`, `c` are counterclockwise. For
+/// clockwise `a`, `b`, `c` the sign is reversed, this is accounted for so that the result does not
+/// depend on their order. The result is undefined for collinear `a`, `b`, `c`.
+pub fn incircle(
+    a: &Vec2,
+    b: &Vec2,
+    c: &Vec2,
+    d: &Vec2,
+) -> CirclePosition
+{
+    let (adx, ady) = (a[0] - d[0], a[1] - d[1]);
+    let (bdx, bdy) = (b[0] - d[0], b[1] - d[1]);
+    let (cdx, cdy) = (c[0] - d[0], c[1] - d[1]);
+    let alift = adx * adx + ady * ady;
+    let blift = bdx * bdx + bdy * bdy;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdx * cdy - cdx * bdy)
+        + blift * (cdx * ady - adx * cdy)
+        + clift * (adx * bdy - bdx * ady);
+    let permanent = alift * ((bdx * cdy).abs() + (cdx * bdy).abs())
+        + blift * ((cdx * ady).abs() + (adx * cdy).abs())
+        + clift * ((adx * bdy).abs() + (bdx * ady).abs());
+
+    if det.abs() <= PREDICATE_TOL * permanent
+    {
+        return CirclePosition::On;
+    }
+    let inside = match orient2d(a, b, c)
+    {
+        Orientation::Clockwise => det < 0.0,
+        _ => det > 0.0,
+    };
+    if inside { CirclePosition::Inside } else { CirclePosition::Outside }
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn orient2d_test()
+    {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(1.0, 0.0);
+        let c = Vec2::new(0.0, 1.0);
+        assert_eq!(orient2d(&a, &b, &c), Orientation::CounterClockwise);
+        assert_eq!(orient2d(&a, &c, &b), Orientation::Clockwise);
+
+        // points on a line whose coordinates are not representable, far from the origin, are
+        // collinear in whichever order they are given
+        let origin = Vec2::new(1.0e4 + 0.1, -3.0e3 + 0.7);
+        let dir = Vec2::new(0.3, 0.7);
+        let points: Vec<Vec2> = [0.1, 0.35, 0.9, 1.7].iter().map(|t| origin + dir * *t).collect();
+        for i in 0..4
+        {
+            for j in 0..4
+            {
+                for k in 0..4
+                {
+                    if i != j && j != k && i != k
+                    {
+                        assert_eq!(
+                            orient2d(&points[i], &points[j], &points[k]),
+                            Orientation::Collinear
+                        );
+                    }
+                }
+            }
+        }
+
+        // a point a small but genuine distance off the line is still classified
+        let off = points[2] + Vec2::new(-0.7, 0.3) * 1.0e-6;
+        assert_eq!(orient2d(&points[0], &points[1], &off), Orientation::CounterClockwise);
+        assert_eq!(orient2d(&points[1], &points[0], &off), Orientation::Clockwise);
+    }
+
+    #[test]
+    fn incircle_test()
+    {
+        let center = Vec2::new(-250.3, 1.0e3 + 0.1);
+        let radius = 7.3;
+        let on_circle = |theta: f64| center + Vec2::new(theta.cos(), theta.sin()) * radius;
+        let (a, b, c) = (on_circle(0.1), on_circle(0.3 * PI), on_circle(1.1 * PI));
+
+        // the orientation of the first three points does not matter
+        for (a, b, c) in [(a, b, c), (a, c, b)]
+        {
+            assert_eq!(incircle(&a, &b, &c, &center), CirclePosition::Inside);
+            assert_eq!(incircle(&a, &b, &c, &(center * 2.0)), CirclePosition::Outside);
+
+            // points on the circle up to rounding are on it
+            for i in 0..32
+            {
+                let d = on_circle(2.0 * PI * i as f64 / 32.0 + 0.05);
+                assert_eq!(incircle(&a, &b, &c, &d), CirclePosition::On);
+            }
+
+            // slightly inside or outside the circle is still classified
+            let d = on_circle(1.5 * PI);
+            let outward = (d - center).normalize();
+            assert_eq!(incircle(&a, &b, &c, &(d - outward * 1.0e-6)), CirclePosition::Inside);
+            assert_eq!(incircle(&a, &b, &c, &(d + outward * 1.0e-6)), CirclePosition::Outside);
+        }
+    }
+}
+//}}}
@@ -0,0 +1,300 @@
+//! This module contains a scene of geometry which can be picked with rays.
+//!
+//! The scene borrows curves, surfaces and bounded planes and keeps their bounding boxes in an
+//! octree. A pick casts a ray into the scene, the octree culls the geometry whose box the ray
+//! misses and the remaining geometry is intersected in order of where the ray enters its box, so
+//! that the search stops as soon as no closer hit is possible. Curves have no area for a ray to
+//! hit, they are picked when the ray passes within a tolerance of them.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::boxing::{ABox, ABoxable, BoundedPlane};
+use crate::common::Vec3;
+use crate::geometry::{Bcurve, Bsurface, Curve, Surface};
+use crate::spatial::octree::Octree;
+//}}}
+//{{{ std imports
+use std::sync::OnceLock;
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ struct: Ray
+/// A ray $\mathbf{o} + t \mathbf{d}, t \geq 0$ with a unit direction, so that $t$ is the distance
+/// along the ray.
+#[derive(Debug, Clone)]
+pub struct Ray
+{
+    pub origin: Vec3,
+    pub dir: Vec3,
+}
+//}}}
+//{{{ impl: Ray
+impl Ray
+{
+    /// Creates a ray from `origin` in the direction of `dir`, which is normalised.
+    pub fn new(origin: Vec3, dir: Vec3) -> Self
+    {
+        Ray { origin, dir: dir.normalize() }
+    }
+}
+//}}}
+//{{{ enum: SceneGeometry
+/// A piece of geometry held by a [Scene].
+pub enum SceneGeometry<'a>
+{
+    Curve(&'a Bcurve<3>),
+    Surface(&'a Bsurface<3>),
+    Plane(BoundedPlane<'a>),
+}
+//}}}
+//{{{ impl: SceneGeometry
+impl SceneGeometry<'_>
+{
+    /// Returns a box containing the geometry, the control boxes of curves and surfaces are used
+    /// since, unlike their tight boxes, they are guaranteed to contain them.
+    fn abox(&self) -> ABox<3>
+    {
+        match self
+        {
+            SceneGeometry::Curve(curve) => curve.control_box(),
+            SceneGeometry::Surface(surface) => surface.control_box(),
+            SceneGeometry::Plane(plane) => plane.compute_box(),
+        }
+    }
+    //..............................................................................................
+
+    /// Intersects `ray` with the geometry, curves are hit if the ray passes within `pick_tol`.
+    ///
+    /// # Returns
+    /// The parameters and point of the hit and the distance to it along the ray.
+    fn intersect_ray(
+        &self,
+        ray: &Ray,
+        pick_tol: f64,
+    ) -> Option<((f64, f64), Vec3, f64)>
+    {
+        match self
+        {
+            SceneGeometry::Curve(curve) =>
+            {
+                let (u, t, dist) = curve.closest_point_to_ray(&ray.origin, &ray.dir);
+                (dist <= pick_tol).then(|| ((u, 0.0), curve.eval(u), t))
+            }
+            SceneGeometry::Surface(surface) =>
+            {
+                let (t, u, v) = surface.intersect_ray(&ray.origin, &ray.dir)?;
+                Some(((u, v), surface.eval(u, v), t))
+            }
+            SceneGeometry::Plane(bounded) =>
+            {
+                let (t, u, v) = bounded.plane.intersect_ray(&ray.origin, &ray.dir)?;
+                let inside = u >= bounded.u_range.0
+                    && u <= bounded.u_range.1
+                    && v >= bounded.v_range.0
+                    && v <= bounded.v_range.1;
+                inside.then(|| ((u, v), bounded.plane.eval(u, v), t))
+            }
+        }
+    }
+}
+//}}}
+//{{{ struct: Hit
+/// The result of picking a [Scene] with a ray.
+#[derive(Debug, Clone)]
+pub struct Hit
+{
+    /// The id of the geometry hit, as returned by [Scene::add]
+    pub geometry_id: usize,
+    /// The parameters of the hit on the geometry, only the first is meaningful for curves
+    pub param: (f64, f64),
+    /// The point hit on the geometry
+    pub point: Vec3,
+    /// The distance along the ray to the hit
+    pub distance: f64,
+}
+//}}}
+//{{{ struct: Scene
+/// A collection of geometry in an octree, which answers ray picking queries.
+pub struct Scene<'a>
+{
+    geometries: Vec<SceneGeometry<'a>>,
+    boxes: Vec<ABox<3>>,
+    /// Octree of `boxes`, built by the first query after the scene changes
+    octree: OnceLock<Octree>,
+    /// Distance within which a ray picks a curve
+    pick_tol: f64,
+}
+//}}}
+//{{{ impl: Scene
+impl<'a> Scene<'a>
+{
+    //{{{ fun: new
+    /// Creates an empty scene in which rays pick curves that they pass within `pick_tol` of.
+    pub fn new(pick_tol: f64) -> Self
+    {
+        Scene {
+            geometries: Vec::new(),
+            boxes: Vec::new(),
+            octree: OnceLock::new(),
+            pick_tol,
+        }
+    }
+    //}}}
+    //{{{ fun: add
+    /// Adds `geometry` to the scene and returns its id. The octree is rebuilt by the next query,
+    /// so adding many pieces of geometry builds it once.
+    pub fn add(&mut self, geometry: SceneGeometry<'a>) -> usize
+    {
+        // grow the box by the pick tolerance so that rays passing near a curve are not culled
+        let abox = geometry.abox();
+        let tol = self.pick_tol;
+        self.boxes.push(ABox::new(
+            [abox.min(0) - tol, abox.min(1) - tol, abox.min(2) - tol],
+            [abox.max(0) + tol, abox.max(1) + tol, abox.max(2) + tol],
+        ));
+        self.geometries.push(geometry);
+        self.octree = OnceLock::new();
+        self.geometries.len() - 1
+    }
+    //}}}
+    //{{{ fun: nearest
+    /// Finds the geometry hit first along `ray`.
+    ///
+    /// The candidates whose boxes the ray passes through are intersected in order of the distance
+    /// at which the ray enters their boxes, stopping once that exceeds the distance of the nearest
+    /// hit found.
+    pub fn nearest(&self, ray: &Ray) -> Option<Hit>
+    {
+        let mut candidates: Vec<(usize, f64)> = self
+            .octree
+            .get_or_init(|| Octree::new(&self.boxes))
+            .query(|abox| abox.intersect_ray(&ray.origin, &ray.dir).is_some())
+            .into_iter()
+            .map(|i| (i, self.boxes[i].intersect_ray(&ray.origin, &ray.dir).unwrap().0))
+            .collect();
+        candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        let mut nearest: Option<Hit> = None;
+        for (i, t_enter) in candidates
+        {
+            if nearest.as_ref().is_some_and(|hit| t_enter > hit.distance)
+            {
+                break;
+            }
+            if let Some((param, point, distance)) = self.geometries[i].intersect_ray(ray, self.pick_tol)
+            {
+                if nearest.as_ref().map_or(true, |hit| distance < hit.distance)
+                {
+                    nearest = Some(Hit { geometry_id: i, param, point, distance });
+                }
+            }
+        }
+        nearest
+    }
+    //}}}
+    //{{{ fun: len
+    /// Returns the number of pieces of geometry in the scene
+    pub fn len(&self) -> usize { self.geometries.len() }
+    //}}}
+    //{{{ fun: is_empty
+    pub fn is_empty(&self) -> bool { self.geometries.is_empty() }
+    //}}}
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::geometry::{BcurveDescriptor, BsurfaceDescriptor, Plane, PlaneDescriptor};
+    use approx::assert_relative_eq;
+
+    fn make_line(start: Vec3, end: Vec3) -> Bcurve<3>
+    {
+        Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            cpoints: (0..4).map(|i| start + (end - start) * i as f64 / 3.0).collect(),
+            cweights: vec![1.0; 4],
+        })
+    }
+
+    #[test]
+    fn nearest_test()
+    {
+        let plane = Plane::new(&PlaneDescriptor {
+            origin: Vec3::new(0.0, 0.0, 0.0),
+            x: Vec3::new(1.0, 0.0, 0.0),
+            y: Vec3::new(0.0, 1.0, 0.0),
+        });
+        let curve1 = make_line(Vec3::new(-4.0, 0.0, 2.0), Vec3::new(4.0, 0.0, 2.0));
+        let curve2 = make_line(Vec3::new(0.0, -4.0, 4.0), Vec3::new(0.0, 4.0, 4.0));
+        // a tilted bilinear patch above the plane, away from the curves
+        let patch = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q: 1,
+            knots_u: vec![0.0, 0.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints: vec![
+                Vec3::new(-4.0, -4.0, 1.0),
+                Vec3::new(-2.0, -4.0, 1.0),
+                Vec3::new(-4.0, -2.0, 1.0),
+                Vec3::new(-2.0, -2.0, 2.0),
+            ],
+            cweights: vec![1.0; 4],
+        });
+
+        let mut scene = Scene::new(0.05);
+        let plane_id = scene.add(SceneGeometry::Plane(BoundedPlane::new(&plane, (-5.0, 5.0), (-5.0, 5.0))));
+        let curve1_id = scene.add(SceneGeometry::Curve(&curve1));
+        let curve2_id = scene.add(SceneGeometry::Curve(&curve2));
+        let patch_id = scene.add(SceneGeometry::Surface(&patch));
+        assert_eq!(scene.len(), 4);
+
+        let down = Vec3::new(0.0, 0.0, -1.0);
+
+        // above the crossing of the curves the upper curve is hit first
+        let hit = scene.nearest(&Ray::new(Vec3::new(0.0, 0.0, 10.0), down)).unwrap();
+        assert_eq!(hit.geometry_id, curve2_id);
+        assert_relative_eq!(hit.distance, 6.0, epsilon = 1e-6);
+        assert_relative_eq!(hit.param.0, 0.5, epsilon = 1e-6);
+        assert_relative_eq!(hit.point, Vec3::new(0.0, 0.0, 4.0), epsilon = 1e-6);
+
+        // a ray passing just beside the lower curve picks it within the tolerance
+        let hit = scene.nearest(&Ray::new(Vec3::new(3.0, 0.02, 10.0), 2.0 * down)).unwrap();
+        assert_eq!(hit.geometry_id, curve1_id);
+        assert_relative_eq!(hit.point, Vec3::new(3.0, 0.0, 2.0), epsilon = 1e-6);
+
+        // away from the curves the plane is hit
+        let hit = scene.nearest(&Ray::new(Vec3::new(3.0, 3.0, 10.0), down)).unwrap();
+        assert_eq!(hit.geometry_id, plane_id);
+        assert_relative_eq!(hit.param.0, 3.0, epsilon = 1e-12);
+        assert_relative_eq!(hit.param.1, 3.0, epsilon = 1e-12);
+        assert_relative_eq!(hit.distance, 10.0, epsilon = 1e-12);
+
+        // the patch shadows the plane beneath it
+        let hit = scene.nearest(&Ray::new(Vec3::new(-3.0, -3.0, 10.0), down)).unwrap();
+        assert_eq!(hit.geometry_id, patch_id);
+        assert_relative_eq!(hit.point, Vec3::new(-3.0, -3.0, 1.25), epsilon = 1e-9);
+
+        // the tolerance is respected and rays outside the scene miss everything
+        let hit = scene.nearest(&Ray::new(Vec3::new(3.0, 0.2, 10.0), down)).unwrap();
+        assert_eq!(hit.geometry_id, plane_id);
+        assert!(scene.nearest(&Ray::new(Vec3::new(20.0, 0.0, 10.0), down)).is_none());
+        assert!(scene.nearest(&Ray::new(Vec3::new(0.0, 0.0, 10.0), -down)).is_none());
+
+        // the octree is only built by a query, and again after the scene changes
+        assert!(scene.octree.get().is_some());
+        let curve3 = make_line(Vec3::new(3.0, -4.0, 6.0), Vec3::new(3.0, 4.0, 6.0));
+        let curve3_id = scene.add(SceneGeometry::Curve(&curve3));
+        assert!(scene.octree.get().is_none());
+        let hit = scene.nearest(&Ray::new(Vec3::new(3.0, 0.0, 10.0), down)).unwrap();
+        assert_eq!(hit.geometry_id, curve3_id);
+        assert_eq!(scene.octree.get().unwrap().len(), 5);
+    }
+}
+//}}}
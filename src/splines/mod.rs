@@ -4,14 +4,47 @@
 //!
 //!
 
+use crate::common::DescriptorError;
 use crate::utilities::NDArrayWrapper;
+use crate::geometry::{Bcurve, BCURVE_DER_MAX};
 use approx::ulps_eq;
+use std::ops::{Add, Mul};
 
 /// This is the maximum allowable order of a bspline basis. It is an arbitrary number.
 pub const PMAX: usize = 8;
 /// This is the tolerance with which two knots are considered equal
 pub const KNOT_ULPS: u32 = 32;
 
+/// Checks each named degree against [PMAX] and each named derivative order against
+/// [BCURVE_DER_MAX], this is the single place where the limits of the spline geometry are
+/// validated.
+///
+/// # Parameters
+///
+/// - `degrees`: Pairs of the name of a degree, such as `"degree in u"`, and its value.
+/// - `ders`: Pairs of the name of a derivative order, such as `"derivative order"`, and its value.
+///
+/// # Returns
+///
+/// A [DescriptorError::LimitExceeded] naming the first degree or derivative order which exceeds
+/// its limit.
+pub fn check_degrees(
+    degrees: &[(&'static str, usize)],
+    ders: &[(&'static str, usize)],
+) -> Result<(), DescriptorError>
+{
+    let limits = degrees.iter().map(|d| (d, PMAX)).chain(ders.iter().map(|d| (d, BCURVE_DER_MAX)));
+    for ((limit, value), max) in limits
+    {
+        if *value > max
+        {
+            return Err(DescriptorError::LimitExceeded { limit, value: *value, max });
+        }
+    }
+    Ok(())
+}
+//..............................................................................................
+
 /// Tolerant less-thant for knots
 fn knot_lt(
     u1: f64,
@@ -32,6 +65,14 @@ fn knot_gt(
 }
 //..............................................................................................
 
+/// Checks whether the knots are in non-decreasing order, adjacent knots which are equal within
+/// the knot tolerance may appear in either order.
+pub fn is_sorted_tol(knots: &[f64]) -> bool
+{
+    knots.windows(2).all(|w| knot_lt(w[0], w[1]))
+}
+//..............................................................................................
+
 /// Checks if two floating-point values are considered equal within a tolerance.
 pub fn knot_eq(
     u1: f64,
@@ -98,12 +139,54 @@ pub fn is_member(
 }
 //..............................................................................................
 
+/// Clamps the parameter value `u` onto the ends of the knot vector if it is within knot tolerance
+/// of them.
+///
+/// Values which lie just outside the parameter range due to floating point noise are moved onto
+/// the nearest end of the range. Values further outside are returned unchanged, so that they are
+/// still caught by [is_member].
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector, sorted in ascending order.
+/// - `u`: The parameter value to clamp.
+///
+/// # Returns
+///
+/// The clamped parameter value.
+pub fn clamp_to_domain(
+    knots: &[f64],
+    u: f64,
+) -> f64
+{
+    let umin = *knots.first().unwrap();
+    let umax = *knots.last().unwrap();
+    if u < umin && knot_eq(u, umin)
+    {
+        umin
+    }
+    else if u > umax && knot_eq(u, umax)
+    {
+        umax
+    }
+    else
+    {
+        u
+    }
+}
+//..............................................................................................
+
 /// Finds the index of the knot vector that contains the given parameter value `u`.
 ///
 /// This function determines the index of the knot vector that contains the given parameter value `u`,
 /// assuming the knot vector is sorted in ascending order. It uses a binary search algorithm to
 /// efficiently locate the appropriate index.
 ///
+/// The spans are half-open, $[u_{i}, u_{i+1})$, so a parameter at an interior knot, of any 
+/// multiplicity and up to the knot tolerance, lies in the non-empty span starting at its last copy
+/// and the basis functions evaluated there are continuous from the right. The exception is the end 
+/// of the parameter range, which lies in the last non-empty span.
+///
 /// # Parameters
 ///
 /// - `knots`: A slice of `f64` values representing the knot vector.
@@ -200,13 +283,230 @@ pub fn eval(
     );
     debug_assert!(is_member(knots, u), "u is outside of ");
 
-    shape_funs.fill(0.0);
-    shape_funs[0] = 1.0;
+    let i = find_span(knots, u, p);
+    eval_at_span(knots, u, p, i, shape_funs);
+}
+//..............................................................................................
+
+/// Reusable buffers for [eval_with], so that the working arrays of the basis function recurrence
+/// are not set up afresh on every evaluation in a tight loop.
+#[derive(Debug, Clone)]
+pub struct BasisScratch
+{
+    left: [f64; PMAX],
+    right: [f64; PMAX],
+    shape_funs: [f64; PMAX + 1],
+}
+//..............................................................................................
+
+impl BasisScratch
+{
+    pub fn new() -> Self
+    {
+        BasisScratch { left: [0.0; PMAX], right: [0.0; PMAX], shape_funs: [0.0; PMAX + 1] }
+    }
+}
+//..............................................................................................
+
+impl Default for BasisScratch
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+//..............................................................................................
+
+/// Evaluates the B-spline basis functions for the given parameter value `u`, as [eval] does, 
+/// working in the buffers of `scratch`.
+///
+/// # Parameters
+///
+/// - `scratch`: The buffers to work in, which may be reused for any knot vector and degree.
+/// - `knots`: A slice of `f64` values representing the knot vector.
+/// - `u`: The parameter value to evaluate the basis functions for.
+/// - `p`: The degree of the spline.
+///
+/// # Returns
+///
+/// The `p + 1` non-zero basis functions, borrowed from `scratch` until its next use.
+pub fn eval_with<'a>(
+    scratch: &'a mut BasisScratch,
+    knots: &[f64],
+    u: f64,
+    p: usize,
+) -> &'a [f64]
+{
+    debug_assert!(is_member(knots, u), "u is outside of parameter range");
+
+    let i = find_span(knots, u, p);
+    let BasisScratch { left, right, shape_funs } = scratch;
+    eval_at_span_with(knots, u, p, i, &mut shape_funs[..p + 1], left, right);
+    &shape_funs[..p + 1]
+}
+//..............................................................................................
+
+/// The non-zero B-spline basis functions at a parameter value, ready to be combined with the 
+/// control points of a curve or with one direction of the control net of a surface.
+#[derive(Debug, Clone, Copy)]
+pub struct BasisAt
+{
+    /// Index of the first non-zero basis function
+    pub start: usize,
+    /// Number of non-zero basis functions
+    pub num: usize,
+    /// Values of the non-zero basis functions, `funs[k]` is the value of basis function `start + k`
+    pub funs: [f64; PMAX + 1],
+}
+//..............................................................................................
+
+/// Evaluates the non-zero B-spline basis functions at the parameter value `u`.
+///
+/// # Parameters
+///
+/// - `knots`: A slice of `f64` values representing the knot vector.
+/// - `p`: The degree of the spline.
+/// - `u`: The parameter value to evaluate the basis functions at.
+///
+/// # Returns
+///
+/// The non-zero basis functions, see [BasisAt::combine].
+pub fn basis_at(
+    knots: &[f64],
+    p: usize,
+    u: f64,
+) -> BasisAt
+{
+    let (start, _end, num) = non_zero_basis(knots, u, p);
+    let mut funs = [0.0; PMAX + 1];
+    eval(knots, u, p, &mut funs);
+    BasisAt { start, num, funs }
+}
+//..............................................................................................
+
+impl BasisAt
+{
+    /// Computes $\sum_{i} N_{i}(u) \mathbf{P}_{i}$ over the non-zero basis functions, where the 
+    /// control point $\mathbf{P}_{i}$ is given by `cpoint(i)`.
+    ///
+    /// The accessor lets the same combination serve a curve, whose control points are a slice, 
+    /// and a surface, whose rows or columns of the control net are strided, or are themselves 
+    /// combinations in the other direction.
+    pub fn combine<T, F>(
+        &self,
+        cpoint: F,
+    ) -> T
+    where
+        T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+        F: Fn(usize) -> T,
+    {
+        let mut out = cpoint(self.start) * self.funs[0];
+        for k in 1..self.num
+        {
+            out = out + cpoint(self.start + k) * self.funs[k];
+        }
+        out
+    }
+}
+//..............................................................................................
+
+/// Evaluates the B-spline basis functions for a monotonically increasing sequence of parameters.
+///
+/// Rather than binary-searching for the knot span of every parameter, as [`eval`] does, the span
+/// index is advanced incrementally from the previous parameter. This makes dense sweeps, such as
+/// those used when rendering, noticeably cheaper.
+///
+/// # Parameters
+///
+/// - `knots`: A slice of `f64` values representing the knot vector.
+/// - `p`: The degree of the spline.
+/// - `us`: The parameter values, sorted in ascending order.
+/// - `out`: A mutable slice to store the basis functions, the `p + 1` non-zero basis functions
+///   of the i'th parameter are stored in `out[i*(p+1)..(i+1)*(p+1)]`.
+///
+/// # Panics
+///
+/// - If `us` is not sorted.
+/// - If `out.len() < us.len() * (p + 1)`, indicating the buffer is too small to hold the results.
+pub fn eval_sweep(
+    knots: &[f64],
+    p: usize,
+    us: &[f64],
+    out: &mut [f64],
+)
+{
+    debug_assert!(us.is_sorted(), "parameters not sorted");
+    debug_assert!(
+        out.len() >= us.len() * (p + 1),
+        "Buffer too small to hold results"
+    );
+
+    if us.is_empty()
+    {
+        return;
+    }
+
+    let n = knots.len() - p - 1;
+    let mut span = find_span(knots, us[0], p);
+    let mut scratch = BasisScratch::new();
+
+    for (idx, u) in us.iter().enumerate()
+    {
+        debug_assert!(is_member(knots, *u), "u is outside of parameter range");
+
+        if knot_eq(*u, knots[n])
+        {
+            span = n - 1;
+        }
+        else
+        {
+            while span + 1 < n && knot_lt(knots[span + 1], *u)
+            {
+                span += 1;
+            }
+        }
+        eval_at_span_with(
+            knots,
+            *u,
+            p,
+            span,
+            &mut out[idx * (p + 1)..(idx + 1) * (p + 1)],
+            &mut scratch.left,
+            &mut scratch.right,
+        );
+    }
+}
+//..............................................................................................
 
+/// Evaluates the B-spline basis functions for the given parameter value `u` in the knot span `i`.
+fn eval_at_span(
+    knots: &[f64],
+    u: f64,
+    p: usize,
+    i: usize,
+    shape_funs: &mut [f64],
+)
+{
     let mut left = [0.0; PMAX];
     let mut right = [0.0; PMAX];
+    eval_at_span_with(knots, u, p, i, shape_funs, &mut left, &mut right);
+}
+//..............................................................................................
 
-    let i = find_span(knots, u, p);
+/// Evaluates the basis functions in the knot span `i` as [eval_at_span] does, with the working 
+/// arrays `left` and `right` supplied by the caller, they need not be initialised.
+fn eval_at_span_with(
+    knots: &[f64],
+    u: f64,
+    p: usize,
+    i: usize,
+    shape_funs: &mut [f64],
+    left: &mut [f64; PMAX],
+    right: &mut [f64; PMAX],
+)
+{
+    shape_funs.fill(0.0);
+    shape_funs[0] = 1.0;
 
     for j in 1..p + 1
     {
@@ -339,7 +639,7 @@ pub fn eval_diff_all(
         shape_ders_arr[&[j, 0]] = shape_funs[j];
     }
 
-    for k2 in 1..p + 1
+    for k2 in 1..k + 1
     {
         let mut shape_ders_loc = [0.0; PMAX + 1];
         eval_diff(knots, u, p, k2, &mut shape_ders_loc);
@@ -350,86 +650,703 @@ pub fn eval_diff_all(
     }
 }
 
-pub fn multiplicites(knots: &[f64]) -> Vec<(f64, usize)>
+/// Computes the uniform clamped knot vector on $[0, 1]$, whose end knots have multiplicity
+/// $p + 1$ and whose interior knots are equally spaced.
+///
+/// # Parameters
+///
+/// - `num_cpoints`: The number of control points of the spline, at least $p + 1$.
+/// - `p`: The degree of the spline.
+///
+/// # Returns
+///
+/// A knot vector of length `num_cpoints + p + 1`.
+pub fn uniform_knots(
+    num_cpoints: usize,
+    p: usize,
+) -> Vec<f64>
 {
-    let mut out = Vec::new();
-    out.reserve(knots.len());
+    debug_assert!(num_cpoints >= p + 1, "Too few control points for degree");
 
-    let mut cur_knot = knots[0];
-    let mut cur_mult = 0;
+    let num_spans = num_cpoints - p;
+    let mut knots = vec![0.0; p + 1];
+    knots.extend((1..num_spans).map(|i| i as f64 / num_spans as f64));
+    knots.extend(vec![1.0; p + 1]);
+    knots
+}
+//..............................................................................................
 
-    for i in 1..knots.len()
+/// Computes a clamped knot vector suitable for least-squares fitting.
+///
+/// The interior knots are placed by averaging the parameters of the data points such that every
+/// knot span contains at least one parameter, which guarantees that the least-squares system is
+/// non-singular (see The NURBS Book, eq. 9.68).
+///
+/// # Parameters
+///
+/// - `params`: The parameters of the data points, sorted in ascending order in $[0, 1]$.
+/// - `p`: The degree of the spline.
+/// - `num_cpoints`: The number of control points of the fitted spline.
+///
+/// # Returns
+///
+/// A knot vector of length `num_cpoints + p + 1` spanning $[0, 1]$.
+pub fn fit_knots(
+    params: &[f64],
+    p: usize,
+    num_cpoints: usize,
+) -> Vec<f64>
+{
+    debug_assert!(num_cpoints >= p + 1, "Too few control points for degree");
+    debug_assert!(params.len() >= num_cpoints, "Too few data points for control points");
+
+    let num_params = params.len();
+    let mut knots = vec![0.0; num_cpoints + p + 1];
+    let d = num_params as f64 / (num_cpoints - p) as f64;
+
+    for j in 1..num_cpoints - p
     {
-        cur_mult += 1;
-        if (!knot_eq(knots[i], cur_knot))
-        {
-            out.push((cur_knot, cur_mult));
-            cur_knot = knots[i];
-            cur_mult = 0;
-        }
+        let jd = j as f64 * d;
+        let i = jd.floor() as usize;
+        let alpha = jd - i as f64;
+        knots[p + j] = (1.0 - alpha) * params[i - 1] + alpha * params[i];
     }
-
-    out.push((cur_knot, cur_mult+1));
-    out
+    for j in 0..p + 1
+    {
+        knots[num_cpoints + j] = 1.0;
+    }
+    knots
 }
 //..............................................................................................
 
-// ------------------------------------------- Tests -------------------------------------------- //
-#[cfg(test)]
-mod tests
+/// Inserts the knot `u` into a spline `r` times, without changing the shape or parameterisation
+/// of the spline.
+///
+/// This is algorithm A5.1 of The NURBS Book. For rational splines the control points must be given
+/// in homogeneous coordinates.
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector of the spline.
+/// - `p`: The degree of the spline.
+/// - `cpoints`: The control points of the spline.
+/// - `u`: The knot value to insert, must be strictly inside the parameter range.
+/// - `r`: The number of times to insert `u`, the final multiplicity must not exceed `p`.
+///
+/// # Returns
+///
+/// A tuple of the new knot vector and the new control points.
+pub fn insert_knot<T>(
+    knots: &[f64],
+    p: usize,
+    cpoints: &[T],
+    u: f64,
+    r: usize,
+) -> (Vec<f64>, Vec<T>)
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
 {
+    let s = knots.iter().filter(|&&x| knot_eq(x, u)).count();
+    debug_assert!(r + s <= p, "Knot multiplicity would exceed the order");
 
-    use super::*;
-
-    use approx::assert_relative_eq;
-    use serde::Deserialize;
-    use std::fs;
+    let k = find_span(knots, u, p);
+    let np = cpoints.len() - 1;
+    let mp = np + p + 1;
 
-    const ZERO_THRESHOLD: f64 = 1e-14;
+    let mut knots_new = Vec::with_capacity(knots.len() + r);
+    knots_new.extend_from_slice(&knots[..k + 1]);
+    knots_new.extend(std::iter::repeat(u).take(r));
+    knots_new.extend_from_slice(&knots[k + 1..mp + 1]);
 
-    #[derive(Deserialize)]
-    struct ParamData
+    let mut cpoints_new = vec![cpoints[0]; np + r + 1];
+    for i in 0..k + 1 - p
     {
-        description: String,
-        values: Vec<f64>,
+        cpoints_new[i] = cpoints[i];
     }
-
-    #[derive(Deserialize)]
-    struct KnotData
+    for i in k - s..np + 1
     {
-        description: String,
-        values: Vec<f64>,
+        cpoints_new[i + r] = cpoints[i];
     }
 
-    #[derive(Deserialize)]
-    struct SpanData
+    let mut rw: Vec<T> = (0..p - s + 1).map(|i| cpoints[k - p + i]).collect();
+    let mut l = k - p;
+    for j in 1..r + 1
     {
-        description: String,
-        values: Vec<usize>,
+        l = k - p + j;
+        for i in 0..p - j - s + 1
+        {
+            let alpha = (u - knots[l + i]) / (knots[i + k + 1] - knots[l + i]);
+            rw[i] = rw[i + 1] * alpha + rw[i] * (1.0 - alpha);
+        }
+        cpoints_new[l] = rw[0];
+        cpoints_new[k + r - j - s] = rw[p - j - s];
     }
-
-    #[derive(Deserialize)]
-    struct BasisData
+    for i in l + 1..k - s
     {
-        description: String,
-        values: Vec<Vec<f64>>,
+        cpoints_new[i] = rw[i - l];
     }
+    (knots_new, cpoints_new)
+}
+//..............................................................................................
 
-    #[derive(Deserialize)]
-    struct TestData
-    {
-        u: ParamData,
-        knots_p0: KnotData,
-        knots_p1: KnotData,
-        knots_p2: KnotData,
-        knots_p3: KnotData,
-        knots_p4: KnotData,
-        span_p0: SpanData,
-        span_p1: SpanData,
-        span_p2: SpanData,
-        span_p3: SpanData,
-        span_p4: SpanData,
-        basis_p0: BasisData,
+/// Splits a spline at the parameter value `u` into two splines, the first covering $[u_{0}, u]$ 
+/// and the second covering $[u, u_{m}]$.
+///
+/// The knot `u` is first inserted up to multiplicity `p`, after which the two halves share the 
+/// control point at `u`. Both halves retain the parameterisation of the original spline.
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector of the spline.
+/// - `p`: The degree of the spline.
+/// - `cpoints`: The control points of the spline.
+/// - `u`: The parameter at which to split, must be strictly inside the parameter range.
+///
+/// # Returns
+///
+/// A tuple of the (knot vector, control points) pairs of the two halves.
+pub fn split<T>(
+    knots: &[f64],
+    p: usize,
+    cpoints: &[T],
+    u: f64,
+) -> ((Vec<f64>, Vec<T>), (Vec<f64>, Vec<T>))
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    let u0 = *knots.first().unwrap();
+    let u1 = *knots.last().unwrap();
+    debug_assert!(u > u0 && u < u1 && !knot_eq(u, u0) && !knot_eq(u, u1));
+
+    let s = knots.iter().filter(|&&x| knot_eq(x, u)).count().min(p);
+    let (knots_full, cpoints_full) = insert_knot(knots, p, cpoints, u, p - s);
+
+    let i0 = knots_full.iter().position(|&x| knot_eq(x, u)).unwrap();
+
+    let mut knots_left = knots_full[..i0 + p].to_vec();
+    knots_left.push(u);
+    let cpoints_left = cpoints_full[..i0].to_vec();
+
+    let mut knots_right = vec![u];
+    knots_right.extend_from_slice(&knots_full[i0..]);
+    let cpoints_right = cpoints_full[i0 - 1..].to_vec();
+
+    ((knots_left, cpoints_left), (knots_right, cpoints_right))
+}
+//..............................................................................................
+
+/// Decomposes a clamped spline into its Bézier segments in a single pass over the knots, one
+/// segment for each non-empty knot span.
+///
+/// This is algorithm A5.6 of The NURBS Book. For rational splines the control points must be given
+/// in homogeneous coordinates.
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector of the spline, clamped at both ends.
+/// - `p`: The degree of the spline.
+/// - `cpoints`: The control points of the spline.
+///
+/// # Returns
+///
+/// The $p + 1$ Bézier control points of each segment, in the order of the spans.
+pub fn decompose<T>(
+    knots: &[f64],
+    p: usize,
+    cpoints: &[T],
+) -> Vec<Vec<T>>
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    let m = knots.len() - 1;
+    let mut alphas = [0.0; PMAX];
+    let mut segments = Vec::new();
+    let mut qw = cpoints[..p + 1].to_vec();
+    let mut a = p;
+    let mut b = p + 1;
+    while b < m
+    {
+        let i = b;
+        while b < m && knot_eq(knots[b + 1], knots[b])
+        {
+            b += 1;
+        }
+        let mult = b - i + 1;
+
+        let mut next = vec![cpoints[0]; p + 1];
+        if mult < p
+        {
+            let numer = knots[b] - knots[a];
+            for j in (mult + 1..p + 1).rev()
+            {
+                alphas[j - mult - 1] = numer / (knots[a + j] - knots[a]);
+            }
+            let r = p - mult;
+            for j in 1..r + 1
+            {
+                let s = mult + j;
+                for k in (s..p + 1).rev()
+                {
+                    let alpha = alphas[k - s];
+                    qw[k] = qw[k] * alpha + qw[k - 1] * (1.0 - alpha);
+                }
+                next[r - j] = qw[p];
+            }
+        }
+        if b < m
+        {
+            // a knot of multiplicity p + 1 breaks the spline, the next segment starts afresh
+            for i in p.saturating_sub(mult)..p + 1
+            {
+                next[i] = cpoints[b - p + i];
+            }
+        }
+        segments.push(std::mem::replace(&mut qw, next));
+        a = b;
+        b += 1;
+    }
+    segments
+}
+//..............................................................................................
+
+pub fn multiplicites(knots: &[f64]) -> Vec<(f64, usize)>
+{
+    let mut out = Vec::new();
+    out.reserve(knots.len());
+
+    let mut cur_knot = knots[0];
+    let mut cur_mult = 0;
+
+    for i in 1..knots.len()
+    {
+        cur_mult += 1;
+        if (!knot_eq(knots[i], cur_knot))
+        {
+            out.push((cur_knot, cur_mult));
+            cur_knot = knots[i];
+            cur_mult = 0;
+        }
+    }
+
+    out.push((cur_knot, cur_mult+1));
+    out
+}
+//..............................................................................................
+
+/// Computes the knot vector of a spline after its degree is raised by `t`.
+///
+/// Raising the degree preserves the continuity at each knot, so the multiplicity of every distinct
+/// knot, including the end knots, grows by `t`.
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector of the spline.
+/// - `t`: The number of degrees by which the spline is raised.
+///
+/// # Returns
+///
+/// The knot vector of the raised spline.
+pub fn elevate_knots(
+    knots: &[f64],
+    t: usize,
+) -> Vec<f64>
+{
+    multiplicites(knots)
+        .iter()
+        .flat_map(|(u, m)| std::iter::repeat(*u).take(m + t))
+        .collect()
+}
+//..............................................................................................
+
+/// Computes the binomial coefficient $\binom{n}{k}$.
+pub fn binom(n: usize, k: usize) -> f64
+{
+    let mut out = 1.0;
+    for i in 0..k
+    {
+        out = out * (n - i) as f64 / (i + 1) as f64;
+    }
+    out
+}
+//..............................................................................................
+
+/// Raises the degree of a clamped spline by `t`, without changing its shape or parameterisation.
+///
+/// This is algorithm A5.9 of The NURBS Book, which elevates each Bézier segment in turn and removes
+/// the knots inserted to form it, in a single pass over the knots. For rational splines the control
+/// points must be given in homogeneous coordinates.
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector of the spline, clamped at both ends.
+/// - `p`: The degree of the spline.
+/// - `cpoints`: The control points of the spline.
+/// - `t`: The number of degrees by which the spline is raised.
+///
+/// # Returns
+///
+/// A tuple of the knot vector of [elevate_knots] and the new control points.
+pub fn elevate_degree<T>(
+    knots: &[f64],
+    p: usize,
+    cpoints: &[T],
+    t: usize,
+) -> (Vec<f64>, Vec<T>)
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    let m = knots.len() - 1;
+    let ph = p + t;
+    let ph2 = ph / 2;
+    debug_assert!(ph <= PMAX, "Degree would exceed the maximum");
+
+    //{{{ com: coefficients of the degree elevation of a Bézier segment
+    let mut bezalfs = [[0.0; PMAX + 1]; PMAX + 1];
+    bezalfs[0][0] = 1.0;
+    bezalfs[ph][p] = 1.0;
+    for i in 1..ph2 + 1
+    {
+        let inv = 1.0 / binom(ph, i);
+        for j in i.saturating_sub(t)..p.min(i) + 1
+        {
+            bezalfs[i][j] = inv * binom(p, j) * binom(t, i - j);
+        }
+    }
+    for i in ph2 + 1..ph
+    {
+        for j in i.saturating_sub(t)..p.min(i) + 1
+        {
+            bezalfs[i][j] = bezalfs[ph - i][p - j];
+        }
+    }
+    //}}}
+
+    let mut knots_new = elevate_knots(knots, t);
+    let mut cpoints_new = vec![cpoints[0]; knots_new.len() - ph - 1];
+    let mut bpts = cpoints[..p + 1].to_vec();
+    let mut next_bpts = vec![cpoints[0]; p];
+    let mut ebpts = vec![cpoints[0]; ph + 1];
+    let mut alfs = [0.0; PMAX];
+
+    let mut kind = ph + 1;
+    let mut cind = 1;
+    let mut r: isize = -1;
+    let mut a = p;
+    let mut b = p + 1;
+    let mut ua = knots[0];
+    while b < m
+    {
+        let i = b;
+        while b < m && knot_eq(knots[b], knots[b + 1])
+        {
+            b += 1;
+        }
+        let mul = b - i + 1;
+        let ub = knots[b];
+        let oldr = r;
+        r = p as isize - mul as isize;
+        let lbz = if oldr > 0 { (oldr as usize + 2) / 2 } else { 1 };
+        let rbz = if r > 0 { ph - (r as usize + 1) / 2 } else { ph };
+
+        //{{{ com: insert ub to extract the Bézier segment
+        if r > 0
+        {
+            let numer = ub - ua;
+            for k in (mul + 1..p + 1).rev()
+            {
+                alfs[k - mul - 1] = numer / (knots[a + k] - ua);
+            }
+            for j in 1..r as usize + 1
+            {
+                let s = mul + j;
+                for k in (s..p + 1).rev()
+                {
+                    bpts[k] = bpts[k] * alfs[k - s] + bpts[k - 1] * (1.0 - alfs[k - s]);
+                }
+                next_bpts[r as usize - j] = bpts[p];
+            }
+        }
+        //}}}
+        //{{{ com: elevate the degree of the segment
+        for i in lbz..ph + 1
+        {
+            ebpts[i] = bpts[0] * 0.0;
+            for j in i.saturating_sub(t)..p.min(i) + 1
+            {
+                ebpts[i] = ebpts[i] + bpts[j] * bezalfs[i][j];
+            }
+        }
+        //}}}
+        //{{{ com: remove the knot ua inserted to extract the previous segment
+        if oldr > 1
+        {
+            let oldr = oldr as usize;
+            let mut first = kind - 2;
+            let mut last = kind;
+            let den = ub - ua;
+            let bet = (ub - knots_new[kind - 1]) / den;
+            for tr in 1..oldr
+            {
+                let (mut i, mut j) = (first, last);
+                while j - i > tr
+                {
+                    if i < cind
+                    {
+                        let alf = (ub - knots_new[i]) / (ua - knots_new[i]);
+                        cpoints_new[i] = cpoints_new[i] * alf + cpoints_new[i - 1] * (1.0 - alf);
+                    }
+                    if j >= lbz
+                    {
+                        let kj = j + 1 - kind;
+                        if j - tr <= kind - ph + oldr
+                        {
+                            let gam = (ub - knots_new[j - tr]) / den;
+                            ebpts[kj] = ebpts[kj] * gam + ebpts[kj + 1] * (1.0 - gam);
+                        }
+                        else
+                        {
+                            ebpts[kj] = ebpts[kj] * bet + ebpts[kj + 1] * (1.0 - bet);
+                        }
+                    }
+                    i += 1;
+                    j -= 1;
+                }
+                first -= 1;
+                last += 1;
+            }
+        }
+        //}}}
+
+        if a != p
+        {
+            let oldr = oldr.max(0) as usize;
+            for _ in 0..ph - oldr
+            {
+                knots_new[kind] = ua;
+                kind += 1;
+            }
+        }
+        for j in lbz..rbz + 1
+        {
+            cpoints_new[cind] = ebpts[j];
+            cind += 1;
+        }
+
+        if b < m
+        {
+            let r = r.max(0) as usize;
+            bpts[..r].copy_from_slice(&next_bpts[..r]);
+            for j in r..p + 1
+            {
+                bpts[j] = cpoints[b - p + j];
+            }
+            a = b;
+            b += 1;
+            ua = ub;
+        }
+        else
+        {
+            for i in 0..ph + 1
+            {
+                knots_new[kind + i] = ub;
+            }
+        }
+    }
+    debug_assert_eq!(cind, cpoints_new.len());
+    (knots_new, cpoints_new)
+}
+//..............................................................................................
+
+/// Computes the Greville abscissae of a spline, the averages of `p` consecutive interior knots.
+///
+/// These are the parameters at which the control points of the spline have the most influence, 
+/// interpolating at them always gives a non-singular system.
+///
+/// # Parameters
+///
+/// - `knots`: The knot vector of the spline.
+/// - `p`: The degree of the spline, must be at least one.
+///
+/// # Returns
+///
+/// One parameter per control point, in ascending order.
+pub fn greville(
+    knots: &[f64],
+    p: usize,
+) -> Vec<f64>
+{
+    debug_assert!(p > 0);
+    let n = knots.len() - p - 1;
+    (0..n).map(|i| knots[i + 1..i + p + 1].iter().sum::<f64>() / p as f64).collect()
+}
+//..............................................................................................
+
+/// Finds the knots which must be inserted into the knot vector `into` for it to contain the 
+/// interior knots of `from`.
+///
+/// Each distinct interior knot of `from` is compared, by [knot_eq], against the knots of `into`
+/// and is repeated by the amount its multiplicity in `from` exceeds that in `into`. Multiplicities
+/// are capped at the degree, beyond which a knot cannot be inserted. The end knots are not
+/// considered, the two knot vectors are assumed to share the same parameter range.
+///
+/// # Parameters
+///
+/// - `into`: The knot vector to be refined.
+/// - `from`: The knot vector whose knots are to be contained.
+/// - `p`: The degree of the splines.
+///
+/// # Returns
+///
+/// The knots to insert into `into`, in ascending order and repeated by the number of insertions.
+pub fn missing_knots(
+    into: &[f64],
+    from: &[f64],
+    p: usize,
+) -> Vec<f64>
+{
+    let mults = multiplicites(from);
+    let mut missing = Vec::new();
+    for (u, m) in mults[1..mults.len() - 1].iter()
+    {
+        let m_into = into.iter().filter(|&&x| knot_eq(x, *u)).count();
+        let r = (*m).min(p).saturating_sub(m_into);
+        missing.extend(std::iter::repeat(*u).take(r));
+    }
+    missing
+}
+//..............................................................................................
+
+/// Makes two curves compatible, so that they share the same degree and knot vector, without 
+/// changing their geometry.
+///
+/// Both curves are reparameterised onto $[0, 1]$, the lower degree curve is raised to the degree 
+/// of the other and then the knots of each curve are inserted into the other, up to the larger of
+/// their two multiplicities. Compatible curves can be combined control point by control point, 
+/// as in ruled and lofted surfaces.
+///
+/// # Parameters
+///
+/// - `a`: The first curve, replaced by its compatible form.
+/// - `b`: The second curve, replaced by its compatible form.
+pub fn make_compatible<const D: usize>(
+    a: &mut Bcurve<D>,
+    b: &mut Bcurve<D>,
+)
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    a.set_param_range(0.0, 1.0);
+    b.set_param_range(0.0, 1.0);
+
+    let p = a.p().max(b.p());
+    *a = a.elevate_degree(p - a.p());
+    *b = b.elevate_degree(p - b.p());
+
+    let missing_a = missing_knots(a.knots(), b.knots(), p);
+    let missing_b = missing_knots(b.knots(), a.knots(), p);
+    for u in missing_b
+    {
+        *b = b.insert_knot(u, 1);
+    }
+    for u in missing_a
+    {
+        *a = a.insert_knot(u, 1);
+    }
+}
+//..............................................................................................
+
+/// Makes any number of curves compatible with one another, as [make_compatible] does for two.
+///
+/// The first curve is made compatible with each of the others in turn, which gathers all their
+/// knots into it, and the pass is repeated until the others have caught up with the first.
+///
+/// # Parameters
+///
+/// - `curves`: The curves, each replaced by its compatible form.
+pub fn make_all_compatible<const D: usize>(curves: &mut [Bcurve<D>])
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    let Some((first, rest)) = curves.split_first_mut() else { return };
+    first.set_param_range(0.0, 1.0);
+    loop
+    {
+        for curve in rest.iter_mut()
+        {
+            make_compatible(first, curve);
+        }
+        let same_knots = |curve: &Bcurve<D>| {
+            curve.knots().len() == first.knots().len()
+                && curve.knots().iter().zip(first.knots()).all(|(a, b)| knot_eq(*a, *b))
+        };
+        if rest.iter().all(same_knots)
+        {
+            break;
+        }
+    }
+}
+//..............................................................................................
+
+// ------------------------------------------- Tests -------------------------------------------- //
+#[cfg(test)]
+mod tests
+{
+
+    use super::*;
+
+    use approx::assert_relative_eq;
+    use serde::Deserialize;
+    use std::fs;
+
+    const ZERO_THRESHOLD: f64 = 1e-14;
+
+    #[derive(Deserialize)]
+    struct ParamData
+    {
+        description: String,
+        values: Vec<f64>,
+    }
+
+    #[derive(Deserialize)]
+    struct KnotData
+    {
+        description: String,
+        values: Vec<f64>,
+    }
+
+    #[derive(Deserialize)]
+    struct SpanData
+    {
+        description: String,
+        values: Vec<usize>,
+    }
+
+    #[derive(Deserialize)]
+    struct BasisData
+    {
+        description: String,
+        values: Vec<Vec<f64>>,
+    }
+
+    #[derive(Deserialize)]
+    struct TestData
+    {
+        u: ParamData,
+        knots_p0: KnotData,
+        knots_p1: KnotData,
+        knots_p2: KnotData,
+        knots_p3: KnotData,
+        knots_p4: KnotData,
+        span_p0: SpanData,
+        span_p1: SpanData,
+        span_p2: SpanData,
+        span_p3: SpanData,
+        span_p4: SpanData,
+        basis_p0: BasisData,
         basis_p1: BasisData,
         basis_p2: BasisData,
         basis_p3: BasisData,
@@ -509,6 +1426,126 @@ mod tests
     eval!(eval4, knots_p4, basis_p4, 4);
     //..............................................................................................
 
+    #[test]
+    fn eval_sweep_test()
+    {
+        let p = 3;
+        let test_data = TestData::new();
+        let knots = test_data.knots_p3.values.clone();
+        let umin = *knots.first().unwrap();
+        let umax = *knots.last().unwrap();
+
+        let num_u = 1000;
+        let us: Vec<f64> = (0..num_u)
+            .map(|i| umin + (umax - umin) * (i as f64) / ((num_u - 1) as f64))
+            .collect();
+
+        let mut basis_funs1 = vec![0.0; num_u * (p + 1)];
+        eval_sweep(&knots, p, &us, &mut basis_funs1);
+
+        for (idx, u) in us.iter().enumerate()
+        {
+            let mut basis_funs2 = [0.0; PMAX];
+            eval(&knots, *u, p, &mut basis_funs2);
+            for j in 0..p + 1
+            {
+                assert_eq!(basis_funs1[idx * (p + 1) + j], basis_funs2[j]);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn eval_with_test()
+    {
+        let test_data = TestData::new();
+        let all_knots = [
+            &test_data.knots_p0.values,
+            &test_data.knots_p1.values,
+            &test_data.knots_p2.values,
+            &test_data.knots_p3.values,
+            &test_data.knots_p4.values,
+        ];
+
+        // one scratch serves every degree and knot vector
+        let mut scratch = BasisScratch::new();
+        for (p, knots) in all_knots.iter().enumerate()
+        {
+            for u in test_data.u.values.iter()
+            {
+                let mut funs = [0.0; PMAX + 1];
+                eval(knots, *u, p, &mut funs);
+                assert_eq!(eval_with(&mut scratch, knots, *u, p), &funs[..p + 1]);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn find_span_interior_knots_test()
+    {
+        for p in 2..=4
+        {
+            for m in 2..=3
+            {
+                // an interior knot of multiplicity m between two simple ones
+                let mut knots = vec![0.0; p + 1];
+                knots.push(0.25);
+                knots.extend(vec![0.5; m]);
+                knots.push(0.75);
+                knots.extend(vec![1.0; p + 1]);
+                let n = knots.len() - p - 1;
+
+                for knot in [0.25, 0.5, 0.75]
+                {
+                    let last = knots.iter().rposition(|x| *x == knot).unwrap();
+                    let mut right = [0.0; PMAX + 1];
+                    eval(&knots, knot + 1e-9, p, &mut right);
+                    let mut right_ders = vec![0.0; 2 * (p + 1)];
+                    eval_diff_all(&knots, knot + 1e-9, p, 1, &mut right_ders);
+
+                    // parameters at the knot, up to rounding, lie in the span which starts there
+                    for u in [knot, knot.next_up(), knot.next_down()]
+                    {
+                        let span = find_span(&knots, u, p);
+                        assert_eq!(span, last, "p = {p}, m = {m}, u = {u}");
+                        assert_eq!(non_zero_basis(&knots, u, p), (span - p, span + 1, p + 1));
+
+                        // and the basis and its derivatives are the limits from the right
+                        let mut funs = [0.0; PMAX + 1];
+                        eval(&knots, u, p, &mut funs);
+                        assert_relative_eq!(funs[..p + 1].iter().sum::<f64>(), 1.0, epsilon = 1e-14);
+                        for j in 0..p + 1
+                        {
+                            assert!((funs[j] - right[j]).abs() < 1e-7, "p = {p}, m = {m}, u = {u}");
+                        }
+
+                        let mut ders = vec![0.0; 2 * (p + 1)];
+                        eval_diff_all(&knots, u, p, 1, &mut ders);
+                        for (d, d_right) in ders.iter().zip(right_ders.iter())
+                        {
+                            assert!((d - d_right).abs() < 1e-5, "p = {p}, m = {m}, u = {u}");
+                        }
+
+                        let mut swept = vec![0.0; 2 * (p + 1)];
+                        eval_sweep(&knots, p, &[0.1, u], &mut swept);
+                        assert_eq!(&swept[p + 1..], &funs[..p + 1]);
+                    }
+                }
+
+                // a knot of full multiplicity separates two independent pieces of the spline
+                if m == p + 1
+                {
+                    let mut funs = [0.0; PMAX + 1];
+                    eval(&knots, 0.5, p, &mut funs);
+                    assert_eq!(funs[0], 1.0);
+                }
+                assert_eq!(find_span(&knots, 1.0, p), n - 1);
+            }
+        }
+    }
+    //..............................................................................................
+
     macro_rules! eval_diff {
         ($test_name:ident, $knots:ident, $ders:ident, $order:expr) => {
             #[test]
@@ -617,6 +1654,59 @@ mod tests
     eval_diff_all!(eval_diff_all4, knots_p4, ders_p4, 4);
     //..............................................................................................
 
+    #[test]
+    fn check_degrees_test()
+    {
+        assert!(check_degrees(&[("degree in u", PMAX), ("degree in v", 1)], &[]).is_ok());
+        match check_degrees(&[("degree in u", 2), ("degree in v", PMAX + 1)], &[])
+        {
+            Err(err @ DescriptorError::LimitExceeded { .. }) =>
+            {
+                assert_eq!(err.to_string(), format!("degree in v {} exceeds the maximum of {}", PMAX + 1, PMAX));
+            }
+            _ => panic!("degree limit not reported"),
+        }
+
+        // derivative orders are checked against their own limit
+        assert!(check_degrees(&[("degree", 3)], &[("derivative order", BCURVE_DER_MAX)]).is_ok());
+        match check_degrees(&[("degree", 3)], &[("derivative order", BCURVE_DER_MAX + 1)])
+        {
+            Err(err @ DescriptorError::LimitExceeded { .. }) =>
+            {
+                assert_eq!(
+                    err.to_string(),
+                    format!("derivative order {} exceeds the maximum of {}", BCURVE_DER_MAX + 1, BCURVE_DER_MAX)
+                );
+            }
+            _ => panic!("derivative limit not reported"),
+        }
+    }
+
+    #[test]
+    fn is_sorted_tol_test()
+    {
+        // near-duplicate knots perturbed out of order by floating noise
+        let knots = [0.0, 0.0, 0.0, 0.5f64.next_up(), 0.5, 1.0f64.next_up(), 1.0, 1.0];
+        assert!(!knots.is_sorted());
+        assert!(is_sorted_tol(&knots));
+
+        assert!(is_sorted_tol(&[0.0, 0.25, 0.25, 1.0]));
+        assert!(is_sorted_tol(&[]));
+        assert!(!is_sorted_tol(&[0.0, 0.5, 0.4999, 1.0]));
+    }
+
+    #[test]
+    fn clamp_to_domain_test()
+    {
+        let knots = [0.0, 0.0, 0.5, 1.0, 1.0];
+        assert_eq!(clamp_to_domain(&knots, 1.0f64.next_up()), 1.0);
+        assert_eq!(clamp_to_domain(&knots, (-0.0f64).next_down().next_down()), 0.0);
+        assert_eq!(clamp_to_domain(&knots, 0.25), 0.25);
+        assert_eq!(clamp_to_domain(&knots, 1.1), 1.1);
+        assert_eq!(clamp_to_domain(&knots, -0.1), -0.1);
+    }
+    //..............................................................................................
+
     #[test]
     fn multiplicites_test()
     {
@@ -642,4 +1732,233 @@ mod tests
             assert_eq!(mults1[i].1, mults2[i].1);
         }
     }
+    //..............................................................................................
+
+    #[test]
+    fn decompose_test()
+    {
+        let p = 3;
+        let knots = vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 0.7, 0.7, 0.7, 1.0, 1.0, 1.0, 1.0];
+        let cpoints: Vec<f64> = (0..10).map(|i| ((i * 7) % 5) as f64 - 0.5 * i as f64).collect();
+
+        // inserting every interior knot up to multiplicity p leaves the Bézier points, shared
+        // between neighbouring segments
+        let (mut knots_full, mut cpoints_full) = (knots.clone(), cpoints.clone());
+        for u in [0.25, 0.5, 0.7]
+        {
+            let s = knots_full.iter().filter(|&&x| knot_eq(x, u)).count();
+            (knots_full, cpoints_full) = insert_knot(&knots_full, p, &cpoints_full, u, p - s);
+        }
+
+        let segments = decompose(&knots, p, &cpoints);
+        assert_eq!(segments.len(), 4);
+        for (i, segment) in segments.iter().enumerate()
+        {
+            assert_eq!(segment.len(), p + 1);
+            for (a, b) in segment.iter().zip(&cpoints_full[i * p..(i + 1) * p + 1])
+            {
+                assert_relative_eq!(a, b, epsilon = 1e-14);
+            }
+        }
+
+        // a single span is already in Bézier form
+        let knots_bezier = vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+        assert_eq!(decompose(&knots_bezier, p, &cpoints[..4]), vec![cpoints[..4].to_vec()]);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn elevate_degree_test()
+    {
+        // a quadratic Bézier segment raised to a cubic
+        let (knots, cpoints) = elevate_degree(&[0.0, 0.0, 0.0, 1.0, 1.0, 1.0], 2, &[1.0, 4.0, -2.0], 1);
+        assert_eq!(knots, vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]);
+        for (a, b) in cpoints.iter().zip([1.0, 3.0, 2.0, -2.0])
+        {
+            assert_relative_eq!(*a, b, epsilon = 1e-14);
+        }
+
+        // interior knots of each multiplicity up to the degree keep their continuity
+        let p = 3;
+        let knots = vec![0.0, 0.0, 0.0, 0.0, 0.25, 0.5, 0.5, 0.7, 0.7, 0.7, 1.0, 1.0, 1.0, 1.0];
+        let cpoints: Vec<f64> = (0..10).map(|i| ((i * 7) % 5) as f64 - 0.5 * i as f64).collect();
+        for t in 1..4
+        {
+            let (knots_new, cpoints_new) = elevate_degree(&knots, p, &cpoints, t);
+            assert_eq!(knots_new, elevate_knots(&knots, t));
+            assert_eq!(cpoints_new.len(), knots_new.len() - p - t - 1);
+            for i in 0..=50
+            {
+                let u = i as f64 / 50.0;
+                let val1 = basis_at(&knots, p, u).combine(|j| cpoints[j]);
+                let val2 = basis_at(&knots_new, p + t, u).combine(|j| cpoints_new[j]);
+                assert_relative_eq!(val1, val2, epsilon = 1e-13);
+            }
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn missing_knots_test()
+    {
+        let p = 3;
+        let knots_a = vec![0.0, 0.0, 0.0, 0.0, 0.3, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0];
+        let knots_b = vec![0.0, 0.0, 0.0, 0.0, 0.5, 0.7, 0.7, 0.7, 1.0, 1.0, 1.0, 1.0];
+        let cpoints_a = vec![1.0; knots_a.len() - p - 1];
+        let cpoints_b = vec![1.0; knots_b.len() - p - 1];
+
+        let missing_a = missing_knots(&knots_a, &knots_b, p);
+        let missing_b = missing_knots(&knots_b, &knots_a, p);
+        assert_eq!(missing_a, vec![0.7, 0.7, 0.7]);
+        assert_eq!(missing_b, vec![0.3, 0.5]);
+
+        // inserting the missing knots into each makes the two knot vectors identical
+        let (mut knots_a, mut cpoints_a) = (knots_a, cpoints_a);
+        for u in missing_a
+        {
+            (knots_a, cpoints_a) = insert_knot(&knots_a, p, &cpoints_a, u, 1);
+        }
+        let (mut knots_b, mut cpoints_b) = (knots_b, cpoints_b);
+        for u in missing_b
+        {
+            (knots_b, cpoints_b) = insert_knot(&knots_b, p, &cpoints_b, u, 1);
+        }
+        assert_eq!(knots_a, knots_b);
+        assert_eq!(cpoints_a.len(), cpoints_b.len());
+        assert!(missing_knots(&knots_a, &knots_b, p).is_empty());
+
+        // knots equal up to rounding are not missing, and multiplicities are capped at the degree
+        let knots_c = vec![0.0, 0.0, 0.3f64.next_up(), 0.5, 0.5, 0.5, 1.0, 1.0];
+        assert!(missing_knots(&knots_a, &knots_c, 2).is_empty());
+    }
+
+    /// A quadratic from the fixtures on $[0, 1]$ and a cubic with knots of its own on $[2, 5]$.
+    fn compatible_test_curves() -> (Bcurve<3>, Bcurve<3>)
+    {
+        use crate::common::Vector;
+        use crate::geometry::BcurveDescriptor;
+        use crate::test_utils::test_bcurve::{load_bcurve, TestData as BcurveTestData};
+
+        let a0 = load_bcurve::<3>(2, &BcurveTestData::new());
+        let b0 = Bcurve::new(&BcurveDescriptor {
+            p: 3,
+            knots: vec![2.0, 2.0, 2.0, 2.0, 2.75, 3.5, 3.5, 5.0, 5.0, 5.0, 5.0],
+            cpoints: vec![
+                Vector::<3>::new(0.0, 0.0, 0.0),
+                Vector::<3>::new(1.0, 2.0, 0.0),
+                Vector::<3>::new(2.0, -1.0, 1.0),
+                Vector::<3>::new(3.0, 0.0, 2.0),
+                Vector::<3>::new(4.0, 1.0, 0.0),
+                Vector::<3>::new(5.0, 3.0, -1.0),
+                Vector::<3>::new(6.0, 0.0, 0.0),
+            ],
+            cweights: vec![1.0; 7],
+        });
+        (a0, b0)
+    }
+
+    #[test]
+    fn make_compatible_test()
+    {
+        use crate::geometry::Curve;
+
+        let (a0, b0) = compatible_test_curves();
+        let (mut a, mut b) = (a0.clone(), b0.clone());
+        make_compatible(&mut a, &mut b);
+        assert_eq!(a.p(), 3);
+        assert_eq!(b.p(), 3);
+        assert_eq!(a.knots(), b.knots());
+        assert_eq!(a.cpoints_w().len(), b.cpoints_w().len());
+
+        for i in 0..=100
+        {
+            let s = i as f64 / 100.0;
+            assert_relative_eq!(a.eval(s), a0.eval(s), epsilon = 1e-11);
+            assert_relative_eq!(b.eval(s), b0.eval(2.0 + 3.0 * s), epsilon = 1e-11);
+        }
+    }
+
+    #[test]
+    fn make_all_compatible_test()
+    {
+        use crate::common::Vector;
+        use crate::geometry::{BcurveDescriptor, Curve};
+
+        // a quadratic with a knot of its own is brought in line with both
+        let (a0, b0) = compatible_test_curves();
+        let c0 = Bcurve::new(&BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 0.1, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vector::<3>::new(0.0, 1.0, 0.0),
+                Vector::<3>::new(1.0, 0.0, 1.0),
+                Vector::<3>::new(2.0, 1.0, 0.0),
+                Vector::<3>::new(3.0, 0.0, 1.0),
+            ],
+            cweights: vec![1.0; 4],
+        });
+        let mut curves = [a0.clone(), c0.clone(), b0.clone()];
+        make_all_compatible(&mut curves);
+        for curve in curves.iter()
+        {
+            assert_eq!(curve.p(), 3);
+            assert_eq!(curve.knots(), curves[0].knots());
+        }
+        assert!(curves[0].knots().iter().any(|k| *k == 0.1));
+        for i in 0..=100
+        {
+            let s = i as f64 / 100.0;
+            assert_relative_eq!(curves[0].eval(s), a0.eval(s), epsilon = 1e-11);
+            assert_relative_eq!(curves[1].eval(s), c0.eval(s), epsilon = 1e-11);
+            assert_relative_eq!(curves[2].eval(s), b0.eval(2.0 + 3.0 * s), epsilon = 1e-11);
+        }
+    }
+    //..............................................................................................
+
+    macro_rules! basis_at {
+        ($test_name:ident, $knots:ident, $basis:ident, $order:expr) => {
+            #[test]
+            fn $test_name()
+            {
+                let test_data = TestData::new();
+                let knots = test_data.$knots.values.clone();
+                let cpoints: Vec<f64> = (0..knots.len() - $order - 1).map(|i| (i as f64).sin()).collect();
+
+                for (idx, u) in test_data.u.values.iter().enumerate()
+                {
+                    let basis_funs = &test_data.$basis.values[idx];
+                    let expected: f64 = cpoints.iter().zip(basis_funs.iter()).map(|(c, n)| c * n).sum();
+                    let basis = basis_at(&knots, $order, *u);
+                    assert_relative_eq!(basis.combine(|i| cpoints[i]), expected, epsilon = 1e-13);
+                }
+            }
+        };
+    }
+    basis_at!(basis_at0, knots_p0, basis_p0, 0);
+    basis_at!(basis_at1, knots_p1, basis_p1, 1);
+    basis_at!(basis_at2, knots_p2, basis_p2, 2);
+    basis_at!(basis_at3, knots_p3, basis_p3, 3);
+    basis_at!(basis_at4, knots_p4, basis_p4, 4);
+    //..............................................................................................
+
+    #[test]
+    fn basis_at_bcurve_test()
+    {
+        use crate::common::{inv_homog, Vector};
+        use crate::geometry::Curve;
+        use crate::test_utils::test_bcurve::{load_bcurve, TestData as BcurveTestData};
+
+        // the combination reproduces the direct evaluation of the curve
+        let bcurve_data = BcurveTestData::new();
+        for p in 1..5
+        {
+            let bcurve = load_bcurve::<3>(p, &bcurve_data);
+            for u in bcurve_data.u.values.iter()
+            {
+                let basis = basis_at(bcurve.knots(), p, *u);
+                let pointw: Vector<4> = basis.combine(|i| bcurve.cpoints_w()[i]);
+                assert_relative_eq!(inv_homog(&pointw), bcurve.eval(*u), epsilon = 1e-13);
+            }
+        }
+    }
 }
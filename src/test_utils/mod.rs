@@ -1,7 +1,8 @@
 //! This is a testing-only module with utilitites and access to data used in tests
 //!
 //--------------------------------------------------------------------------------------------------
-use crate::common::Vector;
+use crate::common::{to_vectors, Vec3, Vector};
+use crate::geometry::{Bsurface, BsurfaceDescriptor};
 
 pub const ZERO_THRESHOLD: f64 = 1e-13;
 
@@ -11,19 +12,7 @@ pub mod test_bcurve;
 
 pub fn convert<const D: usize>(data: &Vec<Vec<f64>>) -> Vec<Vector<D>>
 {
-    for val in data
-    {
-        assert!(val.len() == D);
-    }
-
-    // let mut out = Vec::<Vector<D>>::with_capacity(data.len());
-    let mut out = vec![Vector::<D>::zeros(); data.len()];
-
-    for (idx, val) in data.iter().enumerate()
-    {
-        out[idx].copy_from_slice(val);
-    }
-    out
+    to_vectors(data).unwrap()
 }
 
 pub fn de_noise(data: &mut [f64])
@@ -34,4 +23,45 @@ pub fn de_noise(data: &mut [f64])
             *elem = 0.0;
         }
     })
-}
\ No newline at end of file
+}
+
+/// Builds the sphere of the given centre and radius as a biquadratic rational surface, revolving
+/// a half circle from the south pole to the north pole about the z axis.
+///
+/// The full circle in $u$ starts on the positive x axis and runs counter-clockwise through a knot
+/// at each quarter, the half circle in $v$ has a knot at the equator, and the normal points outward.
+pub fn sphere_patch(centre: &Vec3, radius: f64) -> Bsurface<3>
+{
+    let w = 0.5f64.sqrt();
+    let circle = [
+        (1.0, 0.0, 1.0),
+        (1.0, 1.0, w),
+        (0.0, 1.0, 1.0),
+        (-1.0, 1.0, w),
+        (-1.0, 0.0, 1.0),
+        (-1.0, -1.0, w),
+        (0.0, -1.0, 1.0),
+        (1.0, -1.0, w),
+        (1.0, 0.0, 1.0),
+    ];
+    let profile = [(0.0, -1.0, 1.0), (1.0, -1.0, w), (1.0, 0.0, 1.0), (1.0, 1.0, w), (0.0, 1.0, 1.0)];
+
+    let mut cpoints = Vec::new();
+    let mut cweights = Vec::new();
+    for (r, z, wv) in profile.iter()
+    {
+        for (cx, cy, wu) in circle.iter()
+        {
+            cpoints.push(centre + radius * Vec3::new(r * cx, r * cy, *z));
+            cweights.push(wu * wv);
+        }
+    }
+    Bsurface::<3>::new(&BsurfaceDescriptor {
+        p: 2,
+        q: 2,
+        knots_u: vec![0.0, 0.0, 0.0, 0.25, 0.25, 0.5, 0.5, 0.75, 0.75, 1.0, 1.0, 1.0],
+        knots_v: vec![0.0, 0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 1.0],
+        cpoints,
+        cweights,
+    })
+}
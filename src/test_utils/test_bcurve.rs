@@ -6,6 +6,7 @@ use serde::Deserialize;
 
 use std::fs;
 
+use crate::common::Vector;
 use crate::geometry::{Bcurve, BcurveDescriptor, BCURVE_DER_MAX};
 
 use super::convert;
@@ -178,3 +179,35 @@ where
     let bcurve = Bcurve::<D>::new(&bcurve_descriptor);
     bcurve
 }
+
+/// Builds a full circle of radius `radius` about `center`, lying in the plane of the first two
+/// coordinates, as four rational quadratic quarter arcs.
+///
+/// The circle starts on the first axis and runs counter-clockwise through a knot at each quarter.
+pub fn circle<const D: usize>(center: &Vector<D>, radius: f64) -> Bcurve<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    let w = 0.5f64.sqrt();
+    let offsets = [
+        (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (-1.0, 1.0), (-1.0, 0.0),
+        (-1.0, -1.0), (0.0, -1.0), (1.0, -1.0), (1.0, 0.0),
+    ];
+    let cpoints = offsets
+        .iter()
+        .map(|(x, y)| {
+            let mut offset = Vector::<D>::zeros();
+            offset[0] = *x;
+            offset[1] = *y;
+            center + offset * radius
+        })
+        .collect();
+    Bcurve::new(&BcurveDescriptor {
+        p: 2,
+        knots: vec![0.0, 0.0, 0.0, 0.25, 0.25, 0.5, 0.5, 0.75, 0.75, 1.0, 1.0, 1.0],
+        cpoints,
+        cweights: vec![1.0, w, 1.0, w, 1.0, w, 1.0, w, 1.0],
+    })
+}
@@ -15,6 +15,7 @@ pub fn make_region_body() -> Body {
 
     let by = Body::create_node();
     let re = Region::create_node();
+    re.borrow_mut().body = Some(Rc::downgrade(&by));
     by.borrow_mut().append_region(re);
     by
 }
@@ -23,6 +24,7 @@ pub fn make_region_body() -> Body {
 /// edge and no vertices
 pub fn make_open_shell(rg: &Region) -> Shell {
     let sh = Shell::create_node();
+    sh.borrow_mut().region = Some(Rc::downgrade(rg));
     rg.borrow_mut().append_shell(sh.clone());
 
     let fa = Face::create_node();
@@ -32,17 +34,38 @@ pub fn make_open_shell(rg: &Region) -> Shell {
 
     {
         let mut fa_ref = fa.borrow_mut();
-        fa_ref.set_outer_loop(lo);
+        fa_ref.set_outer_loop(lo.clone());
         fa_ref.set_front_shell(sh.clone());
         fa_ref.set_back_shell(sh.clone());
     }
 
     {
-        // let mut lo_ref = lo.borrow_mut();
-        
+        let mut lo_ref = lo.borrow_mut();
+        lo_ref.fin = Some(Rc::downgrade(&fi));
+        lo_ref.face = Some(Rc::downgrade(&fa));
     }
 
+    {
+        // the ring edge has a single fin which is next to itself in the loop and around the edge
+        let mut fi_ref = fi.borrow_mut();
+        fi_ref.looop = Some(Rc::downgrade(&lo));
+        fi_ref.edge = Some(Rc::downgrade(&ed));
+        fi_ref.next_in_loop = Some(Rc::downgrade(&fi));
+        fi_ref.next_around_edge = Some(Rc::downgrade(&fi));
+    }
+    ed.borrow_mut().fins.push(fi);
+
+    {
+        let mut sh_ref = sh.borrow_mut();
+        sh_ref.front_faces.push(fa.clone());
+        sh_ref.back_faces.push(fa);
+    }
 
+    let by = rg.borrow().body.as_ref().and_then(|by| by.upgrade());
+    if let Some(by) = by
+    {
+        by.borrow_mut().edges.push(ed);
+    }
 
     sh
 }
@@ -66,5 +89,17 @@ mod tests
     fn make_open_shell() {  
         let body = make_region_body();
         assert_eq!(body.borrow().num_regions(), 1);
+
+        let region = body.borrow().outer_region();
+        let shell = super::make_open_shell(&region);
+        assert_eq!(region.borrow().shells.len(), 1);
+        assert_eq!(shell.borrow().front_faces.len(), 1);
+        assert_eq!(body.borrow().edges.len(), 1);
+
+        let face = shell.borrow().front_faces[0].clone();
+        let looop = face.borrow().loops[0].clone();
+        let fin = looop.borrow().fin.as_ref().unwrap().upgrade().unwrap();
+        let next = fin.borrow().next_in_loop.as_ref().unwrap().upgrade().unwrap();
+        assert!(Rc::ptr_eq(&fin, &next));
     }
 }
\ No newline at end of file
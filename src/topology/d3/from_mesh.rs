@@ -0,0 +1,205 @@
+//! This module converts triangle meshes into topological shells.
+//!
+//! Each bounded face of a [DynMesh] becomes a face of the shell with a single loop, each pair of
+//! twin mesh fins becomes an edge carrying two fins of opposite sense, and each mesh vertex becomes
+//! a vertex of the body. The result is a manifold shell which can be edited further with the Euler
+//! operators.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::common::ModellerError;
+use crate::mesh::DynMesh;
+use super::schema::*;
+//}}}
+//{{{ std imports
+use std::collections::HashMap;
+use std::rc::Rc;
+//}}}
+//{{{ dep imports
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ fun: shell_from_mesh
+/// Builds a manifold shell in the region `rg` from the faces of a watertight mesh.
+///
+/// The shell owns the new faces as its front faces, their loops run in the same direction as the
+/// loops of the mesh faces. The edges and vertices are owned by the body of the region.
+///
+/// # Arguments
+/// * `rg` - The region which the new shell bounds, it must belong to a body.
+/// * `mesh` - A watertight mesh whose faces are consistently oriented.
+///
+/// # Returns
+/// The new shell, which has been appended to the shells of `rg`, or
+/// [ModellerError::TopologyViolation] if `rg` does not belong to a body, in which case nothing is
+/// created.
+pub fn shell_from_mesh(rg: &Region, mesh: &DynMesh<3>) -> Result<Shell, ModellerError>
+{
+    debug_assert!(mesh.is_watertight(), "mesh must be watertight");
+    let by = rg.borrow().body.as_ref().and_then(|by| by.upgrade()).ok_or_else(|| {
+        ModellerError::TopologyViolation("region does not belong to a body".to_string())
+    })?;
+    let (points, mesh_loops) = mesh.indexed_loops();
+
+    let sh = Shell::create_node();
+    sh.borrow_mut().region = Some(Rc::downgrade(rg));
+    rg.borrow_mut().append_shell(sh.clone());
+
+    //{{{ com: vertices
+    let vertices: Vec<Vertex> = points
+        .iter()
+        .map(|point| {
+            let ve = Vertex::create_node();
+            ve.borrow_mut().point = *point;
+            ve
+        })
+        .collect();
+    //}}}
+    //{{{ com: faces, loops and fins, sharing an edge between each pair of twin fins
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut edge_idx: HashMap<(usize, usize), usize> = HashMap::new();
+    for indices in mesh_loops.iter()
+    {
+        let fa = Face::create_node();
+        let lo = Loop::create_node();
+        let fins: Vec<Fin> = indices.iter().map(|_| Fin::create_node()).collect();
+        let n = fins.len();
+
+        for (i, fi) in fins.iter().enumerate()
+        {
+            let (from, to) = (indices[i], indices[(i + 1) % n]);
+            let key = (from.min(to), from.max(to));
+            let e = *edge_idx.entry(key).or_insert_with(|| {
+                edges.push(Edge::create_node());
+                edges.len() - 1
+            });
+
+            // the edge runs in the direction of the first fin placed on it
+            let mut fi_ref = fi.borrow_mut();
+            fi_ref.looop = Some(Rc::downgrade(&lo));
+            fi_ref.forward_vertex = Some(Rc::downgrade(&vertices[to]));
+            fi_ref.edge = Some(Rc::downgrade(&edges[e]));
+            fi_ref.next_in_loop = Some(Rc::downgrade(&fins[(i + 1) % n]));
+            fi_ref.sense = edges[e].borrow().fins.is_empty();
+            edges[e].borrow_mut().fins.push(fi.clone());
+            vertices[to].borrow_mut().fins.push(Rc::downgrade(fi));
+        }
+
+        {
+            let mut lo_ref = lo.borrow_mut();
+            lo_ref.fin = Some(Rc::downgrade(&fins[0]));
+            lo_ref.face = Some(Rc::downgrade(&fa));
+        }
+        {
+            let mut fa_ref = fa.borrow_mut();
+            fa_ref.set_outer_loop(lo);
+            fa_ref.set_front_shell(sh.clone());
+        }
+        sh.borrow_mut().front_faces.push(fa);
+    }
+    //}}}
+    //{{{ com: cycles of fins around each edge and at each vertex
+    for ed in edges.iter()
+    {
+        let ed_ref = ed.borrow();
+        let n = ed_ref.fins.len();
+        for (i, fi) in ed_ref.fins.iter().enumerate()
+        {
+            fi.borrow_mut().next_around_edge = Some(Rc::downgrade(&ed_ref.fins[(i + 1) % n]));
+        }
+    }
+    for ve in vertices.iter()
+    {
+        let ve_ref = ve.borrow();
+        let n = ve_ref.fins.len();
+        for (i, fi) in ve_ref.fins.iter().enumerate()
+        {
+            let fi = fi.upgrade().unwrap();
+            fi.borrow_mut().next_at_vertex = Some(ve_ref.fins[(i + 1) % n].clone());
+        }
+    }
+    //}}}
+
+    let mut by_ref = by.borrow_mut();
+    by_ref.edges.extend(edges);
+    by_ref.vertices.extend(vertices);
+
+    Ok(sh)
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::Vec3;
+    use crate::topology::d3::bodies_regions_shells::make_region_body;
+    use crate::topology::d3::{from_json, to_json};
+
+    #[test]
+    fn cube_shell()
+    {
+        // the unit cube, two outward facing triangles on each side
+        let points: Vec<Vec3> = (0..8)
+            .map(|i| Vec3::new((i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64))
+            .collect();
+        let quads = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+        let triangles: Vec<[usize; 3]> = quads
+            .iter()
+            .flat_map(|[a, b, c, d]| [[*a, *b, *c], [*a, *c, *d]])
+            .collect();
+        let mesh = DynMesh::<3>::from_triangles(&points, &triangles);
+        assert!(mesh.is_watertight());
+
+        let body = make_region_body();
+        let region = body.borrow().outer_region();
+        let shell = shell_from_mesh(&region, &mesh).unwrap();
+        assert_eq!(region.borrow().shells.len(), 1);
+
+        let num_faces = shell.borrow().front_faces.len();
+        let num_edges = body.borrow().edges.len();
+        let num_vertices = body.borrow().vertices.len();
+        assert_eq!((num_faces, num_edges, num_vertices), (12, 18, 8));
+        assert_eq!(num_vertices as i64 - num_edges as i64 + num_faces as i64, 2);
+
+        // each edge is shared by two fins of opposite sense, which run between the same vertices
+        // in opposite directions
+        for ed in body.borrow().edges.iter()
+        {
+            let ed_ref = ed.borrow();
+            assert_eq!(ed_ref.fins.len(), 2);
+            let (f0, f1) = (ed_ref.fins[0].borrow(), ed_ref.fins[1].borrow());
+            assert!(f0.sense && !f1.sense);
+            assert!(Rc::ptr_eq(&f0.next_around_edge.as_ref().unwrap().upgrade().unwrap(), &ed_ref.fins[1]));
+
+            // in a triangle the fin before is two along the loop, and it points to the tail
+            let tail = |fi: &FinDef| {
+                let prev = fi.next_in_loop.as_ref().unwrap().upgrade().unwrap();
+                let prev = prev.borrow().next_in_loop.as_ref().unwrap().upgrade().unwrap();
+                let point = prev.borrow().forward_vertex.as_ref().unwrap().upgrade().unwrap().borrow().point;
+                point
+            };
+            let head = |fi: &FinDef| fi.forward_vertex.as_ref().unwrap().upgrade().unwrap().borrow().point;
+            assert_eq!(head(&f0), tail(&f1));
+            assert_eq!(tail(&f0), head(&f1));
+        }
+
+        // the cube vertices are each met by three to six fins
+        for ve in body.borrow().vertices.iter()
+        {
+            assert!((3..=6).contains(&ve.borrow().fins.len()));
+        }
+
+        let json = to_json(&body);
+        let body2 = from_json(&json).unwrap();
+        assert_eq!(to_json(&body2), json);
+
+        // a region outside any body could not own the edges and vertices, so it is rejected
+        let lone = Region::create_node();
+        assert!(matches!(shell_from_mesh(&lone, &mesh), Err(ModellerError::TopologyViolation(_))));
+        assert!(lone.borrow().shells.is_empty());
+    }
+}
+//}}}
@@ -0,0 +1,536 @@
+//! This module provides serialization of a topological body to and from JSON.
+//!
+//! The body is flattened into one array of records per entity type. Every reference between
+//! entities, whether owning or weak, is stored as the index of the referenced record in its array.
+//! On load all nodes are created first and then linked, so that weak back-references can be
+//! resolved by index.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::common::{ModellerError, Vec3};
+use super::schema::*;
+//}}}
+//{{{ std imports
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+//}}}
+//{{{ dep imports
+use serde::{Deserialize, Serialize};
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ collection: records
+#[derive(Serialize, Deserialize)]
+struct VertexRecord
+{
+    tag: usize,
+    node_id: usize,
+    point: [f64; 3],
+    fins: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeRecord
+{
+    tag: usize,
+    node_id: usize,
+    fins: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FinRecord
+{
+    tag: usize,
+    node_id: usize,
+    looop: Option<usize>,
+    forward_vertex: Option<usize>,
+    edge: Option<usize>,
+    next_in_loop: Option<usize>,
+    next_around_edge: Option<usize>,
+    next_at_vertex: Option<usize>,
+    sense: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LoopRecord
+{
+    tag: usize,
+    node_id: usize,
+    fin: Option<usize>,
+    face: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FaceRecord
+{
+    tag: usize,
+    node_id: usize,
+    loops: Vec<usize>,
+    front_shell: Option<usize>,
+    back_shell: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShellRecord
+{
+    tag: usize,
+    node_id: usize,
+    ac_vertices: Vec<usize>,
+    wf_edges: Vec<usize>,
+    front_faces: Vec<usize>,
+    back_faces: Vec<usize>,
+    region: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegionRecord
+{
+    tag: usize,
+    node_id: usize,
+    material: RegionMaterial,
+    shells: Vec<usize>,
+    /// Whether the region points back to the body
+    body: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BodyRecord
+{
+    tag: usize,
+    node_id: usize,
+    regions: Vec<usize>,
+    edges: Vec<usize>,
+    vertices: Vec<usize>,
+    region_records: Vec<RegionRecord>,
+    shell_records: Vec<ShellRecord>,
+    face_records: Vec<FaceRecord>,
+    loop_records: Vec<LoopRecord>,
+    fin_records: Vec<FinRecord>,
+    edge_records: Vec<EdgeRecord>,
+    vertex_records: Vec<VertexRecord>,
+}
+//}}}
+//{{{ struct: Registry
+/// Assigns consecutive indices to nodes of one entity type, keyed on the address of the node.
+struct Registry<T>
+{
+    ids: HashMap<*const RefCell<T>, usize>,
+    nodes: Vec<Rc<RefCell<T>>>,
+}
+
+impl<T> Registry<T>
+{
+    fn new() -> Self
+    {
+        Self {
+            ids: HashMap::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Returns the index of the node, registering it if it has not been seen before.
+    fn id(&mut self, node: &Rc<RefCell<T>>) -> usize
+    {
+        let key = Rc::as_ptr(node);
+        match self.ids.get(&key)
+        {
+            Some(id) => *id,
+            None => {
+                let id = self.nodes.len();
+                self.ids.insert(key, id);
+                self.nodes.push(node.clone());
+                id
+            }
+        }
+    }
+
+    /// Returns the index of the node pointed to by a weak reference, if it is still alive.
+    fn weak_id(&mut self, node: &Option<Weak<RefCell<T>>>) -> Option<usize>
+    {
+        node.as_ref().and_then(|w| w.upgrade()).map(|n| self.id(&n))
+    }
+
+    fn ids(&mut self, nodes: &[Rc<RefCell<T>>]) -> Vec<usize>
+    {
+        nodes.iter().map(|n| self.id(n)).collect()
+    }
+}
+//}}}
+//{{{ fun: to_json
+/// Serializes the connectivity of a body to a JSON string.
+///
+/// All entities reachable from the body are written, along with their tags and node ids.
+/// References between entities are written as indices into the per-type record arrays.
+pub fn to_json(body: &Body) -> String
+{
+    //{{{ locals
+    let mut regions = Registry::<RegionDef>::new();
+    let mut shells = Registry::<ShellDef>::new();
+    let mut faces = Registry::<FaceDef>::new();
+    let mut loops = Registry::<LoopDef>::new();
+    let mut fins = Registry::<FinDef>::new();
+    let mut edges = Registry::<EdgeDef>::new();
+    let mut vertices = Registry::<VertexDef>::new();
+
+    let mut region_records = Vec::new();
+    let mut shell_records = Vec::new();
+    let mut face_records = Vec::new();
+    let mut loop_records = Vec::new();
+    let mut fin_records = Vec::new();
+    let mut edge_records = Vec::new();
+    let mut vertex_records = Vec::new();
+    //}}}
+    //{{{ com: body record, this seeds the registries
+    let by = body.borrow();
+    let mut body_record = BodyRecord {
+        tag: by.tag,
+        node_id: by.node_id,
+        regions: regions.ids(&by.regions),
+        edges: edges.ids(&by.edges),
+        vertices: vertices.ids(&by.vertices),
+        region_records: Vec::new(),
+        shell_records: Vec::new(),
+        face_records: Vec::new(),
+        loop_records: Vec::new(),
+        fin_records: Vec::new(),
+        edge_records: Vec::new(),
+        vertex_records: Vec::new(),
+    };
+    //}}}
+    //{{{ com: visit registered nodes until no new nodes are discovered
+    loop
+    {
+        let mut visited = false;
+
+        while region_records.len() < regions.nodes.len()
+        {
+            let re = regions.nodes[region_records.len()].clone();
+            let re = re.borrow();
+            let body_ref = re.body.as_ref().and_then(|w| w.upgrade());
+            region_records.push(RegionRecord {
+                tag: re.tag,
+                node_id: re.node_id,
+                material: re.material,
+                shells: shells.ids(&re.shells),
+                body: body_ref.map_or(false, |b| Rc::ptr_eq(&b, body)),
+            });
+            visited = true;
+        }
+
+        while shell_records.len() < shells.nodes.len()
+        {
+            let sh = shells.nodes[shell_records.len()].clone();
+            let sh = sh.borrow();
+            shell_records.push(ShellRecord {
+                tag: sh.tag,
+                node_id: sh.node_id,
+                ac_vertices: vertices.ids(&sh.ac_vertices),
+                wf_edges: edges.ids(&sh.wf_edges),
+                front_faces: faces.ids(&sh.front_faces),
+                back_faces: faces.ids(&sh.back_faces),
+                region: regions.weak_id(&sh.region),
+            });
+            visited = true;
+        }
+
+        while face_records.len() < faces.nodes.len()
+        {
+            let fa = faces.nodes[face_records.len()].clone();
+            let fa = fa.borrow();
+            face_records.push(FaceRecord {
+                tag: fa.tag,
+                node_id: fa.node_id,
+                loops: loops.ids(&fa.loops),
+                front_shell: shells.weak_id(&fa.front_shell),
+                back_shell: shells.weak_id(&fa.back_shell),
+            });
+            visited = true;
+        }
+
+        while loop_records.len() < loops.nodes.len()
+        {
+            let lo = loops.nodes[loop_records.len()].clone();
+            let lo = lo.borrow();
+            loop_records.push(LoopRecord {
+                tag: lo.tag,
+                node_id: lo.node_id,
+                fin: fins.weak_id(&lo.fin),
+                face: faces.weak_id(&lo.face),
+            });
+            visited = true;
+        }
+
+        while fin_records.len() < fins.nodes.len()
+        {
+            let fi = fins.nodes[fin_records.len()].clone();
+            let fi = fi.borrow();
+            fin_records.push(FinRecord {
+                tag: fi.tag,
+                node_id: fi.node_id,
+                looop: loops.weak_id(&fi.looop),
+                forward_vertex: vertices.weak_id(&fi.forward_vertex),
+                edge: edges.weak_id(&fi.edge),
+                next_in_loop: fins.weak_id(&fi.next_in_loop),
+                next_around_edge: fins.weak_id(&fi.next_around_edge),
+                next_at_vertex: fins.weak_id(&fi.next_at_vertex),
+                sense: fi.sense,
+            });
+            visited = true;
+        }
+
+        while edge_records.len() < edges.nodes.len()
+        {
+            let ed = edges.nodes[edge_records.len()].clone();
+            let ed = ed.borrow();
+            edge_records.push(EdgeRecord {
+                tag: ed.tag,
+                node_id: ed.node_id,
+                fins: fins.ids(&ed.fins),
+            });
+            visited = true;
+        }
+
+        while vertex_records.len() < vertices.nodes.len()
+        {
+            let ve = vertices.nodes[vertex_records.len()].clone();
+            let ve = ve.borrow();
+            vertex_records.push(VertexRecord {
+                tag: ve.tag,
+                node_id: ve.node_id,
+                point: [ve.point[0], ve.point[1], ve.point[2]],
+                fins: ve.fins.iter().filter_map(|w| w.upgrade()).map(|f| fins.id(&f)).collect(),
+            });
+            visited = true;
+        }
+
+        if !visited
+        {
+            break;
+        }
+    }
+    //}}}
+
+    body_record.region_records = region_records;
+    body_record.shell_records = shell_records;
+    body_record.face_records = face_records;
+    body_record.loop_records = loop_records;
+    body_record.fin_records = fin_records;
+    body_record.edge_records = edge_records;
+    body_record.vertex_records = vertex_records;
+
+    serde_json::to_string(&body_record).expect("Could not serialize body")
+}
+//}}}
+//{{{ fun: from_json
+/// Rebuilds a body from a JSON string produced by [to_json].
+///
+/// All nodes are created before any links are made, owning references are then restored as
+/// `Rc` clones and back-references as `Weak` pointers.
+///
+/// # Returns
+/// The body, or [ModellerError::InvalidInput] if the string is not valid JSON and
+/// [ModellerError::TopologyViolation] if it refers to a node which does not exist.
+pub fn from_json(json: &str) -> Result<Body, ModellerError>
+{
+    let rec: BodyRecord =
+        serde_json::from_str(json).map_err(|e| ModellerError::InvalidInput(e.to_string()))?;
+
+    //{{{ com: create all nodes
+    let body = Body::create_node();
+    let regions: Vec<Region> = rec.region_records.iter().map(|_| Region::create_node()).collect();
+    let shells: Vec<Shell> = rec.shell_records.iter().map(|_| Shell::create_node()).collect();
+    let faces: Vec<Face> = rec.face_records.iter().map(|_| Face::create_node()).collect();
+    let loops: Vec<Loop> = rec.loop_records.iter().map(|_| Loop::create_node()).collect();
+    let fins: Vec<Fin> = rec.fin_records.iter().map(|_| Fin::create_node()).collect();
+    let edges: Vec<Edge> = rec.edge_records.iter().map(|_| Edge::create_node()).collect();
+    let vertices: Vec<Vertex> = rec.vertex_records.iter().map(|_| Vertex::create_node()).collect();
+    //}}}
+    //{{{ com: link nodes
+    {
+        let mut by = body.borrow_mut();
+        by.tag = rec.tag;
+        by.node_id = rec.node_id;
+        by.regions = strong_refs(&regions, &rec.regions, "region")?;
+        by.edges = strong_refs(&edges, &rec.edges, "edge")?;
+        by.vertices = strong_refs(&vertices, &rec.vertices, "vertex")?;
+    }
+
+    for (re, r) in regions.iter().zip(rec.region_records.iter())
+    {
+        let mut re = re.borrow_mut();
+        re.tag = r.tag;
+        re.node_id = r.node_id;
+        re.material = r.material;
+        re.shells = strong_refs(&shells, &r.shells, "shell")?;
+        re.body = if r.body { Some(Rc::downgrade(&body)) } else { None };
+    }
+
+    for (sh, r) in shells.iter().zip(rec.shell_records.iter())
+    {
+        let mut sh = sh.borrow_mut();
+        sh.tag = r.tag;
+        sh.node_id = r.node_id;
+        sh.ac_vertices = strong_refs(&vertices, &r.ac_vertices, "vertex")?;
+        sh.wf_edges = strong_refs(&edges, &r.wf_edges, "edge")?;
+        sh.front_faces = strong_refs(&faces, &r.front_faces, "face")?;
+        sh.back_faces = strong_refs(&faces, &r.back_faces, "face")?;
+        sh.region = weak_ref(&regions, r.region, "region")?;
+    }
+
+    for (fa, r) in faces.iter().zip(rec.face_records.iter())
+    {
+        let mut fa = fa.borrow_mut();
+        fa.tag = r.tag;
+        fa.node_id = r.node_id;
+        fa.loops = strong_refs(&loops, &r.loops, "loop")?;
+        fa.front_shell = weak_ref(&shells, r.front_shell, "shell")?;
+        fa.back_shell = weak_ref(&shells, r.back_shell, "shell")?;
+    }
+
+    for (lo, r) in loops.iter().zip(rec.loop_records.iter())
+    {
+        let mut lo = lo.borrow_mut();
+        lo.tag = r.tag;
+        lo.node_id = r.node_id;
+        lo.fin = weak_ref(&fins, r.fin, "fin")?;
+        lo.face = weak_ref(&faces, r.face, "face")?;
+    }
+
+    for (fi, r) in fins.iter().zip(rec.fin_records.iter())
+    {
+        let mut fi = fi.borrow_mut();
+        fi.tag = r.tag;
+        fi.node_id = r.node_id;
+        fi.looop = weak_ref(&loops, r.looop, "loop")?;
+        fi.forward_vertex = weak_ref(&vertices, r.forward_vertex, "vertex")?;
+        fi.edge = weak_ref(&edges, r.edge, "edge")?;
+        fi.next_in_loop = weak_ref(&fins, r.next_in_loop, "fin")?;
+        fi.next_around_edge = weak_ref(&fins, r.next_around_edge, "fin")?;
+        fi.next_at_vertex = weak_ref(&fins, r.next_at_vertex, "fin")?;
+        fi.sense = r.sense;
+    }
+
+    for (ed, r) in edges.iter().zip(rec.edge_records.iter())
+    {
+        let mut ed = ed.borrow_mut();
+        ed.tag = r.tag;
+        ed.node_id = r.node_id;
+        ed.fins = strong_refs(&fins, &r.fins, "fin")?;
+    }
+
+    for (ve, r) in vertices.iter().zip(rec.vertex_records.iter())
+    {
+        let mut ve = ve.borrow_mut();
+        ve.tag = r.tag;
+        ve.node_id = r.node_id;
+        ve.point = Vec3::new(r.point[0], r.point[1], r.point[2]);
+        ve.fins = r.fins.iter()
+                        .map(|&i| weak_ref(&fins, Some(i), "fin").map(|w| w.unwrap()))
+                        .collect::<Result<_, _>>()?;
+    }
+    //}}}
+    Ok(body)
+}
+//}}}
+//{{{ fun: strong_refs
+fn strong_refs<T>(nodes: &[Rc<RefCell<T>>], ids: &[usize], name: &str) -> Result<Vec<Rc<RefCell<T>>>, ModellerError>
+{
+    ids.iter()
+       .map(|&i| nodes.get(i).cloned().ok_or(invalid_index(name, i)))
+       .collect()
+}
+//}}}
+//{{{ fun: weak_ref
+fn weak_ref<T>(nodes: &[Rc<RefCell<T>>], id: Option<usize>, name: &str) -> Result<Option<Weak<RefCell<T>>>, ModellerError>
+{
+    match id
+    {
+        Some(i) => nodes.get(i)
+                        .map(|n| Some(Rc::downgrade(n)))
+                        .ok_or(invalid_index(name, i)),
+        None => Ok(None),
+    }
+}
+//}}}
+//{{{ fun: invalid_index
+fn invalid_index(name: &str, i: usize) -> ModellerError
+{
+    ModellerError::TopologyViolation(format!("Invalid {} index {}", name, i))
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::topology::d3::bodies_regions_shells::{make_open_shell, make_region_body};
+
+    fn upgrade<T>(w: &Option<Weak<RefCell<T>>>) -> Rc<RefCell<T>>
+    {
+        w.as_ref().unwrap().upgrade().unwrap()
+    }
+
+    /// Checks that the back-references of the open shell body point to the owning entities.
+    fn check_open_shell_linkage(body: &Body)
+    {
+        let by = body.borrow();
+        assert_eq!(by.num_regions(), 1);
+        assert_eq!(by.edges.len(), 1);
+
+        let re = by.outer_region();
+        assert!(Rc::ptr_eq(&upgrade(&re.borrow().body), body));
+        assert_eq!(re.borrow().shells.len(), 1);
+
+        let sh = re.borrow().shells[0].clone();
+        assert!(Rc::ptr_eq(&upgrade(&sh.borrow().region), &re));
+        assert_eq!(sh.borrow().front_faces.len(), 1);
+        assert_eq!(sh.borrow().back_faces.len(), 1);
+
+        let fa = sh.borrow().front_faces[0].clone();
+        assert!(Rc::ptr_eq(&fa, &sh.borrow().back_faces[0]));
+        assert!(Rc::ptr_eq(&upgrade(&fa.borrow().front_shell), &sh));
+        assert!(Rc::ptr_eq(&upgrade(&fa.borrow().back_shell), &sh));
+        assert_eq!(fa.borrow().loops.len(), 1);
+
+        let lo = fa.borrow().loops[0].clone();
+        assert!(Rc::ptr_eq(&upgrade(&lo.borrow().face), &fa));
+
+        let fi = upgrade(&lo.borrow().fin);
+        assert!(Rc::ptr_eq(&upgrade(&fi.borrow().looop), &lo));
+        assert!(Rc::ptr_eq(&upgrade(&fi.borrow().next_in_loop), &fi));
+        assert!(Rc::ptr_eq(&upgrade(&fi.borrow().next_around_edge), &fi));
+        assert!(fi.borrow().forward_vertex.is_none());
+
+        let ed = upgrade(&fi.borrow().edge);
+        assert!(Rc::ptr_eq(&ed, &by.edges[0]));
+        assert_eq!(ed.borrow().fins.len(), 1);
+        assert!(Rc::ptr_eq(&ed.borrow().fins[0], &fi));
+    }
+
+    #[test]
+    fn round_trip()
+    {
+        let body = make_region_body();
+        let region = body.borrow().outer_region();
+        make_open_shell(&region);
+        check_open_shell_linkage(&body);
+
+        let json = to_json(&body);
+        let body2 = from_json(&json).unwrap();
+        check_open_shell_linkage(&body2);
+        assert_eq!(to_json(&body2), json);
+    }
+
+    #[test]
+    fn invalid_index()
+    {
+        let body = make_region_body();
+        let json = to_json(&body).replace("\"regions\":[0]", "\"regions\":[3]");
+        assert!(matches!(from_json(&json), Err(ModellerError::TopologyViolation(_))));
+        assert!(matches!(from_json("not json"), Err(ModellerError::InvalidInput(_))));
+    }
+}
+//}}}
@@ -5,4 +5,8 @@
 
 
 pub mod schema;
-pub mod bodies_regions_shells;
\ No newline at end of file
+pub mod bodies_regions_shells;
+pub mod json;
+pub mod from_mesh;
+pub use json::{from_json, to_json};
+pub use from_mesh::shell_from_mesh;
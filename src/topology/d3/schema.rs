@@ -8,6 +8,7 @@ use crate::common::Vec3;
 use std::borrow::{Borrow, BorrowMut};
 use std::rc::{Rc, Weak};
 use std::cell::{Ref, RefCell};
+use serde::{Deserialize, Serialize};
 
 
 const UID_NULL: usize = usize::MAX;
@@ -47,13 +48,13 @@ pub trait Node {
 /// - An acorn vertex, which exists on its own and is bounded by a minimal shell.
 pub struct VertexDef 
 {
-    tag: usize,
-    node_id: usize,
+    pub(super) tag: usize,
+    pub(super) node_id: usize,
 
     /// Point in 3-space of the vertex
-    point: Vec3, 
+    pub(super) point: Vec3, 
     /// Set of fins which point to this vertex
-    fins: Vec<Finw>,
+    pub(super) fins: Vec<Finw>,
 }
 
 impl VertexDef 
@@ -97,11 +98,11 @@ impl Node for Vertex
 /// the edge 
 pub struct EdgeDef
 {
-    tag: usize, 
-    node_id: usize, 
+    pub(super) tag: usize, 
+    pub(super) node_id: usize, 
 
     /// Set of fins attached to the edge in counter-clockwise order
-    fins: Vec<Fin>, 
+    pub(super) fins: Vec<Fin>, 
 
 }
 
@@ -143,23 +144,23 @@ pub struct FinDef
 {
 
     /// tag in the session 
-    tag: usize,
+    pub(super) tag: usize,
     /// node id in the body
-    node_id: usize,
+    pub(super) node_id: usize,
 
-    looop: Option<Loopw>,
+    pub(super) looop: Option<Loopw>,
     /// forward vertex of find
-    forward_vertex: Option<Vertexw>,
+    pub(super) forward_vertex: Option<Vertexw>,
     /// Edge to which fin belongs
-    edge: Option<Edgew>,
+    pub(super) edge: Option<Edgew>,
     /// next fin in the loop, this is owning and it keeps the chain alive
-    next_in_loop: Option<Finw>,
+    pub(super) next_in_loop: Option<Finw>,
     /// next fin on edge looking counter-clockwise down edge
-    next_around_edge: Option<Finw>,
+    pub(super) next_around_edge: Option<Finw>,
     /// next fin referencing the vertex of this fin
-    next_at_vertex: Option<Finw>,
+    pub(super) next_at_vertex: Option<Finw>,
     /// same direction as edges (+ve), opposite direction as edge (-ve)
-    sense: bool,
+    pub(super) sense: bool,
 
 }
 
@@ -204,13 +205,13 @@ impl Node for Fin
 
 pub struct LoopDef 
 {
-    tag: usize,
-    node_id: usize,
+    pub(super) tag: usize,
+    pub(super) node_id: usize,
 
     /// first fin in loop
-    fin: Option<Finw>,
+    pub(super) fin: Option<Finw>,
     /// Face to which loop belongs
-    face: Option<Facew>,
+    pub(super) face: Option<Facew>,
 
 }
 
@@ -251,15 +252,15 @@ impl Node for Loop
 
 pub struct FaceDef
 {
-    tag: usize,
-    node_id: usize,
+    pub(super) tag: usize,
+    pub(super) node_id: usize,
 
     /// First loop outer loop, other loops are holes
-    loops: Vec<Loop>,
+    pub(super) loops: Vec<Loop>,
     /// shell of which this is a front face
-    front_shell: Option<Shellw>,
+    pub(super) front_shell: Option<Shellw>,
     /// shell of which this is a back face
-    back_shell: Option<Shellw>, 
+    pub(super) back_shell: Option<Shellw>, 
 
 }
 
@@ -320,21 +321,21 @@ impl Node for Face
 
 pub struct ShellDef
 {
-    tag: usize, 
-    node_id: usize,
+    pub(super) tag: usize, 
+    pub(super) node_id: usize,
 
     /// Acorn vertices
-    ac_vertices: Vec<Vertex>,
+    pub(super) ac_vertices: Vec<Vertex>,
     /// Wireframe edges
-    wf_edges: Vec<Edge>,
+    pub(super) wf_edges: Vec<Edge>,
     /// Set of front faces, faces with normal into shell region
     /// shells own their front faces
-    front_faces: Vec<Face>,
+    pub(super) front_faces: Vec<Face>,
     /// Set of back faces, faces with normal pointer out of shell region
     /// Shells do not own their back faces
-    back_faces: Vec<Face>,
+    pub(super) back_faces: Vec<Face>,
     /// Region which this shell bounds
-    region: Option<Regionw>,
+    pub(super) region: Option<Regionw>,
 }
 
 impl ShellDef
@@ -377,6 +378,7 @@ impl Node for Shell
 }
 //..................................................................................................
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RegionMaterial {
     Void, 
     Solid,
@@ -384,12 +386,12 @@ pub enum RegionMaterial {
 
 pub struct RegionDef 
 {
-    tag: usize, 
-    node_id: usize,
-    material: RegionMaterial,
+    pub(super) tag: usize, 
+    pub(super) node_id: usize,
+    pub(super) material: RegionMaterial,
 
-    shells: Vec<Shell>,
-    body: Option<Bodyw>,
+    pub(super) shells: Vec<Shell>,
+    pub(super) body: Option<Bodyw>,
 
 }
 
@@ -438,15 +440,15 @@ impl Node for Region
 
 pub struct BodyDef
 {
-    tag: usize, 
-    node_id: usize, 
+    pub(super) tag: usize, 
+    pub(super) node_id: usize, 
 
     /// Set of regions which constitute the body
-    regions: Vec<Region>,
+    pub(super) regions: Vec<Region>,
     /// Set of non-wireframe edges in body
-    edges: Vec<Edge>,
+    pub(super) edges: Vec<Edge>,
     /// Set of non-acorn vertices 
-    vertices: Vec<Vertex>,
+    pub(super) vertices: Vec<Vertex>,
 }
 
 /// Body is the ref-counted pointer to the BodyDef struct
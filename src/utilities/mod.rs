@@ -11,7 +11,7 @@ use num_traits::Float;
 mod ndarray;
 
 
-pub use ndarray::{NDArrayWrapper, IndexHelper};
+pub use ndarray::{NDArray, NDArrayWrapper, IndexHelper};
 
 
 use crate::common::ResConstants;
@@ -21,15 +21,20 @@ pub fn lower_bound<T: PartialOrd>(slice: &[T], value: T) -> usize {
         .unwrap_or_else(|x| x)
 }
 
-pub fn normalize_min_max<T>(fvals: &mut Vec::<T>)
+/// Rescales the values in place onto $[0, 1]$ by their minimum and maximum, values which are all 
+/// equal to within the linear resolution are left unchanged.
+///
+/// # Returns
+/// The minimum and maximum of the original values, the range mapped onto $[0, 1]$.
+pub fn normalize_min_max<T>(fvals: &mut Vec::<T>) -> (T, T)
 where 
     T: Float + ResConstants 
 {
-        let mut min_f = *fvals
+        let min_f = *fvals
             .iter()
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
-        let mut max_f = *fvals
+        let max_f = *fvals
             .iter()
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
@@ -39,4 +44,24 @@ where
                 *x = (*x - min_f) / (max_f - min_f);
             });
         }
-}
\ No newline at end of file
+        (min_f, max_f)
+}
+
+//-------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn normalize_min_max_test()
+    {
+        let mut fvals = vec![3.0, -1.0, 1.0];
+        assert_eq!(normalize_min_max(&mut fvals), (-1.0, 3.0));
+        assert_eq!(fvals, vec![1.0, 0.0, 0.5]);
+
+        let mut fvals = vec![2.0, 2.0];
+        assert_eq!(normalize_min_max(&mut fvals), (2.0, 2.0));
+        assert_eq!(fvals, vec![2.0, 2.0]);
+    }
+}
@@ -129,6 +129,78 @@ impl<'a, T, const N: usize> IndexMut<&[usize; N]> for NDArrayWrapper<'a, T, N>
 }
 //..................................................................................................
 
+/// Provides an owning multi-dimensional array.
+/// 
+/// This is the owning counterpart of [NDArrayWrapper], indexing is provided by [IndexHelper] so 
+/// the leftmost index varies the fastest.
+pub struct NDArray<T, const N: usize> {
+    /// Underlying data
+    data: Vec<T>,
+    /// Indexing helper
+    idx_helper: IndexHelper<N>,
+}
+//..................................................................................................
+
+impl<T: Clone, const N: usize> NDArray<T, N> {
+
+    /// Creates a new array of the given dimensions with every element set to `value`.
+    pub fn new(dims: &[usize], value: T) -> Self {
+        debug_assert!(dims.len() >= N);
+
+        let len = dims[0..N].iter().product();
+        NDArray::<T, N> {
+            data: vec![value; len],
+            idx_helper: IndexHelper::new(dims),
+        }
+    }
+}
+//..................................................................................................
+
+impl<T, const N: usize> NDArray<T, N> {
+
+    /// Creates a new array of the given dimensions which takes ownership of `data`.
+    pub fn from_vec(data: Vec<T>, dims: &[usize]) -> Self {
+        debug_assert!(dims.len() >= N);
+        debug_assert!(data.len() == dims[0..N].iter().product::<usize>());
+
+        NDArray::<T, N> {
+            data: data,
+            idx_helper: IndexHelper::new(dims),
+        }
+    }
+
+    /// Returns the dimensions of the array.
+    pub fn dims(&self) -> &[usize; N] {
+        &self.idx_helper.dims
+    }
+
+    /// Returns the underlying data in linear order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+}
+//..................................................................................................
+
+impl<T, const N: usize> Index<&[usize; N]> for NDArray<T, N>
+{
+    type Output = T;
+
+    fn index(&self, index_tuple: &[usize; N]) -> &Self::Output {
+        let idx = self.idx_helper.lin_index(index_tuple);
+        &self.data[idx]
+    }
+}
+//..................................................................................................
+
+impl<T, const N: usize> IndexMut<&[usize; N]> for NDArray<T, N>
+{
+    fn index_mut(&mut self, index_tuple: &[usize; N]) -> &mut Self::Output {
+        let idx = self.idx_helper.lin_index(index_tuple);
+        &mut self.data[idx]
+    }
+}
+//..................................................................................................
+
 impl<'a, T, const N: usize> fmt::Display for NDArrayWrapper<'a, T, N>
 where 
     T: fmt::Display
@@ -189,7 +261,7 @@ where
 
 // ------------------------------------------- Tests -------------------------------------------- //
 mod tests {
-    use crate::utilities::NDArrayWrapper;
+    use crate::utilities::{NDArray, NDArrayWrapper};
 
     #[test]
     fn linear_index2() {
@@ -245,7 +317,26 @@ mod tests {
 
     } 
 
+    #[test]
+    fn owned_index2() {
+
+        let data: Vec<f64> = (0..12).map(|n| n as f64).collect();
+        let mut arr = NDArray::<f64, 2>::from_vec(data, &[3, 4]);
+        assert_eq!(arr.dims(), &[3, 4]);
+
+        let mut val1 = 0.0;
+        for j in 0..4
+        {
+            for i in 0..3
+            {
+                assert_eq!(arr[&[i, j]], val1);
+                val1 += 1.0;
+            }
+        }
 
+        arr[&[2, 1]] = -1.0;
+        assert_eq!(arr.as_slice()[5], -1.0);
+    }
 
     #[test]
     fn print1() {
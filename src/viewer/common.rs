@@ -4,7 +4,8 @@
 //--------------------------------------------------------------------------------------------------
 
 //{{{ crate imports 
-use crate::common::{Vec2, Vec3, Vector};
+use crate::common::{ResConstants, Vec2, Vec3, Vector};
+use crate::utilities::normalize_min_max;
 //}}}
 //{{{ std imports 
 use std::fmt::Debug;
@@ -45,6 +46,11 @@ pub enum CurveViewMethod
     Uniform,
     /// Points clustered in areas of high curvature
     Curvature,
+    /// Points evenly spaced along the curve in arc length
+    ArcLength,
+    /// The given number of points uniformly spaced in parameter space within each knot span, so
+    /// that narrow spans are drawn as finely as wide ones
+    PerSpan(usize),
 }
 //}}}
 //{{{ impl: Default for CurveViewMethod
@@ -66,6 +72,9 @@ pub enum CurveColor<const D: usize>
     Solid(Color),
     ParamFunction(Box<dyn Fn(f64) -> f64>),
     PositionFunction(Box<dyn Fn(Vector<D>) -> f64>),
+    /// Colors by a function of the fraction of the arc length of the curve, from 0 at its start to
+    /// 1 at its end, so that the coloring does not depend on the parameterisation
+    ArcLengthFunction(Box<dyn Fn(f64) -> f64>),
 }
 //}}}
 //{{{ impl : Default for CurveColor
@@ -77,6 +86,50 @@ impl<const D: usize> Default for CurveColor<D>
     }
 }
 //}}}
+//{{{ impl: CurveColor
+impl<const D: usize> CurveColor<D>
+{
+    /// Computes the colors of the vertices `points`, at the parameters `params`, of a curve.
+    ///
+    /// When coloring by a function its values are rescaled onto the color ramp, see 
+    /// [ramp_color], by their minimum and maximum over the vertices. The arc length fractions of
+    /// the vertices are only computed, by `arc_fractions`, when coloring by arc length.
+    ///
+    /// # Returns
+    /// A tuple of the vertex colors and, when coloring by a function, the range of function values
+    /// mapped onto the ramp.
+    pub fn sample(
+        &self,
+        params: &[f64],
+        points: &[Vector<D>],
+        arc_fractions: impl FnOnce() -> Vec<f64>,
+    ) -> (Vec<Color>, Option<ColorScale>)
+    {
+        let mut fvals: Vec<f64> = match self
+        {
+            CurveColor::None => return (vec![Color::default(); points.len()], None),
+            CurveColor::Solid(color) => return (vec![*color; points.len()], None),
+            CurveColor::ParamFunction(f) => params.iter().map(|u| f(*u)).collect(),
+            CurveColor::PositionFunction(f) => points.iter().map(|p| f(*p)).collect(),
+            CurveColor::ArcLengthFunction(f) => arc_fractions().iter().map(|s| f(*s)).collect(),
+        };
+        if fvals.is_empty()
+        {
+            return (Vec::new(), None);
+        }
+        let (min, max) = normalize_min_max(&mut fvals);
+        let colors = if max - min > f64::RES_LINEAR 
+        {
+            fvals.iter().map(|t| ramp_color(*t)).collect()
+        }
+        else
+        {
+            vec![ramp_color(0.0); fvals.len()]
+        };
+        (colors, Some(ColorScale { min, max }))
+    }
+}
+//}}}
 //{{{ impl: Debug for CurveColor
 impl<const D: usize> Debug for CurveColor<D>
 {
@@ -86,12 +139,47 @@ impl<const D: usize> Debug for CurveColor<D>
             CurveColor::Solid(c) => write!(f, "Solid({:?})", c),
             CurveColor::ParamFunction(_) => write!(f, "ParamFunction"),
             CurveColor::PositionFunction(_) => write!(f, "PositionFunction"),
+            CurveColor::ArcLengthFunction(_) => write!(f, "ArcLengthFunction"),
         }
     }
 }
 //}}}
 //..................................................................................................
 //}}}
+//{{{ collection: ColorScale
+//{{{ struct: ColorScale
+/// The range of values mapped onto the color ramp when coloring by a function, so that a legend
+/// can be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ColorScale
+{
+    /// The value mapped to the bottom of the ramp
+    pub min: f64,
+    /// The value mapped to the top of the ramp
+    pub max: f64,
+}
+//}}}
+//{{{ fun: ramp_color
+/// Maps a value in $[0, 1]$ onto the color ramp, which runs from blue through white to red in
+/// three equal bands.
+pub fn ramp_color(t: f64) -> Color
+{
+    if t < 1.0 / 3.0
+    {
+        Color::Blue
+    }
+    else if t < 2.0 / 3.0
+    {
+        Color::White
+    }
+    else
+    {
+        Color::Red
+    }
+}
+//}}}
+//..................................................................................................
+//}}}
 pub enum SurfaceColor
 {
     Solid(Color),
@@ -4,7 +4,7 @@ pub use topohedral_viewer::{Color, d2, d3};
 //..................................................................................................
 // core 
 mod common;
-pub use common::{Viewable, CurveColor, CurveViewMethod, SurfaceColor, tv};
+pub use common::{Viewable, ColorScale, CurveColor, CurveViewMethod, SurfaceColor, ramp_color, tv};
 //..................................................................................................
 // misc
 mod view_box;
@@ -14,9 +14,11 @@ pub use view_box::{ABoxViewOptions};
 mod view_line;
 mod view_bcurve;
 pub use view_line::{LineViewOptions};
-pub use view_bcurve::{BcurveViewOptions, CtrlPointOptions};
+pub use view_bcurve::{BcurveViewOptions, CtrlPointOptions, IndicatrixOptions};
 //..................................................................................................
 // surfaces
 mod view_plane;
+mod view_bsurface;
 pub use view_plane::{PlaneViewOptions};
+pub use view_bsurface::{BsurfaceViewOptions, WireframeOptions};
 //..................................................................................................
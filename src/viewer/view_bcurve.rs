@@ -7,9 +7,10 @@
 use crate::boxing::ABoxable;
 use crate::common::{Vec3, Vector};
 use crate::geometry::{Bcurve, Curve, BCURVE_DER_MAX};
-use crate::viewer::common::{tv, Convert, Viewable, CurveViewMethod, CurveColor};
+use crate::viewer::common::{tv, Convert, Viewable, ColorScale, CurveViewMethod, CurveColor};
 //}}}
 //{{{ std imports 
+use std::cell::Cell;
 //}}}
 //{{{ dep imports 
 use topohedral_viewer::{CellType, Color, d2, d3, d2::Mesh2D, d3::Mesh3D};
@@ -34,6 +35,25 @@ impl Default for CtrlPointOptions
 }
 //..................................................................................................
 //}}}
+//{{{ enum: IndicatrixOptions
+/// Options for viewing the tangent indicatrix of a 3D curve, drawn on the unit sphere centred at
+/// the origin
+pub enum IndicatrixOptions
+{
+    NoIndicatrix,
+    WithIndicatrix(Color),
+}
+//}}}
+//{{{ impl Default for IndicatrixOptions
+impl Default for IndicatrixOptions
+{
+    fn default() -> Self
+    {
+        IndicatrixOptions::NoIndicatrix
+    }
+}
+//..................................................................................................
+//}}}
 //{{{ struct: BcurveViewOptions
 /// Options for Viewing a B-spline curve
 #[derive(Default)]
@@ -41,7 +61,7 @@ pub struct BcurveViewOptions<const D: usize>
 {
     /// Method to use
     pub method: CurveViewMethod,
-    /// Number of divisions in Viewed curve
+    /// Number of divisions in Viewed curve, a value of 0 is taken as 1
     pub num_div: usize,
     /// Color options for the curve
     pub color: CurveColor<D>,
@@ -49,50 +69,156 @@ pub struct BcurveViewOptions<const D: usize>
     pub with_param_pts: bool,
     /// Controls whether to include the control points in the visualization
     pub with_ctrl_pts: CtrlPointOptions,
+    /// Controls whether to include the tangent indicatrix in the visualization, 3D only
+    pub with_indicatrix: IndicatrixOptions,
+    /// Set by the viewer when the curve is colored by a function, to the range of sampled function
+    /// values mapped onto the color ramp
+    pub color_scale: Cell<Option<ColorScale>>,
+}
+//..................................................................................................
+//}}}
+//{{{ impl<const D: usize> BcurveViewOptions<D>
+impl<const D: usize> BcurveViewOptions<D>
+{
+    /// Returns the number of divisions to view the curve with, which is at least one so that a
+    /// curve is always drawn as at least the segment between its ends.
+    pub fn divisions(&self) -> usize
+    {
+        self.num_div.max(1)
+    }
 }
 //..................................................................................................
 //}}}
+//{{{ fun: arc_fractions
+/// Computes the fraction of the arc length of `curve` from its start to each of the increasing
+/// parameters `params`, the length between consecutive parameters is integrated once.
+fn arc_fractions<const D: usize>(
+    curve: &Bcurve<D>,
+    params: &[f64],
+) -> Vec<f64>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    let (u1, u2) = curve.param_range();
+    let length = curve.eval_arclen(u1, u2);
+    if length <= 0.0
+    {
+        return vec![0.0; params.len()];
+    }
+
+    let mut fractions = Vec::with_capacity(params.len());
+    let (mut u_prev, mut s) = (u1, 0.0);
+    for u in params
+    {
+        s += curve.eval_arclen(u_prev, *u);
+        fractions.push(s / length);
+        u_prev = *u;
+    }
+    fractions
+}
+//}}}
+//{{{ impl<const D: usize> Bcurve<D>
+impl<const D: usize> Bcurve<D>
+where
+    [(); D + 1]:,
+    [(); D * BCURVE_DER_MAX]:,
+    [(); D * 3]:,
+{
+    //{{{ fun: sample_view
+    /// Samples the curve by the method in `opts` and passes the parameters of the samples to
+    /// `sink`, which builds the mesh through them and sends it to the viewer.
+    fn sample_view<S: FnOnce(&[f64])>(
+        &self,
+        opts: &BcurveViewOptions<D>,
+        sink: S,
+    )
+    {
+        let params = match opts.method
+        {
+            CurveViewMethod::Uniform => self.sample_params(opts.divisions()),
+            CurveViewMethod::Curvature => self.curvature_params(opts.divisions()),
+            CurveViewMethod::ArcLength => self.arc_length_params(opts.divisions()),
+            CurveViewMethod::PerSpan(num_per_span) => self.per_span_params(num_per_span),
+        };
+        sink(&params);
+    }
+    //}}}
+    //{{{ fun: curvature_params
+    /// Returns `n + 1` parameters with greater sampling density on areas of high curvature.
+    fn curvature_params(
+        &self,
+        n: usize,
+    ) -> Vec<f64>
+    {
+        todo!()
+    }
+    //}}}
+}
+//}}}
 //{{{ collection: 2D Viewing 
 //{{{ impl: Bcurve<2>
 impl Bcurve<2>
 {
-
-    /// This method renders the B-curve with greater sampling density on areas of high curvature.
-    fn view_curvature(
-        &mut self,
+    /// Sends the line mesh through the curve points at `params` to the viewer
+    fn view_params(
+        &self,
         port: usize,
+        params: &[f64],
         opts: &BcurveViewOptions<2>,
     )
     {
-        todo!()
+        let mesh = self.line_mesh(params, opts);
+
+        match d2::Client2D::new(port) {
+            Ok(mut client) => {
+                match client.add_mesh(mesh){
+                    Ok(mesh_id) => {
+                        //{{{ trace
+                        info!("Curve added with id: {}", mesh_id);
+                        //}}}
+                    }
+                    Err(e) => {
+                        //{{{ trace
+                        error!("Failed to add curve: {}", e);
+                        //}}}
+                    }
+                }
+            }
+            Err(e) => {
+                //{{{ trace
+                error!("Failed to connect to client: {}", e);
+                //}}}
+            }
+        }
     }
 
-    /// This method renders the B-curve with an even distribution of sample points in parameter 
-    /// space
-    fn view_uniform(
-        &mut self,
-        port: usize,
+    /// Builds the line mesh through the curve points at `params`, recording the color scale in the
+    /// options.
+    fn line_mesh(
+        &self,
+        params: &[f64],
         opts: &BcurveViewOptions<2>,
-    )
+    ) -> d2::Mesh
     {
-        let nl = opts.num_div;    
-        let np = nl + 1;
-        let u1  = *self.knots().first().unwrap();
-        let u2 = *self.knots().last().unwrap();
-        let du = (u2 - u1) / nl as f64; 
+        let nl = params.len() - 1;
+
+        let points: Vec<Vector<2>> = params.iter().map(|u| self.eval(*u)).collect();
+        let (colors, scale) = opts.color.sample(params, &points, || arc_fractions(self, params));
+        opts.color_scale.set(scale);
 
         let mut mesh = d2::Mesh::from_num_lines(nl);
-        for i in 0..np
+        for (p, color) in points.iter().zip(colors.iter())
         {
-            let u = u1 + i as f64 * du;
-            let p = self.eval(u);
-            mesh.add_vertex(&p.convert(), &Color::default(), &Color::default())
+            mesh.add_vertex(&p.convert(), color, color)
         }
 
         for i in 0..nl
         {
             mesh.add_line_indices(i as u32, (i+1) as u32);
         }
+        mesh
     }
 }
 //}}}
@@ -107,11 +233,7 @@ impl Viewable for Bcurve<2>
         opts: &Self::Options,
     )
     {
-        match opts.method
-        {
-            CurveViewMethod::Uniform => self.view_uniform(port, opts),
-            CurveViewMethod::Curvature => self.view_curvature(port, opts),
-        };
+        self.sample_view(opts, |params| self.view_params(port, params, opts));
     }
 }
 //}}}
@@ -120,56 +242,88 @@ impl Viewable for Bcurve<2>
 //{{{ impl: Bcurve<3>
 impl Bcurve<3>
 {
-    //{{{ fun: view_curvature
-    /// This method renders the B-curve with greater sampling density on areas of high curvature.
-    fn view_curvature(
-        &mut self,
+    //{{{ fun: view_params
+    /// Sends the line mesh through the curve points at `params` to the viewer.
+    fn view_params(
+        &self,
         port: usize,
+        params: &[f64],
         opts: &BcurveViewOptions<3>,
     )
     {
-        todo!()
+        let mesh = self.line_mesh(params, opts);
+
+        match d3::Client3D::new(port) {
+            Ok(mut client) => {
+                match client.add_mesh(mesh){
+                    Ok(mesh_id) => {
+                        //{{{ trace
+                        info!("Plane added with id: {}", mesh_id);
+                        //}}}
+                    }
+                    Err(e) => {
+                        //{{{ trace
+                        error!("Failed to add plane: {}", e);
+                        //}}}
+                    }
+                }
+            }
+            Err(e) => {
+                //{{{ trace
+                error!("Failed to connect to client: {}", e);
+                //}}}
+            }
+        }
+
+
     }
     //}}}
-    //{{{ fun: view_uniform
-    /// This method renders the B-curve using a uniform sampling density along the curve.
-    /// 
-    /// The method takes the following parameters:
-    /// - `port`: The port number to connect to the 3D viewer client.
-    /// - `opts`: The options for rendering the B-curve, including the number of divisions and the 
-    ///           color.
-    ///
-    /// The method first calculates the number of points to sample along the curve, the parameter 
-    /// range, and the step size. It then creates a 3D mesh and adds vertices for each sampled 
-    /// point, using the curve's `eval` method to compute the point positions. The method also 
-    /// adds line indices to connect the vertices into a polyline representation of the curve.
-    /// 
-    /// Finally, the method connects to the 3D viewer client and adds the mesh to the viewer.
-    fn view_uniform(
-        &mut self,
-        port: usize,
+    //{{{ fun: line_mesh
+    /// Builds the line mesh through the curve points at `params`, recording the color scale in the
+    /// options.
+    fn line_mesh(
+        &self,
+        params: &[f64],
         opts: &BcurveViewOptions<3>,
-    )
+    ) -> d3::Mesh
     {
-        let nl = opts.num_div;    
-        let np = nl + 1;
-        let u1  = *self.knots().first().unwrap();
-        let u2 = *self.knots().last().unwrap();
-        let du = (u2 - u1) / nl as f64; 
+        let nl = params.len() - 1;
         let normal = tv::Vec3::zeros();
 
+        let points: Vec<Vec3> = params.iter().map(|u| self.eval(*u)).collect();
+        let (colors, scale) = opts.color.sample(params, &points, || arc_fractions(self, params));
+        opts.color_scale.set(scale);
+
         let mut mesh = d3::Mesh::from_num_lines(nl);
-        for i in 0..np
+        for (p, color) in points.iter().zip(colors.iter())
         {
-            let u = u1 + i as f64 * du;
-            let p = self.eval(u);
+            mesh.add_vertex(&p.convert(), &normal, color, color)
+        }
 
-            let color = match opts.color {
-                CurveColor::Solid(c) => c,
-                _ => Color::default(),
+        for i in 0..nl
+        {
+            mesh.add_line_indices(i as u32, (i+1) as u32).unwrap();
+        }
+        mesh
+    }
+    //}}}
+    //{{{ fun: view_indicatrix
+    /// This method renders the tangent indicatrix of the B-curve as a polyline on the unit sphere
+    /// centred at the origin, with the same number of divisions as the curve itself.
+    fn view_indicatrix(
+        &self,
+        port: usize,
+        opts: &BcurveViewOptions<3>,
+        color: &Color,
+    )
+    {
+        let nl = opts.divisions();
+        let normal = tv::Vec3::zeros();
 
-            };
-            mesh.add_vertex(&p.convert(), &normal, &color, &color)
+        let mut mesh = d3::Mesh::from_num_lines(nl);
+        for t in self.tangent_indicatrix(nl + 1)
+        {
+            mesh.add_vertex(&t.convert(), &normal, color, color)
         }
 
         for i in 0..nl
@@ -182,12 +336,12 @@ impl Bcurve<3>
                 match client.add_mesh(mesh){
                     Ok(mesh_id) => {
                         //{{{ trace
-                        info!("Plane added with id: {}", mesh_id);
+                        info!("Indicatrix added with id: {}", mesh_id);
                         //}}}
                     }
                     Err(e) => {
                         //{{{ trace
-                        error!("Failed to add plane: {}", e);
+                        error!("Failed to add indicatrix: {}", e);
                         //}}}
                     }
                 }
@@ -198,8 +352,6 @@ impl Bcurve<3>
                 //}}}
             }
         }
-
-
     }
     //}}}
 }
@@ -215,12 +367,230 @@ impl Viewable for Bcurve<3>
         opts: &Self::Options,
     )
     {
-        match opts.method
+        self.sample_view(opts, |params| self.view_params(port, params, opts));
+
+        if let IndicatrixOptions::WithIndicatrix(color) = &opts.with_indicatrix
         {
-            CurveViewMethod::Uniform => self.view_uniform(port, opts),
-            CurveViewMethod::Curvature => self.view_curvature(port, opts),
-        };
+            self.view_indicatrix(port, opts, color);
+        }
     }
 }
 //}}}
 //}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test_utils::test_bcurve::{TestData, load_bcurve};
+    use crate::viewer::common::ramp_color;
+
+    /// Ranks a color of the ramp by the band it falls in, blue below white below the warm colors.
+    fn band(color: &Color) -> i32
+    {
+        match color
+        {
+            Color::Blue => 0,
+            Color::White => 1,
+            _ => 2,
+        }
+    }
+
+    /// The bands of the vertex colors, see [band].
+    fn bands(colors: &[Color]) -> Vec<i32>
+    {
+        colors.iter().map(band).collect()
+    }
+    //..............................................................................................
+
+    #[test]
+    fn color_scale_test()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let opts = BcurveViewOptions::<3> {
+            num_div: 50,
+            color: CurveColor::PositionFunction(Box::new(|p: Vec3| p[0])),
+            ..Default::default()
+        };
+        let params = bcurve.sample_params(50);
+        bcurve.line_mesh(&params, &opts);
+
+        let xs: Vec<f64> = params.iter().map(|u| bcurve.eval(*u)[0]).collect();
+        let min = xs.iter().cloned().fold(f64::MAX, f64::min);
+        let max = xs.iter().cloned().fold(f64::MIN, f64::max);
+        assert_eq!(opts.color_scale.get(), Some(ColorScale { min, max }));
+
+        // solid colors have no scale
+        let opts = BcurveViewOptions::<3> { num_div: 50, ..Default::default() };
+        bcurve.line_mesh(&params, &opts);
+        assert_eq!(opts.color_scale.get(), None);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn arc_length_sampling_test()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+        let opts = BcurveViewOptions::<3> {
+            method: CurveViewMethod::ArcLength,
+            num_div: 100,
+            ..Default::default()
+        };
+        let params = bcurve.arc_length_params(opts.num_div);
+        bcurve.line_mesh(&params, &opts);
+        assert_eq!(params.len(), 101);
+
+        let chords: Vec<f64> = params
+            .windows(2)
+            .map(|w| (bcurve.eval(w[1]) - bcurve.eval(w[0])).norm())
+            .collect();
+        let mean = chords.iter().sum::<f64>() / chords.len() as f64;
+        for chord in chords
+        {
+            assert!((chord - mean).abs() < 5e-2 * mean);
+        }
+    }
+    //..............................................................................................
+
+    #[test]
+    fn arc_length_color_test()
+    {
+        // a straight curve along x whose speed grows more than tenfold along it
+        let bcurve = Bcurve::new(&crate::geometry::BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            cpoints: vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.2, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)],
+            cweights: vec![1.0; 3],
+        });
+        let opts = BcurveViewOptions::<3> {
+            num_div: 30,
+            color: CurveColor::ArcLengthFunction(Box::new(|s| s)),
+            ..Default::default()
+        };
+        let params = bcurve.sample_params(opts.divisions());
+        let points: Vec<Vec3> = params.iter().map(|u| bcurve.eval(*u)).collect();
+
+        // the fractions are the distances along the line, not the parameters
+        let fractions = arc_fractions(&bcurve, &params);
+        for (s, point) in fractions.iter().zip(points.iter())
+        {
+            assert!((s - point[0] / 3.0).abs() < 1e-10);
+        }
+
+        // so the colors run through the ramp once, in the bands of the distance along the line
+        let (colors, scale) = opts.color.sample(&params, &points, || arc_fractions(&bcurve, &params));
+        let bands = bands(&colors);
+        assert!(bands.windows(2).all(|w| w[0] <= w[1]));
+        for (b, point) in bands.iter().zip(points.iter())
+        {
+            assert_eq!(*b, band(&ramp_color(point[0] / 3.0)));
+        }
+        let scale = scale.unwrap();
+        assert!(scale.min.abs() < 1e-12 && (scale.max - 1.0).abs() < 1e-10);
+
+        bcurve.line_mesh(&params, &opts);
+        assert_eq!(opts.color_scale.get(), Some(scale));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn param_color_2d_test()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<2>(3, &test_data);
+        let opts = BcurveViewOptions::<2> {
+            num_div: 50,
+            color: CurveColor::ParamFunction(Box::new(|u| u)),
+            ..Default::default()
+        };
+        let params = bcurve.sample_params(opts.divisions());
+        let points: Vec<Vector<2>> = params.iter().map(|u| bcurve.eval(*u)).collect();
+
+        // the vertex colors of a 2D curve run through the ramp along it, as they do in 3D
+        let (colors, scale) = opts.color.sample(&params, &points, || arc_fractions(&bcurve, &params));
+        let bands = bands(&colors);
+        assert_eq!(bands.len(), 51);
+        assert!(bands.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!((bands[0], bands[50]), (0, 2));
+
+        let (u1, u2) = bcurve.param_range();
+        let scale = scale.unwrap();
+        assert!((scale.min - u1).abs() < 1e-12 && (scale.max - u2).abs() < 1e-12);
+
+        bcurve.line_mesh(&params, &opts);
+        assert_eq!(opts.color_scale.get(), Some(scale));
+    }
+    //..............................................................................................
+
+    #[test]
+    fn per_span_sampling_test()
+    {
+        // spans of very different widths are each sampled the same number of times
+        let bcurve = Bcurve::new(&crate::geometry::BcurveDescriptor {
+            p: 2,
+            knots: vec![0.0, 0.0, 0.0, 0.01, 0.02, 1.0, 1.0, 1.0],
+            cpoints: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(2.0, 0.0, 1.0),
+                Vec3::new(3.0, 1.0, 0.0),
+                Vec3::new(4.0, 0.0, 0.0),
+            ],
+            cweights: vec![1.0; 5],
+        });
+        let num_per_span = 7;
+        let opts = BcurveViewOptions::<3> {
+            method: CurveViewMethod::PerSpan(num_per_span),
+            color: CurveColor::ParamFunction(Box::new(|u| u)),
+            ..Default::default()
+        };
+        let num_spans = bcurve.num_spans();
+        assert_eq!(num_spans, 3);
+
+        // a mock sink in place of the viewer records the samples and builds the mesh through them
+        let mut params = Vec::new();
+        bcurve.sample_view(&opts, |sampled| {
+            bcurve.line_mesh(sampled, &opts);
+            params = sampled.to_vec();
+        });
+        assert_eq!(params.len(), num_per_span * num_spans + 1);
+        assert_eq!(opts.color_scale.get(), Some(ColorScale { min: 0.0, max: 1.0 }));
+
+        assert!(params.windows(2).all(|w| w[0] < w[1]));
+        for (k, (a, b)) in bcurve.spans().enumerate()
+        {
+            assert_eq!(params[k * num_per_span], a);
+            let inside = params.iter().filter(|u| **u >= a && **u < b).count();
+            assert_eq!(inside, num_per_span);
+        }
+        assert_eq!(bcurve.per_span_params(0).len(), num_spans + 1);
+    }
+    //..............................................................................................
+
+    #[test]
+    fn num_div_test()
+    {
+        let test_data = TestData::new();
+        let bcurve = load_bcurve::<3>(3, &test_data);
+
+        // no divisions is taken as a single one, a segment between the ends of the curve
+        for num_div in [0, 1]
+        {
+            let opts = BcurveViewOptions::<3> { num_div, ..Default::default() };
+            assert_eq!(opts.divisions(), 1);
+
+            let uniform = bcurve.sample_params(opts.divisions());
+            let arc_length = bcurve.arc_length_params(opts.divisions());
+            for params in [uniform, arc_length]
+            {
+                assert_eq!(params, vec![bcurve.param_range().0, bcurve.param_range().1]);
+                bcurve.line_mesh(&params, &opts);
+            }
+        }
+    }
+}
+//}}}
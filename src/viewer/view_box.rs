@@ -10,7 +10,7 @@ use super::common::{tv ,Viewable, Convert};
 //{{{ std imports 
 //}}}
 //{{{ dep imports 
-use topohedral_viewer::{Color, CellType, d3::Client3D, d3::Mesh3D, d3::Mesh, d3::CuboidDescriptor};
+use topohedral_viewer::{Color, CellType, d2, d2::Mesh2D, d3::Client3D, d3::Mesh3D, d3::Mesh, d3::CuboidDescriptor};
 use topohedral_tracing::*;
 //}}}
 //--------------------------------------------------------------------------------------------------
@@ -66,3 +66,50 @@ impl Viewable for ABox<3>
         };
     }
 }
+
+impl Viewable for ABox<2>
+{
+    type Options = ABoxViewOptions;
+    fn view(
+        &mut self,
+        port: usize,
+        opts: &Self::Options,
+    )
+    {
+        // corners in order around the box, starting from the minimum
+        let [c00, c10, c01, c11] = self.corners();
+        let corners = [c00, c10, c11, c01];
+
+        let mut mesh = d2::Mesh::from_num_lines(4);
+        for p in corners.iter()
+        {
+            mesh.add_vertex(&p.convert(), &opts.color, &opts.color);
+        }
+        for i in 0..4
+        {
+            mesh.add_line_indices(i as u32, ((i + 1) % 4) as u32);
+        }
+
+        match d2::Client2D::new(port) {
+            Ok(mut client) => {
+                match client.add_mesh(mesh) {
+                    Ok(mesh_id) => {
+                        //{{{ trace
+                        info!("mesh_id: {}", mesh_id);
+                        //}}}
+                    }
+                    Err(err) => {
+                        //{{{ trace
+                        error!("Failed to add mesh with error: {}", err);
+                        //}}}
+                    }
+                }
+            }
+            Err(err) => {
+                //{{{ trace
+                error!("Failed to connect to client with error: {}", err);
+                //}}}
+            }
+        };
+    }
+}
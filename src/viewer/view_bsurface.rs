@@ -0,0 +1,218 @@
+//! This module contains the code for viewing B-spline surfaces.
+//!
+//! Surfaces are displayed as an isoparametric wireframe, a grid of constant-$u$ and constant-$v$
+//! isocurves each sent to the viewer as a line mesh.
+//--------------------------------------------------------------------------------------------------
+
+//{{{ crate imports
+use crate::geometry::{Bsurface, Bcurve, Curve};
+use crate::viewer::common::{tv, Convert, Viewable};
+//}}}
+//{{{ std imports
+//}}}
+//{{{ dep imports
+use topohedral_viewer::{Color, d3};
+use topohedral_tracing::*;
+//}}}
+//--------------------------------------------------------------------------------------------------
+
+//{{{ enum: WireframeOptions
+/// Options for viewing the isoparametric wireframe of a surface
+pub enum WireframeOptions
+{
+    NoWireframe,
+    /// Draw `nu` isocurves of constant $u$ and `nv` isocurves of constant $v$, evenly spaced
+    /// across the parameter domain and including its boundaries
+    WithWireframe
+    {
+        nu: usize,
+        nv: usize,
+        color: Color,
+    },
+}
+//}}}
+//{{{ impl Default for WireframeOptions
+impl Default for WireframeOptions
+{
+    fn default() -> Self
+    {
+        WireframeOptions::NoWireframe
+    }
+}
+//..................................................................................................
+//}}}
+//{{{ struct: BsurfaceViewOptions
+/// Options for viewing a B-spline surface
+#[derive(Default)]
+pub struct BsurfaceViewOptions
+{
+    /// Number of divisions along each isocurve, a value of 0 is taken as 1
+    pub num_div: usize,
+    /// Controls whether to display the isoparametric wireframe
+    pub with_wireframe: WireframeOptions,
+}
+//..................................................................................................
+//}}}
+//{{{ fun: iso_params
+/// Returns `num` parameter values evenly spaced over `[u1, u2]`, a single isocurve is placed in
+/// the middle of the range.
+fn iso_params(
+    u1: f64,
+    u2: f64,
+    num: usize,
+) -> Vec<f64>
+{
+    match num
+    {
+        0 => Vec::new(),
+        1 => vec![0.5 * (u1 + u2)],
+        _ => (0..num).map(|i| u1 + (u2 - u1) * i as f64 / (num - 1) as f64).collect(),
+    }
+}
+//}}}
+//{{{ fun: curve_mesh
+/// Samples a curve uniformly in parameter space into a line mesh with `nl` segments, at least one.
+fn curve_mesh(
+    curve: &Bcurve<3>,
+    nl: usize,
+    color: &Color,
+) -> d3::Mesh
+{
+    let nl = nl.max(1);
+    let normal = tv::Vec3::zeros();
+
+    let mut mesh = d3::Mesh::from_num_lines(nl);
+    for (_, p) in curve.sample(nl)
+    {
+        mesh.add_vertex(&p.convert(), &normal, color, color)
+    }
+
+    for i in 0..nl
+    {
+        mesh.add_line_indices(i as u32, (i + 1) as u32).unwrap();
+    }
+    mesh
+}
+//}}}
+//{{{ impl: Bsurface<3>
+impl Bsurface<3>
+{
+    //{{{ fun: wireframe_meshes
+    /// Builds one line mesh for each isocurve of the wireframe, the constant-$u$ isocurves first.
+    fn wireframe_meshes(
+        &self,
+        nu: usize,
+        nv: usize,
+        num_div: usize,
+        color: &Color,
+    ) -> Vec<d3::Mesh>
+    {
+        let (u1, u2) = self.param_range_u();
+        let (v1, v2) = self.param_range_v();
+
+        let mut meshes = Vec::with_capacity(nu + nv);
+        for u in iso_params(u1, u2, nu)
+        {
+            meshes.push(curve_mesh(&self.iso_u(u), num_div, color));
+        }
+        for v in iso_params(v1, v2, nv)
+        {
+            meshes.push(curve_mesh(&self.iso_v(v), num_div, color));
+        }
+        meshes
+    }
+    //}}}
+    //{{{ fun: view_wireframe
+    /// This method renders the isoparametric wireframe of the surface, sending each isocurve to
+    /// the viewer as a separate line mesh.
+    fn view_wireframe(
+        &self,
+        port: usize,
+        opts: &BsurfaceViewOptions,
+        nu: usize,
+        nv: usize,
+        color: &Color,
+    )
+    {
+        let meshes = self.wireframe_meshes(nu, nv, opts.num_div, color);
+
+        match d3::Client3D::new(port) {
+            Ok(mut client) => {
+                for mesh in meshes
+                {
+                    match client.add_mesh(mesh){
+                        Ok(mesh_id) => {
+                            //{{{ trace
+                            info!("Isocurve added with id: {}", mesh_id);
+                            //}}}
+                        }
+                        Err(e) => {
+                            //{{{ trace
+                            error!("Failed to add isocurve: {}", e);
+                            //}}}
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                //{{{ trace
+                error!("Failed to connect to client: {}", e);
+                //}}}
+            }
+        }
+    }
+    //}}}
+}
+//}}}
+//{{{ impl: Viewable for Bsurface<3>
+impl Viewable for Bsurface<3>
+{
+    type Options = BsurfaceViewOptions;
+
+    fn view(
+        &mut self,
+        port: usize,
+        opts: &Self::Options,
+    )
+    {
+        if let WireframeOptions::WithWireframe { nu, nv, color } = &opts.with_wireframe
+        {
+            self.view_wireframe(port, opts, *nu, *nv, color);
+        }
+    }
+}
+//}}}
+
+//-------------------------------------------------------------------------------------------------
+//{{{ mod: tests
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::common::Vec3;
+    use crate::geometry::BsurfaceDescriptor;
+
+    #[test]
+    fn wireframe_meshes_test()
+    {
+        let bsurf = Bsurface::<3>::new(&BsurfaceDescriptor {
+            p: 1,
+            q: 1,
+            knots_u: vec![0.0, 0.0, 1.0, 1.0],
+            knots_v: vec![0.0, 0.0, 1.0, 1.0],
+            cpoints: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(1.0, 1.0, 1.0),
+            ],
+            cweights: vec![1.0; 4],
+        });
+
+        let color = Color::default();
+        assert_eq!(bsurf.wireframe_meshes(5, 3, 10, &color).len(), 8);
+        assert_eq!(bsurf.wireframe_meshes(1, 0, 10, &color).len(), 1);
+        assert_eq!(iso_params(0.0, 2.0, 5), vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+    }
+}
+//}}}